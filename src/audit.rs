@@ -0,0 +1,377 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先のファイル名の衝突の検査を行うモジュール
+//!
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::error;
+
+use crate::cache::{summaries, volume_label, ExifCache};
+use crate::cmd_args::{AuditNamesArgs, Options};
+use crate::exif_view::ExifView;
+use crate::template::{NameContext, PathContext, Sequencer};
+use crate::{
+    build_file_type, build_name, file_stem, input_files, is_target_file,
+    original_dir, parse_datetime, resolve_datetime, shooting_date, src_dir,
+    stored_name,
+};
+
+///
+/// 検査の件数を集計する構造体
+///
+#[derive(Debug, Default)]
+struct AuditCount {
+    /// 振り分け先を求めたファイル数
+    resolved: usize,
+
+    /// 撮影日時が得られず振り分け先を求められなかったファイル数
+    unresolved: usize,
+
+    /// 振り分け先を求める際に失敗したファイル数
+    failed: usize,
+}
+
+///
+/// 振り分け先ごとのファイルの一覧
+///
+#[derive(Debug, Default)]
+struct Destinations {
+    /// 比較用に正規化した振り分け先のパスごとの振り分け先と入力ファイルの一覧
+    map: BTreeMap<PathBuf, (PathBuf, Vec<String>)>,
+}
+
+impl Destinations {
+    ///
+    /// 振り分け先を登録する
+    ///
+    /// # 引数
+    /// * `dst` - 振り分け先のパス
+    /// * `source` - 入力ファイル(表示用の文字列)
+    ///
+    /// # 注記
+    /// 大文字と小文字を区別しないファイルシステムが既定となるWindowsとmacOSで
+    /// は、大文字と小文字のみが異なるパスも同じ振り分け先として扱う。
+    ///
+    fn add(&mut self, dst: PathBuf, source: String) {
+        let key = if cfg!(any(windows, target_os = "macos")) {
+            PathBuf::from(dst.to_string_lossy().to_lowercase())
+        } else {
+            dst.clone()
+        };
+
+        self.map
+            .entry(key)
+            .or_insert_with(|| (dst, Vec::new()))
+            .1
+            .push(source);
+    }
+}
+
+///
+/// `audit-names`サブコマンドの実行関数
+///
+/// # 引数
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 衝突が無かった場合は`Ok(())`、衝突が見つかった場合や処理に失敗した場合は
+/// エラー情報を`Err()`でラップして返す
+///
+/// # 注記
+/// 現在の設定(パステンプレート、ファイル名テンプレート、格納方式等)で振り分
+/// けた場合の振り分け先を求め、複数のファイルが同じ振り分け先となるものを表
+/// 示する。ファイルのコピーおよびキャッシュの更新は一切行わない。入力ディレ
+/// クトリが指定された場合はその中のファイルのExif情報を用い、指定されない場
+/// 合はキャッシュデータベースに記録されたExif情報の抜粋を用いる(撮影者名と
+/// 秒未満の値は記録されていないため、何れも無いものとして扱う)。
+///
+pub(crate) fn run(args: &AuditNamesArgs, opts: &Options) -> Result<()> {
+    let mut count = AuditCount::default();
+    let mut dsts = Destinations::default();
+
+    match args.input_path {
+        Some(ref input_path) => {
+            audit_tree(input_path, opts, &mut dsts, &mut count)?
+        }
+
+        None => audit_cache(opts, &mut dsts, &mut count)?,
+    }
+
+    /*
+     * 衝突の表示
+     */
+    let mut collisions = 0;
+
+    for (dst, sources) in dsts.map.values() {
+        if sources.len() < 2 {
+            continue;
+        }
+
+        println!("collision  {}", dst.display());
+
+        for source in sources {
+            println!("           <- {}", source);
+        }
+
+        collisions += 1;
+    }
+
+    println!(
+        "files: {}, destinations: {}, collisions: {}, unresolved: {}, \
+         failed: {}",
+        count.resolved,
+        dsts.map.len(),
+        collisions,
+        count.unresolved,
+        count.failed
+    );
+
+    if collisions > 0 {
+        return Err(anyhow!("{} naming collisions found", collisions));
+    }
+
+    Ok(())
+}
+
+///
+/// 入力ディレクトリ中のファイルの振り分け先を求める
+///
+/// # 引数
+/// * `input_path` - 入力ディレクトリのパス
+/// * `opts` - オプション設定の参照
+/// * `dsts` - 振り分け先の登録先
+/// * `count` - 件数の集計先
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn audit_tree(
+    input_path: &Path,
+    opts: &Options,
+    dsts: &mut Destinations,
+    count: &mut AuditCount,
+) -> Result<()> {
+    if !input_path.is_dir() {
+        return Err(anyhow!("{} is not directory", input_path.display()));
+    }
+
+    let opts = opts.with_input_path(input_path.to_path_buf());
+    let exif_cache = ExifCache::new(&opts);
+    let mut sequencer = Sequencer::default();
+    let label = volume_label(input_path);
+
+    for entry in input_files(&opts, &exif_cache) {
+        let path = entry.path();
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if !is_target_file(&ext, &opts) {
+            continue;
+        }
+
+        let name = path.strip_prefix(input_path).unwrap_or(path);
+
+        let result = tree_destination(
+            path,
+            &ext,
+            &opts,
+            &exif_cache,
+            label.as_deref(),
+            &mut sequencer,
+        );
+
+        match result {
+            Ok(Some(dst)) => {
+                dsts.add(dst, name.display().to_string());
+                count.resolved += 1;
+            }
+
+            Ok(None) => count.unresolved += 1,
+
+            Err(err) => {
+                error!("audit failed {}: {}", path.display(), err);
+                count.failed += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// 入力ファイルの振り分け先を求める
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+/// * `ext` - 入力ファイルの拡張子
+/// * `opts` - オプション設定の参照
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+/// * `label` - 入力元のボリュームのラベル
+/// * `sequencer` - ファイル名の通し番号のカウンタ
+///
+/// # 戻り値
+/// 処理が成功した場合は振り分け先のパス(撮影日時が得られない場合や振り分け
+/// 先の無い形式の場合はNone)を`Ok()`でラップして返す。失敗した場合はエラー
+/// 情報を`Err()`でラップして返す。
+///
+pub(crate) fn tree_destination(
+    path: &Path,
+    ext: &str,
+    opts: &Options,
+    exif_cache: &ExifCache,
+    label: Option<&str>,
+    sequencer: &mut Sequencer,
+) -> Result<Option<PathBuf>> {
+    let (exif, _) = exif_cache.read(path)?;
+
+    let datetime = match resolve_datetime(&exif, path, opts)? {
+        Some(datetime) => datetime,
+        None => return Ok(None),
+    };
+
+    let view = ExifView::from(&*exif);
+    let ctx = PathContext {
+        date: shooting_date(&datetime, opts),
+        artist: view.artist.as_deref(),
+        event: opts.event_at(&datetime),
+        camera: view.camera_name(),
+        ext,
+        original_dir: original_dir(path, opts),
+        src_dir: src_dir(path),
+        volume_label: label,
+    };
+
+    let file_type = match build_file_type(ext, &ctx, opts) {
+        Some(file_type) => file_type,
+        None => return Ok(None),
+    };
+
+    let name_ctx = NameContext {
+        datetime: datetime.naive_local(),
+        subsec: view.subsec.as_deref(),
+        artist: ctx.artist,
+        event: ctx.event,
+        stem: &file_stem(path),
+        src_dir: ctx.src_dir,
+        volume_label: ctx.volume_label,
+    };
+    let name = build_name(path, &file_type, &name_ctx, opts, sequencer);
+
+    Ok(Some(file_type.dir().join(stored_name(&name, &file_type, opts))))
+}
+
+///
+/// キャッシュデータベースに記録されたファイルの振り分け先を求める
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `dsts` - 振り分け先の登録先
+/// * `count` - 件数の集計先
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn audit_cache(
+    opts: &Options,
+    dsts: &mut Destinations,
+    count: &mut AuditCount,
+) -> Result<()> {
+    let mut sequencer = Sequencer::default();
+
+    for (key, exif) in summaries(&opts.cache_db_path())? {
+        // キーは"ボリュームID:相対パス"の形式
+        let rel_path = match key.split_once(':') {
+            Some((_, rel_path)) => PathBuf::from(rel_path),
+            None => continue,
+        };
+
+        let ext = rel_path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if !is_target_file(&ext, opts) {
+            continue;
+        }
+
+        let datetime = match exif.datetime_original.as_deref() {
+            Some(value) => parse_datetime(value, opts.datetime_formats()),
+            None => {
+                count.unresolved += 1;
+                continue;
+            }
+        };
+
+        let datetime = match datetime {
+            Ok(datetime) => datetime,
+            Err(err) => {
+                error!("audit failed {}: {}", key, err);
+                count.failed += 1;
+                continue;
+            }
+        };
+
+        // 機種名は"Make/Model"の形式で記録されている。撮影者名とボリューム
+        // のラベルは記録されておらず、入力ディレクトリも定まらない
+        let camera = exif.make_model.as_deref().map(|value| {
+            value
+                .split_once('/')
+                .map_or(value, |(_, model)| model)
+                .trim_matches('"')
+        });
+        let ctx = PathContext {
+            date: shooting_date(&datetime, opts),
+            artist: None,
+            event: opts.event_at(&datetime),
+            camera,
+            ext: &ext,
+            original_dir: None,
+            src_dir: src_dir(&rel_path),
+            volume_label: None,
+        };
+
+        let file_type = match build_file_type(&ext, &ctx, opts) {
+            Some(file_type) => file_type,
+            None => {
+                count.unresolved += 1;
+                continue;
+            }
+        };
+
+        let name_ctx = NameContext {
+            datetime: datetime.naive_local(),
+            subsec: None,
+            artist: None,
+            event: ctx.event,
+            stem: &file_stem(&rel_path),
+            src_dir: ctx.src_dir,
+            volume_label: None,
+        };
+        let name = build_name(
+            &rel_path,
+            &file_type,
+            &name_ctx,
+            opts,
+            &mut sequencer,
+        );
+
+        let dst = file_type.dir().join(stored_name(&name, &file_type, opts));
+        dsts.add(dst, key);
+        count.resolved += 1;
+    }
+
+    Ok(())
+}
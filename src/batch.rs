@@ -0,0 +1,280 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! マウントされている複数のカードから順に取り込むモジュール
+//!
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+
+use crate::cache::Cache;
+use crate::cmd_args::{ImportAllArgs, Options};
+use crate::estimate::format_size;
+use crate::mail;
+use crate::report::Report;
+use crate::{finalize, import, prune_expired};
+
+/// カードであることを示すディレクトリの名前
+const DCIM: &str = "DCIM";
+
+///
+/// カードごとの取り込み結果
+///
+#[derive(Debug, Default)]
+struct CardResult {
+    /// コピーしたファイル数
+    copied: usize,
+
+    /// スキップしたファイル数
+    skipped: usize,
+
+    /// 処理に失敗したファイル数
+    failed: usize,
+
+    /// コピーしたバイト数
+    copied_bytes: u64,
+
+    /// 取り込み自体が失敗した場合のエラーメッセージ
+    error: Option<String>,
+}
+
+impl CardResult {
+    ///
+    /// 取り込みの実行結果から件数を取り込む
+    ///
+    /// # 引数
+    /// * `report` - 取り込みの実行結果
+    ///
+    fn count(&mut self, report: &Report) {
+        self.copied = report.copied();
+        self.skipped = report.skipped();
+        self.failed = report.failed();
+        self.copied_bytes = report.copied_bytes();
+    }
+
+    ///
+    /// 1行分の概要を表示する
+    ///
+    /// # 引数
+    /// * `label` - 行の見出し
+    ///
+    fn print(&self, label: &str) {
+        println!(
+            "{}: copied {}, skipped {}, failed {}, {} copied{}",
+            label,
+            self.copied,
+            self.skipped,
+            self.failed,
+            format_size(self.copied_bytes),
+            self.error
+                .as_ref()
+                .map(|err| format!(" (error: {})", err))
+                .unwrap_or_default()
+        );
+    }
+}
+
+///
+/// `import-all`サブコマンドの実行関数
+///
+/// # 引数
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 全てのカードの取り込みが成功した場合は`Ok(())`、取り込みに失敗したカード
+/// があった場合はエラー情報を`Err()`でラップして返す
+///
+/// # 注記
+/// 指定されたディレクトリからDCIMディレクトリを持つボリューム(カード)を探
+/// し、カードごとに`<カード>/DCIM`を入力ディレクトリとして順に取り込む。キャ
+/// ッシュデータベースは同時に一つのプロセスからしか開けないため、並行しての
+/// 取り込みは行わない。一つのカードの取り込みに失敗しても残りのカードの取り
+/// 込みは継続し、最後にカードごとの概要と合計を表示する。
+///
+pub(crate) fn run(args: &ImportAllArgs, opts: &Options) -> Result<()> {
+    let cards = find_cards(&args.roots)?;
+
+    if cards.is_empty() {
+        return Err(anyhow!("no volume with {} found", DCIM));
+    }
+
+    info!("{} cards found", cards.len());
+
+    /*
+     * カードごとの取り込み
+     */
+    let mut results = Vec::new();
+
+    for card in &cards {
+        info!("importing from card {}", card.display());
+        results.push(import_card(card, opts));
+    }
+
+    /*
+     * 概要の表示
+     */
+    let mut total = CardResult::default();
+    let mut errors = 0;
+
+    for (card, result) in cards.iter().zip(&results) {
+        result.print(&card.display().to_string());
+
+        total.copied += result.copied;
+        total.skipped += result.skipped;
+        total.failed += result.failed;
+        total.copied_bytes += result.copied_bytes;
+
+        if result.error.is_some() {
+            errors += 1;
+        }
+    }
+
+    total.print(&format!("total ({} cards)", cards.len()));
+
+    if errors > 0 {
+        return Err(anyhow!(
+            "import failed on {} of {} cards",
+            errors,
+            cards.len()
+        ));
+    }
+
+    Ok(())
+}
+
+///
+/// 取り込み対象のカードを探す
+///
+/// # 引数
+/// * `roots` - カードを探すディレクトリまたはカードのパスのグロブパターン
+///
+/// # 戻り値
+/// 処理が成功した場合は見つかったカードのパスの一覧(重複を除いて整列したも
+/// の)を`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラップし
+/// て返す。
+///
+/// # 注記
+/// パターンに一致したディレクトリがDCIMディレクトリを持つ場合はそれ自体を、
+/// 持たない場合はその直下のディレクトリのうちDCIMディレクトリを持つものを
+/// カードとする(`/Volumes`や`/media/<ユーザ名>`の指定を想定)。
+///
+fn find_cards(roots: &[String]) -> Result<Vec<PathBuf>> {
+    let mut cards = Vec::new();
+
+    for root in roots {
+        let paths = glob::glob(root)
+            .map_err(|err| anyhow!("invalid pattern {}: {}", root, err))?;
+
+        for path in paths {
+            let path = match path {
+                Ok(path) => path,
+                Err(err) => {
+                    warn!("{}", err);
+                    continue;
+                }
+            };
+
+            if is_card(&path) {
+                cards.push(path);
+                continue;
+            }
+
+            if let Ok(entries) = path.read_dir() {
+                cards.extend(
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .map(|entry| entry.path())
+                        .filter(|path| is_card(path))
+                );
+            }
+        }
+    }
+
+    cards.sort();
+    cards.dedup();
+
+    Ok(cards)
+}
+
+///
+/// カードか否かを判定する
+///
+/// # 引数
+/// * `path` - 判定するディレクトリのパス
+///
+/// # 戻り値
+/// DCIMディレクトリを持つ場合は`true`
+///
+fn is_card(path: &Path) -> bool {
+    path.join(DCIM).is_dir()
+}
+
+///
+/// カード1枚分の取り込みを行う
+///
+/// # 引数
+/// * `card` - カードのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// カードの取り込み結果
+///
+/// # 注記
+/// 振り分け先がカードの中にある場合やカードが振り分け先の中にある場合は、
+/// 取り込みを行わずに失敗とする。レポート等の出力先が指定されている場合、
+/// カードごとに上書きされる。
+///
+fn import_card(card: &Path, opts: &Options) -> CardResult {
+    let mut result = CardResult::default();
+    let opts = opts.with_input_path(card.join(DCIM));
+
+    let report = opts
+        .check_input_overlap()
+        .and_then(|_| {
+            let cache = if opts.is_dry_run() {
+                Cache::open_dry_run(
+                    opts.cache_db_path(),
+                    opts.cache_eval_mode(),
+                    opts.input_path(),
+                )?
+            } else {
+                Cache::open(
+                    opts.cache_db_path(),
+                    opts.cache_eval_mode(),
+                    opts.input_path(),
+                )?
+            };
+
+            let report = import(&opts, &cache)?;
+            Ok((cache, report))
+        });
+
+    let (cache, report) = match report {
+        Ok(pair) => pair,
+        Err(err) => {
+            error!("import from {} failed: {}", card.display(), err);
+            mail::notify_failure(&opts, &err);
+            result.error = Some(err.to_string());
+            return result;
+        }
+    };
+
+    result.count(&report);
+
+    let finished = finalize(&opts, &report)
+        .and_then(|_| prune_expired(&opts, &cache, &report));
+
+    if let Err(err) = finished {
+        error!("{}", err);
+        result.error = Some(err.to_string());
+    }
+
+    result
+}
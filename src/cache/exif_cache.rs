@@ -0,0 +1,193 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 1回の実行の間、読み出したExif情報を保持するモジュール
+//!
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use exif::Exif;
+
+use super::{read_exif, ExifSummary};
+use crate::class_registry::ClassRegistry;
+use crate::cmd_args::Options;
+
+/// 保持するExif情報の合計サイズの上限(バイト)
+///
+/// TIFF形式のRAWファイルではファイル全体がExif情報として読み込まれるため、件
+/// 数ではなくサイズで制限する。
+const DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
+
+///
+/// 保持しているExif情報
+///
+struct Entry {
+    /// 読み出した時点のファイルサイズと更新日時
+    stamp: (u64, SystemTime),
+
+    /// Exif情報
+    exif: Rc<Exif>,
+
+    /// Exif情報の抜粋
+    summary: ExifSummary,
+
+    /// Exif情報のサイズ(バイト)
+    size: usize,
+}
+
+///
+/// 保持しているExif情報の一覧
+///
+#[derive(Default)]
+struct Entries {
+    /// パスごとのExif情報
+    map: HashMap<PathBuf, Entry>,
+
+    /// 登録順のパス(古いものから破棄する)
+    order: VecDeque<PathBuf>,
+
+    /// 保持しているExif情報の合計サイズ(バイト)
+    total: usize,
+}
+
+impl Entries {
+    ///
+    /// 指定のパスのExif情報を破棄する
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    ///
+    fn remove(&mut self, path: &Path) {
+        if let Some(entry) = self.map.remove(path) {
+            self.total -= entry.size;
+            self.order.retain(|other| other != path);
+        }
+    }
+}
+
+///
+/// Exif情報の読み出し結果を実行中に共有するためのキャッシュ
+///
+/// # 注記
+/// キャッシュ評価、並べ替え、付随ファイルの処理など、同じファイルのExif情報
+/// を複数の箇所で用いる場合に解析を1回で済ませるためのもの。実行ごとに生成
+/// し、処理の流れに沿って受け渡す(Exif情報はスレッド間で共有できないため、
+/// 単一のスレッド内でのみ用いる)。ファイルのサイズか更新日時が読み出した時点
+/// から変化している場合は読み直す。読み出しに失敗した結果は保持しない。
+///
+pub(crate) struct ExifCache {
+    /// 保持しているExif情報
+    entries: RefCell<Entries>,
+
+    /// 保持するExif情報の合計サイズの上限(バイト)
+    budget: usize,
+
+    /// 設定で追加した形式の登録内容
+    classes: Arc<ClassRegistry>,
+}
+
+impl ExifCache {
+    ///
+    /// 空のキャッシュを生成する
+    ///
+    /// # 引数
+    /// * `opts` - オプション設定の参照
+    ///
+    /// # 戻り値
+    /// 生成したキャッシュ
+    ///
+    pub(crate) fn new(opts: &Options) -> Self {
+        Self {
+            entries: RefCell::new(Entries::default()),
+            budget: DEFAULT_BUDGET,
+            classes: opts.classes().clone(),
+        }
+    }
+
+    ///
+    /// Exif情報を読み出す
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はExif情報とその抜粋の組を`Ok()`でラップして返す。失
+    /// 敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 保持しているものがあればそれを返し、無ければ`read_exif()`で読み出して
+    /// 保持する。RAWファイルのExif情報をペアのJPEGファイルで代用する扱いも
+    /// `read_exif()`と同じとなる。
+    ///
+    pub(crate) fn read<P>(&self, path: P) -> Result<(Rc<Exif>, ExifSummary)>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let meta = path.metadata()?;
+        let stamp = (meta.len(), meta.modified()?);
+
+        /*
+         * 保持しているExif情報の参照
+         */
+        {
+            let mut entries = self.entries.borrow_mut();
+
+            match entries.map.get(path) {
+                Some(entry) if entry.stamp == stamp => {
+                    return Ok((entry.exif.clone(), entry.summary.clone()));
+                }
+
+                // 読み出した後に変化したものは破棄して読み直す
+                Some(_) => entries.remove(path),
+
+                None => {}
+            }
+        }
+
+        /*
+         * 読み出しと登録
+         */
+        let (exif, summary) = read_exif(path, &self.classes)?;
+        let exif = Rc::new(exif);
+        let size = exif.buf().len();
+
+        // 単独で上限を超えるものは保持しない
+        if size <= self.budget {
+            let mut entries = self.entries.borrow_mut();
+
+            while entries.total + size > self.budget {
+                match entries.order.pop_front() {
+                    Some(oldest) => {
+                        if let Some(entry) = entries.map.remove(&oldest) {
+                            entries.total -= entry.size;
+                        }
+                    }
+
+                    None => break,
+                }
+            }
+
+            entries.map.insert(path.to_path_buf(), Entry {
+                stamp,
+                exif: exif.clone(),
+                summary: summary.clone(),
+                size,
+            });
+            entries.order.push_back(path.to_path_buf());
+            entries.total += size;
+        }
+
+        Ok((exif, summary))
+    }
+}
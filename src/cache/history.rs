@@ -0,0 +1,94 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 他のマシンでの取り込みの履歴(履歴データベース)を照会するモジュール
+//!
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use redb::{Database, ReadableTable, ReadableTableMetadata};
+
+use super::{TxnHandle, TABLE};
+
+///
+/// 読み込んだ履歴データベース
+///
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    /// 取り込み済みのファイルのサイズとExif情報の抜粋のハッシュ値の組
+    entries: HashSet<(u64, u64)>,
+}
+
+impl History {
+    ///
+    /// 履歴データベースを読み込む
+    ///
+    /// # 引数
+    /// * `path` - 他のマシンから持ち出したキャッシュデータベースのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は読み込んだ履歴を`Ok()`でラップして返す。失敗した場
+    /// 合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// キャッシュのキーはマシンごとに異なるボリュームIDを含むため、照合はファ
+    /// イルサイズとExif情報の抜粋で行う。撮影日時を持たないレコード(画像以外の
+    /// ファイルのもの)はサイズのみでの照合となり誤判定のおそれがあるため読み込
+    /// まない。データベースへの書き込みは行わない。
+    ///
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Err(anyhow!("{} is not file", path.display()));
+        }
+
+        let db = Database::open(path)?;
+        let txn = db.begin_read()?;
+        let table = txn.open_table(TABLE)?;
+        let mut history = Self::default();
+
+        for item in table.iter()? {
+            let (_, value) = item?;
+            let record = value.value();
+
+            if record.exif.datetime_original.is_some() {
+                history.entries.insert(
+                    (record.file_size, record.exif.calc_hash())
+                );
+            }
+        }
+
+        info!(
+            "loaded {} of {} records from history {}",
+            history.entries.len(),
+            table.len()?,
+            path.display()
+        );
+
+        Ok(history)
+    }
+
+    ///
+    /// ファイルが取り込み済みか否かを判定する
+    ///
+    /// # 引数
+    /// * `handle` - キャッシュの評価で得たコミット用ハンドル
+    ///
+    /// # 戻り値
+    /// サイズとExif情報の抜粋が一致するレコードが履歴にあれば`true`
+    ///
+    pub(crate) fn contains(&self, handle: &TxnHandle) -> bool {
+        let record = handle.record();
+
+        record.exif.datetime_original.is_some()
+            && self.entries.contains(
+                &(record.file_size, record.exif.calc_hash())
+            )
+    }
+}
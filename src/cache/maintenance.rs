@@ -0,0 +1,1252 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! キャッシュデータベースの保守操作をまとめたモジュール
+//!
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate};
+use glob::{MatchOptions, Pattern};
+use log::{info, warn};
+use redb::{Database, DatabaseError, ReadableTable, ReadableTableMetadata};
+use walkdir::WalkDir;
+
+use super::{
+    content_hash, open_database, CacheRecord, ExifSummary, ARCHIVE_TABLE,
+    STATS_TABLE, TABLE,
+};
+use crate::cmd_args::{
+    BackupArgs, CacheCommand, ClearArgs, InvalidateArgs, ListArgs, MergeArgs,
+    Options, PruneArgs, RestoreArgs,
+};
+use crate::estimate::format_size;
+use crate::{is_target_file, parse_datetime, rebuild, shooting_date};
+
+///
+/// キャッシュレコードの絞り込み条件
+///
+#[derive(Debug, Default)]
+struct RecordFilter {
+    /// ボリュームID
+    volume: Option<String>,
+
+    /// 相対パスのグロブパターン
+    path: Option<Pattern>,
+
+    /// 撮影日付の始点(この日付を含む)
+    from: Option<NaiveDate>,
+
+    /// 撮影日付の終点(この日付を含まない)
+    to: Option<NaiveDate>,
+}
+
+impl RecordFilter {
+    ///
+    /// 条件が一つも指定されていないか否かを判定する
+    ///
+    /// # 戻り値
+    /// 条件が指定されていない場合は`true`
+    ///
+    fn is_empty(&self) -> bool {
+        self.volume.is_none()
+            && self.path.is_none()
+            && self.from.is_none()
+            && self.to.is_none()
+    }
+
+    ///
+    /// キャッシュレコードが条件に合致するか否かを判定する
+    ///
+    /// # 引数
+    /// * `key` - キャッシュレコードのキー
+    /// * `record` - キャッシュレコード
+    /// * `opts` - オプション設定の参照
+    ///
+    /// # 戻り値
+    /// 全ての条件に合致する場合は`true`
+    ///
+    /// # 注記
+    /// 撮影日付の条件が指定されている場合、撮影日時を持たないレコードは合致し
+    /// ないものとして扱う。
+    ///
+    fn matches(&self, key: &str, record: &CacheRecord, opts: &Options)
+        -> bool
+    {
+        let (volume, rel_path) = split_key(key);
+
+        if let Some(expect) = &self.volume {
+            if volume != expect {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.path {
+            let options = MatchOptions {
+                require_literal_separator: true,
+                ..MatchOptions::new()
+            };
+
+            if !pattern.matches_with(rel_path, options) {
+                return false;
+            }
+        }
+
+        if self.from.is_some() || self.to.is_some() {
+            let date = match record_date(record, opts) {
+                Some(date) => date,
+                None => return false,
+            };
+
+            if self.from.is_some_and(|from| date < from) {
+                return false;
+            }
+
+            if self.to.is_some_and(|to| date >= to) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+///
+/// キャッシュデータベースの取り込み結果
+///
+#[derive(Debug, Default)]
+struct MergeStats {
+    /// 新たに追加したレコード数
+    added: usize,
+
+    /// 取り込み元の方が新しかったため更新したレコード数
+    updated: usize,
+
+    /// 取り込み先の方が新しかったため維持したレコード数
+    kept: usize,
+}
+
+///
+/// キャッシュデータベースの統計情報
+///
+#[derive(Debug, Default)]
+struct CacheStats {
+    /// レコード数
+    records: usize,
+
+    /// 振り分け先が記録されたレコード数
+    with_destination: usize,
+
+    /// 壊れていて読み出せなかったレコード数
+    corrupt: usize,
+
+    /// ボリュームIDごとのレコード数
+    volumes: BTreeMap<String, usize>,
+
+    /// 最も古い記録日時
+    oldest: Option<DateTime<FixedOffset>>,
+
+    /// 最も新しい記録日時
+    newest: Option<DateTime<FixedOffset>>,
+
+    /// キャッシュヒット数の累計
+    hits: u64,
+
+    /// キャッシュミス数の累計
+    misses: u64,
+
+    /// データベースファイルのサイズ(バイト)
+    file_size: u64,
+}
+
+///
+/// キャッシュ操作のサブコマンドを実行する
+///
+/// # 引数
+/// * `command` - 実行するサブコマンド
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+pub(crate) fn run_command(command: &CacheCommand, opts: &Options)
+    -> Result<()>
+{
+    match command {
+        CacheCommand::List(args) => list(&opts.cache_db_path(), args, opts)?,
+
+        CacheCommand::Prune(args) => {
+            let count = prune(&opts.cache_db_path(), args)?;
+            println!("{} entries pruned", count);
+        }
+
+        CacheCommand::Clear(args) => {
+            let count = clear(&opts.cache_db_path(), args, opts)?;
+            println!("{} entries cleared", count);
+        }
+
+        CacheCommand::Stats => {
+            let stats = stats(&opts.cache_db_path())?;
+            print_stats(&stats);
+        }
+
+        CacheCommand::Invalidate(args) => {
+            let count = invalidate(&opts.cache_db_path(), args, opts)?;
+            println!("{} entries invalidated", count);
+        }
+
+        CacheCommand::Merge(args) => {
+            let stats = merge(&opts.cache_db_path(), args)?;
+            println!(
+                "merged: {} added, {} updated, {} kept",
+                stats.added,
+                stats.updated,
+                stats.kept
+            );
+        }
+
+        CacheCommand::Backup(args) => {
+            let count = backup(&opts.cache_db_path(), args)?;
+            println!(
+                "{} entries backed up to {}",
+                count,
+                args.dest.display()
+            );
+        }
+
+        CacheCommand::Restore(args) => {
+            let count = restore(&opts.cache_db_path(), args, opts)?;
+            println!("{} entries restored", count);
+        }
+
+        CacheCommand::Rebuild(args) => rebuild::run(args, opts)?,
+
+        CacheCommand::IndexArchive => {
+            let count = index_archive(&opts.cache_db_path(), opts)?;
+            println!("{} files indexed", count);
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// キャッシュレコードに記録されたExif情報の抜粋を列挙する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合はキーとExif情報の抜粋の組の一覧を`Ok()`でラップして返
+/// す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+pub(crate) fn summaries(db_path: &Path) -> Result<Vec<(String, ExifSummary)>> {
+    let db = open_database(db_path)?;
+    let txn = db.begin_read()?;
+    let table = txn.open_table(TABLE)?;
+    let mut list = Vec::new();
+
+    for item in table.iter()? {
+        let (key, value) = item?;
+        let record = value.value();
+
+        // 壊れたレコードはExif情報の抜粋を持たない
+        if !record.corrupt {
+            list.push((key.value(), record.exif));
+        }
+    }
+
+    Ok(list)
+}
+
+///
+/// 条件に合致するキャッシュレコードを一覧表示する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップし
+/// て返す
+///
+/// # 注記
+/// 1レコードにつき1行で、キー、記録日時、ファイルサイズ、振り分け先(記録さ
+/// れていない場合は`-`)を出力する。
+///
+fn list(db_path: &Path, args: &ListArgs, opts: &Options) -> Result<()> {
+    let mut filter = RecordFilter {
+        volume: args.volume.clone(),
+        ..Default::default()
+    };
+
+    if let Some(path) = &args.path {
+        filter.path = Some(Pattern::new(path)?);
+    }
+
+    let db = open_database(db_path)?;
+    let txn = db.begin_read()?;
+    let table = txn.open_table(TABLE)?;
+
+    for item in table.iter()? {
+        let (key, value) = item?;
+        let key = key.value();
+        let record = value.value();
+
+        if !filter.matches(&key, &record, opts) {
+            continue;
+        }
+
+        if record.corrupt {
+            println!("{}  (corrupt)", key);
+            continue;
+        }
+
+        println!(
+            "{}  {}  {}  {}",
+            key,
+            record.timestamp,
+            record.file_size,
+            record
+                .destination
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        );
+    }
+
+    Ok(())
+}
+
+///
+/// 記録から指定の日数が経過したキャッシュレコードを削除する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `args` - サブコマンドの引数
+///
+/// # 戻り値
+/// 処理が成功した場合は削除したレコード数を`Ok()`でラップして返す。失敗した場
+/// 合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 経過日数はキャッシュに記録した日時(最後に取り込んだ日時)から数える。記録
+/// 日時がパースできないレコード(壊れたものを含む)は最も古いものとして扱い削
+/// 除する。
+///
+fn prune(db_path: &Path, args: &PruneArgs) -> Result<usize> {
+    if args.older_than == 0 {
+        return Err(anyhow!("--older-than must be greater than 0"));
+    }
+
+    let cutoff = Local::now()
+        .checked_sub_days(Days::new(args.older_than as u64))
+        .ok_or_else(|| anyhow!("--older-than is too large"))?;
+
+    remove_records(db_path, |key, record| {
+        if args.volume.as_deref().is_some_and(|id| split_key(key).0 != id) {
+            return false;
+        }
+
+        record_timestamp(record).is_none_or(|timestamp| timestamp < cutoff)
+    })
+}
+
+///
+/// ボリューム単位またはデータベース全体のキャッシュレコードを削除する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は削除したレコード数を`Ok()`でラップして返す。失敗した場
+/// 合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// ボリュームIDを指定しない場合は全てのレコードを削除するため、事前に利用者
+/// に確認を求める。この場合はキャッシュ評価の累計も消去する。
+///
+fn clear(db_path: &Path, args: &ClearArgs, opts: &Options) -> Result<usize> {
+    let Some(volume) = &args.volume else {
+        if !opts.confirm("clear all cache entries?")? {
+            return Err(anyhow!("cache clear aborted"));
+        }
+
+        let count = remove_records(db_path, |_, _| true)?;
+
+        let db = open_database(db_path)?;
+        let txn = db.begin_write()?;
+        txn.delete_table(STATS_TABLE)?;
+        txn.open_table(STATS_TABLE)?;
+        txn.commit()?;
+
+        return Ok(count);
+    };
+
+    remove_records(db_path, |key, _| split_key(key).0 == volume)
+}
+
+///
+/// 条件に合致するキャッシュレコードを削除する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `pred` - 削除の対象か否かを判定するクロージャ
+///
+/// # 戻り値
+/// 処理が成功した場合は削除したレコード数を`Ok()`でラップして返す。失敗した場
+/// 合はエラー情報を`Err()`でラップして返す。
+///
+fn remove_records<F>(db_path: &Path, pred: F) -> Result<usize>
+where
+    F: Fn(&str, &CacheRecord) -> bool,
+{
+    let db = open_database(db_path)?;
+    let txn = db.begin_write()?;
+    let count = {
+        let mut table = txn.open_table(TABLE)?;
+        let mut keys = Vec::new();
+
+        for item in table.iter()? {
+            let (key, value) = item?;
+            let key = key.value();
+
+            if pred(&key, &value.value()) {
+                keys.push(key);
+            }
+        }
+
+        for key in &keys {
+            info!("remove {}", key);
+            table.remove(key)?;
+        }
+
+        keys.len()
+    };
+
+    txn.commit()?;
+
+    Ok(count)
+}
+
+///
+/// 振り分け先に格納済みのファイルを重複の検出の索引に登録する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は登録したファイル数を`Ok()`でラップして返す。失敗した
+/// 場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 振り分け先の基点ディレクトリ以下の取り込み対象の形式のファイルについて、
+/// `--checksum-algorithm`で指定したハッシュ関数でハッシュ値を求めて登録す
+/// る。本ツール以外で格納したファイルや、`--dedup`を指定する前に取り込んだ
+/// ファイルを重複の検出の対象とするために用いる。読み出せないファイルは警告
+/// を記録して読み飛ばす。
+///
+fn index_archive(db_path: &Path, opts: &Options) -> Result<usize> {
+    let algo = opts.checksum_algorithm();
+    let db = open_database(db_path)?;
+    let mut count = 0;
+
+    for root in opts.destination_roots() {
+        if !root.is_dir() {
+            continue;
+        }
+
+        let txn = db.begin_write()?;
+        {
+            let mut index = txn.open_table(ARCHIVE_TABLE)?;
+
+            let files = WalkDir::new(&root)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file());
+
+            for entry in files {
+                let path = entry.path();
+                let ext = path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                if !is_target_file(&ext, opts) {
+                    continue;
+                }
+
+                match content_hash(path, algo) {
+                    Ok(hash) => {
+                        info!("index {}", path.display());
+                        index.insert(hash, path.display().to_string())?;
+                        count += 1;
+                    }
+
+                    Err(err) => {
+                        warn!("index {} failed: {}", path.display(), err);
+                    }
+                }
+            }
+        }
+
+        txn.commit()?;
+    }
+
+    Ok(count)
+}
+
+///
+/// キャッシュデータベースの統計情報を集計する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は統計情報を`Ok()`でラップして返す。失敗した場合はエ
+/// ラー情報を`Err()`でラップして返す。
+///
+fn stats(db_path: &Path) -> Result<CacheStats> {
+    let db = open_database(db_path)?;
+    let txn = db.begin_read()?;
+    let table = txn.open_table(TABLE)?;
+    let mut stats = CacheStats::default();
+
+    for item in table.iter()? {
+        let (key, value) = item?;
+        let key = key.value();
+        let record = value.value();
+
+        stats.records += 1;
+        *stats
+            .volumes
+            .entry(split_key(&key).0.to_string())
+            .or_default() += 1;
+
+        if record.corrupt {
+            stats.corrupt += 1;
+            continue;
+        }
+
+        if record.destination.is_some() {
+            stats.with_destination += 1;
+        }
+
+        if let Some(timestamp) = record_timestamp(&record) {
+            if stats.oldest.is_none_or(|oldest| timestamp < oldest) {
+                stats.oldest = Some(timestamp);
+            }
+
+            if stats.newest.is_none_or(|newest| timestamp > newest) {
+                stats.newest = Some(timestamp);
+            }
+        }
+    }
+
+    let counters = txn.open_table(STATS_TABLE)?;
+    let counter = |key: &str| -> Result<u64> {
+        Ok(counters.get(key.to_string())?.map_or(0, |value| value.value()))
+    };
+
+    stats.hits = counter("hits")?;
+    stats.misses = counter("misses")?;
+    stats.file_size = fs::metadata(db_path)?.len();
+
+    Ok(stats)
+}
+
+///
+/// キャッシュデータベースの統計情報を表示する
+///
+/// # 引数
+/// * `stats` - 統計情報
+///
+fn print_stats(stats: &CacheStats) {
+    let lookups = stats.hits + stats.misses;
+
+    println!("entries:         {}", stats.records);
+    println!("with dest:       {}", stats.with_destination);
+    println!("corrupt:         {}", stats.corrupt);
+    println!("file size:       {}", format_size(stats.file_size));
+
+    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+        println!("oldest:          {}", oldest.to_rfc3339());
+        println!("newest:          {}", newest.to_rfc3339());
+    }
+
+    if lookups > 0 {
+        println!(
+            "hits:            {} of {} ({:.1}%)",
+            stats.hits,
+            lookups,
+            stats.hits as f64 * 100.0 / lookups as f64,
+        );
+    } else {
+        println!("hits:            -");
+    }
+
+    println!("volumes:");
+
+    for (volume, count) in &stats.volumes {
+        println!("  {}: {}", volume, count);
+    }
+}
+
+///
+/// 条件に合致するキャッシュレコードを削除する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は削除したレコード数を`Ok()`でラップして返す。失敗した場
+/// 合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// データベース全体の消去を誤って行わないよう、条件が一つも指定されていない場
+/// 合はエラーとする。
+///
+fn invalidate(db_path: &Path, args: &InvalidateArgs, opts: &Options)
+    -> Result<usize>
+{
+    /*
+     * 絞り込み条件の構築
+     */
+    let mut filter = RecordFilter {
+        volume: args.volume.clone(),
+        ..Default::default()
+    };
+
+    if let Some(path) = &args.path {
+        filter.path = Some(Pattern::new(path)?);
+    }
+
+    if let Some(range) = &args.date_range {
+        (filter.from, filter.to) = parse_date_range(range)?;
+    }
+
+    if filter.is_empty() {
+        return Err(anyhow!(
+            "at least one of --volume, --path or --date-range is required"
+        ));
+    }
+
+    /*
+     * 合致するレコードの削除
+     */
+    remove_records(db_path, |key, record| filter.matches(key, record, opts))
+}
+
+///
+/// 他のキャッシュデータベースのレコードを取り込む
+///
+/// # 引数
+/// * `db_path` - 取り込み先のキャッシュデータベースファイルのパス
+/// * `args` - サブコマンドの引数
+///
+/// # 戻り値
+/// 処理が成功した場合は取り込み結果を`Ok()`でラップして返す。失敗した場合はエ
+/// ラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 同じキーのレコードが両方に存在する場合は、キャッシュに記録した日時が新しい
+/// 方を採用する。
+///
+fn merge(db_path: &Path, args: &MergeArgs) -> Result<MergeStats> {
+    /*
+     * 取り込み元の確認
+     */
+    if !args.source.is_file() {
+        return Err(anyhow!("{} is not file", args.source.display()));
+    }
+
+    if db_path.exists()
+        && args.source.canonicalize()? == db_path.canonicalize()?
+    {
+        return Err(anyhow!("cannot merge the cache database into itself"));
+    }
+
+    /*
+     * レコードの取り込み
+     */
+    let src_db = Database::open(&args.source)?;
+    let src_txn = src_db.begin_read()?;
+    let src_table = src_txn.open_table(TABLE)?;
+
+    let db = open_database(db_path)?;
+    let txn = db.begin_write()?;
+    let mut stats = MergeStats::default();
+
+    {
+        let mut table = txn.open_table(TABLE)?;
+
+        for item in src_table.iter()? {
+            let (key, value) = item?;
+            let key = key.value();
+            let record = value.value();
+
+            // 取り込み元の壊れたレコードは取り込まない
+            if record.corrupt {
+                continue;
+            }
+
+            let current = table.get(&key)?.map(|data| data.value());
+
+            match current {
+                None => {
+                    table.insert(&key, &record)?;
+                    stats.added += 1;
+                }
+
+                Some(current) if is_newer(&record, &current) => {
+                    table.insert(&key, &record)?;
+                    stats.updated += 1;
+                }
+
+                Some(_) => stats.kept += 1,
+            }
+        }
+    }
+
+    txn.commit()?;
+
+    Ok(stats)
+}
+
+///
+/// キャッシュレコードの記録日時を比較する
+///
+/// # 引数
+/// * `record` - 比較するレコード
+/// * `base` - 比較の基準となるレコード
+///
+/// # 戻り値
+/// `record`の記録日時の方が新しい場合は`true`
+///
+/// # 注記
+/// 記録日時がパースできないレコードは最も古いものとして扱う。
+///
+fn is_newer(record: &CacheRecord, base: &CacheRecord) -> bool {
+    match (record_timestamp(record), record_timestamp(base)) {
+        (Some(record), Some(base)) => record > base,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+///
+/// キャッシュレコードの記録日時を取得する
+///
+/// # 引数
+/// * `record` - キャッシュレコード
+///
+/// # 戻り値
+/// 記録日時(パースできない場合は`None`)
+///
+fn record_timestamp(record: &CacheRecord) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(&record.timestamp).ok()
+}
+
+///
+/// キャッシュデータベースをバックアップする
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `args` - サブコマンドの引数
+///
+/// # 戻り値
+/// 処理が成功した場合はバックアップしたレコード数を`Ok()`でラップして返す。失
+/// 敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// コピー中に他のプロセスから書き込まれないよう、コピーが完了するまでキャッ
+/// シュデータベースを開いたままにする。
+///
+fn backup(db_path: &Path, args: &BackupArgs) -> Result<u64> {
+    if !db_path.is_file() {
+        return Err(anyhow!("{} is not file", db_path.display()));
+    }
+
+    let db = Database::open(db_path)?;
+    let expect = db.begin_read()?.open_table(TABLE)?.len()?;
+    let count = copy_verified(db_path, &args.dest)?;
+
+    drop(db);
+
+    if count != expect {
+        let _ = fs::remove_file(&args.dest);
+        return Err(anyhow!(
+            "backup verification failed: {} entries expected, {} found",
+            expect,
+            count
+        ));
+    }
+
+    Ok(count)
+}
+
+///
+/// バックアップからキャッシュデータベースを復元する
+///
+/// # 引数
+/// * `db_path` - キャッシュデータベースファイルのパス
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は復元したレコード数を`Ok()`でラップして返す。失敗した場
+/// 合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 既存のキャッシュデータベースを置き換える場合は、事前に利用者に確認を求め、
+/// 置き換える前の内容を`.bak`を付与した名前で残す。バックアップの検証に失敗
+/// した場合、既存のキャッシュデータベースは変更しない。他のプロセス(デーモ
+/// ン等)が使用中の場合は、置き換えた内容が失われるためエラーとする。
+///
+fn restore(db_path: &Path, args: &RestoreArgs, opts: &Options)
+    -> Result<u64>
+{
+    if !args.source.is_file() {
+        return Err(anyhow!("{} is not file", args.source.display()));
+    }
+
+    if db_path.exists()
+        && args.source.canonicalize()? == db_path.canonicalize()?
+    {
+        return Err(anyhow!("cannot restore the cache database from itself"));
+    }
+
+    if db_path.exists() {
+        // 開けるか否かで使用中か否かを確認する(壊れている場合も置き換える)
+        match Database::open(db_path) {
+            Ok(db) => drop(db),
+            Err(DatabaseError::DatabaseAlreadyOpen) => {
+                return Err(anyhow!(
+                    "{} is in use, stop the running imgdist first",
+                    db_path.display()
+                ));
+            }
+            Err(err) => warn!("open {} failed: {}", db_path.display(), err),
+        }
+
+        let msg = format!(
+            "replace the cache database with {}?",
+            args.source.display()
+        );
+
+        if !opts.confirm(&msg)? {
+            return Err(anyhow!("cache restore aborted"));
+        }
+
+        /*
+         * 置き換える前の内容の退避
+         */
+        let mut bak = db_path.as_os_str().to_owned();
+        bak.push(".bak");
+        let bak = PathBuf::from(bak);
+
+        fs::copy(db_path, &bak)?;
+        println!("previous cache database saved to {}", bak.display());
+    } else if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    copy_verified(&args.source, db_path)
+}
+
+///
+/// キャッシュデータベースファイルを検証しながらコピーする
+///
+/// # 引数
+/// * `src` - コピー元のパス
+/// * `dst` - コピー先のパス
+///
+/// # 戻り値
+/// 処理が成功した場合はコピーしたデータベースのレコード数を`Ok()`でラップして
+/// 返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 一時ファイルにコピーして検証を行い、検証に成功した場合にのみコピー先へリ
+/// ネームする。
+///
+fn copy_verified(src: &Path, dst: &Path) -> Result<u64> {
+    let mut tmp = dst.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    fs::copy(src, &tmp)?;
+
+    match verify_database(&tmp) {
+        Ok(count) => {
+            fs::rename(&tmp, dst)?;
+            Ok(count)
+        }
+
+        Err(err) => {
+            let _ = fs::remove_file(&tmp);
+            Err(err)
+        }
+    }
+}
+
+///
+/// キャッシュデータベースファイルの整合性を検証する
+///
+/// # 引数
+/// * `path` - 検証するデータベースファイルのパス
+///
+/// # 戻り値
+/// 検証に成功した場合はレコード数を`Ok()`でラップして返す。失敗した場合はエ
+/// ラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 整合性チェックで修復が必要となったファイルも破損とみなす。
+///
+fn verify_database(path: &Path) -> Result<u64> {
+    let mut db = Database::open(path)?;
+
+    if !db.check_integrity()? {
+        return Err(anyhow!("integrity check failed: {}", path.display()));
+    }
+
+    let count = db.begin_read()?.open_table(TABLE)?.len()?;
+
+    Ok(count)
+}
+
+///
+/// キャッシュレコードのキーをボリュームIDと相対パスに分割する
+///
+/// # 引数
+/// * `key` - キャッシュレコードのキー
+///
+/// # 戻り値
+/// ボリュームIDと相対パスのタプル
+///
+fn split_key(key: &str) -> (&str, &str) {
+    key.split_once(':').unwrap_or((key, ""))
+}
+
+///
+/// キャッシュレコードに記録された撮影日付を取得する
+///
+/// # 引数
+/// * `record` - キャッシュレコード
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 撮影日付(記録されていない場合や解析できない場合は`None`)
+///
+/// # 注記
+/// 取り込み時の振り分けと同じ日付となるよう、Exifの撮影日時の書式
+/// (`--datetime-format`を含む)で解析し、`--day-start`を適用する。
+///
+fn record_date(record: &CacheRecord, opts: &Options) -> Option<NaiveDate> {
+    let value = record.exif.datetime_original.as_deref()?;
+    let datetime = parse_datetime(value, opts.datetime_formats()).ok()?;
+
+    Some(shooting_date(&datetime, opts))
+}
+
+///
+/// 日付範囲の文字列をパースする
+///
+/// # 引数
+/// * `range` - FROM..TO形式の日付範囲の文字列(いずれも省略可)
+///
+/// # 戻り値
+/// パースが成功した場合は始点と終点のタプルを`Ok()`でラップして返す。失敗した
+/// 場合はエラー情報を`Err()`でラップして返す。
+///
+pub(crate) fn parse_date_range(range: &str)
+    -> Result<(Option<NaiveDate>, Option<NaiveDate>)>
+{
+    let (from, to) = match range.split_once("..") {
+        Some(pair) => pair,
+        None => {
+            return Err(anyhow!(
+                "invalid date range: {} (expected FROM..TO)",
+                range
+            ));
+        }
+    };
+
+    Ok((parse_date(from)?, parse_date(to)?))
+}
+
+///
+/// 日付範囲の片側の日付文字列をパースする
+///
+/// # 引数
+/// * `date` - YYYY-MM-DD形式の日付文字列(空文字列の場合は指定なし)
+///
+/// # 戻り値
+/// パースが成功した場合は日付を`Ok()`でラップして返す。失敗した場合はエラー情
+/// 報を`Err()`でラップして返す。
+///
+fn parse_date(date: &str) -> Result<Option<NaiveDate>> {
+    if date.is_empty() {
+        return Ok(None);
+    }
+
+    match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => Ok(Some(date)),
+        Err(_) => Err(anyhow!(
+            "invalid date format: {} (expected YYYY-MM-DD)",
+            date
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// テスト用の作業ディレクトリを作成する
+    ///
+    fn work_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("imgdist-maint-{}-{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    ///
+    /// 記録日時とファイルサイズを指定してレコードを作成する
+    ///
+    fn record(timestamp: &str, file_size: u64) -> CacheRecord {
+        CacheRecord {
+            timestamp: timestamp.to_string(),
+            mtime: "2024-01-01T00:00:00+09:00".to_string(),
+            file_size,
+            exif: ExifSummary::default(),
+            destination: None,
+            corrupt: false,
+        }
+    }
+
+    ///
+    /// レコードを書き込んだキャッシュデータベースを作成する
+    ///
+    fn write_db(path: &Path, records: &[(&str, CacheRecord)]) {
+        let db = open_database(path).unwrap();
+        let txn = db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(TABLE).unwrap();
+
+            for (key, record) in records {
+                table.insert(key.to_string(), record).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+    }
+
+    ///
+    /// キャッシュデータベースのレコードのファイルサイズを読み出す
+    ///
+    fn read_sizes(path: &Path) -> BTreeMap<String, u64> {
+        let db = Database::open(path).unwrap();
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+
+        table
+            .iter()
+            .unwrap()
+            .map(|item| {
+                let (key, value) = item.unwrap();
+                (key.value(), value.value().file_size)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merge_keeps_newer_record_on_conflict() {
+        let dir = work_dir("merge");
+        let db_path = dir.join("cache.redb");
+        let source = dir.join("other.redb");
+
+        write_db(&db_path, &[
+            ("vol:older.jpg", record("2024-01-01T00:00:00+09:00", 1)),
+            ("vol:newer.jpg", record("2024-06-01T00:00:00+09:00", 2)),
+            ("vol:same.jpg", record("2024-03-01T00:00:00+09:00", 3)),
+            ("vol:broken.jpg", record("2024-03-01T00:00:00+09:00", 4)),
+        ]);
+
+        write_db(&source, &[
+            ("vol:older.jpg", record("2024-03-01T00:00:00+09:00", 10)),
+            ("vol:newer.jpg", record("2024-03-01T00:00:00+09:00", 20)),
+            ("vol:same.jpg", record("2024-03-01T00:00:00+09:00", 30)),
+            ("vol:broken.jpg", record("not a timestamp", 40)),
+            ("vol:added.jpg", record("2024-03-01T00:00:00+09:00", 50)),
+        ]);
+
+        let stats = merge(&db_path, &MergeArgs { source }).unwrap();
+
+        assert_eq!((stats.added, stats.updated, stats.kept), (1, 1, 3));
+
+        let sizes = read_sizes(&db_path);
+        assert_eq!(sizes["vol:older.jpg"], 10);
+        assert_eq!(sizes["vol:newer.jpg"], 2);
+        assert_eq!(sizes["vol:same.jpg"], 3);
+        assert_eq!(sizes["vol:broken.jpg"], 4);
+        assert_eq!(sizes["vol:added.jpg"], 50);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merge_compares_timestamps_across_offsets() {
+        let dir = work_dir("merge-offset");
+        let db_path = dir.join("cache.redb");
+        let source = dir.join("other.redb");
+
+        // 09:00+09:00は01:00+01:00と同時刻、02:00+01:00はそれより新しい
+        write_db(&db_path, &[
+            ("vol:a.jpg", record("2024-03-01T09:00:00+09:00", 1)),
+            ("vol:b.jpg", record("2024-03-01T09:00:00+09:00", 2)),
+        ]);
+
+        write_db(&source, &[
+            ("vol:a.jpg", record("2024-03-01T01:00:00+01:00", 10)),
+            ("vol:b.jpg", record("2024-03-01T02:00:00+01:00", 20)),
+        ]);
+
+        let stats = merge(&db_path, &MergeArgs { source }).unwrap();
+
+        assert_eq!((stats.added, stats.updated, stats.kept), (0, 1, 1));
+
+        let sizes = read_sizes(&db_path);
+        assert_eq!(sizes["vol:a.jpg"], 1);
+        assert_eq!(sizes["vol:b.jpg"], 20);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restore_replaces_live_database_and_keeps_previous() {
+        let dir = work_dir("restore");
+        let db_path = dir.join("cache.redb");
+        let source = dir.join("backup.redb");
+
+        write_db(&db_path, &[
+            ("vol:live.jpg", record("2024-06-01T00:00:00+09:00", 1)),
+        ]);
+        write_db(&source, &[
+            ("vol:a.jpg", record("2024-03-01T00:00:00+09:00", 10)),
+            ("vol:b.jpg", record("2024-03-01T00:00:00+09:00", 20)),
+        ]);
+
+        let opts = Options::with_args(&["--yes"]);
+        let args = RestoreArgs { source: source.clone() };
+
+        assert_eq!(restore(&db_path, &args, &opts).unwrap(), 2);
+
+        let sizes = read_sizes(&db_path);
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes["vol:a.jpg"], 10);
+
+        let previous = read_sizes(&dir.join("cache.redb.bak"));
+        assert_eq!(previous.len(), 1);
+        assert_eq!(previous["vol:live.jpg"], 1);
+
+        assert!(!dir.join("cache.redb.tmp").exists());
+        assert!(source.is_file());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restore_refuses_database_in_use() {
+        let dir = work_dir("restore-in-use");
+        let db_path = dir.join("cache.redb");
+        let source = dir.join("backup.redb");
+
+        write_db(&db_path, &[
+            ("vol:live.jpg", record("2024-06-01T00:00:00+09:00", 1)),
+        ]);
+        write_db(&source, &[]);
+
+        let live = Database::open(&db_path).unwrap();
+        let opts = Options::with_args(&["--yes"]);
+        let args = RestoreArgs { source };
+
+        assert!(restore(&db_path, &args, &opts).is_err());
+        assert!(!dir.join("cache.redb.bak").exists());
+
+        drop(live);
+        assert_eq!(read_sizes(&db_path)["vol:live.jpg"], 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restore_keeps_live_database_on_bad_backup() {
+        let dir = work_dir("restore-bad");
+        let db_path = dir.join("cache.redb");
+        let source = dir.join("backup.redb");
+
+        write_db(&db_path, &[
+            ("vol:live.jpg", record("2024-06-01T00:00:00+09:00", 1)),
+        ]);
+        fs::write(&source, b"not a database").unwrap();
+
+        let opts = Options::with_args(&["--yes"]);
+        let args = RestoreArgs { source };
+
+        assert!(restore(&db_path, &args, &opts).is_err());
+        assert_eq!(read_sizes(&db_path)["vol:live.jpg"], 1);
+        assert!(!dir.join("cache.redb.tmp").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn restore_without_confirmation_keeps_live_database() {
+        let dir = work_dir("restore-no-input");
+        let db_path = dir.join("cache.redb");
+        let source = dir.join("backup.redb");
+
+        write_db(&db_path, &[
+            ("vol:live.jpg", record("2024-06-01T00:00:00+09:00", 1)),
+        ]);
+        write_db(&source, &[]);
+
+        let opts = Options::with_args(&["--no-input"]);
+        let args = RestoreArgs { source };
+
+        assert!(restore(&db_path, &args, &opts).is_err());
+        assert_eq!(read_sizes(&db_path)["vol:live.jpg"], 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merge_refuses_same_database() {
+        let dir = work_dir("merge-self");
+        let db_path = dir.join("cache.redb");
+
+        write_db(&db_path, &[]);
+
+        let args = MergeArgs { source: db_path.clone() };
+        assert!(merge(&db_path, &args).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
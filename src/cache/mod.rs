@@ -0,0 +1,2886 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! キャッシュデータベースを扱うモジュール
+//!
+
+mod exif_cache;
+mod history;
+mod maintenance;
+
+use std::fs::{File, Metadata};
+use std::io::{BufRead, BufReader};
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use exif::{Context, Exif, Tag};
+use fnv::FnvHasher;
+use log::{debug, info, warn};
+use redb::backends::InMemoryBackend;
+use redb::{
+    Database, DatabaseError, ReadTransaction, ReadableTable, StorageError,
+    TableDefinition, TypeName, Value, WriteTransaction,
+};
+use serde::{Deserialize, Serialize};
+use std::hash::Hasher;
+
+use crate::checksum;
+use crate::class_registry::ClassRegistry;
+use crate::cmd_args::{CacheEvalMode, ChecksumAlgorithm};
+use crate::error::ImportError;
+use crate::report::FileTimings;
+use crate::video;
+use crate::{is_jpeg_file, is_raw_file, is_video_file};
+
+pub(crate) use exif_cache::ExifCache;
+pub(crate) use history::History;
+pub(crate) use maintenance::{parse_date_range, run_command, summaries};
+
+/// キャッシュテーブルの定義
+const TABLE: TableDefinition<String, CacheRecord> =
+    TableDefinition::new("cache");
+
+/// 内容のハッシュ値の索引テーブルの定義(SHA-256の16進表記からキャッシュのキー
+/// を引く)
+const HASH_TABLE: TableDefinition<String, String> =
+    TableDefinition::new("hash");
+
+/// 振り分け先に格納したファイルの内容のハッシュ値の索引テーブルの定義(ハッシ
+/// ュ値から格納先のパスを引く)
+const ARCHIVE_TABLE: TableDefinition<String, String> =
+    TableDefinition::new("archive");
+
+/// キャッシュ評価の累計のテーブルの定義(キーは`hits`または`misses`)
+const STATS_TABLE: TableDefinition<String, u64> =
+    TableDefinition::new("stats");
+
+/// メタデータのテーブルの定義(ボリュームIDの移行済みの記録等)
+#[cfg(any(target_os = "windows", test))]
+const META_TABLE: TableDefinition<String, String> =
+    TableDefinition::new("meta");
+
+/// ボリュームIDの移行済みの記録のキーの接頭辞(続けてシリアル番号を付与し、
+/// 値には移行先のボリュームIDを格納する)
+#[cfg(any(target_os = "windows", test))]
+const MIGRATED_KEY: &str = "volume_id_migrated";
+
+/// 振り分け先が記録されたレコードの一覧(入力元のボリュームのものの振り分け
+/// 先とExif情報の抜粋の組の一覧と、他のボリュームのものの振り分け先の一覧)
+pub(crate) type Destinations = (Vec<(PathBuf, ExifSummary)>, Vec<PathBuf>);
+
+/// ImageNumberのタグ(kamadak-exifに定義が無いため自前で定義)
+const TAG_IMAGE_NUMBER: u16 = 0x9211;
+
+///
+/// 処理済みファイル情報
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CacheRecord {
+    /// キャッシュに記録した日時（秒単位、ISO8601）
+    timestamp: String,
+
+    /// mtime（秒単位、ISO8601）
+    mtime: String,
+
+    /// ファイルサイズ
+    file_size: u64,
+
+    /// 抜粋したExif情報
+    exif: ExifSummary,
+
+    /// 振り分け先のパス(名前の付け替えを適用したもの、コピーした場合のみ)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    destination: Option<PathBuf>,
+
+    /// 記録が壊れていて読み出せなかったか否か(データベースには記録しない)
+    #[serde(skip)]
+    corrupt: bool,
+}
+
+impl CacheRecord {
+    ///
+    /// インスタンスを構築する
+    ///
+    /// # 引数
+    /// * `mtime` - mtime（ISO8601、秒精度）
+    /// * `file_size` - ファイルサイズ
+    /// * `exif` - Exif情報のサマリ
+    ///
+    /// # 戻り値
+    /// 構築された`CacheRecord`
+    ///
+    fn new(mtime: String, file_size: u64, exif: ExifSummary) -> Result<Self> {
+        let timestamp = format_iso8601(truncate_system_time(SystemTime::now())?)?;
+
+        Ok(Self {
+            timestamp,
+            mtime,
+            file_size,
+            exif,
+            destination: None,
+            corrupt: false,
+        })
+    }
+
+    ///
+    /// 読み出せなかったレコードの代わりとするインスタンスを構築する
+    ///
+    /// # 戻り値
+    /// 壊れていることを示す`CacheRecord`
+    ///
+    /// # 注記
+    /// mtimeとファイルサイズが何れのファイルとも一致しないため、評価では常に
+    /// ミスとなり、取り込み時のコミットで正しいレコードに置き換えられる。
+    ///
+    fn corrupted() -> Self {
+        Self {
+            timestamp: String::new(),
+            mtime: String::new(),
+            file_size: 0,
+            exif: ExifSummary::default(),
+            destination: None,
+            corrupt: true,
+        }
+    }
+}
+
+// Valueトレイトの実装
+impl Value for CacheRecord {
+    type SelfType<'a> = Self;
+    type AsBytes<'a> = String;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a
+    {
+        match serde_json::from_slice::<Self>(data) {
+            Ok(record) => record,
+            Err(err) => {
+                warn!("broken cache record ignored: {}", err);
+                Self::corrupted()
+            }
+        }
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b
+    {
+        serde_json::to_string(value).expect("JSON serialize failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("CacheRecord")
+    }
+}
+
+///
+/// Exif情報の抜粋
+///
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct ExifSummary {
+    /// DateTimeOriginal
+    pub(crate) datetime_original: Option<String>,
+
+    /// Make/Model
+    pub(crate) make_model: Option<String>,
+
+    /// CameraSerialNumber/BodySerialNumber
+    pub(crate) camera_serial: Option<String>,
+
+    /// ExifImageUniqueID
+    pub(crate) image_unique_id: Option<String>,
+
+    /// ImageWidth/Height
+    pub(crate) image_dimensions: Option<String>,
+
+    /// ImageNumber(シャッター回数)
+    pub(crate) image_number: Option<u32>,
+
+    /// Exif情報を代用したペアのJPEGファイルの名前(RAWファイルのExif情報が
+    /// 読めなかった場合のみ)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) paired_exif: Option<String>,
+}
+
+impl ExifSummary {
+    ///
+    /// 抜粋したExif情報からハッシュ値を計算する
+    ///
+    /// # 戻り値
+    /// FNV1 64bitによるハッシュ値
+    ///
+    /// # 注記
+    /// 既存のキャッシュレコードとの互換性を保つため、ImageNumberはハッシュ値の
+    /// 計算に含めない。
+    ///
+    fn calc_hash(&self) -> u64 {
+        let null = "".to_string();
+        let s = format!(
+            "{}:{}:{}:{}:{}",
+            self.datetime_original.as_ref().unwrap_or(&null),
+            self.make_model.as_ref().unwrap_or(&null),
+            self.camera_serial.as_ref().unwrap_or(&null),
+            self.image_unique_id.as_ref().unwrap_or(&null),
+            self.image_dimensions.as_ref().unwrap_or(&null),
+        );
+
+        let mut hasher = FnvHasher::default();
+        hasher.write(s.as_bytes());
+        hasher.finish()
+    }
+}
+
+// トレイトFrom<&Exif>の実装
+impl From<&Exif> for ExifSummary {
+    fn from(value: &Exif) -> Self {
+        let datetime_original = value
+            .get_field(Tag::DateTimeOriginal, exif::In::PRIMARY)
+            .map(|field| field.display_value().to_string());
+
+        let make = value
+            .get_field(Tag::Make, exif::In::PRIMARY)
+            .map(|field| field.display_value().to_string());
+
+        let model = value
+            .get_field(Tag::Model, exif::In::PRIMARY)
+            .map(|field| field.display_value().to_string());
+
+        let make_model = match (make, model) {
+            (Some(make), Some(model)) => Some(format!("{}/{}", make, model)),
+            (Some(make), None) => Some(make),
+            (None, Some(model)) => Some(model),
+            (None, None) => None,
+        };
+
+        let camera_serial = value
+            .get_field(Tag::BodySerialNumber, exif::In::PRIMARY)
+            .map(|field| field.display_value().to_string());
+
+        let image_unique_id = value
+            .get_field(Tag::ImageUniqueID, exif::In::PRIMARY)
+            .map(|field| field.display_value().to_string());
+
+        let width = value
+            .get_field(Tag::PixelXDimension, exif::In::PRIMARY)
+            .map(|field| field.display_value().to_string());
+
+        let height = value
+            .get_field(Tag::PixelYDimension, exif::In::PRIMARY)
+            .map(|field| field.display_value().to_string());
+
+        let image_dimensions = match (width, height) {
+            (Some(w), Some(h)) => Some(format!("{}x{}", w, h)),
+            _ => None,
+        };
+
+        // カメラによってIFD0とExif IFDのいずれかに記録される
+        let image_number = [Context::Tiff, Context::Exif]
+            .into_iter()
+            .find_map(|ctx| {
+                value.get_field(Tag(ctx, TAG_IMAGE_NUMBER), exif::In::PRIMARY)
+            })
+            .and_then(|field| field.value.get_uint(0));
+
+        Self {
+            datetime_original,
+            make_model,
+            camera_serial,
+            image_unique_id,
+            image_dimensions,
+            image_number,
+            paired_exif: None,
+        }
+    }
+}
+
+
+///
+/// キャッシュ判定の結果
+///
+pub(crate) enum CacheDecision {
+    /// キャッシュヒット
+    Hit,
+
+    /// キャッシュミスまたは差分あり（コピー・コミットが必要）
+    Miss { handle: TxnHandle, exif: Rc<Exif> },
+}
+
+///
+/// キャッシュとの差分の種別
+///
+#[derive(Debug)]
+pub(crate) enum DiffStatus {
+    /// キャッシュに記録されていない
+    New,
+
+    /// キャッシュの記録と異なる（異なる項目の名前）
+    Changed(Vec<&'static str>),
+
+    /// キャッシュの記録と一致する
+    Unchanged,
+}
+
+///
+/// ファイルに対応するキャッシュの参照情報
+///
+#[derive(Debug)]
+pub(crate) struct CacheTrace {
+    /// ボリュームID
+    pub(crate) volume_id: String,
+
+    /// ボリュームプレフィクス
+    pub(crate) volume_prefix: PathBuf,
+
+    /// ボリュームプレフィクスからの相対パス
+    pub(crate) rel_path: PathBuf,
+
+    /// キャッシュデータのキー
+    pub(crate) key: String,
+
+    /// 記録されているキャッシュデータ(JSON形式、記録が無い場合はNone)
+    pub(crate) record: Option<String>,
+}
+
+///
+/// コミット用ハンドル
+///
+pub(crate) struct TxnHandle {
+    rel_path: PathBuf,
+    record: CacheRecord,
+    hash: Option<String>,
+    archive_hash: Option<String>,
+}
+
+impl TxnHandle {
+    ///
+    /// ハンドルの相対パスの参照を返す
+    ///
+    /// # 戻り値
+    /// キャッシュキーのバイト列
+    ///
+    fn rel_path<'a>(&'a self) -> &'a PathBuf {
+        &self.rel_path
+    }
+
+    ///
+    /// ハンドルのレコードの参照を返す
+    ///
+    /// # 戻り値
+    /// キャッシュ値のバイト列
+    ///
+    fn record<'a>(&'a self) -> &'a CacheRecord {
+        &self.record
+    }
+
+    ///
+    /// Exif情報を代用したペアのJPEGファイルの名前を返す
+    ///
+    /// # 戻り値
+    /// RAWファイルのExif情報の代わりにペアのJPEGファイルのものを用いた場合は
+    /// そのファイル名
+    ///
+    pub(crate) fn paired_exif(&self) -> Option<&str> {
+        self.record.exif.paired_exif.as_deref()
+    }
+
+    ///
+    /// 振り分け先のパスを設定する
+    ///
+    /// # 引数
+    /// * `path` - コピーしたファイルの振り分け先でのパス
+    ///
+    /// # 注記
+    /// コミット時にキャッシュレコードに記録される。
+    ///
+    pub(crate) fn set_destination(&mut self, path: &Path) {
+        self.record.destination = Some(path.to_path_buf());
+    }
+}
+
+///
+/// キャッシュデータベースを管理する構造体
+///
+#[derive(Debug)]
+pub struct Cache {
+    /// redbデータベース
+    db: Database,
+
+    /// キャッシュ評価モード
+    eval_mode: CacheEvalMode,
+
+    /// ボリュームID
+    volume_id: String,
+
+    /// ボリュームプレフィクス
+    volume_prefix: PathBuf,
+
+    /// ボリュームのラベル（取得できない場合はNone）
+    volume_label: Option<String>,
+
+    /// 読み出しに用いているシャドウコピーのルート（Windowsのみ）
+    shadow_root: Mutex<Option<PathBuf>>,
+
+    /// データベースに未反映のキャッシュヒット数
+    hits: AtomicU64,
+
+    /// データベースに未反映のキャッシュミス数
+    misses: AtomicU64,
+
+    /// 予行演習か否か（`true`の場合はコミットを行わない）
+    dry_run: bool,
+}
+
+impl Cache {
+    ///
+    /// キャッシュデータベースを開く
+    ///
+    /// # 引数
+    /// * `path` - データベースファイルのパス
+    /// * `eval_mode` - キャッシュ評価モード
+    ///
+    /// # 戻り値
+    /// 初期化済みの`Cache`構造体
+    ///
+    pub(crate) fn open<P>(db_path: P, eval_mode: CacheEvalMode, input_path: P)
+        -> Result<Self>
+    where 
+        P: AsRef<Path>
+    {
+        /*
+         * データベースのオープン
+         */
+        let db = open_or_recover_database(db_path.as_ref())?;
+
+        /*
+         * 入力パスのボリューム情報の取得
+         */
+        let volume_id = get_volume_id(&input_path)?;
+        let volume_prefix = get_volume_prefix(&input_path)?;
+        let volume_label = volume_label(&input_path);
+
+        /*
+         * ボリュームシリアル番号によるキーの移行（Windowsのみ）
+         */
+        #[cfg(target_os = "windows")]
+        if let Ok(serial) = windows_volume_serial(input_path.as_ref()) {
+            if serial != volume_id {
+                migrate_volume_id(&db, &serial, &volume_id)?;
+            }
+        }
+
+        debug!("volume_id: {} , volume_prefix: {}", volume_id, volume_prefix.display());
+
+        Ok(Self {
+            db,
+            eval_mode,
+            volume_id,
+            volume_prefix,
+            volume_label,
+            shadow_root: Mutex::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            dry_run: false,
+        })
+    }
+
+    ///
+    /// 予行演習用にキャッシュデータベースを開く
+    ///
+    /// # 引数
+    /// * `db_path` - データベースファイルのパス
+    /// * `eval_mode` - キャッシュ評価モード
+    /// * `input_path` - 入力ディレクトリのパス
+    ///
+    /// # 戻り値
+    /// 初期化済みの`Cache`構造体
+    ///
+    /// # 注記
+    /// データベースファイルへの書き込みは一切行わない。データベースはメモリ上
+    /// に作成し、データベースファイルが存在する場合はその一時的な複製から内容
+    /// を読み込む(データベースファイル自体は開かない)。存在しない場合は空のま
+    /// まとする(全てのファイルがミスとなる)。コミットはコミットする予定のキー
+    /// をログに出力するのみとなる。ボリュームシリアル番号によるキーの移行も行
+    /// わない。
+    ///
+    pub(crate) fn open_dry_run<P>(
+        db_path: P,
+        eval_mode: CacheEvalMode,
+        input_path: P,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>
+    {
+        let db = Database::builder()
+            .create_with_backend(InMemoryBackend::new())?;
+        create_tables(&db)?;
+
+        if db_path.as_ref().exists() {
+            load_snapshot(&db, db_path.as_ref())?;
+        }
+
+        let volume_id = get_volume_id(&input_path)?;
+        let volume_prefix = get_volume_prefix(&input_path)?;
+        let volume_label = volume_label(&input_path);
+
+        debug!(
+            "volume_id: {} , volume_prefix: {} (dry run)",
+            volume_id,
+            volume_prefix.display()
+        );
+
+        Ok(Self {
+            db,
+            eval_mode,
+            volume_id,
+            volume_prefix,
+            volume_label,
+            shadow_root: Mutex::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            dry_run: true,
+        })
+    }
+
+    ///
+    /// ボリューム情報を指定してキャッシュデータベースを開く
+    ///
+    /// # 引数
+    /// * `db_path` - データベースファイルのパス
+    /// * `eval_mode` - キャッシュ評価モード
+    /// * `volume_id` - ボリュームID
+    /// * `volume_prefix` - ボリュームプレフィクス
+    ///
+    /// # 戻り値
+    /// 初期化済みの`Cache`構造体
+    ///
+    /// # 注記
+    /// iOSデバイスのようにファイルシステムからボリューム情報を取得できない入力
+    /// 元に対して使用する。ボリュームのラベルは無いものとして扱う。
+    ///
+    pub(crate) fn open_volume<P>(
+        db_path: P,
+        eval_mode: CacheEvalMode,
+        volume_id: String,
+        volume_prefix: PathBuf,
+    ) -> Result<Self>
+    where
+        P: AsRef<Path>
+    {
+        let db = open_or_recover_database(db_path.as_ref())?;
+
+        debug!(
+            "volume_id: {} , volume_prefix: {}",
+            volume_id,
+            volume_prefix.display()
+        );
+
+        Ok(Self {
+            db,
+            eval_mode,
+            volume_id,
+            volume_prefix,
+            volume_label: None,
+            shadow_root: Mutex::new(None),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            dry_run: false,
+        })
+    }
+
+    ///
+    /// ボリュームIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// キャッシュのキーに用いる入力元のボリュームID
+    ///
+    pub(crate) fn volume_id(&self) -> &str {
+        &self.volume_id
+    }
+
+    ///
+    /// ボリュームプレフィクスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力パスのボリュームのマウントポイント
+    ///
+    pub(crate) fn volume_prefix(&self) -> &Path {
+        &self.volume_prefix
+    }
+
+    ///
+    /// ボリュームのラベルへのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力パスのボリュームのラベル（取得できない場合はNone）
+    ///
+    pub(crate) fn volume_label(&self) -> Option<&str> {
+        self.volume_label.as_deref()
+    }
+
+    ///
+    /// 読み出しに用いるシャドウコピーのルートを設定する
+    ///
+    /// # 引数
+    /// * `root` - シャドウコピーのルート（解除する場合はNone）
+    ///
+    /// # 注記
+    /// 設定されている間は、シャドウコピー上のパスをボリュームプレフィクスから
+    /// の相対パスに読み替えて評価する(キャッシュのキーは元のファイルと同じもの
+    /// となる)。
+    ///
+    pub(crate) fn set_shadow_root(&self, root: Option<PathBuf>) {
+        *self.shadow_root.lock().unwrap() = root;
+    }
+
+    ///
+    /// ボリュームプレフィクスからの相対パスを求める
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は相対パスを`Ok()`でラップして返す。失敗した場合はエ
+    /// ラー情報を`Err()`でラップして返す。
+    ///
+    fn relative_path(&self, path: &Path) -> Result<PathBuf> {
+        if let Some(root) = self.shadow_root.lock().unwrap().as_ref() {
+            if let Ok(rel_path) = path.strip_prefix(root) {
+                return Ok(rel_path.to_path_buf());
+            }
+        }
+
+        let abs_path = path.canonicalize()?;
+        Ok(abs_path.strip_prefix(&self.volume_prefix)?.to_path_buf())
+    }
+
+    ///
+    /// コミット用ハンドルを構築する
+    ///
+    /// # 引数
+    /// * `volume_id` - ボリュームID
+    /// * `rel_path` - 相対パス
+    /// * `meta` - 現在のファイル情報
+    ///
+    /// # 戻り値
+    /// コミット用ハンドル
+    ///
+    fn build_handle(&self, rel_path: PathBuf, record: CacheRecord,)
+        -> Result<TxnHandle>
+    {
+        Ok(TxnHandle {rel_path, record, hash: None, archive_hash: None})
+    }
+
+    ///
+    /// キャッシュの更新をコミットする
+    ///
+    /// # 引数
+    /// * `handle` - コミット用ハンドル
+    ///
+    /// # 戻り値
+    /// コミット結果
+    ///
+    /// # 注記
+    /// 予行演習の場合は書き込みを行わず、コミットする予定のキーをログに出力す
+    /// る。
+    ///
+    pub(crate) fn commit(&self, handle: TxnHandle) -> Result<()> {
+        if self.dry_run {
+            info!(
+                "would commit cache entry: {}",
+                build_key(&self.volume_id, handle.rel_path())
+            );
+            return Ok(());
+        }
+
+        let archive = handle
+            .archive_hash
+            .as_deref()
+            .zip(handle.record.destination.as_deref());
+
+        self.put_cache_record(
+            handle.rel_path(),
+            handle.record(),
+            handle.hash.as_deref(),
+            archive,
+        ).map_err(|err| {
+            ImportError::Cache(format!("commit failed: {}", err)).into()
+        })
+    }
+
+    ///
+    /// 内容のハッシュ値が一致するファイルを索引から探す
+    ///
+    /// # 引数
+    /// * `handle` - キャッシュの評価で得たコミット用ハンドル
+    /// * `path` - 対象ファイルのパス
+    /// * `algo` - ハッシュ値を求めるハッシュ関数
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は、内容が一致する処理済みのファイルがあればそのキャッ
+    /// シュのキーを、無ければNoneを`Ok()`でラップして返す。失敗した場合はエラー
+    /// 情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 求めたハッシュ値はハンドルに保持し、コミット時に索引に登録する。一致し
+    /// たファイルに振り分け先が記録されている場合はハンドルにも引き継ぐ。索引
+    /// が指すレコードが削除されている場合や、ファイルサイズが異なる場合(ハッ
+    /// シュ値の衝突)は一致しなかったものとして扱う。
+    /// SHA-256以外のハッシュ値は関数名を前置して索引に登録する(異なる関数で求
+    /// めた値を取り違えないため)。
+    ///
+    pub(crate) fn find_by_hash<P>(
+        &self,
+        handle: &mut TxnHandle,
+        path: P,
+        algo: ChecksumAlgorithm,
+    ) -> Result<Option<String>>
+    where
+        P: AsRef<Path>,
+    {
+        let hash = content_hash(path.as_ref(), algo)?;
+        let own_key = build_key(&self.volume_id, handle.rel_path());
+
+        let txn = self.db.begin_read()?;
+        let found = {
+            let index = txn.open_table(HASH_TABLE)?;
+            let table = txn.open_table(TABLE)?;
+
+            match index.get(&hash)?.map(|key| key.value()) {
+                Some(key) if key != own_key => table
+                    .get(&key)?
+                    .map(|data| (key, data.value()))
+                    .filter(|(_, data)| {
+                        data.file_size == handle.record.file_size
+                    }),
+                _ => None,
+            }
+        };
+
+        handle.hash = Some(hash);
+
+        Ok(found.map(|(key, data)| {
+            handle.record.destination = data.destination;
+            key
+        }))
+    }
+
+    ///
+    /// 内容が一致するファイルが振り分け先に格納済みか否かを索引から探す
+    ///
+    /// # 引数
+    /// * `handle` - キャッシュの評価で得たコミット用ハンドル
+    /// * `path` - 対象ファイルのパス
+    /// * `algo` - ハッシュ値を求めるハッシュ関数
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は、内容が一致する格納済みのファイルがあればそのパス
+    /// を、無ければNoneを`Ok()`でラップして返す。失敗した場合はエラー情報を
+    /// `Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 求めたハッシュ値はハンドルに保持し、コミット時に振り分け先のパスと共に
+    /// 索引に登録する。`find_by_hash()`で同じハッシュ関数によるハッシュ値を求
+    /// め済みの場合はそれを用いる。索引が指すファイルが振り分け先から削除され
+    /// ている場合や、ファイルサイズが異なる場合(ハッシュ値の衝突)は一致しな
+    /// かったものとして扱う。圧縮や暗号化をして格納したファイル(拡張子が入力
+    /// ファイルと異なるもの)はサイズを比較できないため、ハッシュ値のみで判定
+    /// する。
+    ///
+    pub(crate) fn find_in_archive<P>(
+        &self,
+        handle: &mut TxnHandle,
+        path: P,
+        algo: ChecksumAlgorithm,
+    ) -> Result<Option<PathBuf>>
+    where
+        P: AsRef<Path>,
+    {
+        let hash = match &handle.hash {
+            Some(hash) => hash.clone(),
+            None => content_hash(path.as_ref(), algo)?,
+        };
+
+        let txn = self.db.begin_read()?;
+        let found = txn
+            .open_table(ARCHIVE_TABLE)?
+            .get(&hash)?
+            .map(|path| PathBuf::from(path.value()))
+            .filter(|stored| match stored.metadata() {
+                Ok(meta) if meta.is_file() => {
+                    !same_extension(stored, path.as_ref())
+                        || meta.len() == handle.record.file_size
+                }
+                _ => false,
+            });
+
+        handle.archive_hash = Some(hash);
+
+        Ok(found)
+    }
+
+    ///
+    /// キャッシュレコードを読み出す
+    ///
+    /// # 引数
+    /// * `rel_path` - 相対パス
+    ///
+    /// # 戻り値
+    /// 見つかった場合はレコードを返し、見つからなければ`None`を返す
+    ///
+    fn get_cache_record(&self, rel_path: &Path) -> Result<Option<CacheRecord>> {
+        let txn = self.db.begin_read()?;
+        {
+            let table = txn.open_table(TABLE)?;
+            let key = build_key(&self.volume_id, &rel_path);
+
+            Ok(table.get(&key)?.map(|data| data.value()))
+        }
+    }
+
+    ///
+    /// ファイルの振り分け先として記録されたパスを読み出す
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は振り分け先のパス(記録が無い場合はNone)を`Ok()`で
+    /// ラップして返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    pub(crate) fn destination_of<P>(&self, path: P) -> Result<Option<PathBuf>>
+    where
+        P: AsRef<Path>,
+    {
+        let rel_path = self.relative_path(path.as_ref())?;
+
+        Ok(self.get_cache_record(&rel_path)?
+            .filter(|record| !record.corrupt)
+            .and_then(|record| record.destination))
+    }
+
+    ///
+    /// 振り分け先が記録されたレコードを列挙する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は、入力元のボリュームのレコードの振り分け先のパスと
+    /// Exif情報の抜粋の組の一覧と、他のボリュームのレコードの振り分け先のパス
+    /// の一覧の組を`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`
+    /// でラップして返す。
+    ///
+    pub(crate) fn destinations(&self) -> Result<Destinations> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(TABLE)?;
+        let prefix = format!("{}:", self.volume_id);
+        let mut own = Vec::new();
+        let mut others = Vec::new();
+
+        for item in table.iter()? {
+            let (key, value) = item?;
+            let record = value.value();
+
+            if record.corrupt {
+                continue;
+            }
+
+            let Some(destination) = record.destination else {
+                continue;
+            };
+
+            if key.value().starts_with(&prefix) {
+                own.push((destination, record.exif));
+            } else {
+                others.push(destination);
+            }
+        }
+
+        Ok((own, others))
+    }
+
+    ///
+    /// キャッシュレコードを書き込む
+    ///
+    /// # 引数
+    /// * `rel_path` - 相対パス
+    /// * `data` - 書き込むキャッシュレコード
+    /// * `hash` - 索引に登録する内容のハッシュ値(登録しない場合はNone)
+    ///
+    /// # 戻り値
+    /// 書き込み結果
+    ///
+    fn put_cache_record(
+        &self,
+        rel_path: &Path,
+        data: &CacheRecord,
+        hash: Option<&str>,
+        archive: Option<(&str, &Path)>,
+    ) -> Result<()> {
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(TABLE)?;
+            let key = build_key(&self.volume_id, &rel_path);
+
+            table.insert(&key, data)?;
+
+            if let Some(hash) = hash {
+                let mut index = txn.open_table(HASH_TABLE)?;
+                index.insert(hash.to_string(), &key)?;
+            }
+
+            if let Some((hash, path)) = archive {
+                let mut index = txn.open_table(ARCHIVE_TABLE)?;
+                index.insert(hash.to_string(), path.display().to_string())?;
+            }
+        }
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    ///
+    /// キャッシュをモードに応じて評価し、必要ならハンドルを返す
+    ///
+    /// # 引数
+    /// * `volume_id` - ボリュームID
+    /// * `rel_path` - 相対パス
+    /// * `file_size` - ファイルサイズ
+    /// * `mtime` - mtime
+    /// * `exif_loader` - Exif情報と撮影日時を遅延取得するクロージャ
+    /// * `exif_cache` - 読み出したExif情報のキャッシュ
+    /// * `timings` - Exif情報の読み出し時間の記録先
+    ///
+    /// # 戻り値
+    /// ヒットまたはコピー・コミットが必要な場合のハンドル
+    ///
+    pub(crate) fn evaluate<P>(
+        &self,
+        path: P,
+        meta: Metadata,
+        exif_cache: &ExifCache,
+        timings: &mut FileTimings,
+    ) -> Result<CacheDecision>
+    where
+        P: AsRef<Path>,
+    {
+        let rel_path = &self.relative_path(path.as_ref())?;
+        let mtime = format_iso8601(meta.modified()?)?;
+
+        /*
+         * キャッシュ情報を読み出してファイルの更新状況を判断
+         *   ヒット→変化無し
+         *   ミス→変化有り
+         */
+        match self.get_cache_record(&rel_path)? {
+            Some(data) => {
+                // キャッシュデータがある場合はヒットかミスかを判断
+                if data.file_size == meta.len() && data.mtime == mtime {
+                    match self.eval_mode {
+                        // Shallowの場合は、サイズとmtimeの一致のみでヒット
+                        CacheEvalMode::Shallow => return Ok(self.hit()),
+
+                        // Strictの場合はサイズとmtimeの一致に加え、Exif情報の
+                        // 一致で判断
+                        CacheEvalMode::Strict => {
+                            // Exifを読み出してハッシュ値をチェック(読み出し
+                            // たExifはキャッシュに保持されるため、ミスの場合
+                            // は後でそれを使う)
+                            let (_, summary) = timed_read_exif(
+                                &path,
+                                exif_cache,
+                                timings,
+                            )?;
+                            if summary.calc_hash() == data.exif.calc_hash() {
+                                return Ok(self.hit());
+                            }
+                        }
+                    }
+                }
+            }
+
+            None => {
+                // キャッシュデータが存在しない場合はミスと判断
+            }
+        }
+
+        /*
+         * キャッシュミスの場合のフォールバック (キャッシュ情報を更新)
+         */
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        self.build_miss(path, rel_path, mtime, &meta, exif_cache, timings)
+    }
+
+    ///
+    /// キャッシュヒットを計上する
+    ///
+    /// # 戻り値
+    /// ヒットを示す判定結果
+    ///
+    fn hit(&self) -> CacheDecision {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        CacheDecision::Hit
+    }
+
+    ///
+    /// キャッシュ評価の件数をデータベースの累計に反映する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    /// # 注記
+    /// 反映した件数はインスタンス上の計数から差し引く。予行演習の場合は何もし
+    /// ない。累計は`cache stats`サブコマンドで参照する。
+    ///
+    pub(crate) fn flush_stats(&self) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let hits = self.hits.swap(0, Ordering::Relaxed);
+        let misses = self.misses.swap(0, Ordering::Relaxed);
+
+        if hits == 0 && misses == 0 {
+            return Ok(());
+        }
+
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(STATS_TABLE)?;
+
+            for (key, count) in [("hits", hits), ("misses", misses)] {
+                let key = key.to_string();
+                let total = table.get(&key)?.map_or(0, |value| value.value());
+
+                table.insert(&key, total + count)?;
+            }
+        }
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    ///
+    /// ファイルに対応するキャッシュの参照情報を取得する
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は参照情報を`Ok()`でラップして返す。失敗した場合はエ
+    /// ラー情報を`Err()`でラップして返す。
+    ///
+    pub(crate) fn trace<P>(&self, path: P) -> Result<CacheTrace>
+    where
+        P: AsRef<Path>,
+    {
+        let abs_path = path.as_ref().canonicalize()?;
+        let rel_path = abs_path.strip_prefix(&self.volume_prefix)?;
+
+        let record = match self.get_cache_record(rel_path)? {
+            Some(record) => Some(serde_json::to_string(&record)?),
+            None => None,
+        };
+
+        Ok(CacheTrace {
+            volume_id: self.volume_id.clone(),
+            volume_prefix: self.volume_prefix.clone(),
+            rel_path: rel_path.to_path_buf(),
+            key: build_key(&self.volume_id, rel_path),
+            record,
+        })
+    }
+
+    ///
+    /// キャッシュの記録とファイルの差分を求める
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    /// * `meta` - 現在のファイル情報
+    /// * `exif_cache` - 読み出したExif情報のキャッシュ
+    ///
+    /// # 戻り値
+    /// 差分の種別
+    ///
+    /// # 注記
+    /// 判定は`evaluate()`と同じ基準で行い、キャッシュの更新は行わない。Exif情
+    /// 報の比較はキャッシュ評価モードが"strict"で、かつサイズとmtimeが一致する
+    /// 場合にのみ行う。
+    ///
+    pub(crate) fn diff<P>(
+        &self,
+        path: P,
+        meta: &Metadata,
+        exif_cache: &ExifCache,
+    ) -> Result<DiffStatus>
+    where
+        P: AsRef<Path>,
+    {
+        let abs_path = path.as_ref().canonicalize()?;
+        let rel_path = abs_path.strip_prefix(&self.volume_prefix)?;
+        let mtime = format_iso8601(meta.modified()?)?;
+
+        let data = match self.get_cache_record(rel_path)? {
+            Some(data) => data,
+            None => return Ok(DiffStatus::New),
+        };
+
+        /*
+         * 記録内容との比較
+         */
+        let mut reasons = Vec::new();
+
+        if data.file_size != meta.len() {
+            reasons.push("size");
+        }
+
+        if data.mtime != mtime {
+            reasons.push("mtime");
+        }
+
+        let strict = matches!(self.eval_mode, CacheEvalMode::Strict);
+
+        if reasons.is_empty() && strict {
+            let (_, summary) = exif_cache.read(&path)?;
+
+            if summary.calc_hash() != data.exif.calc_hash() {
+                reasons.push("exif");
+            }
+        }
+
+        if reasons.is_empty() {
+            Ok(DiffStatus::Unchanged)
+        } else {
+            Ok(DiffStatus::Changed(reasons))
+        }
+    }
+
+    ///
+    /// キャッシュ情報を無視して常にキャッシュミスとして評価する
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    /// * `meta` - 現在のファイル情報
+    /// * `exif_cache` - 読み出したExif情報のキャッシュ
+    /// * `timings` - Exif情報の読み出し時間の記録先
+    ///
+    /// # 戻り値
+    /// コピー・コミットを行うためのハンドル
+    ///
+    /// # 注記
+    /// 強制再処理モードで用いる。キャッシュ情報の読み出しは行わない。
+    ///
+    pub(crate) fn evaluate_forced<P>(
+        &self,
+        path: P,
+        meta: Metadata,
+        exif_cache: &ExifCache,
+        timings: &mut FileTimings,
+    ) -> Result<CacheDecision>
+    where
+        P: AsRef<Path>,
+    {
+        let rel_path = &self.relative_path(path.as_ref())?;
+        let mtime = format_iso8601(meta.modified()?)?;
+
+        self.build_miss(path, rel_path, mtime, &meta, exif_cache, timings)
+    }
+
+    ///
+    /// Exif情報を用いずにキャッシュ情報を評価する
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    /// * `meta` - 現在のファイル情報
+    /// * `forced` - キャッシュ情報を無視するか否か
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は、キャッシュにヒットした場合はNone、ミスした場合は
+    /// コミット用ハンドルを`Ok()`でラップして返す。失敗した場合はエラー情報を
+    /// `Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 画像以外のファイル(Exif情報を持たないもの)に用いる。キャッシュ評価モー
+    /// ドによらず、サイズとmtimeの一致のみでヒットと判断する。
+    ///
+    pub(crate) fn evaluate_plain<P>(
+        &self,
+        path: P,
+        meta: &Metadata,
+        forced: bool,
+    ) -> Result<Option<TxnHandle>>
+    where
+        P: AsRef<Path>,
+    {
+        let rel_path = &self.relative_path(path.as_ref())?;
+        let mtime = format_iso8601(meta.modified()?)?;
+
+        if !forced {
+            if let Some(data) = self.get_cache_record(rel_path)? {
+                if data.file_size == meta.len() && data.mtime == mtime {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(None);
+                }
+            }
+
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let record = CacheRecord::new(
+            mtime,
+            meta.len(),
+            ExifSummary::default(),
+        )?;
+
+        Ok(Some(self.build_handle(rel_path.to_path_buf(), record)?))
+    }
+
+    ///
+    /// キャッシュミス時の判定結果を構築する
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    /// * `rel_path` - ボリュームを基点とした相対パス
+    /// * `mtime` - mtime（ISO8601、秒精度）
+    /// * `meta` - 現在のファイル情報
+    /// * `exif_cache` - 読み出したExif情報のキャッシュ
+    /// * `timings` - Exif情報の読み出し時間の記録先
+    ///
+    /// # 戻り値
+    /// コミット用ハンドルとExif情報をパックしたキャッシュミスの判定結果
+    ///
+    fn build_miss<P>(
+        &self,
+        path: P,
+        rel_path: &Path,
+        mtime: String,
+        meta: &Metadata,
+        exif_cache: &ExifCache,
+        timings: &mut FileTimings,
+    ) -> Result<CacheDecision>
+    where
+        P: AsRef<Path>,
+    {
+        // 評価中に読み出し済みの場合はキャッシュに保持されたものを用いる
+        let (exif, summary) = timed_read_exif(path, exif_cache, timings)?;
+
+        let handle = self.build_handle(
+            rel_path.to_path_buf(), 
+            CacheRecord::new(mtime, meta.len(), summary)?,
+        )?;
+
+        Ok(CacheDecision::Miss {handle, exif})
+    }
+}
+
+///
+/// キャッシュデータベースを開く(テーブルが存在しない場合は作成する)
+///
+/// # 引数
+/// * `db_path` - データベースファイルのパス
+///
+/// # 戻り値
+/// オープンしたデータベース
+///
+/// # 注記
+/// 開けない場合はそのままエラーとする。データベースの退避は行わないため、
+/// キャッシュ管理コマンドからはこちらを用いる。
+///
+fn open_database(db_path: &Path) -> Result<Database> {
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let db = Database::builder().create(db_path)?;
+
+    // あろうがなかろうが、とりあえずテーブルを作る
+    create_tables(&db)?;
+
+    Ok(db)
+}
+
+///
+/// 取り込みのためにキャッシュデータベースを開く(壊れている場合は作り直す)
+///
+/// # 引数
+/// * `db_path` - データベースファイルのパス
+///
+/// # 戻り値
+/// オープンしたデータベース
+///
+/// # 注記
+/// データベースが壊れていて開けない場合は、ファイルを退避した上で新たに作
+/// 成する。退避先は警告として標準エラー出力とログに出力する。故障と判定す
+/// るのは`StorageError::Corrupted`の場合のみで、他のプロセスが使用中の場合
+/// や、ファイル形式の更新が必要な場合等はそのままエラーとする。
+///
+fn open_or_recover_database(db_path: &Path) -> Result<Database> {
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let db = match Database::builder().create(db_path) {
+        Ok(db) => db,
+
+        Err(DatabaseError::Storage(StorageError::Corrupted(msg))) => {
+            let quarantine = quarantine_database(db_path)?;
+
+            eprintln!(
+                "warning: cache database {} is corrupted, moved to {}",
+                db_path.display(),
+                quarantine.display(),
+            );
+
+            warn!(
+                "cache open failed ({}), moved to {} and recreating: {}",
+                db_path.display(),
+                quarantine.display(),
+                msg
+            );
+
+            Database::builder().create(db_path)?
+        },
+
+        Err(err) => return Err(err.into()),
+    };
+
+    create_tables(&db)?;
+
+    Ok(db)
+}
+
+///
+/// 開けなかったデータベースファイルを退避する
+///
+/// # 引数
+/// * `db_path` - データベースファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は退避先のパスを`Ok()`でラップして返す。失敗した場合は
+/// エラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 調査や手作業での復旧に使えるよう、削除はせずに同じディレクトリの
+/// `<ファイル名>.corrupt-<YYYYmmddHHMMSS>`に名前を変更する。
+///
+fn quarantine_database(db_path: &Path) -> Result<PathBuf> {
+    let mut name = db_path.as_os_str().to_os_string();
+    name.push(format!(".corrupt-{}", Local::now().format("%Y%m%d%H%M%S")));
+
+    let quarantine = PathBuf::from(name);
+    std::fs::rename(db_path, &quarantine)?;
+
+    Ok(quarantine)
+}
+
+///
+/// キャッシュデータベースのテーブルを作成する
+///
+/// # 引数
+/// * `db` - 対象のデータベース
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn create_tables(db: &Database) -> Result<()> {
+    let write_txn = db.begin_write()?;
+    {
+        write_txn.open_table(TABLE)?;
+        write_txn.open_table(HASH_TABLE)?;
+        write_txn.open_table(ARCHIVE_TABLE)?;
+        write_txn.open_table(STATS_TABLE)?;
+        write_txn.commit()?;
+    }
+
+    Ok(())
+}
+
+///
+/// データベースファイルの内容をメモリ上のデータベースに読み込む
+///
+/// # 引数
+/// * `db` - 読み込み先のデータベース
+/// * `db_path` - データベースファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// データベースを開くとファイルのロックや修復のための書き込みが行われること
+/// があるため、一時ディレクトリに複製したものを開いて読み込み、読み込み後に
+/// 複製を削除する。
+///
+fn load_snapshot(db: &Database, db_path: &Path) -> Result<()> {
+    // 同じプロセス内で同時に開かれた場合に備えて通し番号を付ける
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+    let tmp_path = std::env::temp_dir().join(format!(
+        "imgdist-dry-run-{}-{}.redb",
+        std::process::id(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    if let Err(err) = std::fs::copy(db_path, &tmp_path) {
+        return Err(anyhow!(
+            "copy {} to {} failed: {}",
+            db_path.display(),
+            tmp_path.display(),
+            err
+        ));
+    }
+
+    let result = (|| -> Result<()> {
+        let src = Database::open(&tmp_path)?;
+        let read_txn = src.begin_read()?;
+        let write_txn = db.begin_write()?;
+
+        copy_table(&read_txn, &write_txn, TABLE)?;
+        copy_table(&read_txn, &write_txn, HASH_TABLE)?;
+        copy_table(&read_txn, &write_txn, ARCHIVE_TABLE)?;
+        copy_table(&read_txn, &write_txn, STATS_TABLE)?;
+
+        write_txn.commit()?;
+        Ok(())
+    })();
+
+    let _ = std::fs::remove_file(&tmp_path);
+
+    result
+}
+
+///
+/// テーブルの内容を別のデータベースに複製する
+///
+/// # 引数
+/// * `src` - 複製元のデータベースの読み出しトランザクション
+/// * `dst` - 複製先のデータベースの書き込みトランザクション
+/// * `table` - 複製するテーブルの定義
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 複製元にテーブルが無い場合(テーブルが追加される前のデータベース)は何もし
+/// ない。
+///
+fn copy_table<V>(
+    src: &ReadTransaction,
+    dst: &WriteTransaction,
+    table: TableDefinition<String, V>,
+) -> Result<()>
+where
+    V: Value + 'static,
+{
+    let src_table = match src.open_table(table) {
+        Ok(src_table) => src_table,
+        Err(redb::TableError::TableDoesNotExist(_)) => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut dst_table = dst.open_table(table)?;
+
+    for item in src_table.iter()? {
+        let (key, value) = item?;
+        dst_table.insert(key.value(), value.value())?;
+    }
+
+    Ok(())
+}
+
+///
+/// 2つのファイルの拡張子が同じか否かを判定する
+///
+/// # 引数
+/// * `a` - 比較するファイルのパス
+/// * `b` - 比較するファイルのパス
+///
+/// # 戻り値
+/// 拡張子(大文字小文字は区別しない)が同じ場合は`true`
+///
+fn same_extension(a: &Path, b: &Path) -> bool {
+    let ext = |path: &Path| {
+        path.extension().map(|ext| ext.to_string_lossy().to_lowercase())
+    };
+
+    ext(a) == ext(b)
+}
+
+///
+/// 索引に登録する内容のハッシュ値を求める
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `algo` - ハッシュ値を求めるハッシュ関数
+///
+/// # 戻り値
+/// 処理が成功した場合はハッシュ値の16進表記(SHA-256以外は関数名を前置した
+/// もの)を`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラッ
+/// プして返す。
+///
+fn content_hash(path: &Path, algo: ChecksumAlgorithm) -> Result<String> {
+    let hash = checksum::digest_file(path, algo)?;
+
+    Ok(match checksum::key_prefix(algo) {
+        Some(prefix) => format!("{}:{}", prefix, hash),
+        None => hash,
+    })
+}
+
+/// キーを構築する
+fn build_key(volume_id: &str, rel_path: &Path) -> String {
+    format!("{}:{}", volume_id, rel_path.display())
+}
+
+/// SystemTimeを秒単位に切り詰める
+fn truncate_system_time(time: SystemTime) -> Result<SystemTime> {
+    let dur = time.duration_since(UNIX_EPOCH)?;
+    Ok(UNIX_EPOCH + Duration::from_secs(dur.as_secs()))
+}
+
+///
+/// SystemTimeをISO8601文字列に変換する
+///
+/// # 引数
+/// * `time` - 変換するシステム時刻
+///
+/// # 戻り値
+/// ISO8601文字列を返す。ただし、秒未満の時刻情報は丸められて秒単位の情報に切り
+/// 詰められる。
+///
+fn format_iso8601(time: SystemTime) -> Result<String> {
+    let time = truncate_system_time(time)?;
+    let datetime = DateTime::<Utc>::from(time).with_timezone(&Local);
+    let seconds = datetime.timestamp();
+
+    Ok(Local
+        .timestamp_opt(seconds, 0)
+        .single()
+        .unwrap()
+        .to_rfc3339()
+    )
+}
+
+///
+/// ボリュームIDを取得する
+///
+/// # 引数
+/// * `path` - 対象となるパス
+///
+/// # 戻り値
+/// ボリュームID
+///
+fn get_volume_id<P>(path: P) -> Result<String>
+where 
+    P: AsRef<Path>,
+{
+    /*
+     * Linuxの場合はファイルシステムUUIDを使う (/dev/disk/by-uuid/のUUID)
+     */
+    #[cfg(target_os = "linux")]
+    {
+        use std::fs::read_dir;
+
+        let (mount_point, source, dev_id) = linux_mount_info(path.as_ref())?;
+        let mut candidates: Vec<PathBuf> = Vec::new();
+
+        if let Some(dev_path) = source {
+            if dev_path.starts_with("/dev") {
+                candidates.push(dev_path);
+            }
+        }
+
+        if let Some(dev_id) = dev_id {
+            let sys_dev = PathBuf::from("/sys/dev/block").join(&dev_id);
+            if sys_dev.exists() {
+                if let Ok(link) = std::fs::read_link(&sys_dev) {
+                    if let Some(name) = link.file_name() {
+                        candidates.push(PathBuf::from("/dev").join(name));
+                    }
+                }
+            }
+        }
+
+        for dev in candidates {
+            let dev_real = match dev.canonicalize() {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+
+            let uuid_dir = PathBuf::from("/dev/disk/by-uuid");
+
+            for entry in read_dir(&uuid_dir)? {
+                let entry = entry?;
+
+                let target = entry.path();
+
+                if let Some(prefix) = target.parent() {
+                    let target_real = std::fs::read_link(&target)
+                        .map(|p| prefix.join(&p))?
+                        .canonicalize()?;
+
+                    if target_real == dev_real {
+                        if let Some(name) = entry.file_name().to_str() {
+                            return Ok(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        Err(anyhow!("filesystem UUID not found for {}", mount_point.display()))
+    }
+
+    /*
+     * macOSの場合はVolume UUID
+     */
+    #[cfg(target_os = "macos")]
+    {
+        use libc::{
+            attrlist, getattrlist, ATTR_BIT_MAP_COUNT, ATTR_VOL_INFO,
+            ATTR_VOL_UUID
+        };
+        use std::ffi::CString;
+        use std::mem::{size_of, zeroed};
+
+        #[repr(C)]
+        #[derive(Debug, Copy, Clone)]
+        struct VolUuidBuffer {
+            length: u32,
+            uuid: [u8; 16],
+        }
+
+        let c_path = CString::new(path.as_ref().as_os_str().as_bytes())?;
+
+        let mut attrs: attrlist = unsafe { zeroed() };
+        attrs.bitmapcount = ATTR_BIT_MAP_COUNT as u16;
+        attrs.volattr = ATTR_VOL_INFO | ATTR_VOL_UUID;
+
+        let mut buf: VolUuidBuffer = VolUuidBuffer {
+            length: size_of::<VolUuidBuffer>() as u32,
+            uuid: [0; 16],
+        };
+
+        let ret = unsafe {
+            getattrlist(
+                c_path.as_ptr(),
+                &mut attrs,
+                &mut buf as *mut _ as *mut _,
+                buf.length as usize,
+                0,
+            )
+        };
+
+        if ret != 0 {
+            return Err(anyhow!("volume uuid is not available"));
+        }
+
+        Ok(format_uuid(buf.uuid))
+    }
+
+    /*
+     * その他のUNIX系
+     */
+    #[cfg(all(not(target_os = "linux"), not(target_os = "macos"),
+        target_family = "unix"))]
+    {
+        use nix::sys::statfs::statfs;
+
+        let info = statfs(path.as_ref())?;
+        let fsid = info.f_fsid();
+        Ok(format!("{:x}:{:x}", fsid.val[0], fsid.val[1]))
+    }
+
+    /*
+     * Windowsの場合はボリュームGUIDを用いる（取得できない場合はボリュームシリ
+     * アル番号を用いる）
+     */
+    #[cfg(target_family = "windows")]
+    {
+        match windows_volume_guid(path.as_ref()) {
+            Ok(guid) => Ok(guid),
+            Err(err) => {
+                debug!("volume guid is not available: {}", err);
+                windows_volume_serial(path.as_ref())
+            }
+        }
+    }
+
+    #[cfg(not(any(target_family = "unix", target_family = "windows")))]
+    {
+        Err(anyhow!("volume id is not available on this platform"))
+    }
+}
+
+///
+/// ボリュームプレフィクスを取得する
+///
+/// # 引数
+/// * `path` - 対象となるパス
+///
+/// # 戻り値
+/// マウントポイントのパス
+///
+fn get_volume_prefix<P>(path: P) -> Result<PathBuf>
+where 
+    P: AsRef<Path>,
+{
+    #[cfg(target_os = "linux")]
+    {
+        let (mount_point, _, _) = linux_mount_info(path.as_ref())?;
+        Ok(mount_point)
+    }
+
+    #[cfg(all(not(target_os = "linux"), target_family= "unix"))]
+    {
+        use nix::sys::statfs::statfs;
+
+        let info = statfs(path)?;
+        let mntonname = info
+            .f_mntonname
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8 as char)
+            .collect::<String>();
+
+        Ok(PathBuf::from(mntonname))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows::core::PCWSTR;
+        use windows::Win32::Storage::FileSystem::GetVolumePathNameW;
+        use std::os::windows::ffi::OsStrExt;
+
+        let wide: Vec<u16> = path.as_ref().as_os_str().encode_wide().chain([0]).collect();
+        let mut buffer = vec![0u16; 260];
+
+        unsafe {
+            if GetVolumePathNameW(
+                PCWSTR(wide.as_ptr() as *mut _),
+                &mut buffer,
+            ).is_ok()
+            {
+                let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+                let s = String::from_utf16_lossy(&buffer[..end]);
+                return Ok(PathBuf::from(s).canonicalize()?);
+            }
+        }
+
+        Err(anyhow::anyhow!("GetVolumePathNameW failed"))
+    }
+}
+
+///
+/// ボリュームのラベルを取得する
+///
+/// # 引数
+/// * `path` - 対象となるパス
+///
+/// # 戻り値
+/// ボリュームのラベル（取得できない場合はNone）
+///
+/// # 注記
+/// Linuxでは`/dev/disk/by-label/`、Windowsではボリューム情報からラベルを
+/// 求める。これらで得られない場合やその他の環境ではマウントポイントの名前で
+/// 代用する(macOSでは`/Volumes/`以下の名前がラベルとなる)。
+///
+pub(crate) fn volume_label<P>(path: P) -> Option<String>
+where
+    P: AsRef<Path>,
+{
+    #[cfg(target_os = "linux")]
+    if let Some(label) = linux_volume_label(path.as_ref()) {
+        return Some(label);
+    }
+
+    #[cfg(target_os = "windows")]
+    if let Some(label) = windows_volume_label(path.as_ref()) {
+        return Some(label);
+    }
+
+    get_volume_prefix(path)
+        .ok()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+///
+/// Linuxのボリュームのラベルを取得する
+///
+/// # 引数
+/// * `path` - 対象となるパス
+///
+/// # 戻り値
+/// `/dev/disk/by-label/`に登録されたラベル（登録が無い場合はNone）
+///
+/// # 注記
+/// `/dev/disk/by-label/`のエントリ名は空白等が`\x20`の形式でエスケープされ
+/// ているため、元の文字に戻して返す。
+///
+#[cfg(target_os = "linux")]
+fn linux_volume_label(path: &Path) -> Option<String> {
+    let (_, source, _) = linux_mount_info(path).ok()?;
+    let dev_real = source?.canonicalize().ok()?;
+
+    std::fs::read_dir("/dev/disk/by-label")
+        .ok()?
+        .flatten()
+        .find(|entry| {
+            entry.path().canonicalize().ok().as_ref() == Some(&dev_real)
+        })
+        .map(|entry| unescape_label(&entry.file_name().to_string_lossy()))
+}
+
+///
+/// `/dev/disk/by-label/`のエントリ名のエスケープを元に戻す
+///
+/// # 引数
+/// * `name` - エントリ名
+///
+/// # 戻り値
+/// `\xHH`の形式の並びを元のバイトに戻した文字列
+///
+#[cfg(target_os = "linux")]
+fn unescape_label(name: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = name;
+
+    while let Some(pos) = rest.find("\\x") {
+        bytes.extend_from_slice(&rest.as_bytes()[..pos]);
+
+        let byte = rest
+            .get(pos + 2..pos + 4)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match byte {
+            Some(byte) => {
+                bytes.push(byte);
+                rest = &rest[pos + 4..];
+            }
+
+            None => {
+                bytes.push(b'\\');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+
+    bytes.extend_from_slice(rest.as_bytes());
+
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+///
+/// Windowsのボリュームのラベルを取得する
+///
+/// # 引数
+/// * `path` - 対象となるパス
+///
+/// # 戻り値
+/// ボリュームのラベル（取得できない場合や空の場合はNone）
+///
+#[cfg(target_os = "windows")]
+fn windows_volume_label(path: &Path) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let volume_root: Vec<u16> = get_volume_prefix(path)
+        .ok()?
+        .as_os_str()
+        .encode_wide()
+        .chain([0])
+        .collect();
+
+    let mut buffer = [0u16; 261];
+
+    unsafe {
+        GetVolumeInformationW(
+            PCWSTR(volume_root.as_ptr()),
+            Some(&mut buffer),
+            None,
+            None,
+            None,
+            None,
+        )
+    }.ok()?;
+
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    let label = String::from_utf16_lossy(&buffer[..end]);
+
+    (!label.is_empty()).then_some(label)
+}
+
+///
+/// Windowsのボリュームシリアル番号を取得する
+///
+/// # 引数
+/// * `path` - 対象となるパス
+///
+/// # 戻り値
+/// 8桁の16進表記のボリュームシリアル番号
+///
+/// # 注記
+/// シリアル番号はフォーマットのたびに変わり、他のボリュームと衝突することもあ
+/// るため、ボリュームGUIDが取得できない場合と旧形式のキーの移行にのみ用いる。
+///
+#[cfg(target_os = "windows")]
+fn windows_volume_serial(path: &Path) -> Result<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let volume_root: Vec<u16> = get_volume_prefix(path)?
+        .as_os_str()
+        .encode_wide()
+        .chain([0])
+        .collect();
+
+    let mut serial: u32 = 0;
+    let mut dummy_max_comp_len: u32 = 0;
+    let mut file_system_flags: u32 = 0;
+
+    if unsafe {
+        GetVolumeInformationW(
+            PCWSTR(volume_root.as_ptr()),
+            None,
+            Some(&mut serial),
+            Some(&mut dummy_max_comp_len),
+            Some(&mut file_system_flags),
+            None,
+        )
+    }.is_ok() {
+        Ok(format!("{:08X}", serial))
+    } else {
+        Err(anyhow!("volume id is not available"))
+    }
+}
+
+///
+/// WindowsのボリュームGUIDを取得する
+///
+/// # 引数
+/// * `path` - 対象となるパス
+///
+/// # 戻り値
+/// 波括弧付き小文字表記のボリュームGUID
+///
+/// # 注記
+/// ボリュームのマウントポイントから`\\?\Volume{GUID}\`形式のボリューム名を
+/// 求め、GUID部分を取り出す。
+///
+#[cfg(target_os = "windows")]
+fn windows_volume_guid(path: &Path) -> Result<String> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetVolumeNameForVolumeMountPointW;
+
+    /*
+     * マウントポイントの正規化（拡張パスの接頭辞を外し、末尾に区切り文字を付
+     * 与する）
+     */
+    let mut root = get_volume_prefix(path)?.to_string_lossy().to_string();
+
+    if let Some(stripped) = root.strip_prefix(r"\\?\") {
+        if !stripped.starts_with(r"UNC\") {
+            root = stripped.to_string();
+        }
+    }
+
+    if !root.ends_with('\\') {
+        root.push('\\');
+    }
+
+    /*
+     * ボリューム名の取得
+     */
+    let wide: Vec<u16> = OsStr::new(&root).encode_wide().chain([0]).collect();
+    let mut buffer = [0u16; 64];
+
+    unsafe {
+        GetVolumeNameForVolumeMountPointW(PCWSTR(wide.as_ptr()), &mut buffer)?;
+    }
+
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    let name = String::from_utf16_lossy(&buffer[..end]);
+
+    match (name.find('{'), name.find('}')) {
+        (Some(open), Some(close)) if open < close => {
+            Ok(name[open..=close].to_lowercase())
+        }
+
+        _ => Err(anyhow!("unexpected volume name: {}", name)),
+    }
+}
+
+///
+/// レコードのキーのボリュームIDを書き換える
+///
+/// # 引数
+/// * `txn` - 書き込みトランザクション
+/// * `from` - 書き換え元のボリュームID
+/// * `to` - 書き換え先のボリュームID
+///
+/// # 戻り値
+/// 処理が成功した場合は書き換えたレコード数を`Ok()`でラップして返す。失敗し
+/// た場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 書き換え先のキーのレコードが既にある場合はそちらを優先し、書き換え元のキ
+/// ーのレコードは削除する。内容のハッシュ値の索引が指すキーも書き換える。書
+/// き換え元のキーが残らないため、繰り返し呼び出しても結果は変わらない。
+///
+#[cfg(any(target_os = "windows", test))]
+fn rekey_volume(txn: &WriteTransaction, from: &str, to: &str)
+    -> Result<usize>
+{
+    let prefix = format!("{}:", from);
+    let rekey = |key: &str| format!("{}:{}", to, &key[prefix.len()..]);
+
+    /*
+     * レコードのキーの書き換え
+     */
+    let mut table = txn.open_table(TABLE)?;
+    let mut entries = Vec::new();
+
+    for item in table.iter()? {
+        let (key, value) = item?;
+        let key = key.value();
+
+        if key.starts_with(&prefix) {
+            entries.push((key, value.value()));
+        }
+    }
+
+    for (key, record) in &entries {
+        let new_key = rekey(key);
+
+        if table.get(&new_key)?.is_none() {
+            table.insert(&new_key, record)?;
+        }
+
+        table.remove(key)?;
+    }
+
+    /*
+     * 索引が指すキーの書き換え
+     */
+    let mut index = txn.open_table(HASH_TABLE)?;
+    let mut hashes = Vec::new();
+
+    for item in index.iter()? {
+        let (hash, key) = item?;
+        let key = key.value();
+
+        if key.starts_with(&prefix) {
+            hashes.push((hash.value(), rekey(&key)));
+        }
+    }
+
+    for (hash, key) in &hashes {
+        index.insert(hash, key)?;
+    }
+
+    Ok(entries.len())
+}
+
+///
+/// 旧形式のボリュームIDによるキーを新しいボリュームIDに移行する
+///
+/// # 引数
+/// * `db` - キャッシュデータベース
+/// * `from` - 旧形式のボリュームID（ボリュームシリアル番号）
+/// * `to` - 新しいボリュームID（ボリュームGUID）
+///
+/// # 戻り値
+/// 処理が成功した場合は移行したレコード数を`Ok()`でラップして返す。失敗した場
+/// 合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// キーの書き換えは[`rekey_volume()`]による。
+///
+/// 移行はシリアル番号ごとに一度だけ行い、移行先をメタデータのテーブルに記録
+/// する(以降のオープンでは記録の確認のみで済ませる)。シリアル番号は複製した
+/// メディア等で重複し得るため、別のボリュームが既に移行を行ったシリアル番号
+/// と、シリアル番号を持たないボリューム(`00000000`)は移行しない。
+///
+/// 移行が必要となるのはWindowsのみだが、移行の処理自体はプラットフォームに
+/// 依存しないため、テストでは全てのプラットフォームで用いる。
+///
+#[cfg(any(target_os = "windows", test))]
+fn migrate_volume_id(db: &Database, from: &str, to: &str) -> Result<usize> {
+    if from.trim_start_matches('0').is_empty() {
+        return Ok(0);
+    }
+
+    let flag = format!("{}:{}", MIGRATED_KEY, from);
+
+    /*
+     * 移行済みか否かの確認
+     */
+    {
+        let txn = db.begin_read()?;
+
+        match txn.open_table(META_TABLE) {
+            Ok(meta) => {
+                if let Some(done) = meta.get(&flag)? {
+                    let done = done.value();
+
+                    if done != to {
+                        debug!(
+                            "volume serial {} was migrated to {}, skipped",
+                            from,
+                            done
+                        );
+                    }
+
+                    return Ok(0);
+                }
+            }
+
+            Err(redb::TableError::TableDoesNotExist(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    /*
+     * キーの書き換え
+     */
+    let txn = db.begin_write()?;
+    let count = rekey_volume(&txn, from, to)?;
+
+    txn.open_table(META_TABLE)?.insert(&flag, to.to_string())?;
+    txn.commit()?;
+
+    if count > 0 {
+        log::info!("{} cache entries migrated from {} to {}", count, from, to);
+    }
+
+    Ok(count)
+}
+
+///
+/// mountinfoから対象パスのマウントポイントとデバイスパスを取得する
+///
+/// # 引数
+/// * `path` - 対象パス
+///
+/// # 戻り値
+/// (マウントポイント, デバイスパス) のタプル
+///
+#[cfg(target_os = "linux")]
+fn linux_mount_info(path: &Path) -> Result<(PathBuf, Option<PathBuf>, Option<String>)> {
+    let target = path.canonicalize()?;
+    let file = File::open("/proc/self/mountinfo")?;
+    let reader = BufReader::new(file);
+
+    let mut best: Option<(PathBuf, Option<PathBuf>, Option<String>)> = None;
+
+    for line in reader.lines() {
+        let line = line?;
+        let parts: Vec<&str> = line.split(' ').collect();
+        if parts.len() < 10 {
+            continue;
+        }
+
+        let dash_pos = parts.iter().position(|v| *v == "-");
+        if dash_pos.is_none() {
+            continue;
+        }
+
+        let dash_pos = dash_pos.unwrap();
+        if dash_pos + 2 >= parts.len() {
+            continue;
+        }
+
+        let mount_point = decode_mount_path(parts[4]);
+        let source = decode_mount_path(parts[dash_pos + 2]);
+        let dev_id = parts[2].to_string();
+
+        let mount_path = PathBuf::from(&mount_point);
+
+        if target.starts_with(&mount_path) {
+            let replace = match &best {
+                Some((existing, _, _)) => {
+                    mount_path.as_os_str().len() > existing.as_os_str().len()
+                }
+                None => true,
+            };
+
+            if replace {
+                best = Some((mount_path, Some(PathBuf::from(source)), Some(dev_id)));
+            }
+        }
+    }
+
+    best.ok_or_else(|| anyhow!("mount point not found"))
+}
+
+#[cfg(target_os = "linux")]
+///
+/// mountinfo内のエスケープをデコードする
+///
+/// # 引数
+/// * `s` - エスケープされたパス文字列
+///
+/// # 戻り値
+/// デコード済みの文字列
+///
+fn decode_mount_path(s: &str) -> String {
+    s.replace("\\040", " ")
+}
+
+///
+/// macOSのUUIDをハイフン付き文字列表現に整形する
+///
+/// # 引数
+/// * `uuid` - 16バイトのUUID
+///
+/// # 戻り値
+/// ハイフン付き大文字16進表記の文字列
+///
+#[cfg(target_os = "macos")]
+fn format_uuid(uuid: [u8; 16]) -> String {
+    format!(
+        concat!(
+            "{:02X}{:02X}{:02X}{:02X}",
+            "-",
+            "{:02X}{:02X}",
+            "-",
+            "{:02X}{:02X}",
+            "-",
+            "{:02X}{:02X}",
+            "-",
+            "{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+        )
+        uuid[0], uuid[1], uuid[2], uuid[3],
+        uuid[4], uuid[5],
+        uuid[6], uuid[7],
+        uuid[8], uuid[9],
+        uuid[10], uuid[11], uuid[12], uuid[13], uuid[14], uuid[15],
+    )
+}
+
+///
+/// 所要時間を記録しつつExifを読み込む
+///
+/// # 引数
+/// * `path` - 対象パス
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+/// * `timings` - 所要時間の記録先
+///
+/// # 戻り値
+/// 読み込んだExif情報とサマリ情報をパックしたタプルを返す
+///
+fn timed_read_exif<P>(
+    path: P,
+    exif_cache: &ExifCache,
+    timings: &mut FileTimings,
+) -> Result<(Rc<Exif>, ExifSummary)>
+where 
+    P: AsRef<Path>,
+{
+    let start = Instant::now();
+    let result = exif_cache.read(path);
+    timings.add_exif_read(start.elapsed());
+
+    result
+}
+
+///
+/// Exifを読み込む。またサマリ情報を作成し一緒に返す
+///
+/// # 引数
+/// * `path` - 対象パス
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// 読み込んだExif情報とサマリ情報をパックしたタプルを返す
+///
+/// # 注記
+/// RAWファイルのExif情報が読めない、または撮影日時を含まない場合は、同じディ
+/// レクトリにある同名のJPEGファイルのExif情報で代用する。代用した場合はサマ
+/// リ情報にそのファイル名を記録する。
+///
+pub(crate) fn read_exif<P>(path: P, classes: &ClassRegistry)
+    -> Result<(Exif, ExifSummary)>
+where 
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let result = read_exif_file(path, classes);
+
+    /*
+     * ペアのJPEGファイルによる代用の要否の判定
+     */
+    let usable = match &result {
+        Ok((_, summary)) => summary.datetime_original.is_some(),
+        Err(_) => false,
+    };
+
+    let is_raw = path
+        .extension()
+        .is_some_and(|ext| is_raw_file(&ext.to_string_lossy(), classes));
+
+    if usable || !is_raw {
+        return result;
+    }
+
+    let jpeg = match paired_jpeg(path, classes) {
+        Some(jpeg) => jpeg,
+        None => return result,
+    };
+
+    /*
+     * ペアのJPEGファイルのExif情報の読み出し
+     */
+    match read_exif_file(&jpeg, classes) {
+        Ok((exif, mut summary)) if summary.datetime_original.is_some() => {
+            info!(
+                "exif of {} substituted by {}",
+                path.display(),
+                jpeg.display()
+            );
+
+            summary.paired_exif = jpeg
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string());
+
+            Ok((exif, summary))
+        }
+
+        // 代用できない場合はRAWファイル自体の読み出し結果を返す
+        _ => result,
+    }
+}
+
+///
+/// 単一のファイルからExifを読み込む
+///
+/// # 引数
+/// * `path` - 対象パス
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// 読み込んだExif情報とサマリ情報をパックしたタプルを返す
+///
+/// # 注記
+/// 動画ファイルの場合はコンテナに記録された作成日時から組み立てたExif情報を
+/// 返す。設定で撮影日時の取得元を更新日時またはファイル名とした形式の場合は
+/// ファイルを読まずに空のExif情報を返す。Exif情報を持たないファイルの場合も
+/// 空のExif情報を返す。
+///
+fn read_exif_file(path: &Path, classes: &ClassRegistry)
+    -> Result<(Exif, ExifSummary)>
+{
+    let is_video = path
+        .extension()
+        .is_some_and(|ext| is_video_file(&ext.to_string_lossy(), classes));
+
+    if is_video {
+        let exif = video::read_exif(path)?;
+        let summary = ExifSummary::from(&exif);
+        return Ok((exif, summary));
+    }
+
+    // 撮影日時をExif情報以外から求める形式は読み込まない
+    let skip = path
+        .extension()
+        .and_then(|ext| classes.date_source(&ext.to_string_lossy()))
+        .is_some();
+
+    if skip {
+        let exif = exif::Reader::new().read_raw(video::build_tiff(None))?;
+        let summary = ExifSummary::from(&exif);
+        return Ok((exif, summary));
+    }
+
+    let mut bufreader = BufReader::new(File::open(path)?);
+
+    match exif::Reader::new().read_from_container(&mut bufreader) {
+        Ok(exif) => {
+            let summary = ExifSummary::from(&exif);
+            Ok((exif, summary))
+        }
+
+        // Exif情報自体を持たないファイル(スクリーンショット、メッセージアプ
+        // リで受け取った画像等)は撮影日時を含まないファイルとして扱う
+        Err(exif::Error::NotFound(_)) => {
+            let exif = exif::Reader::new().read_raw(video::build_tiff(None))?;
+            let summary = ExifSummary::from(&exif);
+            Ok((exif, summary))
+        }
+
+        Err(err) => Err(ImportError::Exif {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        }.into()),
+    }
+}
+
+///
+/// RAWファイルとペアになるJPEGファイルを探す
+///
+/// # 引数
+/// * `path` - RAWファイルのパス
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// 同じディレクトリに拡張子以外が同名のJPEGファイルがある場合はそのパス
+///
+fn paired_jpeg(path: &Path, classes: &ClassRegistry) -> Option<PathBuf> {
+    let stem = path.file_stem()?;
+
+    path.parent()?
+        .read_dir()
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|candidate| {
+            candidate.file_stem() == Some(stem)
+                && candidate
+                    .extension()
+                    .is_some_and(|ext| {
+                        is_jpeg_file(&ext.to_string_lossy(), classes)
+                    })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// テスト用の作業ディレクトリを作成する
+    ///
+    fn work_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("imgdist-cache-{}-{}", name, std::process::id()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("in")).unwrap();
+        dir
+    }
+
+    ///
+    /// テスト用のレコードを作成する
+    ///
+    fn record(file_size: u64) -> CacheRecord {
+        let mtime = "2024-05-01T12:00:00+09:00".to_string();
+        CacheRecord::new(mtime, file_size, ExifSummary::default()).unwrap()
+    }
+
+    ///
+    /// テスト用のキャッシュデータベースを開く
+    ///
+    fn open_cache(dir: &Path) -> Cache {
+        let db_path = dir.join("cache.redb");
+        let input = dir.join("in");
+
+        Cache::open(&db_path, CacheEvalMode::Shallow, &input).unwrap()
+    }
+
+    ///
+    /// 内容を指定してファイルを作成する
+    ///
+    fn write_file(path: &Path, data: &[u8]) -> PathBuf {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+        path.to_path_buf()
+    }
+
+    ///
+    /// 入力ファイルに対するコミット用ハンドルを作成する
+    ///
+    fn handle_for(cache: &Cache, name: &str, file_size: u64) -> TxnHandle {
+        cache.build_handle(PathBuf::from(name), record(file_size)).unwrap()
+    }
+
+    #[test]
+    fn find_in_archive_matches_identical_file_under_other_name() {
+        let dir = work_dir("dedup");
+        let cache = open_cache(&dir);
+        let input = write_file(&dir.join("in/IMG_0001.jpg"), b"image");
+        let stored = write_file(&dir.join("out/20240501_001.jpg"), b"image");
+        let hash = content_hash(&stored, ChecksumAlgorithm::Sha256).unwrap();
+
+        cache.put_cache_record(
+            Path::new("other.jpg"),
+            &record(5),
+            None,
+            Some((&hash, &stored)),
+        ).unwrap();
+
+        let mut handle = handle_for(&cache, "IMG_0001.jpg", 5);
+        let found = cache
+            .find_in_archive(&mut handle, &input, ChecksumAlgorithm::Sha256)
+            .unwrap();
+
+        assert_eq!(found, Some(stored));
+        assert_eq!(handle.archive_hash, Some(hash));
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn find_in_archive_rejects_hash_collision_with_other_size() {
+        let dir = work_dir("dedup-collision");
+        let cache = open_cache(&dir);
+        let input = write_file(&dir.join("in/IMG_0001.jpg"), b"image");
+        let stored = write_file(&dir.join("out/other.jpg"), b"other image");
+        let hash = content_hash(&input, ChecksumAlgorithm::Sha256).unwrap();
+
+        // 入力ファイルと同じハッシュ値でサイズの異なるファイルを登録する
+        cache.put_cache_record(
+            Path::new("other.jpg"),
+            &record(11),
+            None,
+            Some((&hash, &stored)),
+        ).unwrap();
+
+        let mut handle = handle_for(&cache, "IMG_0001.jpg", 5);
+        let found = cache
+            .find_in_archive(&mut handle, &input, ChecksumAlgorithm::Sha256)
+            .unwrap();
+
+        assert_eq!(found, None);
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn find_in_archive_ignores_size_of_transformed_file() {
+        let dir = work_dir("dedup-transformed");
+        let cache = open_cache(&dir);
+        let input = write_file(&dir.join("in/IMG_0001.ARW"), b"raw image");
+        let stored = write_file(&dir.join("out/IMG_0001.ARW.zst"), b"zst");
+        let hash = content_hash(&input, ChecksumAlgorithm::Sha256).unwrap();
+
+        cache.put_cache_record(
+            Path::new("IMG_0001.ARW"),
+            &record(9),
+            None,
+            Some((&hash, &stored)),
+        ).unwrap();
+
+        let mut handle = handle_for(&cache, "copy/IMG_0001.ARW", 9);
+        let found = cache
+            .find_in_archive(&mut handle, &input, ChecksumAlgorithm::Sha256)
+            .unwrap();
+
+        assert_eq!(found, Some(stored));
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn find_by_hash_rejects_hash_collision_with_other_size() {
+        let dir = work_dir("hash-collision");
+        let cache = open_cache(&dir);
+        let input = write_file(&dir.join("in/IMG_0001.jpg"), b"image");
+        let hash = content_hash(&input, ChecksumAlgorithm::Sha256).unwrap();
+
+        cache.put_cache_record(
+            Path::new("other.jpg"),
+            &record(11),
+            Some(&hash),
+            None,
+        ).unwrap();
+
+        let mut handle = handle_for(&cache, "IMG_0001.jpg", 5);
+        let found = cache
+            .find_by_hash(&mut handle, &input, ChecksumAlgorithm::Sha256)
+            .unwrap();
+
+        assert_eq!(found, None);
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn find_by_hash_round_trips_inserted_hash() {
+        let dir = work_dir("hash-index");
+        let cache = open_cache(&dir);
+        let input = write_file(&dir.join("in/renamed.jpg"), b"image");
+        let hash = content_hash(&input, ChecksumAlgorithm::Sha256).unwrap();
+        let mut stored = record(5);
+
+        stored.destination = Some(dir.join("out/IMG_0001.jpg"));
+        cache.put_cache_record(
+            Path::new("IMG_0001.jpg"),
+            &stored,
+            Some(&hash),
+            None,
+        ).unwrap();
+
+        let mut handle = handle_for(&cache, "renamed.jpg", 5);
+        let found = cache
+            .find_by_hash(&mut handle, &input, ChecksumAlgorithm::Sha256)
+            .unwrap();
+
+        let key = build_key(cache.volume_id(), Path::new("IMG_0001.jpg"));
+        assert_eq!(found, Some(key));
+        assert_eq!(handle.hash, Some(hash));
+        assert_eq!(handle.record.destination, stored.destination);
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn find_by_hash_ignores_own_key() {
+        let dir = work_dir("hash-index-own");
+        let cache = open_cache(&dir);
+        let input = write_file(&dir.join("in/IMG_0001.jpg"), b"image");
+        let hash = content_hash(&input, ChecksumAlgorithm::Sha256).unwrap();
+
+        cache.put_cache_record(
+            Path::new("IMG_0001.jpg"),
+            &record(5),
+            Some(&hash),
+            None,
+        ).unwrap();
+
+        let mut handle = handle_for(&cache, "IMG_0001.jpg", 5);
+        let found = cache
+            .find_by_hash(&mut handle, &input, ChecksumAlgorithm::Sha256)
+            .unwrap();
+
+        assert_eq!(found, None);
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn commit_keeps_hash_index_consistent_with_cache_table() {
+        let dir = work_dir("hash-index-commit");
+        let cache = open_cache(&dir);
+        let first = write_file(&dir.join("in/a.jpg"), b"image");
+        let second = write_file(&dir.join("in/b.jpg"), b"image");
+        let algo = ChecksumAlgorithm::Sha256;
+
+        /*
+         * 1件目の登録
+         */
+        let mut handle = handle_for(&cache, "a.jpg", 5);
+        let found = cache.find_by_hash(&mut handle, &first, algo).unwrap();
+        assert_eq!(found, None);
+        cache.commit(handle).unwrap();
+
+        /*
+         * 同じ内容の2件目の登録(索引は後から登録したものを指す)
+         */
+        let mut handle = handle_for(&cache, "b.jpg", 5);
+        let found = cache.find_by_hash(&mut handle, &second, algo).unwrap();
+        let key = build_key(cache.volume_id(), Path::new("a.jpg"));
+        assert_eq!(found, Some(key));
+        cache.commit(handle).unwrap();
+
+        /*
+         * 索引の全ての値がキャッシュのレコードを指していることの確認
+         */
+        let txn = cache.db.begin_read().unwrap();
+        let index = txn.open_table(HASH_TABLE).unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+        let hash = content_hash(&first, algo).unwrap();
+
+        let key = build_key(cache.volume_id(), Path::new("b.jpg"));
+
+        assert_eq!(index.iter().unwrap().count(), 1);
+        assert_eq!(index.get(&hash).unwrap().map(|key| key.value()), Some(key));
+
+        for item in index.iter().unwrap() {
+            let (_, key) = item.unwrap();
+            assert!(table.get(&key.value()).unwrap().is_some());
+        }
+
+        drop((index, table, txn, cache));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn open_dry_run_leaves_database_file_untouched() {
+        let dir = work_dir("dry-run");
+        let db_path = dir.join("cache.redb");
+        let input = dir.join("in");
+
+        {
+            let cache = Cache::open(&db_path, CacheEvalMode::Shallow, &input)
+                .unwrap();
+            cache.put_cache_record(Path::new("a.jpg"), &record(1), None, None)
+                .unwrap();
+        }
+
+        let bytes = std::fs::read(&db_path).unwrap();
+        let mtime = db_path.metadata().unwrap().modified().unwrap();
+
+        {
+            let cache = Cache::open_dry_run(
+                &db_path,
+                CacheEvalMode::Shallow,
+                &input,
+            ).unwrap();
+
+            // 既存のレコードは読み出せ、書き込みはメモリ上に留まる
+            assert!(cache.get_cache_record(Path::new("a.jpg"))
+                .unwrap()
+                .is_some());
+
+            cache.put_cache_record(Path::new("b.jpg"), &record(2), None, None)
+                .unwrap();
+            cache.flush_stats().unwrap();
+        }
+
+        assert_eq!(std::fs::read(&db_path).unwrap(), bytes);
+        assert_eq!(db_path.metadata().unwrap().modified().unwrap(), mtime);
+
+        let cache = Cache::open(&db_path, CacheEvalMode::Shallow, &input)
+            .unwrap();
+        assert!(cache.get_cache_record(Path::new("b.jpg")).unwrap().is_none());
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn open_dry_run_without_database_starts_empty() {
+        let dir = work_dir("dry-run-empty");
+        let db_path = dir.join("cache.redb");
+        let input = dir.join("in");
+
+        let cache = Cache::open_dry_run(
+            &db_path,
+            CacheEvalMode::Shallow,
+            &input,
+        ).unwrap();
+
+        assert!(cache.get_cache_record(Path::new("a.jpg")).unwrap().is_none());
+        assert!(!db_path.exists());
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    ///
+    /// ボリュームIDの移行のテスト用のデータベースを作成する
+    ///
+    fn volume_db(dir: &Path) -> Database {
+        let db = open_database(&dir.join("cache.redb")).unwrap();
+        let txn = db.begin_write().unwrap();
+
+        {
+            let mut table = txn.open_table(TABLE).unwrap();
+            table.insert("1234abcd:a.jpg".to_string(), record(1)).unwrap();
+            table.insert("1234abcd:b.jpg".to_string(), record(2)).unwrap();
+            table.insert("{guid}:b.jpg".to_string(), record(3)).unwrap();
+            table.insert("5678ef01:c.jpg".to_string(), record(4)).unwrap();
+
+            let mut index = txn.open_table(HASH_TABLE).unwrap();
+            index.insert("h1".to_string(), "1234abcd:a.jpg".to_string())
+                .unwrap();
+            index.insert("h4".to_string(), "5678ef01:c.jpg".to_string())
+                .unwrap();
+        }
+
+        txn.commit().unwrap();
+        db
+    }
+
+    ///
+    /// レコードのキーとファイルサイズ、索引の内容を読み出す
+    ///
+    fn volume_entries(db: &Database)
+        -> (Vec<(String, u64)>, Vec<(String, String)>)
+    {
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+        let index = txn.open_table(HASH_TABLE).unwrap();
+
+        let records = table.iter().unwrap()
+            .map(|item| {
+                let (key, value) = item.unwrap();
+                (key.value(), value.value().file_size)
+            })
+            .collect();
+
+        let hashes = index.iter().unwrap()
+            .map(|item| {
+                let (hash, key) = item.unwrap();
+                (hash.value(), key.value())
+            })
+            .collect();
+
+        (records, hashes)
+    }
+
+    #[test]
+    fn rekey_volume_rewrites_keys_and_is_idempotent() {
+        let dir = work_dir("rekey");
+        let db = volume_db(&dir);
+
+        for expect in [2, 0] {
+            let txn = db.begin_write().unwrap();
+            let count = rekey_volume(&txn, "1234abcd", "{guid}").unwrap();
+            txn.commit().unwrap();
+
+            assert_eq!(count, expect);
+
+            // 既存の新しいキーのレコード(b.jpg)はそのまま残る
+            let (records, hashes) = volume_entries(&db);
+            assert_eq!(records, vec![
+                ("5678ef01:c.jpg".to_string(), 4),
+                ("{guid}:a.jpg".to_string(), 1),
+                ("{guid}:b.jpg".to_string(), 3),
+            ]);
+            assert_eq!(hashes, vec![
+                ("h1".to_string(), "{guid}:a.jpg".to_string()),
+                ("h4".to_string(), "5678ef01:c.jpg".to_string()),
+            ]);
+        }
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn migrate_volume_id_runs_once_per_serial() {
+        let dir = work_dir("migrate");
+        let db = volume_db(&dir);
+
+        assert_eq!(migrate_volume_id(&db, "1234abcd", "{guid}").unwrap(), 2);
+        let migrated = volume_entries(&db);
+
+        // 同じボリュームでも、同じシリアル番号の別のボリュームでも再移行しない
+        assert_eq!(migrate_volume_id(&db, "1234abcd", "{guid}").unwrap(), 0);
+        assert_eq!(migrate_volume_id(&db, "1234abcd", "{other}").unwrap(), 0);
+        assert_eq!(volume_entries(&db), migrated);
+
+        // シリアル番号を持たないボリュームは移行しない
+        assert_eq!(migrate_volume_id(&db, "00000000", "{guid}").unwrap(), 0);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
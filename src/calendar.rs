@@ -0,0 +1,210 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! カレンダー(ICS形式)のイベントを取り扱うモジュール
+//!
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::{
+    DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use ical::parser::ical::component::IcalEvent;
+use ical::IcalParser;
+
+/// 日時の値の書式
+const DATETIME: &str = "%Y%m%dT%H%M%S";
+
+///
+/// カレンダーのイベント1件分の情報
+///
+#[derive(Debug, Clone)]
+struct Event {
+    /// 開始日時
+    start: DateTime<Local>,
+
+    /// 終了日時(この日時を含まない)
+    end: DateTime<Local>,
+
+    /// イベントのタイトル
+    summary: String,
+}
+
+///
+/// 読み込んだカレンダー
+///
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Calendar {
+    /// イベントの一覧
+    events: Vec<Event>,
+}
+
+impl Calendar {
+    ///
+    /// ICS形式のファイルを読み込む
+    ///
+    /// # 引数
+    /// * `path` - ICSファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は読み込んだカレンダーを`Ok()`でラップして返す。失敗
+    /// した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// タイトルまたは開始日時を持たないイベントは無視する。繰り返しの指定
+    /// (RRULE)には対応しない。
+    ///
+    pub(crate) fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+        let mut events = Vec::new();
+
+        for calendar in IcalParser::new(reader) {
+            let calendar = match calendar {
+                Ok(calendar) => calendar,
+                Err(err) => {
+                    return Err(anyhow!(
+                        "read {} failed: {}",
+                        path.display(),
+                        err
+                    ));
+                }
+            };
+
+            events.extend(calendar.events.iter().filter_map(parse_event));
+        }
+
+        Ok(Self { events })
+    }
+
+    ///
+    /// 指定日時を含むイベントのタイトルを求める
+    ///
+    /// # 引数
+    /// * `datetime` - 撮影日時
+    ///
+    /// # 戻り値
+    /// 撮影日時を含むイベントのタイトル(該当するイベントが無い場合はNone)
+    ///
+    /// # 注記
+    /// 複数のイベントが該当する場合は期間の最も短いものを採用する(終日のイベ
+    /// ントの中に時間指定のイベントがある場合は後者を優先する)。
+    ///
+    pub(crate) fn event_at(&self, datetime: &DateTime<Local>) -> Option<&str> {
+        self.events
+            .iter()
+            .filter(|event| event.start <= *datetime && *datetime < event.end)
+            .min_by_key(|event| event.end - event.start)
+            .map(|event| event.summary.as_str())
+    }
+}
+
+///
+/// イベント情報を変換する
+///
+/// # 引数
+/// * `event` - パーサが返したイベント情報
+///
+/// # 戻り値
+/// 変換したイベント情報(必要な情報が揃わない場合はNone)
+///
+fn parse_event(event: &IcalEvent) -> Option<Event> {
+    let mut start = None;
+    let mut end = None;
+    let mut summary = None;
+
+    for prop in &event.properties {
+        let value = match prop.value.as_deref() {
+            Some(value) => value,
+            None => continue,
+        };
+
+        match prop.name.as_str() {
+            "DTSTART" => start = parse_time(value),
+            "DTEND" => end = parse_time(value),
+            "SUMMARY" => summary = Some(unescape(value)),
+            _ => {}
+        }
+    }
+
+    let (start, all_day) = start?;
+    let summary = summary.filter(|summary| !summary.trim().is_empty())?;
+
+    // 終了日時が無い場合、終日のイベントは1日、時刻指定のイベントは開始時点
+    // のみとする
+    let end = match end {
+        Some((end, _)) => end,
+        None if all_day => start + Duration::days(1),
+        None => start,
+    };
+
+    Some(Event { start, end, summary })
+}
+
+///
+/// 日時の値を変換する
+///
+/// # 引数
+/// * `value` - ICS形式の日付(YYYYMMDD)または日時(YYYYMMDDThhmmss[Z])
+///
+/// # 戻り値
+/// 変換した日時と終日指定か否かのフラグ(変換できない場合はNone)
+///
+/// # 注記
+/// UTC指定(末尾のZ)の無い日時はローカル時刻として扱う(TZIDによるタイムゾー
+/// ンの指定は考慮しない)。
+///
+fn parse_time(value: &str) -> Option<(DateTime<Local>, bool)> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        let datetime = date.and_hms_opt(0, 0, 0)?;
+        return Some((Local.from_local_datetime(&datetime).earliest()?, true));
+    }
+
+    if let Some(value) = value.strip_suffix('Z') {
+        let datetime = NaiveDateTime::parse_from_str(value, DATETIME).ok()?;
+        let datetime = Utc.from_utc_datetime(&datetime);
+        return Some((datetime.with_timezone(&Local), false));
+    }
+
+    let datetime = NaiveDateTime::parse_from_str(value, DATETIME).ok()?;
+    Some((Local.from_local_datetime(&datetime).earliest()?, false))
+}
+
+///
+/// テキスト値のエスケープを解除する
+///
+/// # 引数
+/// * `value` - ICS形式のテキスト値
+///
+/// # 戻り値
+/// エスケープを解除した文字列
+///
+fn unescape(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => text.push(' '),
+            Some(c) => text.push(c),
+            None => {}
+        }
+    }
+
+    text
+}
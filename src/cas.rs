@@ -0,0 +1,198 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 内容のハッシュ値による格納(コンテントアドレス方式)を行うモジュール
+//!
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::info;
+use sha2::{Digest, Sha256};
+
+use crate::cmd_args::Options;
+use crate::error::ImportError;
+use crate::mkdir;
+
+/// 取り込み中のファイルを書き出す一時ファイルの名前
+const INCOMING_NAME: &str = ".incoming";
+
+///
+/// ファイルをオブジェクトストアに格納し、振り分け先にリンクを作成する
+///
+/// # 引数
+/// * `src` - 格納するファイルのパス
+/// * `dir` - 振り分け先のディレクトリ
+/// * `name` - 振り分け先でのファイル名
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は振り分け先に作成したリンクのパスと、読み出した内容の
+/// SHA-256ハッシュ値の16進表記の組を`Ok()`でラップして返す。失敗した場合は
+/// エラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 読み出した内容とハッシュ値が食い違わないよう、ハッシュ値はオブジェクトスト
+/// ア内の一時ファイルへのコピーと同時に求める(コピーの照合にもこれを用いる)。
+/// 同じ内容のオブジェクトが既にある場合は一時ファイルを破棄して既存のものを
+/// 共有する。
+///
+pub(crate) fn distribute(src: &Path, dir: &Path, name: &str, opts: &Options)
+    -> Result<(PathBuf, String)>
+{
+    /*
+     * オブジェクトストアへの格納
+     */
+    let store = &opts.cas_dir();
+
+    if let Err(err) = std::fs::create_dir_all(store) {
+        return Err(anyhow!("create directory failed: {}", err));
+    }
+
+    let tmp = store.join(INCOMING_NAME);
+
+    let hash = match copy_with_digest(src, &tmp) {
+        Ok(hash) => hash,
+        Err(err) => {
+            let _ = std::fs::remove_file(&tmp);
+            let context = format!("copy to {} failed", tmp.display());
+            return Err(match err.downcast::<io::Error>() {
+                Ok(err) => ImportError::io(context, err).into(),
+                Err(err) => anyhow!("{}: {}", context, err),
+            });
+        }
+    };
+
+    let object = object_path(store, &hash, name);
+
+    if object.exists() {
+        std::fs::remove_file(&tmp)?;
+        info!("deduplicated {} as {}", src.display(), object.display());
+    } else {
+        if let Some(parent) = object.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::rename(&tmp, &object)?;
+    }
+
+    /*
+     * 振り分け先へのリンクの作成
+     */
+    mkdir::ensure(dir, opts)?;
+
+    let dst = dir.join(name);
+
+    if dst.symlink_metadata().is_ok() {
+        std::fs::remove_file(&dst)?;
+    }
+
+    link(&object, &dst)?;
+
+    info!("linked {} to {}", src.display(), dst.display());
+
+    Ok((dst, hash))
+}
+
+///
+/// オブジェクトのパスを求める
+///
+/// # 引数
+/// * `store` - オブジェクトストアのディレクトリ
+/// * `hash` - 内容のハッシュ値の16進表記
+/// * `name` - 振り分け先でのファイル名(拡張子の決定に用いる)
+///
+/// # 戻り値
+/// ハッシュ値の先頭2桁と続く2桁をディレクトリとしたオブジェクトのパス
+/// (`ab/cd/abcdef....jpg`の形式)
+///
+fn object_path(store: &Path, hash: &str, name: &str) -> PathBuf {
+    let file_name = match Path::new(name).extension() {
+        Some(ext) => {
+            format!("{}.{}", hash, ext.to_string_lossy().to_lowercase())
+        }
+
+        None => hash.to_string(),
+    };
+
+    store.join(&hash[..2]).join(&hash[2..4]).join(file_name)
+}
+
+///
+/// ハッシュ値を求めながらファイルをコピーする
+///
+/// # 引数
+/// * `src` - コピー元のファイルのパス
+/// * `dst` - コピー先のファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は内容のSHA-256ハッシュ値の16進表記を`Ok()`でラップして
+/// 返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+fn copy_with_digest(src: &Path, dst: &Path) -> Result<String> {
+    let mut reader = BufReader::new(File::open(src)?);
+    let mut writer = BufWriter::new(File::create(dst)?);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let len = reader.read(&mut buf)?;
+
+        if len == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..len]);
+        writer.write_all(&buf[..len])?;
+    }
+
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+///
+/// オブジェクトへのリンクを作成する
+///
+/// # 引数
+/// * `object` - オブジェクトのパス
+/// * `dst` - 作成するリンクのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// ハードリンクを優先し、作成できない場合(オブジェクトストアと振り分け先が別
+/// のファイルシステムにある場合等)はシンボリックリンクを作成する。シンボリッ
+/// クリンクのリンク先は絶対パスとする。
+///
+fn link(object: &Path, dst: &Path) -> Result<()> {
+    if std::fs::hard_link(object, dst).is_ok() {
+        return Ok(());
+    }
+
+    let target = object.canonicalize()?;
+
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(&target, dst);
+
+    #[cfg(windows)]
+    let result = std::os::windows::fs::symlink_file(&target, dst);
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => Err(anyhow!("link to {} failed: {}", dst.display(), err)),
+    }
+}
@@ -0,0 +1,347 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先フォルダのチェックサムマニフェストを取り扱うモジュール
+//!
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use sha2::{Digest, Sha256};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::cmd_args::ChecksumAlgorithm;
+
+///
+/// ファイルのチェックサムをマニフェストに記録する
+///
+/// # 引数
+/// * `path` - 振り分け先にコピーしたファイルのパス
+/// * `algo` - 用いるハッシュ関数
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// マニフェストはファイルと同じディレクトリに置き、`sha256sum -c`で検証できる
+/// 形式で記述する。マニフェストの名前はハッシュ関数ごとに異なり(後から検証す
+/// る際にハッシュ関数を判別できるようにするため)、BLAKE3のものは`b3sum -c`で
+/// 検証できる。同名のファイルのエントリが既にある場合は置き換える。
+///
+pub(crate) fn record(path: &Path, algo: ChecksumAlgorithm) -> Result<()> {
+    let (dir, name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => (dir, name.to_string_lossy()),
+        _ => return Err(anyhow!("invalid file path: {}", path.display())),
+    };
+
+    let hash = digest_file(path, algo)?;
+    let manifest = dir.join(manifest_name(algo));
+    let entry_name = escape_name(&name);
+
+    /*
+     * 既存のエントリの読み込みと置き換え
+     */
+    let mut lines = if manifest.exists() {
+        std::fs::read_to_string(&manifest)?
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    } else {
+        Vec::new()
+    };
+
+    let line = format_line(&hash, &entry_name);
+
+    match lines
+        .iter()
+        .position(|line| {
+            line_name(line, algo) == Some(entry_name.as_str())
+        })
+    {
+        Some(index) => lines[index] = line,
+        None => lines.push(line),
+    }
+
+    /*
+     * マニフェストの書き出し(一時ファイル経由で置き換える)
+     */
+    let mut body = lines.join("\n");
+    body.push('\n');
+
+    let tmp = tmp_path(&manifest);
+    std::fs::write(&tmp, body)?;
+    std::fs::rename(&tmp, &manifest)?;
+
+    debug!("checksum recorded: {} {}", hash, path.display());
+
+    Ok(())
+}
+
+///
+/// ファイルのハッシュ値を求める
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `algo` - 用いるハッシュ関数
+///
+/// # 戻り値
+/// 処理が成功した場合はハッシュ値の16進表記を`Ok()`でラップして返す。失敗した
+/// 場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// BLAKE3の場合はファイルをメモリにマップし、複数のスレッドで並行して求める。
+///
+pub(crate) fn digest_file(path: &Path, algo: ChecksumAlgorithm)
+    -> Result<String>
+{
+    match algo {
+        ChecksumAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            read_chunks(path, |chunk| hasher.update(chunk))?;
+
+            Ok(hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect())
+        }
+
+        ChecksumAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_mmap_rayon(path)?;
+
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+
+        ChecksumAlgorithm::Xxh3 => {
+            let mut hasher = Xxh3::new();
+            read_chunks(path, |chunk| hasher.update(chunk))?;
+
+            Ok(format!("{:016x}", hasher.digest()))
+        }
+    }
+}
+
+///
+/// ハッシュ値を求めながらファイルをコピーする
+///
+/// # 引数
+/// * `src` - コピー元のファイルのパス
+/// * `dst` - コピー先のファイルのパス
+/// * `algo` - 用いるハッシュ関数
+///
+/// # 戻り値
+/// 処理が成功した場合は読み出した内容のハッシュ値の16進表記を`Ok()`でラップし
+/// て返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// コピーの照合で入力ファイルを読み直さずに済むよう、ハッシュ値はコピーのた
+/// めに読み出した内容から求める。
+///
+pub(crate) fn copy_with_digest(
+    src: &Path,
+    dst: &Path,
+    algo: ChecksumAlgorithm,
+) -> io::Result<String> {
+    let mut reader = BufReader::new(File::open(src)?);
+    let mut writer = BufWriter::new(File::create(dst)?);
+    let mut buf = [0u8; 64 * 1024];
+
+    let mut sha256 = Sha256::new();
+    let mut blake3 = blake3::Hasher::new();
+    let mut xxh3 = Xxh3::new();
+
+    loop {
+        let len = reader.read(&mut buf)?;
+
+        if len == 0 {
+            break;
+        }
+
+        match algo {
+            ChecksumAlgorithm::Sha256 => sha256.update(&buf[..len]),
+            ChecksumAlgorithm::Blake3 => {
+                blake3.update(&buf[..len]);
+            }
+            ChecksumAlgorithm::Xxh3 => xxh3.update(&buf[..len]),
+        }
+
+        writer.write_all(&buf[..len])?;
+    }
+
+    writer.flush()?;
+
+    // std::fs::copyと同様に元のファイルの権限を引き継ぐ
+    let perms = reader.get_ref().metadata()?.permissions();
+    writer.get_ref().set_permissions(perms)?;
+
+    Ok(match algo {
+        ChecksumAlgorithm::Sha256 => sha256
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect(),
+        ChecksumAlgorithm::Blake3 => blake3.finalize().to_hex().to_string(),
+        ChecksumAlgorithm::Xxh3 => format!("{:016x}", xxh3.digest()),
+    })
+}
+
+///
+/// ファイルを先頭から順に読み出す
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `f` - 読み出した断片ごとに呼び出す関数
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn read_chunks<F>(path: &Path, mut f: F) -> Result<()>
+where
+    F: FnMut(&[u8]),
+{
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let len = reader.read(&mut buf)?;
+
+        if len == 0 {
+            break;
+        }
+
+        f(&buf[..len]);
+    }
+
+    Ok(())
+}
+
+///
+/// マニフェストファイルの名前を求める
+///
+/// # 引数
+/// * `algo` - 用いるハッシュ関数
+///
+/// # 戻り値
+/// ハッシュ関数に対応するマニフェストファイルの名前
+///
+fn manifest_name(algo: ChecksumAlgorithm) -> &'static str {
+    match algo {
+        ChecksumAlgorithm::Sha256 => "SHA256SUMS",
+        ChecksumAlgorithm::Blake3 => "B3SUMS",
+        ChecksumAlgorithm::Xxh3 => "XXH3SUMS",
+    }
+}
+
+///
+/// 索引のキーに前置するハッシュ関数の名前を求める
+///
+/// # 引数
+/// * `algo` - 用いるハッシュ関数
+///
+/// # 戻り値
+/// ハッシュ関数の名前(SHA-256の場合はNone)
+///
+/// # 注記
+/// 名前はキャッシュデータベースに記録するキーの一部となるため、列挙子の名
+/// 前やその表記とは独立に固定する。
+///
+pub(crate) fn key_prefix(algo: ChecksumAlgorithm) -> Option<&'static str> {
+    match algo {
+        ChecksumAlgorithm::Sha256 => None,
+        ChecksumAlgorithm::Blake3 => Some("blake3"),
+        ChecksumAlgorithm::Xxh3 => Some("xxh3"),
+    }
+}
+
+///
+/// ハッシュ値の16進表記の桁数を求める
+///
+/// # 引数
+/// * `algo` - 用いるハッシュ関数
+///
+/// # 戻り値
+/// ハッシュ値の16進表記の桁数
+///
+fn hash_len(algo: ChecksumAlgorithm) -> usize {
+    match algo {
+        ChecksumAlgorithm::Sha256 | ChecksumAlgorithm::Blake3 => 64,
+        ChecksumAlgorithm::Xxh3 => 16,
+    }
+}
+
+///
+/// マニフェストの1行を構築する
+///
+/// # 引数
+/// * `hash` - ハッシュ値の16進表記
+/// * `name` - エスケープ済みのファイル名
+///
+/// # 戻り値
+/// マニフェストの1行分の文字列
+///
+/// # 注記
+/// sha256sumの仕様に合わせ、ファイル名をエスケープした場合は行頭に`\`を付与す
+/// る。
+///
+fn format_line(hash: &str, name: &str) -> String {
+    if name.contains('\\') {
+        format!("\\{}  {}", hash, name)
+    } else {
+        format!("{}  {}", hash, name)
+    }
+}
+
+///
+/// マニフェストの1行からファイル名部分を取り出す
+///
+/// # 引数
+/// * `line` - マニフェストの1行
+/// * `algo` - マニフェストのハッシュ関数
+///
+/// # 戻り値
+/// エスケープされたままのファイル名（形式が不正な場合はNone）
+///
+fn line_name(line: &str, algo: ChecksumAlgorithm) -> Option<&str> {
+    let line = line.strip_prefix('\\').unwrap_or(line);
+
+    // ハッシュ値に続く区切り(空白とモード指定文字)の2文字を読み飛ばす
+    line.get(hash_len(algo) + 2..)
+}
+
+///
+/// ファイル名をマニフェスト用にエスケープする
+///
+/// # 引数
+/// * `name` - ファイル名
+///
+/// # 戻り値
+/// バックスラッシュと改行をエスケープしたファイル名
+///
+fn escape_name(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+///
+/// 一時ファイルのパスを生成する
+///
+/// # 引数
+/// * `path` - 置き換え対象のファイルのパス
+///
+/// # 戻り値
+/// 置き換え対象と同じディレクトリに置く一時ファイルのパス
+///
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    PathBuf::from(tmp)
+}
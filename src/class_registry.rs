@@ -0,0 +1,114 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 設定で追加した形式(拡張子ごとの分類と撮影日時の取得元)を管理するモジュール
+//!
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::cmd_args::{DateSource, DatetimeFallback, FileClass, FileKind};
+use crate::{is_jpeg_file, is_raw_file, is_video_file};
+
+///
+/// 設定で追加した形式の登録内容
+///
+#[derive(Debug, Default)]
+pub(crate) struct ClassRegistry {
+    /// 拡張子(小文字)ごとに追加した形式の定義
+    classes: BTreeMap<String, FileClass>,
+}
+
+impl ClassRegistry {
+    ///
+    /// 追加する形式を登録したインスタンスを構築する
+    ///
+    /// # 引数
+    /// * `classes` - 拡張子(小文字)と形式の定義の対応表
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は構築したインスタンスを`Ok()`でラップして返す。失敗
+    /// した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 組み込みで対応している形式(JPEG、RAW、動画)の拡張子は再定義できない。
+    ///
+    pub(crate) fn new(classes: &BTreeMap<String, FileClass>) -> Result<Self> {
+        // 組み込みの形式の判定は空の登録内容で行う
+        let builtin = Self::default();
+
+        for ext in classes.keys() {
+            if ext.is_empty() {
+                return Err(anyhow!("file class with empty extension"));
+            }
+
+            if is_jpeg_file(ext, &builtin)
+                || is_raw_file(ext, &builtin)
+                || is_video_file(ext, &builtin)
+            {
+                return Err(anyhow!(
+                    "file class for built-in extension {}",
+                    ext
+                ));
+            }
+        }
+
+        Ok(Self { classes: classes.clone() })
+    }
+
+    ///
+    /// 拡張子が指定の分類として登録されているか否かを判定する
+    ///
+    /// # 引数
+    /// * `ext` - ファイルの拡張子(大文字小文字は区別しない)
+    /// * `kind` - 分類
+    ///
+    /// # 戻り値
+    /// 指定の分類として登録されている場合は`true`
+    ///
+    pub(crate) fn is_kind(&self, ext: &str, kind: FileKind) -> bool {
+        self.classes
+            .get(&ext.to_lowercase())
+            .is_some_and(|class| class.class == kind)
+    }
+
+    ///
+    /// 指定の分類として登録されている拡張子を列挙する
+    ///
+    /// # 引数
+    /// * `kind` - 分類
+    ///
+    /// # 戻り値
+    /// 登録されている拡張子(小文字)の一覧
+    ///
+    pub(crate) fn exts_of(&self, kind: FileKind) -> Vec<String> {
+        self.classes
+            .iter()
+            .filter(|(_, class)| class.class == kind)
+            .map(|(ext, _)| ext.clone())
+            .collect()
+    }
+
+    ///
+    /// Exif情報の代わりに撮影日時を求める方法を取得する
+    ///
+    /// # 引数
+    /// * `ext` - ファイルの拡張子(大文字小文字は区別しない)
+    ///
+    /// # 戻り値
+    /// 撮影日時の取得元に更新日時またはファイル名が指定されている場合はそれに
+    /// 対応する方法(Exif情報を用いる場合や登録されていない場合はNone)
+    ///
+    pub(crate) fn date_source(&self, ext: &str) -> Option<DatetimeFallback> {
+        match self.classes.get(&ext.to_lowercase())?.date {
+            DateSource::Exif => None,
+            DateSource::Mtime => Some(DatetimeFallback::Mtime),
+            DateSource::Filename => Some(DatetimeFallback::Filename),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! GPS時刻によるカメラの時計のずれの検出を行うモジュール
+//!
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeZone};
+use log::warn;
+
+use crate::exif_view::ExifView;
+
+/// ずれが一定であると判断するために必要なサンプル数
+const MIN_SAMPLES: usize = 3;
+
+/// 中央値と同じずれとみなす許容幅(秒)
+const TOLERANCE: i64 = 60;
+
+///
+/// カメラごとの時計のずれを集計する構造体
+///
+#[derive(Debug, Default)]
+pub(crate) struct ClockTracker {
+    /// カメラごとのずれのサンプル(秒、カメラの時計が進んでいる場合に正)
+    samples: BTreeMap<String, Vec<i64>>,
+}
+
+impl ClockTracker {
+    ///
+    /// ずれのサンプルを追加する
+    ///
+    /// # 引数
+    /// * `camera` - カメラを識別する文字列
+    /// * `offset` - GPS時刻に対するずれ(秒)
+    ///
+    pub(crate) fn add(&mut self, camera: &str, offset: i64) {
+        self.samples.entry(camera.to_string()).or_default().push(offset);
+    }
+
+    ///
+    /// カメラの時計の一定のずれを求める
+    ///
+    /// # 引数
+    /// * `camera` - カメラを識別する文字列
+    ///
+    /// # 戻り値
+    /// ずれが一定であると判断できる場合はその値(秒、サンプルの中央値)。サンプ
+    /// ルが不足している場合やばらつきが大きい場合はNone。
+    ///
+    /// # 注記
+    /// GPSの測位の遅れ等による外れ値を許容するため、サンプルの3/4以上が中央値
+    /// の前後60秒以内に収まっていれば一定とみなす。
+    ///
+    pub(crate) fn offset(&self, camera: &str) -> Option<i64> {
+        let samples = self.samples.get(camera)?;
+
+        if samples.len() < MIN_SAMPLES {
+            return None;
+        }
+
+        let mut sorted = samples.clone();
+        sorted.sort_unstable();
+        let median = sorted[sorted.len() / 2];
+
+        let near = sorted
+            .iter()
+            .filter(|offset| (**offset - median).abs() <= TOLERANCE)
+            .count();
+
+        if near * 4 >= sorted.len() * 3 {
+            Some(median)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// 時計がずれているカメラを警告する
+    ///
+    /// # 引数
+    /// * `threshold` - 警告の対象とするずれの大きさ
+    ///
+    pub(crate) fn warn(&self, threshold: Duration) {
+        for (camera, samples) in &self.samples {
+            if let Some(offset) = self.offset(camera) {
+                if offset.unsigned_abs() >= threshold.as_secs() {
+                    warn!(
+                        "camera clock of {} is off by {:+}s ({} GPS samples)",
+                        camera,
+                        offset,
+                        samples.len()
+                    );
+                }
+            }
+        }
+    }
+}
+
+///
+/// カメラを識別する文字列を求める
+///
+/// # 引数
+/// * `view` - Exif情報から取り出した項目
+///
+/// # 戻り値
+/// 機種名とシリアル番号を連結した文字列
+///
+pub(crate) fn camera_key(view: &ExifView) -> String {
+    format!(
+        "{} ({})",
+        view.camera().as_deref().unwrap_or("unknown"),
+        view.serial.as_deref().unwrap_or("no serial")
+    )
+}
+
+///
+/// GPS時刻に対するカメラの時計のずれを求める
+///
+/// # 引数
+/// * `view` - Exif情報から取り出した項目
+/// * `datetime` - 撮影日時(DateTimeOriginal)
+///
+/// # 戻り値
+/// GPS時刻が記録されている場合はずれ(秒、カメラの時計が進んでいる場合に正)。
+/// 記録されていない場合はNone。
+///
+/// # 注記
+/// 撮影日時のタイムゾーンはOffsetTimeOriginalが記録されている場合はそれを用
+/// い、記録されていない場合はローカルタイムゾーンとみなす。
+///
+pub(crate) fn gps_offset(view: &ExifView, datetime: &DateTime<Local>)
+    -> Option<i64>
+{
+    let gps = view.gps_time?;
+
+    let camera = match view.offset_time {
+        Some(offset) => offset
+            .from_local_datetime(&datetime.naive_local())
+            .single()?
+            .timestamp(),
+        None => datetime.timestamp(),
+    };
+
+    Some(camera - gps.timestamp())
+}
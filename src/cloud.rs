@@ -0,0 +1,289 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! クラウドストレージからの取り込みを行うモジュール
+//!
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+use crate::cmd_args::Options;
+use crate::external::execute;
+use crate::mail;
+use crate::report::{Action, Report, SkipReason};
+use crate::state::StateStore;
+use crate::{finalize, import, is_target_file, prune_expired};
+
+/// ボリュームIDの接頭辞
+const VOLUME_PREFIX: &str = "cloud-";
+
+///
+/// リモートのファイル1件分の情報
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RemoteFile {
+    /// ファイルサイズ(バイト)
+    size: u64,
+
+    /// 更新日時(リモートが返した文字列のまま)
+    mod_time: String,
+}
+
+///
+/// `rclone lsjson`が返すエントリ
+///
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListEntry {
+    /// リモートのフォルダからの相対パス
+    path: String,
+
+    /// ファイルサイズ(バイト)
+    size: u64,
+
+    /// 更新日時
+    mod_time: String,
+}
+
+///
+/// クラウドストレージからの取り込みを実行する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 概要
+/// rcloneでリモートのフォルダのファイルを列挙し、前回までに取り込んだものから
+/// 追加・変更されたファイルのみをステートディレクトリ内の一時フォルダにダウン
+/// ロードして通常の取り込みを行う。キャッシュのボリュームIDにはリモートの名前
+/// から生成したものを用いる。取り込みが終わると一時フォルダは削除する。
+///
+pub(crate) fn run(opts: &Options) -> Result<()> {
+    let remote = opts.cloud_remote().unwrap();
+    let volume_id = volume_id(&remote);
+    let state = StateStore::open(opts.state_dir())?;
+    let state_name = format!("{}.json", volume_id);
+
+    /*
+     * 新規のファイルの列挙
+     */
+    let mut pulled = state
+        .read::<BTreeMap<String, RemoteFile>>(&state_name)?
+        .unwrap_or_default();
+
+    let files = list(&remote)?
+        .into_iter()
+        .filter(|(path, file)| {
+            is_target(path, opts) && pulled.get(path) != Some(file)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    if files.is_empty() {
+        info!("no new files on {}", remote);
+        return Ok(());
+    }
+
+    info!("pulling {} files from {}", files.len(), remote);
+
+    /*
+     * 一時フォルダへのダウンロード
+     */
+    let staging = state.path(&volume_id);
+    let list_path = state.path(&format!("{}.files", volume_id));
+
+    fs::create_dir_all(&staging)?;
+
+    {
+        let mut writer = BufWriter::new(File::create(&list_path)?);
+
+        for path in files.keys() {
+            writeln!(writer, "{}", path)?;
+        }
+
+        writer.flush()?;
+    }
+
+    let result = execute(
+        Command::new("rclone")
+            .arg("copy")
+            .arg(&remote)
+            .arg(&staging)
+            .arg("--files-from")
+            .arg(&list_path)
+            .arg("--no-traverse")
+    );
+
+    let _ = fs::remove_file(&list_path);
+    result?;
+
+    /*
+     * 取り込みの実行
+     */
+    let opts = opts.with_input_path(staging.clone());
+    let cache = Cache::open_volume(
+        opts.cache_db_path(),
+        opts.cache_eval_mode(),
+        volume_id,
+        staging.clone(),
+    )?;
+
+    let report = import(&opts, &cache).inspect_err(|err| {
+        mail::notify_failure(&opts, err);
+    })?;
+
+    /*
+     * 取り込んだファイルの記録
+     */
+    let done = done_files(&report);
+
+    for (path, file) in files {
+        if done.contains(staging.join(&path).as_path()) {
+            pulled.insert(path, file);
+        }
+    }
+
+    state.write(&state_name, &pulled)?;
+
+    if let Err(err) = fs::remove_dir_all(&staging) {
+        return Err(anyhow!("remove {} failed: {}", staging.display(), err));
+    }
+
+    finalize(&opts, &report)?;
+    prune_expired(&opts, &cache, &report)
+}
+
+///
+/// リモートのフォルダのファイルを列挙する
+///
+/// # 引数
+/// * `remote` - rcloneのリモートのパス(`<REMOTE>:<PATH>`の形式)
+///
+/// # 戻り値
+/// 処理が成功した場合は相対パスとファイル情報の組の一覧を`Ok()`でラップして返
+/// す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+fn list(remote: &str) -> Result<Vec<(String, RemoteFile)>> {
+    let output = execute(
+        Command::new("rclone")
+            .arg("lsjson")
+            .arg("--recursive")
+            .arg("--files-only")
+            .arg(remote)
+    )?;
+
+    let entries = match serde_json::from_str::<Vec<ListEntry>>(&output) {
+        Ok(entries) => entries,
+        Err(err) => return Err(anyhow!("list {} failed: {}", remote, err)),
+    };
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| {
+            let file = RemoteFile {
+                size: entry.size,
+                mod_time: entry.mod_time,
+            };
+
+            (entry.path, file)
+        })
+        .collect())
+}
+
+///
+/// ダウンロードの対象とするファイルかの判定
+///
+/// # 引数
+/// * `path` - リモートのフォルダからの相対パス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 取り込み対象の形式のファイル(画像以外のファイルの保全が指定されている場合
+/// は拡張子を持つ全てのファイル)であれば`true`
+///
+fn is_target(path: &str, opts: &Options) -> bool {
+    match Path::new(path).extension() {
+        Some(ext) => {
+            opts.copy_unknown_dir().is_some()
+                || is_target_file(&ext.to_string_lossy(), opts)
+        }
+
+        None => false,
+    }
+}
+
+///
+/// 取り込みが済んだファイルの一覧を求める
+///
+/// # 引数
+/// * `report` - 取り込みの実行結果
+///
+/// # 戻り値
+/// 次回以降にダウンロードする必要の無いファイルのパスの集合
+///
+/// # 注記
+/// 処理に失敗したもの、撮影日や画像の向きが対象外でスキップしたもの、コピー
+/// 中に更新されたもの、処理件数の上限によって処理しなかったものは次回も対象と
+/// する。
+///
+fn done_files(report: &Report) -> HashSet<&Path> {
+    report
+        .entries()
+        .filter(|entry| match entry.action {
+            Action::Copied => true,
+            Action::Skipped => !matches!(
+                entry.reason,
+                Some(
+                    SkipReason::OutOfRange
+                        | SkipReason::Orientation
+                        | SkipReason::ModifiedDuringCopy
+                )
+            ),
+            Action::Failed => false,
+        })
+        .map(|entry| entry.source.as_path())
+        .collect()
+}
+
+///
+/// リモートのパスからボリュームIDを生成する
+///
+/// # 引数
+/// * `remote` - rcloneのリモートのパス
+///
+/// # 戻り値
+/// 英数字、ハイフン、アンダースコア以外の文字を`_`に置き換えたボリュームID
+///
+/// # 注記
+/// キャッシュのキーはボリュームIDと相対パスを`:`で連結したものであるため、ボ
+/// リュームIDには`:`を含めない。
+///
+fn volume_id(remote: &str) -> String {
+    let name = remote
+        .trim_end_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    format!("{}{}", VOLUME_PREFIX, name)
+}
@@ -0,0 +1,226 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! サブコマンドの定義をまとめたモジュール
+//!
+
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+
+///
+/// サブコマンドを指し示す列挙子
+///
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum Command {
+    /// キャッシュデータベースの操作
+    #[command(subcommand)]
+    Cache(CacheCommand),
+
+    /// 入力ディレクトリ中のファイルとキャッシュの差分を表示する
+    Diff(DiffArgs),
+
+    /// 指定したファイルに対する処理内容を詳細に表示する
+    Why(WhyArgs),
+
+    /// 取り込みの記録からカメラごとの使用状況を表示する
+    Stats(StatsArgs),
+
+    /// 現在の設定で振り分けた場合のファイル名の衝突を検査する
+    AuditNames(AuditNamesArgs),
+
+    /// マウントされている全てのカードから順に取り込む
+    ImportAll(ImportAllArgs),
+}
+
+///
+/// キャッシュ操作のサブコマンドを指し示す列挙子
+///
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum CacheCommand {
+    /// キャッシュレコードを一覧表示する
+    List(ListArgs),
+
+    /// 記録から指定の日数が経過したキャッシュレコードを削除する
+    Prune(PruneArgs),
+
+    /// ボリューム単位またはデータベース全体のキャッシュレコードを削除する
+    Clear(ClearArgs),
+
+    /// キャッシュデータベースの統計情報を表示する
+    Stats,
+
+    /// 条件に合致するキャッシュレコードを削除する
+    Invalidate(InvalidateArgs),
+
+    /// 他のキャッシュデータベースのレコードを取り込む
+    Merge(MergeArgs),
+
+    /// キャッシュデータベースをバックアップする
+    Backup(BackupArgs),
+
+    /// バックアップからキャッシュデータベースを復元する
+    Restore(RestoreArgs),
+
+    /// 入力ディレクトリと振り分け先からキャッシュレコードを再構築する
+    Rebuild(RebuildArgs),
+
+    /// 振り分け先に格納済みのファイルを重複の検出の索引に登録する
+    IndexArchive,
+}
+
+///
+/// `cache invalidate`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct InvalidateArgs {
+    /// 対象とするボリュームID
+    #[arg(long = "volume", value_name = "ID")]
+    pub(crate) volume: Option<String>,
+
+    /// 対象とする相対パスのグロブパターン（ボリュームのマウントポイントを基点
+    /// とする）
+    #[arg(long = "path", value_name = "GLOB")]
+    pub(crate) path: Option<String>,
+
+    /// 対象とする撮影日付の範囲（FROM..TO形式、YYYY-MM-DD、FROMは含みTOは含ま
+    /// ない、いずれも省略可）
+    #[arg(long = "date-range", value_name = "FROM..TO")]
+    pub(crate) date_range: Option<String>,
+}
+
+///
+/// `cache list`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ListArgs {
+    /// 対象とするボリュームID
+    #[arg(long = "volume", value_name = "ID")]
+    pub(crate) volume: Option<String>,
+
+    /// 対象とする相対パスのグロブパターン（ボリュームのマウントポイントを基点
+    /// とする）
+    #[arg(long = "path", value_name = "GLOB")]
+    pub(crate) path: Option<String>,
+}
+
+///
+/// `cache prune`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct PruneArgs {
+    /// 削除の対象とする記録からの経過日数
+    #[arg(long = "older-than", value_name = "DAYS")]
+    pub(crate) older_than: u32,
+
+    /// 対象とするボリュームID
+    #[arg(long = "volume", value_name = "ID")]
+    pub(crate) volume: Option<String>,
+}
+
+///
+/// `cache clear`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ClearArgs {
+    /// 対象とするボリュームID（省略時はデータベース全体）
+    #[arg(long = "volume", value_name = "ID")]
+    pub(crate) volume: Option<String>,
+}
+
+///
+/// `diff`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct DiffArgs {
+    /// 比較する入力ディレクトリのパス
+    #[arg(value_name = "DIR")]
+    pub(crate) input_path: PathBuf,
+}
+
+///
+/// `why`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct WhyArgs {
+    /// 対象とするファイルのパス
+    #[arg(value_name = "FILE")]
+    pub(crate) path: PathBuf,
+}
+
+///
+/// `stats`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct StatsArgs {
+    /// 撮影の無い日を列挙する日付の範囲（FROM..TO形式、YYYY-MM-DD、FROMは含
+    /// みTOは含まない、いずれも省略可）
+    #[arg(long = "gaps", value_name = "FROM..TO")]
+    pub(crate) gaps: Option<String>,
+}
+
+///
+/// `audit-names`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct AuditNamesArgs {
+    /// 検査に用いる入力ディレクトリのパス（省略時はキャッシュデータベースの
+    /// 記録を用いる）
+    #[arg(value_name = "DIR")]
+    pub(crate) input_path: Option<PathBuf>,
+}
+
+///
+/// `import-all`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ImportAllArgs {
+    /// カードを探すディレクトリ（/Volumes等）またはカードのパスのグロブパ
+    /// ターン（複数指定可）
+    #[arg(value_name = "DIR", required = true)]
+    pub(crate) roots: Vec<String>,
+}
+
+///
+/// `cache merge`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct MergeArgs {
+    /// 取り込むキャッシュデータベースファイルのパス
+    #[arg(value_name = "FILE")]
+    pub(crate) source: PathBuf,
+}
+
+///
+/// `cache backup`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct BackupArgs {
+    /// バックアップファイルの出力先パス
+    #[arg(value_name = "FILE")]
+    pub(crate) dest: PathBuf,
+}
+
+///
+/// `cache restore`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct RestoreArgs {
+    /// 復元に使用するバックアップファイルのパス
+    #[arg(value_name = "FILE")]
+    pub(crate) source: PathBuf,
+}
+
+///
+/// `cache rebuild`サブコマンドの引数をまとめた構造体
+///
+#[derive(Args, Debug, Clone)]
+pub(crate) struct RebuildArgs {
+    /// 再構築の対象とする入力ディレクトリのパス
+    #[arg(value_name = "DIR")]
+    pub(crate) input_path: PathBuf,
+}
@@ -8,10 +8,12 @@
 //! コンフィギュレーションファイル関連の処理をまとめたモジュール
 //!
 
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 
 use anyhow::Result;
+use chrono::Local;
 use serde::Serialize;
 use serde::Deserialize;
 
@@ -30,57 +32,895 @@ pub(super) struct Config {
 
     /// キャッシュ情報の格納先
     cache_info: Option<CacheInfo>,
+
+    /// 処理方法に関する情報の格納先
+    process_info: Option<ProcessInfo>,
+
+    /// デーモンモードに関する情報の格納先
+    daemon_info: Option<DaemonInfo>,
+
+    /// メールによる通知に関する情報の格納先
+    mail_info: Option<MailInfo>,
+
+    /// 設定ファイル自体の取り扱いに関する情報の格納先
+    config_info: Option<ConfigInfo>,
 }
 
-impl Config {
+impl Config {
+    ///
+    /// ログレベルへのアクセサ
+    ///
+    pub(super) fn log_level(&self) -> Option<LogLevel> {
+        self.log_info.level.clone()
+    }
+
+    ///
+    /// ログの出力先へのアクセサ
+    ///
+    pub(super) fn log_output(&self) -> Option<PathBuf> {
+        self.log_info.output.clone()
+    }
+
+    ///
+    /// RAWファイル格納先へのアクセサ
+    ///
+    pub(super) fn raw_output_path(&self) -> Option<PathBuf> {
+        self.path_info.raw_output_path.clone()
+    }
+
+    ///
+    /// 動画ファイル格納先へのアクセサ
+    ///
+    pub(super) fn video_output_path(&self) -> Option<PathBuf> {
+        self.path_info.video_output_path.clone()
+    }
+
+    ///
+    /// 画像以外のファイルの保全先へのアクセサ
+    ///
+    pub(super) fn copy_unknown(&self) -> Option<PathBuf> {
+        self.path_info.copy_unknown.clone()
+    }
+
+    ///
+    /// オブジェクトストアのパスへのアクセサ
+    ///
+    pub(super) fn cas_dir(&self) -> Option<PathBuf> {
+        self.path_info.cas_dir.clone()
+    }
+
+    ///
+    /// ファイル出力先へのアクセサ
+    ///
+    pub(super) fn output_path(&self) -> Option<PathBuf> {
+        self.path_info.output_path.clone()
+    }
+
+    ///
+    /// パステンプレートへのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先パスのテンプレート文字列（未設定の場合はNone）
+    ///
+    pub(super) fn path_template(&self) -> Option<String> {
+        self.path_info.path_template.clone()
+    }
+
+    ///
+    /// ファイル名テンプレートへのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先のファイル名のテンプレート文字列（未設定の場合はNone）
+    ///
+    pub(super) fn name_template(&self) -> Option<String> {
+        self.path_info.name_template.clone()
+    }
+
+    ///
+    /// カレンダーのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// カレンダー(ICS形式)のパス（未設定の場合はNone）
+    ///
+    pub(super) fn calendar(&self) -> Option<PathBuf> {
+        self.path_info.calendar.clone()
+    }
+
+    ///
+    /// 除外リストのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 受け渡し済みのファイルの一覧のパス（未設定の場合はNone）
+    ///
+    pub(super) fn exclude_names_from(&self) -> Option<PathBuf> {
+        self.path_info.exclude_names_from.clone()
+    }
+
+    ///
+    /// 履歴データベースのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 他のマシンから持ち出したキャッシュデータベースのパス（未設定の場合は
+    /// None）
+    ///
+    pub(super) fn history_db(&self) -> Option<PathBuf> {
+        self.path_info.history_db.clone()
+    }
+
+    ///
+    /// 撮影者名ごとの出力ディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影者名と出力ディレクトリの対応表（未設定の場合はNone）
+    ///
+    pub(super) fn artist_roots(&self) -> Option<BTreeMap<String, PathBuf>> {
+        self.path_info.artist_roots.clone()
+    }
+
+    ///
+    /// シャードの一覧へのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先とするディレクトリの一覧（未設定の場合はNone）
+    ///
+    pub(super) fn shards(&self) -> Option<Vec<PathBuf>> {
+        self.path_info.shards.clone()
+    }
+
+    ///
+    /// 撮影年の範囲ごとの出力ディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影年の範囲と出力ディレクトリの対応表（未設定の場合はNone）
+    ///
+    pub(super) fn year_roots(&self) -> Option<BTreeMap<String, PathBuf>> {
+        self.path_info.year_roots.clone()
+    }
+
+    ///
+    /// 拡張子ごとの振り分け先へのアクセサ
+    ///
+    /// # 戻り値
+    /// 拡張子と振り分け先の対応表（未設定の場合はNone）
+    ///
+    pub(super) fn ext_routes(&self) -> Option<BTreeMap<String, PathBuf>> {
+        self.path_info.ext_routes.clone()
+    }
+
+    ///
+    /// 追加する形式の定義へのアクセサ
+    ///
+    /// # 戻り値
+    /// 拡張子と形式の定義の対応表（未設定の場合はNone）
+    ///
+    pub(super) fn file_classes(
+        &self,
+    ) -> Option<BTreeMap<String, super::FileClass>> {
+        self.path_info.file_classes.clone()
+    }
+
+    ///
+    /// ファイルの分類ごとの振り分け先へのアクセサ
+    ///
+    /// # 戻り値
+    /// ティアの一覧（未設定の場合はNone）
+    ///
+    pub(super) fn tiers(&self) -> Option<Vec<super::Tier>> {
+        self.path_info.tiers.clone()
+    }
+
+    ///
+    /// キャッシュデータベースファイルのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// キャッシュデータベースファイルのパス（未設定の場合はNone）
+    ///
+    pub(super) fn cache_db_path(&self) -> Option<PathBuf> {
+        self.path_info.cache_db_path.clone()
+    }
+
+    ///
+    /// キャッシュ評価モードへのアクセサ
+    ///
+    /// # 戻り値
+    /// キャッシュ評価モード（未設定の場合はNone）
+    ///
+    pub(super) fn cache_eval_mode(&self) -> Option<super::CacheEvalMode> {
+        self.cache_info
+            .as_ref()
+            .and_then(|info| info.cache_eval_mode)
+    }
+
+    ///
+    /// 内容のハッシュ値による索引の使用フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 索引を用いるか否か（未設定の場合はNone）
+    ///
+    pub(super) fn hash_index(&self) -> Option<bool> {
+        self.cache_info
+            .as_ref()
+            .and_then(|info| info.hash_index)
+    }
+
+    ///
+    /// 振り分け先全体での重複の検出フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 重複の検出を行うか否か（未設定の場合はNone）
+    ///
+    pub(super) fn dedup(&self) -> Option<bool> {
+        self.cache_info
+            .as_ref()
+            .and_then(|info| info.dedup)
+    }
+
+    ///
+    /// 撮影日の区切り時刻へのアクセサ
+    ///
+    /// # 戻り値
+    /// HH:MM形式の時刻文字列（未設定の場合はNone）
+    ///
+    pub(super) fn day_start(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.day_start.clone())
+    }
+
+    ///
+    /// 撮影日時の解析に追加で用いる書式へのアクセサ
+    ///
+    /// # 戻り値
+    /// strftime形式の書式の一覧（未設定の場合はNone）
+    ///
+    pub(super) fn datetime_formats(&self) -> Option<Vec<String>> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.datetime_formats.clone())
+    }
+
+    ///
+    /// 走査で除外するグロブパターンへのアクセサ
+    ///
+    /// # 戻り値
+    /// グロブパターンの一覧（未設定の場合はNone）
+    ///
+    pub(super) fn excludes(&self) -> Option<Vec<String>> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.excludes.clone())
+    }
+
+    ///
+    /// 走査で処理対象とするグロブパターンへのアクセサ
+    ///
+    /// # 戻り値
+    /// グロブパターンの一覧（未設定の場合はNone）
+    ///
+    pub(super) fn includes(&self) -> Option<Vec<String>> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.includes.clone())
+    }
+
+    ///
+    /// Exif情報に撮影日時が無いファイルの取り扱いへのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影日時が無いファイルの取り扱い（未設定の場合はNone）
+    ///
+    pub(super) fn datetime_fallback(&self)
+        -> Option<super::DatetimeFallback>
+    {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.datetime_fallback)
+    }
+
+    ///
+    /// 振り分け先に同名のファイルが存在する場合の取り扱いへのアクセサ
+    ///
+    /// # 戻り値
+    /// 同名のファイルが存在する場合の取り扱い（未設定の場合はNone）
+    ///
+    pub(super) fn on_conflict(&self) -> Option<super::Collision> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.on_conflict)
+    }
+
+    ///
+    /// モーションフォトの取り扱いモードへのアクセサ
+    ///
+    /// # 戻り値
+    /// モーションフォトの取り扱いモード（未設定の場合はNone）
+    ///
+    pub(super) fn motion_photo(&self) -> Option<super::MotionPhotoMode> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.motion_photo)
+    }
+
+    ///
+    /// 入力ファイルの処理順へのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力ファイルの処理順（未設定の場合はNone）
+    ///
+    pub(super) fn order(&self) -> Option<super::ProcessOrder> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.order)
+    }
+
+    ///
+    /// GPS時刻によるカメラの時計のずれの取り扱いへのアクセサ
+    ///
+    /// # 戻り値
+    /// GPS時刻によるカメラの時計のずれの取り扱い（未設定の場合はNone）
+    ///
+    pub(super) fn gps_clock(&self) -> Option<super::GpsClockMode> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.gps_clock)
+    }
+
+    ///
+    /// カメラの時計がずれているとみなす閾値へのアクセサ
+    ///
+    /// # 戻り値
+    /// カメラの時計がずれているとみなす閾値（秒、未設定の場合はNone）
+    ///
+    pub(super) fn gps_clock_threshold(&self) -> Option<u64> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.gps_clock_threshold)
+    }
+
+    ///
+    /// 振り分け先への格納方式へのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先への格納方式（未設定の場合はNone）
+    ///
+    pub(super) fn layout(&self) -> Option<super::StorageLayout> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.layout)
+    }
+
+    ///
+    /// RAWファイルの圧縮レベルへのアクセサ
+    ///
+    /// # 戻り値
+    /// 圧縮レベル（未設定の場合はNone）
+    ///
+    pub(super) fn compress_raw(&self) -> Option<i32> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.compress_raw)
+    }
+
+    ///
+    /// 暗号化の受信者へのアクセサ
+    ///
+    /// # 戻り値
+    /// 暗号化の受信者（未設定の場合はNone）
+    ///
+    pub(super) fn encrypt_recipient(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.encrypt_recipient.clone())
+    }
+
+    ///
+    /// 暗号化に用いるツールへのアクセサ
+    ///
+    /// # 戻り値
+    /// 暗号化に用いるツール（未設定の場合はNone）
+    ///
+    pub(super) fn encrypt_tool(&self) -> Option<super::EncryptTool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.encrypt_tool)
+    }
+
+    ///
+    /// チェックサムマニフェストの記録フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// チェックサムマニフェストを記録するか否か（未設定の場合はNone）
+    ///
+    pub(super) fn checksums(&self) -> Option<bool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.checksums)
+    }
+
+    ///
+    /// チェックサムに用いるハッシュ関数へのアクセサ
+    ///
+    /// # 戻り値
+    /// チェックサムに用いるハッシュ関数（未設定の場合はNone）
+    ///
+    pub(super) fn checksum_algorithm(
+        &self,
+    ) -> Option<super::ChecksumAlgorithm> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.checksum_algorithm)
+    }
+
+    ///
+    /// 取り込みプロファイルへのアクセサ
+    ///
+    /// # 戻り値
+    /// 取り込みプロファイル（未設定の場合はNone）
+    ///
+    pub(super) fn profile(&self) -> Option<super::IngestProfile> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.profile)
+    }
+
+    ///
+    /// シャドウコピー経由での読み出しフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// ロックされたファイルをシャドウコピー経由で読み出すか否か（未設定の場合
+    /// はNone）
+    ///
+    pub(super) fn vss(&self) -> Option<bool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.vss)
+    }
+
+    ///
+    /// 取り込みマーカーの使用フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力ディレクトリの取り込みマーカーを読み書きするか否か（未設定の場合は
+    /// None）
+    ///
+    pub(super) fn card_marker(&self) -> Option<bool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.card_marker)
+    }
+
+    ///
+    /// 入力ファイルの移動フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先に格納した入力ファイルを削除するか否か（未設定の場合は
+    /// None）
+    ///
+    pub(super) fn move_files(&self) -> Option<bool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.move_files)
+    }
+
+    ///
+    /// 入力元の読み出し専用フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力元への書き込みを伴う機能を禁止するか否か（未設定の場合はNone）
+    ///
+    pub(super) fn source_read_only(&self) -> Option<bool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.source_read_only)
+    }
+
+    ///
+    /// ファイルに適用するパーミッションへのアクセサ
+    ///
+    /// # 戻り値
+    /// 8進表記のパーミッション（未設定の場合はNone）
+    ///
+    pub(super) fn file_mode(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.file_mode.clone())
+    }
+
+    ///
+    /// ディレクトリに適用するパーミッションへのアクセサ
+    ///
+    /// # 戻り値
+    /// 8進表記のパーミッション（未設定の場合はNone）
+    ///
+    pub(super) fn dir_mode(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.dir_mode.clone())
+    }
+
+    ///
+    /// 振り分け先の所有者へのアクセサ
+    ///
+    /// # 戻り値
+    /// 所有者のユーザ名またはID（未設定の場合はNone）
+    ///
+    pub(super) fn owner(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.owner.clone())
+    }
+
+    ///
+    /// 振り分け先のグループへのアクセサ
+    ///
+    /// # 戻り値
+    /// グループ名またはID（未設定の場合はNone）
+    ///
+    pub(super) fn group(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.group.clone())
+    }
+
+    ///
+    /// 振り分け先に追加するACLのエントリへのアクセサ
+    ///
+    /// # 戻り値
+    /// ACLのエントリ（未設定の場合はNone）
+    ///
+    pub(super) fn acl(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.acl.clone())
+    }
+
+    ///
+    /// 振り分け先のファイルに付与するFinderタグへのアクセサ
+    ///
+    /// # 戻り値
+    /// Finderタグの指定の一覧（未設定の場合はNone）
+    ///
+    pub(super) fn finder_tags(&self) -> Option<Vec<String>> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.finder_tags.clone())
+    }
+
+    ///
+    /// 取り込み情報ファイルの記録フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先フォルダに取り込み情報ファイルを記録するか否か（未設定の場合
+    /// はNone）
+    ///
+    pub(super) fn folder_info(&self) -> Option<bool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.folder_info)
+    }
+
+    ///
+    /// PAR2リカバリファイルの冗長度へのアクセサ
+    ///
+    /// # 戻り値
+    /// 冗長度（パーセント、未設定の場合はNone）
+    ///
+    pub(super) fn par2(&self) -> Option<u8> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.par2)
+    }
+
+    ///
+    /// スナップショットの作成方法へのアクセサ
+    ///
+    /// # 戻り値
+    /// スナップショットの作成方法（未設定の場合はNone）
+    ///
+    pub(super) fn snapshot(&self) -> Option<super::SnapshotMode> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.snapshot)
+    }
+
+    ///
+    /// スナップショット作成用のフックコマンドへのアクセサ
+    ///
+    /// # 戻り値
+    /// フックコマンド（未設定の場合はNone）
+    ///
+    pub(super) fn snapshot_command(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.snapshot_command.clone())
+    }
+
+    ///
+    /// 入力元の回復を確認する最大回数へのアクセサ
+    ///
+    /// # 戻り値
+    /// 確認の最大回数（未設定の場合はNone）
+    ///
+    pub(super) fn source_retries(&self) -> Option<usize> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.source_retries)
+    }
+
+    ///
+    /// 入力元の接続の確認で応答を待つ時間へのアクセサ
+    ///
+    /// # 戻り値
+    /// 応答を待つ時間（秒、未設定の場合はNone）
+    ///
+    pub(super) fn source_timeout(&self) -> Option<u64> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.source_timeout)
+    }
+
+    ///
+    /// 振り分け先のドライブの状態を確認するフックコマンドへのアクセサ
+    ///
+    /// # 戻り値
+    /// フックコマンド（未設定の場合はNone）
+    ///
+    pub(super) fn health_command(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.health_command.clone())
+    }
+
+    ///
+    /// 振り分け先のドライブの状態を確認する間隔へのアクセサ
+    ///
+    /// # 戻り値
+    /// 確認の間隔（秒、未設定の場合はNone）
+    ///
+    pub(super) fn health_interval(&self) -> Option<u64> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.health_interval)
+    }
+
+    ///
+    /// コピーした全てのファイルの内容を照合するフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 全てのファイルの内容を照合するか否か（未設定の場合はNone）
+    ///
+    pub(super) fn verify(&self) -> Option<bool> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.verify)
+    }
+
+    ///
+    /// コピーしたファイルのうち内容を照合するものの割合へのアクセサ
+    ///
+    /// # 戻り値
+    /// 照合する割合（"5%"等、未設定の場合はNone）
+    ///
+    pub(super) fn verify_sample(&self) -> Option<String> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.verify_sample.clone())
+    }
+
+    ///
+    /// 振り分け先に残す期間へのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先に残す期間（日数、未設定の場合はNone）
+    ///
+    pub(super) fn retain_days(&self) -> Option<u32> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.retain_days)
+    }
+
+    ///
+    /// 保存期間を過ぎたファイルを削除する対象のディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// 削除対象のディレクトリへのパス（未設定の場合はNone）
+    ///
+    pub(super) fn prune_root(&self) -> Option<PathBuf> {
+        self.process_info
+            .as_ref()
+            .and_then(|info| info.prune_root.clone())
+    }
+
     ///
-    /// ログレベルへのアクセサ
+    /// 入力ディレクトリを確認する間隔へのアクセサ
     ///
-    pub(super) fn log_level(&self) -> Option<LogLevel> {
-        self.log_info.level.clone()
+    /// # 戻り値
+    /// 確認間隔（秒、未設定の場合はNone）
+    ///
+    pub(super) fn interval(&self) -> Option<u64> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.interval)
     }
 
     ///
-    /// ログの出力先へのアクセサ
+    /// 取り込みを始めるまでの静止時間へのアクセサ
     ///
-    pub(super) fn log_output(&self) -> Option<PathBuf> {
-        self.log_info.output.clone()
+    /// # 戻り値
+    /// 静止時間（秒、未設定の場合はNone）
+    ///
+    pub(super) fn quiet_period(&self) -> Option<u64> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.quiet_period)
     }
 
     ///
-    /// RAWファイル格納先へのアクセサ
+    /// デーモンモードでの定期取り込みの一覧へのアクセサ
     ///
-    pub(super) fn raw_output_path(&self) -> Option<PathBuf> {
-        self.path_info.raw_output_path.clone()
+    /// # 戻り値
+    /// 定期取り込みの設定の一覧（未設定の場合はNone）
+    ///
+    pub(super) fn schedules(&self) -> Option<Vec<super::Schedule>> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.schedules.clone())
     }
 
     ///
-    /// ファイル出力先へのアクセサ
+    /// デーモンモードでのホットフォルダからの配信の一覧へのアクセサ
     ///
-    pub(super) fn output_path(&self) -> Option<PathBuf> {
-        self.path_info.output_path.clone()
+    /// # 戻り値
+    /// 配信の設定の一覧（未設定の場合はNone）
+    ///
+    pub(super) fn exports(&self) -> Option<Vec<super::Export>> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.exports.clone())
     }
 
     ///
-    /// キャッシュデータベースファイルのパスへのアクセサ
+    /// HTTPエンドポイントの待ち受けアドレスへのアクセサ
     ///
     /// # 戻り値
-    /// キャッシュデータベースファイルのパス（未設定の場合はNone）
+    /// 待ち受けアドレス（未設定の場合はNone）
     ///
-    pub(super) fn cache_db_path(&self) -> Option<PathBuf> {
-        self.path_info.cache_db_path.clone()
+    pub(super) fn http(&self) -> Option<String> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.http.clone())
     }
 
     ///
-    /// キャッシュ評価モードへのアクセサ
+    /// プレビューページ提供フラグへのアクセサ
     ///
     /// # 戻り値
-    /// キャッシュ評価モード（未設定の場合はNone）
+    /// プレビューページを提供するか否か（未設定の場合はNone）
     ///
-    pub(super) fn cache_eval_mode(&self) -> Option<super::CacheEvalMode> {
-        self.cache_info
+    pub(super) fn preview(&self) -> Option<bool> {
+        self.daemon_info
             .as_ref()
-            .and_then(|info| info.cache_eval_mode)
+            .and_then(|info| info.preview)
+    }
+
+    ///
+    /// 制御用ソケットのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 制御用ソケットのパス（未設定の場合はNone）
+    ///
+    pub(super) fn control_socket(&self) -> Option<PathBuf> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.control_socket.clone())
+    }
+
+    ///
+    /// FTPサーバの待ち受けアドレスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 待ち受けアドレス（未設定の場合はNone）
+    ///
+    pub(super) fn ftp(&self) -> Option<String> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.ftp.clone())
+    }
+
+    ///
+    /// FTPサーバへのログインに用いるユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ名（未設定の場合はNone）
+    ///
+    pub(super) fn ftp_user(&self) -> Option<String> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.ftp_user.clone())
+    }
+
+    ///
+    /// FTPサーバへのログインに用いるパスワードへのアクセサ
+    ///
+    /// # 戻り値
+    /// パスワード（未設定の場合はNone）
+    ///
+    pub(super) fn ftp_password(&self) -> Option<String> {
+        self.daemon_info
+            .as_ref()
+            .and_then(|info| info.ftp_password.clone())
+    }
+
+    ///
+    /// SMTPサーバのURLへのアクセサ
+    ///
+    /// # 戻り値
+    /// SMTPサーバのURL（未設定の場合はNone）
+    ///
+    pub(super) fn smtp(&self) -> Option<String> {
+        self.mail_info
+            .as_ref()
+            .and_then(|info| info.smtp.clone())
+    }
+
+    ///
+    /// SMTPサーバの認証に用いるユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ名（未設定の場合はNone）
+    ///
+    pub(super) fn smtp_user(&self) -> Option<String> {
+        self.mail_info
+            .as_ref()
+            .and_then(|info| info.smtp_user.clone())
+    }
+
+    ///
+    /// SMTPサーバの認証に用いるパスワードへのアクセサ
+    ///
+    /// # 戻り値
+    /// パスワード（未設定の場合はNone）
+    ///
+    pub(super) fn smtp_password(&self) -> Option<String> {
+        self.mail_info
+            .as_ref()
+            .and_then(|info| info.smtp_password.clone())
+    }
+
+    ///
+    /// 通知メールの送信元アドレスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 送信元アドレス（未設定の場合はNone）
+    ///
+    pub(super) fn mail_from(&self) -> Option<String> {
+        self.mail_info
+            .as_ref()
+            .and_then(|info| info.mail_from.clone())
+    }
+
+    ///
+    /// 通知メールの宛先アドレスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 宛先アドレスの一覧（未設定の場合はNone）
+    ///
+    pub(super) fn mail_to(&self) -> Option<Vec<String>> {
+        self.mail_info
+            .as_ref()
+            .and_then(|info| info.mail_to.clone())
+    }
+
+    ///
+    /// 設定ファイルのバックアップの保持世代数へのアクセサ
+    ///
+    /// # 戻り値
+    /// 保持する世代数（未設定の場合はNone）
+    ///
+    pub(super) fn config_backups(&self) -> Option<usize> {
+        self.config_info
+            .as_ref()
+            .and_then(|info| info.backups)
     }
 }
 
@@ -104,11 +944,53 @@ struct PathInfo {
     /// RAWファイルの格納先
     raw_output_path: Option<PathBuf>,
 
+    /// 動画ファイルの格納先
+    video_output_path: Option<PathBuf>,
+
+    /// 画像以外のファイルの保全先
+    copy_unknown: Option<PathBuf>,
+
+    /// オブジェクトストアのパス
+    cas_dir: Option<PathBuf>,
+
     /// 出力先
     output_path: Option<PathBuf>,
 
     /// キャッシュデータベースのパス
     cache_db_path: Option<PathBuf>,
+
+    /// 振り分け先パスのテンプレート
+    path_template: Option<String>,
+
+    /// 振り分け先のファイル名のテンプレート
+    name_template: Option<String>,
+
+    /// `{event}`の決定に用いるカレンダーのパス
+    calendar: Option<PathBuf>,
+
+    /// 取り込みから除外する受け渡し済みのファイルの一覧のパス
+    exclude_names_from: Option<PathBuf>,
+
+    /// 取り込み済みか否かの照会に用いる履歴データベースのパス
+    history_db: Option<PathBuf>,
+
+    /// 撮影者名ごとの出力先
+    artist_roots: Option<BTreeMap<String, PathBuf>>,
+
+    /// 撮影年の範囲ごとの出力先
+    year_roots: Option<BTreeMap<String, PathBuf>>,
+
+    /// 出力先の代わりに振り分け先とするボリュームの一覧
+    shards: Option<Vec<PathBuf>>,
+
+    /// 拡張子ごとの振り分け先
+    ext_routes: Option<BTreeMap<String, PathBuf>>,
+
+    /// 拡張子ごとに追加する形式の定義
+    file_classes: Option<BTreeMap<String, super::FileClass>>,
+
+    /// ファイルの分類ごとの振り分け先
+    tiers: Option<Vec<super::Tier>>,
 }
 
 ///
@@ -118,6 +1000,201 @@ struct PathInfo {
 struct CacheInfo {
     /// キャッシュ評価モード
     cache_eval_mode: Option<super::CacheEvalMode>,
+
+    /// 内容のハッシュ値による索引を用いるか否か
+    hash_index: Option<bool>,
+
+    /// 振り分け先全体での重複の検出を行うか否か
+    dedup: Option<bool>,
+}
+
+///
+/// 処理方法に関する設定を格納するサブ構造体
+///
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ProcessInfo {
+    /// 撮影日の区切り時刻（HH:MM形式）
+    day_start: Option<String>,
+
+    /// 撮影日時の解析に追加で用いる書式
+    datetime_formats: Option<Vec<String>>,
+
+    /// 走査で除外するグロブパターン
+    excludes: Option<Vec<String>>,
+
+    /// 走査で処理対象とするグロブパターン
+    includes: Option<Vec<String>>,
+
+    /// Exif情報に撮影日時が無いファイルの取り扱い
+    datetime_fallback: Option<super::DatetimeFallback>,
+
+    /// 振り分け先に同名のファイルが存在する場合の取り扱い
+    on_conflict: Option<super::Collision>,
+
+    /// モーションフォトの取り扱いモード
+    motion_photo: Option<super::MotionPhotoMode>,
+
+    /// 入力ファイルの処理順
+    order: Option<super::ProcessOrder>,
+
+    /// GPS時刻によるカメラの時計のずれの取り扱い
+    gps_clock: Option<super::GpsClockMode>,
+
+    /// カメラの時計がずれているとみなす閾値（秒）
+    gps_clock_threshold: Option<u64>,
+
+    /// 振り分け先への格納方式
+    layout: Option<super::StorageLayout>,
+
+    /// RAWファイルの圧縮レベル
+    compress_raw: Option<i32>,
+
+    /// 暗号化の受信者
+    encrypt_recipient: Option<String>,
+
+    /// 暗号化に用いるツール
+    encrypt_tool: Option<super::EncryptTool>,
+
+    /// チェックサムマニフェストの記録フラグ
+    checksums: Option<bool>,
+
+    /// チェックサムに用いるハッシュ関数
+    checksum_algorithm: Option<super::ChecksumAlgorithm>,
+
+    /// 取り込みプロファイル
+    profile: Option<super::IngestProfile>,
+
+    /// シャドウコピー経由での読み出しフラグ
+    vss: Option<bool>,
+
+    /// 取り込みマーカーの使用フラグ
+    card_marker: Option<bool>,
+
+    /// 入力ファイルの移動フラグ
+    move_files: Option<bool>,
+
+    /// 入力元の読み出し専用フラグ
+    source_read_only: Option<bool>,
+
+    /// ファイルに適用するパーミッション（8進表記）
+    file_mode: Option<String>,
+
+    /// ディレクトリに適用するパーミッション（8進表記）
+    dir_mode: Option<String>,
+
+    /// 振り分け先の所有者
+    owner: Option<String>,
+
+    /// 振り分け先のグループ
+    group: Option<String>,
+
+    /// 振り分け先に追加するACLのエントリ
+    acl: Option<String>,
+
+    /// 振り分け先のファイルに付与するFinderタグ
+    finder_tags: Option<Vec<String>>,
+
+    /// 取り込み情報ファイルの記録フラグ
+    folder_info: Option<bool>,
+
+    /// PAR2リカバリファイルの冗長度（パーセント）
+    par2: Option<u8>,
+
+    /// スナップショットの作成方法
+    snapshot: Option<super::SnapshotMode>,
+
+    /// スナップショット作成用のフックコマンド
+    snapshot_command: Option<String>,
+
+    /// 入力元の回復を確認する最大回数
+    source_retries: Option<usize>,
+
+    /// 入力元の接続の確認で応答を待つ時間（秒）
+    source_timeout: Option<u64>,
+
+    /// 振り分け先のドライブの状態を確認するフックコマンド
+    health_command: Option<String>,
+
+    /// 振り分け先のドライブの状態を確認する間隔（秒）
+    health_interval: Option<u64>,
+
+    /// コピーした全てのファイルの内容を照合するフラグ
+    verify: Option<bool>,
+
+    /// コピーしたファイルのうち内容を照合するものの割合（"5%"等）
+    verify_sample: Option<String>,
+
+    /// 振り分け先に残す期間（日数）
+    retain_days: Option<u32>,
+
+    /// 保存期間を過ぎたファイルを削除する対象のディレクトリ
+    prune_root: Option<PathBuf>,
+}
+
+///
+/// デーモンモードに関する設定を格納するサブ構造体
+///
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct DaemonInfo {
+    /// 入力ディレクトリを確認する間隔（秒）
+    interval: Option<u64>,
+
+    /// 取り込みを始めるまでの静止時間（秒）
+    quiet_period: Option<u64>,
+
+    /// HTTPエンドポイントの待ち受けアドレス
+    http: Option<String>,
+
+    /// 直近の取り込みのプレビューページを提供するか否か
+    preview: Option<bool>,
+
+    /// 制御用ソケットのパス
+    control_socket: Option<PathBuf>,
+
+    /// FTPサーバの待ち受けアドレス
+    ftp: Option<String>,
+
+    /// FTPサーバへのログインに用いるユーザ名
+    ftp_user: Option<String>,
+
+    /// FTPサーバへのログインに用いるパスワード
+    ftp_password: Option<String>,
+
+    /// 定期取り込みの一覧
+    schedules: Option<Vec<super::Schedule>>,
+
+    /// ホットフォルダからの配信の一覧
+    exports: Option<Vec<super::Export>>,
+}
+
+///
+/// メールによる通知に関する設定を格納するサブ構造体
+///
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct MailInfo {
+    /// SMTPサーバのURL
+    smtp: Option<String>,
+
+    /// SMTPサーバの認証に用いるユーザ名
+    smtp_user: Option<String>,
+
+    /// SMTPサーバの認証に用いるパスワード
+    smtp_password: Option<String>,
+
+    /// 通知メールの送信元アドレス
+    mail_from: Option<String>,
+
+    /// 通知メールの宛先アドレスの一覧
+    mail_to: Option<Vec<String>>,
+}
+
+///
+/// 設定ファイル自体の取り扱いに関する設定を格納するサブ構造体
+///
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ConfigInfo {
+    /// 上書き時に保持するバックアップの世代数
+    backups: Option<usize>,
 }
 
 ///
@@ -147,7 +1224,47 @@ where
     let mut path_info = PathInfo::default();
     path_info.output_path = Some(config.output_path());
     path_info.raw_output_path = config.raw_output_path();
+    path_info.video_output_path = config.video_output_path();
+    path_info.copy_unknown = config.copy_unknown_dir();
+    path_info.cas_dir = Some(config.cas_dir())
+        .filter(|_| config.layout() == super::StorageLayout::Cas);
     path_info.cache_db_path = Some(config.cache_db_path());
+    path_info.path_template = Some(config.path_template().to_string());
+    path_info.name_template = config.name_template()
+        .map(|template| template.to_string());
+    path_info.calendar = config.calendar_path();
+    path_info.exclude_names_from = config.exclude_names_from();
+    path_info.history_db = config.history_db();
+
+    let artist_roots = config.artist_roots();
+    if !artist_roots.is_empty() {
+        path_info.artist_roots = Some(artist_roots);
+    }
+
+    let shards = config.shards();
+    if !shards.is_empty() {
+        path_info.shards = Some(shards.to_vec());
+    }
+
+    let year_roots = config.year_roots();
+    if !year_roots.is_empty() {
+        path_info.year_roots = Some(year_roots);
+    }
+
+    let ext_routes = config.ext_routes();
+    if !ext_routes.is_empty() {
+        path_info.ext_routes = Some(ext_routes);
+    }
+
+    let file_classes = config.file_classes();
+    if !file_classes.is_empty() {
+        path_info.file_classes = Some(file_classes);
+    }
+
+    let tiers = config.tiers();
+    if !tiers.is_empty() {
+        path_info.tiers = Some(tiers);
+    }
 
     let log_info = LogInfo {
         level: Some(config.log_level()),
@@ -156,12 +1273,122 @@ where
 
     let cache_info = CacheInfo {
         cache_eval_mode: Some(config.cache_eval_mode()),
+        hash_index: Some(config.is_hash_index()),
+        dedup: Some(config.is_dedup()),
+    };
+
+    let mut process_info = ProcessInfo {
+        day_start: Some(config.day_start().format("%H:%M").to_string()),
+        datetime_fallback: Some(config.datetime_fallback()),
+        on_conflict: Some(config.on_conflict()),
+        motion_photo: Some(config.motion_photo_mode()),
+        order: Some(config.order()),
+        gps_clock: Some(config.gps_clock_mode()),
+        gps_clock_threshold: Some(config.gps_clock_threshold().as_secs()),
+        layout: Some(config.layout()),
+        compress_raw: config.compress_raw_level(),
+        encrypt_recipient: config.encrypt_recipient(),
+        encrypt_tool: Some(config.encrypt_tool()),
+        checksums: Some(config.is_checksums()),
+        checksum_algorithm: Some(config.checksum_algorithm()),
+        profile: config.ingest_profile(),
+        vss: Some(config.is_vss()),
+        card_marker: Some(config.is_card_marker()),
+        move_files: Some(config.is_move()),
+        source_read_only: Some(config.is_source_read_only()),
+        file_mode: config.file_mode().map(|mode| format!("{:04o}", mode)),
+        dir_mode: config.dir_mode().map(|mode| format!("{:04o}", mode)),
+        owner: config.owner(),
+        group: config.group(),
+        acl: config.acl(),
+        folder_info: Some(config.is_folder_info()),
+        par2: config.par2_redundancy(),
+        snapshot: Some(config.snapshot_mode()),
+        snapshot_command: config.snapshot_command(),
+        source_retries: Some(config.source_retries()),
+        source_timeout: Some(config.source_timeout().as_secs()),
+        health_command: config.health_command(),
+        health_interval: Some(config.health_interval().as_secs()),
+        verify: Some(config.is_verify()),
+        verify_sample: config
+            .verify_sample()
+            .map(|percent| format!("{}%", percent)),
+        retain_days: config.retain_days(),
+        prune_root: config.prune_root(),
+        ..Default::default()
+    };
+
+    let formats = config.datetime_formats();
+    if !formats.is_empty() {
+        process_info.datetime_formats = Some(formats.to_vec());
+    }
+
+    let excludes = config.excludes();
+    if !excludes.is_empty() {
+        process_info.excludes = Some(
+            excludes.iter().map(|p| p.as_str().to_string()).collect()
+        );
+    }
+
+    let includes = config.includes();
+    if !includes.is_empty() {
+        process_info.includes = Some(
+            includes.iter().map(|p| p.as_str().to_string()).collect()
+        );
+    }
+
+    let finder_tags = config.finder_tags();
+    if !finder_tags.is_empty() {
+        process_info.finder_tags = Some(finder_tags);
+    }
+
+    let mut daemon_info = DaemonInfo {
+        interval: Some(config.daemon_interval().as_secs()),
+        quiet_period: Some(config.quiet_period().as_secs()),
+        http: config.http_addr().map(|addr| addr.to_string()),
+        preview: Some(config.is_preview()),
+        control_socket: config.control_socket(),
+        ftp: config.ftp_addr().map(|addr| addr.to_string()),
+        ftp_user: config.ftp_user(),
+        ftp_password: config.ftp_password(),
+        ..Default::default()
+    };
+
+    let schedules = config.schedule_entries();
+    if !schedules.is_empty() {
+        daemon_info.schedules = Some(schedules);
+    }
+
+    let exports = config.export_entries();
+    if !exports.is_empty() {
+        daemon_info.exports = Some(exports);
+    }
+
+    let mut mail_info = MailInfo {
+        smtp: config.smtp_url(),
+        smtp_user: config.smtp_user(),
+        smtp_password: config.smtp_password(),
+        mail_from: config.mail_from(),
+        ..Default::default()
+    };
+
+    let mail_to = config.mail_to();
+    if !mail_to.is_empty() {
+        mail_info.mail_to = Some(mail_to);
+    }
+
+    let config_info = ConfigInfo {
+        backups: Some(config.config_backups()),
     };
 
     let cfg = Config {
         log_info,
         path_info,
         cache_info: Some(cache_info),
+        process_info: Some(process_info),
+        daemon_info: Some(daemon_info),
+        mail_info: Some(mail_info),
+        config_info: Some(config_info),
     };
 
     let toml = toml::to_string_pretty(&cfg)?;
@@ -173,3 +1400,68 @@ where
     std::fs::write(path, toml)?;
     Ok(())
 }
+
+///
+/// コンフィギュレーションファイルのバックアップを作成する
+///
+/// # 引数
+/// * `path` - バックアップ対象のコンフィギュレーションファイルのパス
+/// * `keep` - 保持するバックアップの世代数
+///
+/// # 戻り値
+/// 処理が成功した場合は作成したバックアップのパスを`Ok()`でラップして返す（作
+/// 成しなかった場合はNone）。失敗した場合はエラー情報を`Err()`でラップして返
+/// す。
+///
+/// # 注記
+/// バックアップは`<ファイル名>.<日時>.bak`の名前で同じディレクトリに作成し、
+/// 保持する世代数を超えた古いものから削除する。`keep`に0を指定した場合はバッ
+/// クアップの作成も削除も行わない。
+///
+pub(super) fn backup<P>(path: P, keep: usize) -> Result<Option<PathBuf>>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    if keep == 0 || !path.is_file() {
+        return Ok(None);
+    }
+
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => return Ok(None),
+    };
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    /*
+     * バックアップの作成
+     */
+    let stamp = Local::now().format("%Y%m%d%H%M%S");
+    let dst = dir.join(format!("{}.{}.bak", name, stamp));
+
+    std::fs::copy(path, &dst)?;
+
+    /*
+     * 古いバックアップの削除
+     */
+    let prefix = format!("{}.", name);
+    let mut backups = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|file| file.starts_with(&prefix) && file.ends_with(".bak"))
+        .collect::<Vec<_>>();
+
+    // 日時は固定長のため名前の降順が新しい順となる
+    backups.sort_unstable_by(|a, b| b.cmp(a));
+
+    for file in backups.iter().skip(keep) {
+        std::fs::remove_file(dir.join(file))?;
+    }
+
+    Ok(Some(dst))
+}
@@ -54,7 +54,7 @@ pub(super) fn init(opts: &Options) -> Result<()> {
                 init_for_file(level, path)?;
 
             } else if path.is_dir() {
-                init_for_directory(level, path)?;
+                init_for_directory(level, path, opts.user())?;
 
             } else {
                 return Err(anyhow!("invalid log output path"));
@@ -185,15 +185,21 @@ where
 ///
 /// # 注記
 /// ログローテションはログの量が2Mバイトを超えた場合に行う。また、ログファイル
-/// は10本までを保存する。
+/// は10本までを保存する。ユーザ名が指定されている場合は、ログファイル名にユー
+/// ザ名を付与しローテーションもユーザごとに行う。
 ///
-fn init_for_directory<S, P>(level:S, path: P) -> Result<()>
+fn init_for_directory<S, P>(level:S, path: P, user: Option<&str>) -> Result<()>
 where
     S: AsRef<str>,
     P: AsRef<Path>
 {
+    let base = match user {
+        Some(user) => format!("log-{}", user),
+        None => "log".to_string(),
+    };
+
     let path = std::fs::canonicalize(path)?;
-    let path = FileSpec::try_from(path.join("log"))?.suffix("txt");
+    let path = FileSpec::try_from(path.join(base))?.suffix("txt");
 
     Logger::try_with_env_or_str(level)?
         .log_to_file(path)
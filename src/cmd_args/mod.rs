@@ -8,34 +8,170 @@
 //! コマンドラインオプション関連の処理をまとめたモジュール
 //!
 
+mod command;
 pub(crate) mod config;
 mod logger;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone};
 use clap::{Parser, ValueEnum};
 use directories::BaseDirs;
+use glob::Pattern;
 use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
 
-use crate::cache::Cache;
+use crate::cache::{Cache, History};
+use crate::calendar::Calendar;
+use crate::class_registry::ClassRegistry;
+use crate::daemon::CronExpr;
+use crate::encrypt::{self, PendingManifests};
+use crate::exclude::ExcludeList;
+use crate::ios;
+use crate::mkdir::CreatedDirs;
+use crate::pause::PauseState;
+use crate::permission;
+use crate::shard::FreeSpaces;
+use crate::template::{NameTemplate, PathTemplate, DEFAULT_TEMPLATE};
+
+pub(crate) use command::{
+    AuditNamesArgs, BackupArgs, CacheCommand, ClearArgs, Command, DiffArgs,
+    ImportAllArgs, InvalidateArgs, ListArgs, MergeArgs, PruneArgs,
+    RebuildArgs, RestoreArgs, StatsArgs, WhyArgs,
+};
 
 ///
 /// デフォルトのコンフィグレーションファイルのパス情報を生成
 ///
+/// # 引数
+/// * `portable` - ポータブルモードのディレクトリ（指定されていない場合はNone）
+/// * `user` - ユーザ名（指定されていない場合はNone）
+///
+/// # 戻り値
+/// コンフィギュレーションファイルのパス情報
+///
+fn default_config_path(portable: Option<&Path>, user: Option<&str>)
+    -> PathBuf
+{
+    let dirs = BaseDirs::new().unwrap();
+
+    user_dir(dirs.config_local_dir(), portable, user).join("config.toml")
+}
+
+///
+/// システム全体のコンフィギュレーションファイルのパス情報を生成
+///
 /// # 戻り値
 /// コンフィギュレーションファイルのパス情報
 ///
-fn default_config_path() -> PathBuf {
-    BaseDirs::new()
-        .unwrap()
-        .config_local_dir()
+fn system_config_path() -> PathBuf {
+    Path::new("/etc")
         .join(env!("CARGO_PKG_NAME"))
         .join("config.toml")
 }
 
+///
+/// プロジェクト（カレントディレクトリ）のコンフィギュレーションファイルのパス
+/// 情報を生成
+///
+/// # 戻り値
+/// コンフィギュレーションファイルのパス情報
+///
+fn project_config_path() -> PathBuf {
+    PathBuf::from(concat!(env!("CARGO_PKG_NAME"), ".toml"))
+}
+
+///
+/// デフォルトのキャッシュデータベースファイルのパス情報を生成
+///
+/// # 引数
+/// * `portable` - ポータブルモードのディレクトリ（指定されていない場合はNone）
+/// * `user` - ユーザ名（指定されていない場合はNone）
+///
+/// # 戻り値
+/// キャッシュデータベースファイルのパス情報
+///
+fn default_cache_db_path(portable: Option<&Path>, user: Option<&str>)
+    -> PathBuf
+{
+    let dirs = BaseDirs::new().unwrap();
+
+    user_dir(dirs.cache_dir(), portable, user).join("cache.redb")
+}
+
+///
+/// デフォルトのステートディレクトリのパス情報を生成
+///
+/// # 引数
+/// * `portable` - ポータブルモードのディレクトリ（指定されていない場合はNone）
+/// * `user` - ユーザ名（指定されていない場合はNone）
+///
+/// # 戻り値
+/// ステートディレクトリのパス情報
+///
+/// # 注記
+/// XDGのステートディレクトリが定義されていないプラットフォームではローカルの
+/// データディレクトリを用いる。
+///
+fn default_state_dir(portable: Option<&Path>, user: Option<&str>) -> PathBuf {
+    let dirs = BaseDirs::new().unwrap();
+    let base = dirs.state_dir().unwrap_or(dirs.data_local_dir());
+
+    user_dir(base, portable, user).join("state")
+}
+
+///
+/// ポータブルモードのデフォルトのディレクトリのパス情報を生成
+///
+/// # 戻り値
+/// 処理が成功した場合は実行ファイルと同じディレクトリに置く`imgdist-data`ディ
+/// レクトリのパスを`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`
+/// でラップして返す。
+///
+fn default_portable_dir() -> Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+
+    match exe.parent() {
+        Some(dir) => Ok(dir.join(concat!(env!("CARGO_PKG_NAME"), "-data"))),
+        None => Err(anyhow!("{} has no parent directory", exe.display())),
+    }
+}
+
+///
+/// アプリケーション用のディレクトリのパス情報を生成
+///
+/// # 引数
+/// * `base` - 基点となるディレクトリ
+/// * `portable` - ポータブルモードのディレクトリ（指定されていない場合はNone）
+/// * `user` - ユーザ名（指定されていない場合はNone）
+///
+/// # 戻り値
+/// ユーザ名が指定されている場合はユーザごとに分離したディレクトリのパス
+///
+/// # 注記
+/// ポータブルモードでは基点となるディレクトリの代わりにポータブルモードのデ
+/// ィレクトリを用いる。
+///
+fn user_dir(base: &Path, portable: Option<&Path>, user: Option<&str>)
+    -> PathBuf
+{
+    let dir = match portable {
+        Some(dir) => dir.to_path_buf(),
+        None => base.join(env!("CARGO_PKG_NAME")),
+    };
+
+    match user {
+        Some(user) => dir.join("users").join(user),
+        None => dir,
+    }
+}
+
 ///
 /// ログレベルを指し示す列挙子
 ///
@@ -76,6 +212,331 @@ pub(crate) enum CacheEvalMode {
     Strict,
 }
 
+///
+/// モーションフォトの取り扱いモードを指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MotionPhotoMode {
+    /// 通常のJPEGとして扱う
+    Ignore,
+
+    /// 検出してログに記録する
+    Tag,
+
+    /// 検出して動画部分を切り出す
+    Extract,
+}
+
+///
+/// 入力ファイルの処理順を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ProcessOrder {
+    /// パスの順
+    Path,
+
+    /// 撮影日時の順
+    Capture,
+}
+
+///
+/// 取り込み対象とする画像の向きを指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Orientation {
+    /// 縦位置(高さが幅より大きい)
+    Portrait,
+
+    /// 横位置(幅が高さより大きい)
+    Landscape,
+}
+
+///
+/// GPS時刻によるカメラの時計のずれの取り扱いを指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GpsClockMode {
+    /// ずれの検出を行わない
+    Off,
+
+    /// ずれを検出して警告する
+    Warn,
+
+    /// ずれを検出して警告し、振り分け先の決定に補正を適用する
+    Fix,
+}
+
+///
+/// 撮影日時が無いファイルの取り扱いを指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DatetimeFallback {
+    /// ファイルの更新日時を撮影日時として用いる
+    Mtime,
+
+    /// ファイル名に含まれる日時を撮影日時として用いる
+    Filename,
+
+    /// 処理を行わずにスキップする
+    Skip,
+}
+
+///
+/// 振り分け先への格納方式を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StorageLayout {
+    /// 撮影日ごとのディレクトリにファイルを格納する
+    Date,
+
+    /// 内容のハッシュ値でオブジェクトストアに格納し、撮影日ごとのディレクト
+    /// リにはリンクを置く
+    Cas,
+}
+
+///
+/// 暗号化に用いるツールを指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EncryptTool {
+    /// ageで暗号化する
+    Age,
+
+    /// GnuPGで暗号化する
+    Gpg,
+}
+
+///
+/// チェックサムの算出に用いるハッシュ関数を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ChecksumAlgorithm {
+    /// SHA-256(CPUのSHA拡張命令を利用する)
+    Sha256,
+
+    /// BLAKE3(SIMD命令と複数スレッドを利用する)
+    Blake3,
+
+    /// XXH3の64ビット版(SIMD命令を利用する、暗号学的ハッシュではない)
+    Xxh3,
+}
+
+///
+/// 入力元のフォルダ構成に合わせた取り込みプロファイルを指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum IngestProfile {
+    /// スマートフォンのバックアップツールが書き出したフォルダ構成
+    Phone,
+}
+
+///
+/// 出力先のスナップショットの作成方法を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SnapshotMode {
+    /// スナップショットを作成しない
+    Off,
+
+    /// btrfsのサブボリュームスナップショットを作成する
+    Btrfs,
+
+    /// ZFSのスナップショットを作成する
+    Zfs,
+
+    /// APFSのローカルスナップショットを作成する
+    Apfs,
+
+    /// フックコマンドを実行する
+    Command,
+}
+
+///
+/// 振り分け先に同名のファイルが存在する場合の取り扱いを指し示す列挙子
+///
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, ValueEnum, Deserialize, Serialize
+)]
+#[clap(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Collision {
+    /// 上書きする
+    #[default]
+    Overwrite,
+
+    /// コピーせずにスキップする
+    Skip,
+
+    /// 末尾に番号を付けた別の名前で格納する
+    Rename,
+
+    /// コピーせずに処理の失敗として扱う
+    Error,
+}
+
+///
+/// ファイルの分類ごとの振り分け先（ティア）の設定
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Tier {
+    /// ティアの名称
+    pub(crate) name: String,
+
+    /// 対象とするファイルの分類（jpeg、raw、video）または拡張子
+    pub(crate) classes: Vec<String>,
+
+    /// 基点ディレクトリのパス
+    pub(crate) path: PathBuf,
+
+    /// 同名のファイルが存在する場合の取り扱い（省略時は`--on-conflict`の指定
+    /// に従う）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) collision: Option<Collision>,
+
+    /// コピー後に内容を読み戻して照合するか否か
+    #[serde(default)]
+    pub(crate) verify: bool,
+}
+
+///
+/// 設定で追加する形式の分類を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FileKind {
+    /// JPEGと同様に扱う画像ファイル
+    Image,
+
+    /// RAW画像ファイル
+    Raw,
+
+    /// 動画ファイル
+    Video,
+
+    /// 画像ファイルと共にコピーする付随ファイル
+    Sidecar,
+}
+
+///
+/// 設定で追加する形式の撮影日時の取得元を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DateSource {
+    /// Exif情報(動画の場合はコンテナの作成日時)
+    #[default]
+    Exif,
+
+    /// ファイルの更新日時
+    Mtime,
+
+    /// ファイル名に含まれる日時
+    Filename,
+}
+
+///
+/// 設定で追加する形式（拡張子ごと）の定義
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct FileClass {
+    /// 分類
+    pub(crate) class: FileKind,
+
+    /// 撮影日時の取得元
+    #[serde(default)]
+    pub(crate) date: DateSource,
+
+    /// 振り分け先（拡張子ごとの振り分け先と同じ扱い）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) route: Option<PathBuf>,
+}
+
+///
+/// デーモンモードでの定期取り込みの設定
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Schedule {
+    /// 取り込みを行う時刻（cron形式）
+    pub(crate) cron: String,
+
+    /// 取り込み元のディレクトリ
+    pub(crate) source: PathBuf,
+}
+
+///
+/// デーモンモードでのホットフォルダからの書き出し済みファイルの配信の設定
+///
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct Export {
+    /// 監視するホットフォルダ（現像ソフトの書き出し先）
+    pub(crate) source: PathBuf,
+
+    /// 配信先の基点ディレクトリ
+    pub(crate) output: PathBuf,
+
+    /// クライアント名（パステンプレートの`{client}`に適用する）
+    pub(crate) client: String,
+
+    /// 配信先のパステンプレート（未指定の場合は`{client}/`に既定のテンプレー
+    /// トを続けたもの）
+    #[serde(default)]
+    pub(crate) path_template: Option<String>,
+
+    /// 配信先のファイル名テンプレート（未指定の場合は取り込みと同じもの）
+    #[serde(default)]
+    pub(crate) name_template: Option<String>,
+}
+
+///
+/// 設定値の出所を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConfigOrigin {
+    /// コマンドラインオプション
+    CommandLine,
+
+    /// プロジェクトのコンフィギュレーションファイル（./imgdist.toml）
+    Project,
+
+    /// ユーザのコンフィギュレーションファイル
+    User,
+
+    /// システム全体のコンフィギュレーションファイル
+    System,
+}
+
+// AsRefトレイトの実装
+impl AsRef<str> for ConfigOrigin {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::CommandLine => "command line",
+            Self::Project => "project config",
+            Self::User => "user config",
+            Self::System => "system config",
+        }
+    }
+}
+
 // Intoトレイトの実装
 impl Into<log::LevelFilter> for LogLevel {
     fn into(self) -> log::LevelFilter {
@@ -116,20 +577,48 @@ impl AsRef<str> for LogLevel {
     ")",
 ))]
 #[command(long_about = None)]
-pub(crate) struct Options {
+#[command(subcommand_negates_reqs = true)]
+pub struct Options {
     /// 記録するログレベルの指定
     #[arg(short = 'l', long = "log-level", value_name = "LEVEL",
-        ignore_case = true)]
+        ignore_case = true, global = true)]
     log_level: Option<LogLevel>,
 
     /// ログの出力先の指定
-    #[arg(short = 'L', long = "log-output", value_name = "PATH")]
+    #[arg(short = 'L', long = "log-output", value_name = "PATH",
+        global = true)]
     log_output: Option<PathBuf>,
 
     /// コンフィギュレーションファイルのパス
-    #[arg(short = 'c', long = "config-file", value_name = "FILE")]
+    #[arg(short = 'c', long = "config-file", value_name = "FILE",
+        global = true)]
     config_file: Option<PathBuf>,
 
+    /// ユーザ名（指定した場合はデフォルトのコンフィギュレーションファイル、
+    /// キャッシュデータベース、ログファイルをユーザごとに分離する）
+    #[arg(long = "user", value_name = "NAME", global = true)]
+    user: Option<String>,
+
+    /// ポータブルモード（コンフィギュレーションファイル、キャッシュデータベー
+    /// ス、ログファイルを実行ファイルの隣または指定したディレクトリに置く）
+    // 入力ディレクトリと区別するため、ディレクトリは`=`でつないで指定させる
+    #[arg(long = "portable", value_name = "DIR", num_args = 0..=1,
+        require_equals = true, global = true)]
+    portable: Option<Option<PathBuf>>,
+
+    /// 確認を求める場面で全て同意したものとして処理を続ける
+    #[arg(short = 'y', long = "yes", default_value = "false",
+        conflicts_with = "no_input", global = true)]
+    yes: bool,
+
+    /// 確認を求める場面では入力を待たずにエラーとする
+    #[arg(long = "no-input", default_value = "false", global = true)]
+    no_input: bool,
+
+    /// 進捗表示を行わない（cron等からの実行向け）
+    #[arg(short = 'q', long = "quiet", default_value = "false", global = true)]
+    quiet: bool,
+
     /// 出力ディレクトリのパス
     #[arg(short = 'o', long = "output", value_name = "DIR")]
     output_path: Option<PathBuf>,
@@ -139,6 +628,46 @@ pub(crate) struct Options {
     #[arg(short = 'r', long = "raw-output", value_name = "DIR")]
     raw_output_path: Option<PathBuf>,
 
+    /// 動画保存ディレクトリのパス（指定された場合、動画ファイルはこのディレク
+    /// トリに保存）
+    #[arg(long = "video-output", value_name = "DIR")]
+    video_output_path: Option<PathBuf>,
+
+    /// 画像以外のファイルを保全するディレクトリのパス（指定された場合、取り込
+    /// み対象外の形式のファイルもこのディレクトリに振り分ける）
+    #[arg(long = "copy-unknown", value_name = "DIR")]
+    copy_unknown: Option<PathBuf>,
+
+    /// コンテントアドレス方式で格納する場合のオブジェクトストアのパス（省略時
+    /// は出力ディレクトリ直下の`.objects`）
+    #[arg(long = "cas-dir", value_name = "DIR")]
+    cas_dir: Option<PathBuf>,
+
+    /// 出力ディレクトリ以下の振り分け先パスのテンプレート（`{year}`、
+    /// `{month}`、`{day}`、`{artist}`、`{event}`を使用可能）
+    #[arg(long = "path-template", value_name = "TEMPLATE")]
+    path_template: Option<String>,
+
+    /// 振り分け先のファイル名のテンプレート（指定された場合、撮影日時に基づい
+    /// てファイル名を付け替える。`{hour}`、`{minute}`、`{second}`、`{subsec}`、
+    /// `{seq}`、`{name}`も使用可能）
+    #[arg(long = "name-template", value_name = "TEMPLATE")]
+    name_template: Option<String>,
+
+    /// `{event}`の決定に用いるカレンダー(ICS形式)のパス
+    #[arg(long = "calendar", value_name = "FILE")]
+    calendar: Option<PathBuf>,
+
+    /// 取り込みから除外する受け渡し済みのファイルの一覧(1行に1件のファイル
+    /// 名またはSHA-256のハッシュ値)のパス
+    #[arg(long = "exclude-names-from", value_name = "FILE")]
+    exclude_names_from: Option<PathBuf>,
+
+    /// 取り込み済みか否かの照会に用いる、他のマシンから持ち出したキャッシュ
+    /// データベース(読み取り専用)のパス
+    #[arg(long = "history-db", value_name = "FILE")]
+    history_db: Option<PathBuf>,
+
     /// 処理対象の撮影日付の始点（YYYY-MM-DD形式、この日付を含む）
     #[arg(short = 'f', long = "from-date", value_name = "DATE")]
     from_date: Option<String>,
@@ -147,16 +676,107 @@ pub(crate) struct Options {
     #[arg(short = 't', long = "to-date", value_name = "DATE")]
     to_date: Option<String>,
 
+    /// 処理対象とする画像の向き（Exif情報の画素数と回転情報で判定）
+    #[arg(long = "orientation", value_name = "ORIENTATION", ignore_case = true)]
+    orientation: Option<Orientation>,
+
+    /// 処理対象から除外するカメラの機種名（`Model`または`Make/Model`、複数指
+    /// 定可）
+    #[arg(long = "exclude-camera", value_name = "MODEL")]
+    exclude_cameras: Vec<String>,
+
+    /// 処理対象から除外するカメラのシリアル番号（複数指定可）
+    #[arg(long = "exclude-serial", value_name = "SERIAL")]
+    exclude_serials: Vec<String>,
+
+    /// 入力ディレクトリの走査で除外するファイルやディレクトリのグロブパター
+    /// ン（複数指定可）
+    #[arg(long = "exclude", value_name = "GLOB")]
+    excludes: Vec<String>,
+
+    /// 入力ディレクトリの走査で処理対象とするファイルのグロブパターン（複数
+    /// 指定可、指定した場合は何れかに一致するファイルのみを処理する）
+    #[arg(long = "include", value_name = "GLOB")]
+    includes: Vec<String>,
+
+    /// 撮影日の区切りとする時刻（HH:MM形式、この時刻より前の撮影は前日扱い）
+    #[arg(long = "day-start", value_name = "TIME")]
+    day_start: Option<String>,
+
+    /// 撮影日時の解析に追加で用いる書式（strftime形式、複数指定可）
+    #[arg(long = "datetime-format", value_name = "FORMAT")]
+    datetime_formats: Vec<String>,
+
+    /// Exif情報に撮影日時が無いファイルの取り扱い
+    #[arg(long = "datetime-fallback", value_name = "MODE", ignore_case = true)]
+    datetime_fallback: Option<DatetimeFallback>,
+
+    /// 振り分け先に同名のファイルが存在する場合の取り扱い
+    #[arg(long = "on-conflict", value_name = "MODE", ignore_case = true)]
+    on_conflict: Option<Collision>,
+
     /// 設定情報の表示
     #[arg(short = 's', long = "show-options", default_value = "false")]
     show_options: bool,
 
+    /// 振り分けるファイル数の上限（フィルタ適用後の件数）
+    #[arg(long = "limit", value_name = "N")]
+    limit: Option<usize>,
+
+    /// キャッシュ情報を無視して強制的に再処理する
+    #[arg(long = "force", default_value = "false")]
+    force: bool,
+
+    /// 強制再処理の対象を限定するパスのプレフィクス（入力ディレクトリからの相
+    /// 対パス、複数指定可、指定した場合は`--force`を暗黙に有効にする）
+    #[arg(long = "force-path", value_name = "PREFIX")]
+    force_paths: Vec<PathBuf>,
+
+    /// 実行結果のレポート(JSON形式)の出力先
+    #[arg(long = "report", value_name = "FILE")]
+    report_path: Option<PathBuf>,
+
+    /// ファイルごとの処理結果(CSV形式)の出力先
+    #[arg(long = "report-csv", value_name = "FILE")]
+    report_csv_path: Option<PathBuf>,
+
+    /// ファイルごとの判定の経過(JSONL形式)の出力先
+    #[arg(long = "trace-report", value_name = "FILE")]
+    trace_report_path: Option<PathBuf>,
+
+    /// 取り込み対象の件数とサイズの見積もりのみを行う
+    #[arg(long = "estimate", default_value = "false")]
+    estimate: bool,
+
+    /// 振り分け先に親の画像ファイルがある付随ファイルのみを取り込む
+    #[arg(long = "sidecars-only", default_value = "false")]
+    sidecars_only: bool,
+
+    /// ファイルのコピーを行わずにキャッシュ情報のみを記録する（取り込み済み
+    /// のメモリカードをキャッシュに登録する場合に用いる）
+    #[arg(long = "mark-processed", default_value = "false")]
+    mark_processed: bool,
+
+    /// 振り分け先へのコピーやキャッシュの更新を行わずに、行う予定の処理のみ
+    /// をログに出力する
+    #[arg(long = "dry-run", default_value = "false")]
+    dry_run: bool,
+
+    /// スキップしたファイルごとにその理由をログに出力する
+    #[arg(long = "explain", default_value = "false")]
+    explain: bool,
+
     /// 現在の設定をconfig.tomlに書き出す
     #[arg(long = "save-config", default_value = "false")]
     save_config: bool,
 
+    /// `--save-config`で上書きする際に保持するバックアップの世代数（0を指定
+    /// した場合はバックアップを作成しない）
+    #[arg(long = "config-backups", value_name = "N")]
+    config_backups: Option<usize>,
+
     /// キャッシュデータベースファイルのパス
-    #[arg(long = "cache-db", value_name = "FILE")]
+    #[arg(long = "cache-db", value_name = "FILE", global = true)]
     cache_db_path: Option<PathBuf>,
 
     /// キャッシュ評価時の詳細度
@@ -164,33 +784,450 @@ pub(crate) struct Options {
         ignore_case = true)]
     cache_eval_mode: Option<CacheEvalMode>,
 
-    /// 入力ディレクトリのパス
-    #[arg()]
-    input_path: PathBuf,
+    /// 内容のハッシュ値による索引を用い、名前の変更・移動のみのファイルを処理
+    /// 済みと判断する
+    #[arg(long = "hash-index", default_value = "false")]
+    hash_index: bool,
 
-    /// パース済みの開始日付（バリデーション時に設定）
-    #[arg(skip)]
-    parsed_from_date: Option<DateTime<Local>>,
+    /// 振り分け先に格納済みのファイルと内容が一致するファイルをコピーしない
+    #[arg(long = "dedup", default_value = "false")]
+    dedup: bool,
 
-    /// パース済みの終了日付（バリデーション時に設定）
-    #[arg(skip)]
-    parsed_to_date: Option<DateTime<Local>>,
+    /// モーションフォトの取り扱いモード
+    #[arg(long = "motion-photo", value_name = "MODE", ignore_case = true)]
+    motion_photo: Option<MotionPhotoMode>,
 
-    /// キャッシュデータベースファイルのパス（バリデーション時に設定）
-    #[arg(skip)]
-    parsed_cache_db_path: Option<PathBuf>,
+    /// 入力ファイルの処理順
+    #[arg(long = "order", value_name = "ORDER", ignore_case = true)]
+    order: Option<ProcessOrder>,
+
+    /// GPS時刻によるカメラの時計のずれの取り扱い
+    #[arg(long = "gps-clock", value_name = "MODE", ignore_case = true)]
+    gps_clock: Option<GpsClockMode>,
+
+    /// カメラの時計がずれているとみなす閾値（秒）
+    #[arg(long = "gps-clock-threshold", value_name = "SECS")]
+    gps_clock_threshold: Option<u64>,
+
+    /// 振り分け先への格納方式
+    #[arg(long = "layout", value_name = "LAYOUT", ignore_case = true)]
+    layout: Option<StorageLayout>,
+
+    /// RAWファイルをzstd形式で圧縮して格納する場合の圧縮レベル（1〜22）
+    #[arg(long = "compress-raw", value_name = "LEVEL")]
+    compress_raw: Option<i32>,
+
+    /// 振り分け先に暗号化して格納する場合の受信者（ageの公開鍵またはGnuPGの鍵
+    /// ID）
+    #[arg(long = "encrypt", value_name = "RECIPIENT")]
+    encrypt_recipient: Option<String>,
+
+    /// 暗号化に用いるツール
+    #[arg(long = "encrypt-tool", value_name = "TOOL", ignore_case = true)]
+    encrypt_tool: Option<EncryptTool>,
+
+    /// 振り分け先フォルダにチェックサムマニフェスト(SHA256SUMS等)を記録する
+    #[arg(long = "checksums", default_value = "false")]
+    checksums: bool,
+
+    /// チェックサム(マニフェスト、照合、ハッシュ値の索引)に用いるハッシュ関数
+    #[arg(long = "checksum-algorithm", value_name = "ALGO", ignore_case = true)]
+    checksum_algorithm: Option<ChecksumAlgorithm>,
+
+    /// 入力元のフォルダ構成に合わせた取り込みプロファイル
+    #[arg(long = "profile", value_name = "PROFILE", ignore_case = true)]
+    profile: Option<IngestProfile>,
+
+    /// 他のアプリケーションにロックされた入力ファイルをボリュームシャドウコピ
+    /// ー経由で読み出す（Windowsのみ）
+    #[arg(long = "vss", default_value = "false")]
+    vss: bool,
+
+    /// 入力ディレクトリに取り込みマーカー(.imgdist-marker.toml)を置き、前回ま
+    /// でに取り込んだファイルをキャッシュによらずスキップする
+    #[arg(long = "card-marker", default_value = "false")]
+    card_marker: bool,
+
+    /// 振り分け先に格納したファイルを入力ディレクトリから削除する（移動）
+    #[arg(long = "move", default_value = "false")]
+    move_files: bool,
+
+    /// 入力元への書き込みを一切行わないことを保証する（入力元への書き込みを
+    /// 伴う機能の指定をエラーとする）
+    #[arg(long = "source-read-only", default_value = "false")]
+    source_read_only: bool,
+
+    /// 振り分け先にコピーしたファイルに適用するパーミッション（8進表記）
+    #[arg(long = "file-mode", value_name = "MODE")]
+    file_mode: Option<String>,
+
+    /// 振り分け先に作成したディレクトリに適用するパーミッション（8進表記）
+    #[arg(long = "dir-mode", value_name = "MODE")]
+    dir_mode: Option<String>,
+
+    /// 振り分け先のファイルとディレクトリの所有者
+    #[arg(long = "owner", value_name = "USER")]
+    owner: Option<String>,
+
+    /// 振り分け先のファイルとディレクトリのグループ
+    #[arg(long = "group", value_name = "GROUP")]
+    group: Option<String>,
+
+    /// 振り分け先のファイルとディレクトリに追加するACLのエントリ（Linuxでは
+    /// setfacl、macOSではchmod +aの書式）
+    #[arg(long = "acl", value_name = "SPEC")]
+    acl: Option<String>,
+
+    /// 振り分け先のファイルに付与するFinderタグ（`{event}`、`{artist}`を使用
+    /// 可能、複数指定可、macOSのみ）
+    #[arg(long = "finder-tag", value_name = "TAG")]
+    finder_tags: Vec<String>,
+
+    /// 振り分け先フォルダに取り込み情報ファイル(.imgdist-info.toml)を記録する
+    #[arg(long = "folder-info", default_value = "false")]
+    folder_info: bool,
+
+    /// 振り分け先フォルダに生成するPAR2リカバリファイルの冗長度（パーセント、
+    /// 指定した場合のみ生成）
+    #[arg(long = "par2", value_name = "PERCENT")]
+    par2: Option<u8>,
+
+    /// 正常終了後に出力先のスナップショットを作成する方法
+    #[arg(long = "snapshot", value_name = "MODE", ignore_case = true)]
+    snapshot: Option<SnapshotMode>,
+
+    /// スナップショット作成に用いるフックコマンド（`--snapshot command`指定時
+    /// に使用）
+    #[arg(long = "snapshot-command", value_name = "COMMAND")]
+    snapshot_command: Option<String>,
+
+    /// 入力元との接続が失われた場合に回復を確認する最大回数（0の場合は直ちに
+    /// 中断する）
+    #[arg(long = "source-retries", value_name = "N")]
+    source_retries: Option<usize>,
+
+    /// 入力元の接続を確認する際に応答を待つ時間（秒）
+    #[arg(long = "source-timeout", value_name = "SECS")]
+    source_timeout: Option<u64>,
+
+    /// 振り分け先のドライブの状態(温度、代替処理保留中のセクタ等)を確認し、
+    /// コピーを減速・停止させるフックコマンド
+    #[arg(long = "health-command", value_name = "COMMAND")]
+    health_command: Option<String>,
+
+    /// 振り分け先のドライブの状態を確認する間隔（秒）
+    #[arg(long = "health-interval", value_name = "SECS")]
+    health_interval: Option<u64>,
+
+    /// コピーした全てのファイルの内容を読み戻して照合する
+    #[arg(long = "verify", default_value = "false")]
+    verify: bool,
+
+    /// コピーしたファイルのうち内容を読み戻して照合するものの割合（"5%"等）
+    #[arg(long = "verify-sample", value_name = "PERCENT")]
+    verify_sample: Option<String>,
+
+    /// 振り分け先に残す期間（日数、撮影日がこれより古いファイルを削除する）
+    #[arg(long = "retain-days", value_name = "DAYS")]
+    retain_days: Option<u32>,
+
+    /// 保存期間を過ぎたファイルを削除する対象のディレクトリ（`--retain-days`
+    /// を指定する場合は必須、常駐する場合等は`--yes`も必要）
+    #[arg(long = "prune-root", value_name = "DIR")]
+    prune_root: Option<PathBuf>,
+
+    /// 取り込みの後も常駐し、入力ディレクトリに追加されたファイルを変更通知
+    /// により検出して取り込む
+    #[arg(long = "watch", default_value = "false")]
+    watch: bool,
+
+    /// デーモンとして常駐し、入力ディレクトリが出現するたびに取り込みを行う
+    #[arg(long = "daemon", default_value = "false")]
+    daemon: bool,
+
+    /// デーモンモードで入力ディレクトリを確認する間隔（秒）
+    #[arg(long = "interval", value_name = "SECS")]
+    interval: Option<u64>,
+
+    /// デーモンモードで入力ディレクトリへの書き込みが途絶えてから取り込みを
+    /// 始めるまでの静止時間（秒）
+    #[arg(long = "quiet-period", value_name = "SECS")]
+    quiet_period: Option<u64>,
+
+    /// デーモンモードで状態取得用のHTTPエンドポイントを待ち受けるアドレス
+    /// （`127.0.0.1:8080`の形式、指定した場合のみ起動）
+    #[arg(long = "http", value_name = "ADDR")]
+    http: Option<String>,
+
+    /// HTTPエンドポイントで直近の取り込みのサムネイルを確認するページを提供
+    /// する
+    #[arg(long = "preview", default_value = "false")]
+    preview: bool,
+
+    /// デーモンモードで制御用のUnixドメインソケットを作成するパス（指定した
+    /// 場合のみ作成）
+    #[arg(long = "control-socket", value_name = "PATH")]
+    control_socket: Option<PathBuf>,
+
+    /// デーモンモードでカメラからのアップロードを受け付けるFTPサーバの待ち受
+    /// けアドレス（`127.0.0.1:2121`の形式、指定した場合のみ起動）。FTPSには対
+    /// 応しない平文のFTPであるため、ループバックアドレスのみ指定できる
+    #[arg(long = "ftp", value_name = "ADDR")]
+    ftp: Option<String>,
+
+    /// FTPサーバへのログインに用いるユーザ名
+    #[arg(long = "ftp-user", value_name = "NAME")]
+    ftp_user: Option<String>,
+
+    /// FTPサーバへのログインに用いるパスワード
+    #[arg(long = "ftp-password", value_name = "PASSWORD")]
+    ftp_password: Option<String>,
+
+    /// 実行結果を通知するメールの送信に用いるSMTPサーバのURL
+    /// （`smtp://HOST:PORT`または`smtps://HOST:PORT`の形式）
+    #[arg(long = "smtp", value_name = "URL")]
+    smtp: Option<String>,
+
+    /// SMTPサーバの認証に用いるユーザ名
+    #[arg(long = "smtp-user", value_name = "NAME")]
+    smtp_user: Option<String>,
+
+    /// SMTPサーバの認証に用いるパスワード
+    #[arg(long = "smtp-password", value_name = "PASSWORD")]
+    smtp_password: Option<String>,
+
+    /// 通知メールの送信元アドレス
+    #[arg(long = "mail-from", value_name = "ADDR")]
+    mail_from: Option<String>,
+
+    /// 通知メールの宛先アドレス（複数指定可）
+    #[arg(long = "mail-to", value_name = "ADDR")]
+    mail_to: Vec<String>,
+
+    /// USB接続されたiOSデバイスから取り込む（入力ディレクトリの代わりに指定）
+    #[arg(long = "ios", default_value = "false")]
+    ios: bool,
+
+    /// 取り込み元のiOSデバイスのUDID（複数台接続されている場合に指定）
+    #[arg(long = "ios-udid", value_name = "UDID", requires = "ios")]
+    ios_udid: Option<String>,
+
+    /// rcloneのリモートのフォルダから取り込む（`<REMOTE>:<PATH>`の形式、入力
+    /// ディレクトリの代わりに指定）
+    #[arg(long = "cloud", value_name = "REMOTE", conflicts_with = "ios")]
+    cloud: Option<String>,
+
+    /// サブコマンド
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// 入力ディレクトリのパス
+    #[arg(required_unless_present_any = ["ios", "cloud"])]
+    input_path: Option<PathBuf>,
+
+    /// パース済みの開始日付（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_from_date: Option<DateTime<Local>>,
+
+    /// パース済みの終了日付（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_to_date: Option<DateTime<Local>>,
+
+    /// パース済みの撮影日の区切り時刻（バリデーション時に設定）
+    #[arg(skip = NaiveTime::MIN)]
+    parsed_day_start: NaiveTime,
+
+    /// パース済みの照合の割合（百分率、バリデーション時に設定）
+    #[arg(skip)]
+    parsed_verify_sample: Option<f64>,
+
+    /// パース済みのパステンプレート（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_path_template: PathTemplate,
+
+    /// パース済みのファイル名テンプレート（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_name_template: Option<NameTemplate>,
+
+    /// 読み込み済みのカレンダー（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_calendar: Option<Arc<Calendar>>,
+
+    /// 読み込み済みの除外リスト（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_exclude_list: Option<Arc<ExcludeList>>,
+
+    /// 読み込み済みの履歴データベース（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_history: Option<Arc<History>>,
+
+    /// 撮影者名ごとの出力ディレクトリ（コンフィギュレーションファイルから設
+    /// 定）
+    #[arg(skip)]
+    artist_roots: BTreeMap<String, PathBuf>,
+
+    /// 撮影年の範囲ごとの出力ディレクトリ（コンフィギュレーションファイルか
+    /// ら設定）
+    #[arg(skip)]
+    year_roots: BTreeMap<String, PathBuf>,
+
+    /// 撮影年の範囲（始点と終点、いずれも含む）ごとの出力ディレクトリ（バリ
+    /// デーション時に設定）
+    #[arg(skip)]
+    parsed_year_roots: Vec<(i32, i32, PathBuf)>,
+
+    /// 出力ディレクトリの代わりに振り分け先とするボリュームの一覧（コンフィ
+    /// ギュレーションファイルから設定）
+    #[arg(skip)]
+    shards: Vec<PathBuf>,
+
+    /// 拡張子（小文字）ごとの振り分け先（コンフィギュレーションファイルから設
+    /// 定）
+    #[arg(skip)]
+    ext_routes: BTreeMap<String, PathBuf>,
+
+    /// 拡張子（小文字）ごとに追加する形式の定義（コンフィギュレーションファ
+    /// イルから設定）
+    #[arg(skip)]
+    file_classes: BTreeMap<String, FileClass>,
+
+    /// ファイルの分類ごとの振り分け先（コンフィギュレーションファイルから設
+    /// 定）
+    #[arg(skip)]
+    tiers: Vec<Tier>,
+
+    /// デーモンモードでの定期取り込みの一覧（コンフィギュレーションファイル
+    /// から設定）
+    #[arg(skip)]
+    schedules: Vec<Schedule>,
+
+    /// パース済みの定期取り込みのスケジュールと取り込み元（バリデーション時
+    /// に設定）
+    #[arg(skip)]
+    parsed_schedules: Vec<(CronExpr, PathBuf)>,
+
+    /// デーモンモードでのホットフォルダからの配信の一覧（コンフィギュレーシ
+    /// ョンファイルから設定）
+    #[arg(skip)]
+    exports: Vec<Export>,
+
+    /// パース済みの配信の設定とパステンプレート、ファイル名テンプレートの組
+    /// （バリデーション時に設定）
+    #[arg(skip)]
+    parsed_exports: Vec<(Export, PathTemplate, Option<NameTemplate>)>,
+
+    /// JPEGファイルのみを対象とするか否か（書き出し済みファイルの配信時に設
+    /// 定）
+    #[arg(skip)]
+    jpeg_only: bool,
+
+    /// キャッシュデータベースファイルのパス（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_cache_db_path: Option<PathBuf>,
 
     /// キャッシュ評価モード（バリデーション時に設定）
     #[arg(skip = CacheEvalMode::Shallow)]
     parsed_cache_eval_mode: CacheEvalMode,
 
+    /// モーションフォトの取り扱いモード（バリデーション時に設定）
+    #[arg(skip = MotionPhotoMode::Ignore)]
+    parsed_motion_photo: MotionPhotoMode,
+
+    /// 入力ファイルの処理順（バリデーション時に設定）
+    #[arg(skip = ProcessOrder::Path)]
+    parsed_order: ProcessOrder,
+
+    /// 撮影日時が無いファイルの取り扱い（バリデーション時に設定）
+    #[arg(skip = DatetimeFallback::Skip)]
+    parsed_datetime_fallback: DatetimeFallback,
+
+    /// GPS時刻によるカメラの時計のずれの取り扱い（バリデーション時に設定）
+    #[arg(skip = GpsClockMode::Warn)]
+    parsed_gps_clock: GpsClockMode,
+
+    /// 振り分け先への格納方式（バリデーション時に設定）
+    #[arg(skip = StorageLayout::Date)]
+    parsed_layout: StorageLayout,
+
+    /// ファイルに適用するパーミッション（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_file_mode: Option<u32>,
+
+    /// ディレクトリに適用するパーミッション（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_dir_mode: Option<u32>,
+
+    /// 暗号化に用いるツール（バリデーション時に設定）
+    #[arg(skip = EncryptTool::Age)]
+    parsed_encrypt_tool: EncryptTool,
+
+    /// 暗号化名を求める際の鍵（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_encrypt_name_key: Option<[u8; 32]>,
+
+    /// チェックサムに用いるハッシュ関数（バリデーション時に設定）
+    #[arg(skip = ChecksumAlgorithm::Sha256)]
+    parsed_checksum_algorithm: ChecksumAlgorithm,
+
+    /// 走査で除外するグロブパターン（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_excludes: Vec<Pattern>,
+
+    /// 走査で処理対象とするグロブパターン（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_includes: Vec<Pattern>,
+
+    /// スナップショットの作成方法（バリデーション時に設定）
+    #[arg(skip = SnapshotMode::Off)]
+    parsed_snapshot: SnapshotMode,
+
+    /// HTTPエンドポイントの待ち受けアドレス（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_http_addr: Option<SocketAddr>,
+
+    /// パース済みのFTPサーバの待ち受けアドレス（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_ftp_addr: Option<SocketAddr>,
+
+    /// 追加する形式の登録内容（バリデーション時に設定）
+    #[arg(skip)]
+    parsed_classes: Arc<ClassRegistry>,
+
     /// キャッシュデータベースオブジェクト（バリデーション時に設定）
     #[arg(skip)]
     cache: Option<Arc<Cache>>,
 
+    /// 取り込みで存在を確認済みのディレクトリ（複製したオプション設定と共有）
+    #[arg(skip)]
+    created_dirs: Arc<CreatedDirs>,
+
+    /// 取り込みで求めたシャードの空き容量（複製したオプション設定と共有）
+    #[arg(skip)]
+    free_spaces: Arc<FreeSpaces>,
+
+    /// 取り込みで更新した暗号化マニフェスト（複製したオプション設定と共有）
+    #[arg(skip)]
+    pending_manifests: Arc<PendingManifests>,
+
+    /// 取り込み処理の一時停止状態（複製したオプション設定と共有）
+    #[arg(skip)]
+    pause: Arc<PauseState>,
+
     /// コンフィギュレーションファイルの最終決定パス（バリデーション時に設定）
     #[arg(skip)]
     parsed_config_path: PathBuf,
+
+    /// ポータブルモードのディレクトリ（適用時に設定）
+    #[arg(skip)]
+    parsed_portable_dir: Option<PathBuf>,
+
+    /// 読み込んだコンフィギュレーションファイル（適用時に設定）
+    #[arg(skip)]
+    config_files: Vec<(ConfigOrigin, PathBuf)>,
+
+    /// 設定項目ごとの値の出所（適用時に設定、未記録の項目はデフォルト値）
+    #[arg(skip)]
+    origins: BTreeMap<&'static str, ConfigOrigin>,
 }
 
 impl Options {
@@ -217,14 +1254,74 @@ impl Options {
         self.log_output.clone()
     }
 
+    ///
+    /// ユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// 指定されたユーザ名（未指定の場合はNone）
+    ///
+    fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    ///
+    /// 利用者に確認を求める
+    ///
+    /// # 引数
+    /// * `message` - 確認内容
+    ///
+    /// # 戻り値
+    /// 同意された場合は`Ok(true)`、拒否された場合は`Ok(false)`を返す。入力を受
+    /// け付けない設定の場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// `--yes`が指定されている場合は入力を待たずに同意したものとし、
+    /// `--no-input`が指定されている場合は入力を待たずにエラーとする。
+    ///
+    pub(crate) fn confirm(&self, message: &str) -> Result<bool> {
+        if self.yes {
+            return Ok(true);
+        }
+
+        if self.no_input {
+            return Err(anyhow!(
+                "confirmation required ({}), use --yes to proceed",
+                message
+            ));
+        }
+
+        print!("{} [y/N]: ", message);
+        io::stdout().flush().ok();
+
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        let answer = answer.trim().to_lowercase();
+
+        Ok(answer == "y" || answer == "yes")
+    }
+
+    ///
+    /// ポータブルモードのディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// ポータブルモードのディレクトリ（ポータブルモードでない場合はNone）
+    ///
+    fn portable_dir(&self) -> Option<&Path> {
+        self.parsed_portable_dir.as_deref()
+    }
+
     /// 
     /// 入力ディレクトリへのアクセサ
     ///
     /// # 戻り値
     /// 入力ディレクトリへのパスオブジェクト
     ///
+    /// # 注記
+    /// サブコマンドが指定されていない場合、self.input_pathがNoneのままこの関数
+    /// が呼ばれることが無いことがコマンドライン引数の定義により保証されている。
+    ///
     pub(crate) fn input_path(&self) -> PathBuf {
-        self.input_path.clone()
+        self.input_path.as_ref().unwrap().clone()
     } 
 
     /// 
@@ -251,211 +1348,2614 @@ impl Options {
         self.raw_output_path.clone()
     }
 
-    /// 
-    /// 撮影日付の始点へのアクセサ
+    ///
+    /// 動画保存ディレクトリへのアクセサ
     ///
     /// # 戻り値
-    /// 撮影日付の始点（未設定の場合はNone）
+    /// 動画保存ディレクトリへのパスオブジェクト（未設定の場合はNone）
     ///
-    pub(crate) fn from_date(&self) -> Option<DateTime<Local>> {
-        self.parsed_from_date
+    pub(crate) fn video_output_path(&self) -> Option<PathBuf> {
+        self.video_output_path.clone()
     }
 
-    /// 
-    /// 撮影日付の終点へのアクセサ
+    ///
+    /// 画像以外のファイルの保全先へのアクセサ
     ///
     /// # 戻り値
-    /// 撮影日付の終点（未設定の場合はNone）
+    /// 保全先ディレクトリへのパスオブジェクト（未設定の場合はNone）
     ///
-    pub(crate) fn to_date(&self) -> Option<DateTime<Local>> {
-        self.parsed_to_date
-    } 
+    pub(crate) fn copy_unknown_dir(&self) -> Option<PathBuf> {
+        self.copy_unknown.clone()
+    }
 
     ///
-    /// オプション情報モードか否かのフラグへのアクセサ
+    /// オブジェクトストアのパスへのアクセサ
     ///
     /// # 戻り値
-    /// オプション情報表示モードが指定されている場合は`true`が、通常モードのが
-    /// 指定されている場合は`false`が返される。
+    /// オブジェクトストアのディレクトリへのパスオブジェクト（未指定の場合は出
+    /// 力ディレクトリ直下の`.objects`）
     ///
-    fn is_show_options(&self) -> bool {
-        self.show_options
+    pub(crate) fn cas_dir(&self) -> PathBuf {
+        self.cas_dir
+            .clone()
+            .unwrap_or_else(|| self.output_path().join(".objects"))
     }
 
     ///
-    /// キャッシュデータベースファイルのパスへのアクセサ
+    /// パステンプレートへのアクセサ
     ///
     /// # 戻り値
-    /// キャッシュデータベースファイルのパス
+    /// 出力ディレクトリ以下の振り分け先パスのテンプレート
     ///
-    pub(crate) fn cache_db_path(&self) -> PathBuf {
-        self.parsed_cache_db_path.as_ref().unwrap().clone()
+    pub(crate) fn path_template(&self) -> &PathTemplate {
+        &self.parsed_path_template
     }
 
     ///
-    /// キャッシュ評価モードへのアクセサ
+    /// ファイル名テンプレートへのアクセサ
     ///
     /// # 戻り値
-    /// キャッシュ評価モード
+    /// 振り分け先のファイル名のテンプレート（未設定の場合はNone）
     ///
-    pub(crate) fn cache_eval_mode(&self) -> CacheEvalMode {
-        self.parsed_cache_eval_mode
+    pub(crate) fn name_template(&self) -> Option<&NameTemplate> {
+        self.parsed_name_template.as_ref()
     }
 
     ///
-    /// コンフィギュレーションファイルパスへのアクセサ
+    /// カレンダーのパスへのアクセサ
     ///
     /// # 戻り値
-    /// 確定したコンフィギュレーションファイルのパス
+    /// カレンダーのパス（未設定の場合はNone）
     ///
-    fn config_path(&self) -> PathBuf {
-        self.parsed_config_path.clone()
+    pub(super) fn calendar_path(&self) -> Option<PathBuf> {
+        self.calendar.clone()
     }
 
     ///
-    /// 設定保存フラグへのアクセサ
+    /// 撮影日時を含むカレンダーのイベント名の取得
+    ///
+    /// # 引数
+    /// * `datetime` - 撮影日時
     ///
     /// # 戻り値
-    /// `--save-config` が指定されていれば`true`
+    /// イベント名（カレンダーが未設定の場合や該当するイベントが無い場合は
+    /// None）
     ///
-    fn is_save_config(&self) -> bool {
-        self.save_config
+    pub(crate) fn event_at(&self, datetime: &DateTime<Local>) -> Option<&str> {
+        self.parsed_calendar
+            .as_ref()
+            .and_then(|calendar| calendar.event_at(datetime))
     }
 
     ///
-    /// キャッシュデータベースオブジェクトへのアクセサ
+    /// 除外リストのパスへのアクセサ
     ///
     /// # 戻り値
-    /// キャッシュデータベースオブジェクト
+    /// 除外リストのパス（未設定の場合はNone）
     ///
-    pub(crate) fn cache(&self) -> Arc<Cache> {
-        self.cache.as_ref().unwrap().clone()
+    pub(super) fn exclude_names_from(&self) -> Option<PathBuf> {
+        self.exclude_names_from.clone()
     }
 
     ///
-    /// オプション設定内容の表示
+    /// 除外リストへのアクセサ
     ///
-    pub(crate) fn show_options(&self) {
-        let config_path = if let Some(path) = &self.config_file {
-            Some(path.clone())
-        } else {
-            let path = default_config_path();
-
-            if path.exists() {
-                Some(path)
-            } else {
-                None
-            }
-        };
-
-        println!("log level:       {}", self.log_level().as_ref());
-        println!("log output:      {:?}", self.log_output());
-        println!("config path:     {:?}", config_path);
-        println!("output path:     {:?}", self.output_path());
-        println!("raw output path: {:?}", self.raw_output_path());
-        println!("from data:       {:?}", self.from_date());
-        println!("to data:         {:?}", self.to_date());
-        println!("input path:      {:?}", self.input_path());
-        println!("cache db path:   {:?}", self.cache_db_path());
-        println!("cache eval mode: {:?}", self.cache_eval_mode());
-        println!("save config:     {:?}", self.is_save_config());
-        println!("config path:     {:?}", self.config_path());
+    /// # 戻り値
+    /// 読み込み済みの除外リスト（未設定の場合はNone）
+    ///
+    pub(crate) fn exclude_list(&self) -> Option<&ExcludeList> {
+        self.parsed_exclude_list.as_deref()
     }
 
     ///
-    /// コンフィギュレーションの適用
+    /// 履歴データベースのパスへのアクセサ
     ///
-    /// # 注記
-    /// config.tomlを読み込みオプション情報に反映する。
+    /// # 戻り値
+    /// 履歴データベースのパス（未設定の場合はNone）
     ///
-    fn apply_config(&mut self) -> Result<()> {
-        let path = if let Some(path) = &self.config_file {
-            // オプションでコンフィギュレーションファイルのパスが指定されて
-            // いる場合、そのパスに何もなければエラー
-            if !path.exists() {
-                return Err(anyhow!("{} is not exists", path.display()));
-            }
-
-            // 指定されたパスを返す
-            path.clone()
-        } else {
-            // 指定されていない場合はデフォルトのパスを返す
-            default_config_path()
-        };
+    pub(super) fn history_db(&self) -> Option<PathBuf> {
+        self.history_db.clone()
+    }
 
-        // この時点でパスに何も無い場合はそのまま何もせず正常終了
-        if !path.exists() {
-            return Ok(());
-        }
+    ///
+    /// 履歴データベースへのアクセサ
+    ///
+    /// # 戻り値
+    /// 読み込み済みの履歴データベース（未設定の場合はNone）
+    ///
+    pub(crate) fn history(&self) -> Option<&History> {
+        self.parsed_history.as_deref()
+    }
 
-        // 指定されたパスにあるのがファイルでなければエラー
-        if !path.is_file() {
-            return Err(anyhow!("{} is not file", path.display()));
-        }
+    ///
+    /// 撮影者名ごとの出力ディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影者名と出力ディレクトリの対応表
+    ///
+    fn artist_roots(&self) -> BTreeMap<String, PathBuf> {
+        self.artist_roots.clone()
+    }
 
-        // そのパスからコンフィギュレーションを読み取る
-        match config::read(&path) {
-            // 読み取れた場合は内容を適用
-            Ok(config) => {
-                if self.log_level.is_none() {
-                    if let Some(level) = config.log_level() {
-                        self.log_level = Some(level);
+    ///
+    /// 撮影者名に対応する出力ディレクトリの取得
+    ///
+    /// # 引数
+    /// * `artist` - 撮影者名
+    ///
+    /// # 戻り値
+    /// 撮影者名に対応する出力ディレクトリ（設定されていない場合はNone）
+    ///
+    pub(crate) fn artist_root(&self, artist: &str) -> Option<PathBuf> {
+        self.artist_roots.get(artist).cloned()
+    }
+
+    ///
+    /// シャードの一覧へのアクセサ
+    ///
+    /// # 戻り値
+    /// 出力ディレクトリの代わりに振り分け先とするディレクトリの一覧（未設定
+    /// の場合は空）
+    ///
+    pub(crate) fn shards(&self) -> &[PathBuf] {
+        &self.shards
+    }
+
+    ///
+    /// 撮影年の範囲ごとの出力ディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影年の範囲と出力ディレクトリの対応表
+    ///
+    fn year_roots(&self) -> BTreeMap<String, PathBuf> {
+        self.year_roots.clone()
+    }
+
+    ///
+    /// 撮影年に対応する出力ディレクトリの取得
+    ///
+    /// # 引数
+    /// * `year` - 撮影年
+    ///
+    /// # 戻り値
+    /// 撮影年を範囲に含む出力ディレクトリ（設定されていない場合はNone）
+    ///
+    pub(crate) fn year_root(&self, year: i32) -> Option<PathBuf> {
+        self.parsed_year_roots
+            .iter()
+            .find(|(start, end, _)| (*start..=*end).contains(&year))
+            .map(|(_, _, path)| path.clone())
+    }
+
+    ///
+    /// 拡張子ごとの振り分け先へのアクセサ
+    ///
+    /// # 戻り値
+    /// 拡張子と振り分け先の対応表
+    ///
+    fn ext_routes(&self) -> BTreeMap<String, PathBuf> {
+        self.ext_routes.clone()
+    }
+
+    ///
+    /// 拡張子に対応する振り分け先の取得
+    ///
+    /// # 引数
+    /// * `ext` - ファイルの拡張子（大文字小文字は区別しない）
+    ///
+    /// # 戻り値
+    /// 拡張子に対応する振り分け先（設定されていない場合はNone）
+    ///
+    pub(crate) fn ext_route(&self, ext: &str) -> Option<PathBuf> {
+        let ext = ext.to_lowercase();
+
+        self.ext_routes.get(&ext).cloned().or_else(|| {
+            self.file_classes
+                .get(&ext)
+                .filter(|class| class.class != FileKind::Sidecar)
+                .and_then(|class| class.route.clone())
+        })
+    }
+
+    ///
+    /// 追加する形式の定義へのアクセサ
+    ///
+    /// # 戻り値
+    /// 拡張子と形式の定義の対応表
+    ///
+    fn file_classes(&self) -> BTreeMap<String, FileClass> {
+        self.file_classes.clone()
+    }
+
+    ///
+    /// 追加する形式の登録内容へのアクセサ
+    ///
+    /// # 戻り値
+    /// 追加する形式の登録内容
+    ///
+    pub(crate) fn classes(&self) -> &Arc<ClassRegistry> {
+        &self.parsed_classes
+    }
+
+    ///
+    /// 振り分け先の一覧（拡張子ごとの振り分け先と追加する形式の振り分け先）
+    ///
+    /// # 戻り値
+    /// 設定された振り分け先を列挙するイテレータ
+    ///
+    fn route_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.ext_routes.values().chain(
+            self.file_classes
+                .values()
+                .filter(|class| class.class != FileKind::Sidecar)
+                .filter_map(|class| class.route.as_ref())
+        )
+    }
+
+    ///
+    /// ファイルの分類ごとの振り分け先へのアクセサ
+    ///
+    /// # 戻り値
+    /// ティアの一覧
+    ///
+    fn tiers(&self) -> Vec<Tier> {
+        self.tiers.clone()
+    }
+
+    ///
+    /// ファイルに適用するティアの取得
+    ///
+    /// # 引数
+    /// * `class` - ファイルの分類（jpeg、raw、video、other）
+    /// * `ext` - ファイルの拡張子（大文字小文字は区別しない）
+    ///
+    /// # 戻り値
+    /// 分類または拡張子が一致した最初のティア（該当しない場合はNone）
+    ///
+    pub(crate) fn tier_for(&self, class: &str, ext: &str) -> Option<&Tier> {
+        let ext = ext.to_lowercase();
+
+        self.tiers.iter().find(|tier| {
+            tier.classes.iter().any(|entry| *entry == class || *entry == ext)
+        })
+    }
+
+    /// 
+    /// 撮影日付の始点へのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影日付の始点（未設定の場合はNone）
+    ///
+    pub(crate) fn from_date(&self) -> Option<DateTime<Local>> {
+        self.parsed_from_date
+    }
+
+    /// 
+    /// 撮影日付の終点へのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影日付の終点（未設定の場合はNone）
+    ///
+    pub(crate) fn to_date(&self) -> Option<DateTime<Local>> {
+        self.parsed_to_date
+    } 
+
+    ///
+    /// 処理対象とする画像の向きへのアクセサ
+    ///
+    /// # 戻り値
+    /// 処理対象とする画像の向き（未設定の場合はNone）
+    ///
+    pub(crate) fn orientation(&self) -> Option<Orientation> {
+        self.orientation
+    }
+
+    ///
+    /// 除外するカメラの機種名へのアクセサ
+    ///
+    /// # 戻り値
+    /// 除外するカメラの機種名の一覧
+    ///
+    pub(crate) fn exclude_cameras(&self) -> &[String] {
+        &self.exclude_cameras
+    }
+
+    ///
+    /// 除外するカメラのシリアル番号へのアクセサ
+    ///
+    /// # 戻り値
+    /// 除外するカメラのシリアル番号の一覧
+    ///
+    pub(crate) fn exclude_serials(&self) -> &[String] {
+        &self.exclude_serials
+    }
+
+    ///
+    /// 走査で除外するグロブパターンへのアクセサ
+    ///
+    /// # 戻り値
+    /// 走査で除外するグロブパターンの一覧
+    ///
+    pub(crate) fn excludes(&self) -> &[Pattern] {
+        &self.parsed_excludes
+    }
+
+    ///
+    /// 走査で処理対象とするグロブパターンへのアクセサ
+    ///
+    /// # 戻り値
+    /// 走査で処理対象とするグロブパターンの一覧（空の場合は全てを対象とする）
+    ///
+    pub(crate) fn includes(&self) -> &[Pattern] {
+        &self.parsed_includes
+    }
+
+    ///
+    /// 撮影日の区切り時刻へのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影日の区切りとする時刻（未設定の場合は00:00）
+    ///
+    pub(crate) fn day_start(&self) -> NaiveTime {
+        self.parsed_day_start
+    }
+
+    ///
+    /// 撮影日時の解析に追加で用いる書式へのアクセサ
+    ///
+    /// # 戻り値
+    /// 書式の一覧（未指定の場合は空）
+    ///
+    pub(crate) fn datetime_formats(&self) -> &[String] {
+        &self.datetime_formats
+    }
+
+    ///
+    /// Exif情報に撮影日時が無いファイルの取り扱いへのアクセサ
+    ///
+    /// # 戻り値
+    /// 撮影日時が無いファイルの取り扱い（未指定の場合はスキップ）
+    ///
+    pub(crate) fn datetime_fallback(&self) -> DatetimeFallback {
+        self.parsed_datetime_fallback
+    }
+
+    ///
+    /// 振り分け先に同名のファイルが存在する場合の取り扱いへのアクセサ
+    ///
+    /// # 戻り値
+    /// 同名のファイルが存在する場合の取り扱い（未指定の場合は上書き）
+    ///
+    pub(crate) fn on_conflict(&self) -> Collision {
+        self.on_conflict.unwrap_or_default()
+    }
+
+    ///
+    /// ファイルに適用する同名のファイルの取り扱いを求める
+    ///
+    /// # 引数
+    /// * `tier` - ファイルに適用されるティア（無い場合はNone）
+    ///
+    /// # 戻り値
+    /// ティアに取り扱いが指定されている場合はそれを、指定されていない場合は
+    /// `--on-conflict`の指定を返す
+    ///
+    pub(crate) fn collision_for(&self, tier: Option<&Tier>) -> Collision {
+        tier.and_then(|tier| tier.collision)
+            .unwrap_or_else(|| self.on_conflict())
+    }
+
+    ///
+    /// オプション情報モードか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// オプション情報表示モードが指定されている場合は`true`が、通常モードのが
+    /// 指定されている場合は`false`が返される。
+    ///
+    fn is_show_options(&self) -> bool {
+        self.show_options
+    }
+
+    ///
+    /// 振り分けるファイル数の上限へのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分けるファイル数の上限（未設定の場合はNone）
+    ///
+    pub(crate) fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    ///
+    /// 強制再処理の対象か否かの判定
+    ///
+    /// # 引数
+    /// * `path` - 判定対象のファイルのパス
+    ///
+    /// # 戻り値
+    /// 強制再処理の対象であれば`true`を返す。
+    ///
+    /// # 注記
+    /// `--force-path`が指定されている場合は、入力ディレクトリを基点としていずれ
+    /// かのプレフィクスに合致するパスのみを対象とする。
+    ///
+    pub(crate) fn is_force_target(&self, path: &Path) -> bool {
+        if self.force_paths.is_empty() {
+            return self.force;
+        }
+
+        match path.strip_prefix(self.input_path()) {
+            Ok(rel_path) => {
+                self.force_paths
+                    .iter()
+                    .any(|prefix| rel_path.starts_with(prefix))
+            }
+
+            Err(_) => false,
+        }
+    }
+
+    ///
+    /// レポートの出力先へのアクセサ
+    ///
+    /// # 戻り値
+    /// レポートの出力先のパス（未設定の場合はNone）
+    ///
+    pub(crate) fn report_path(&self) -> Option<PathBuf> {
+        self.report_path.clone()
+    }
+
+    ///
+    /// CSV形式のレポートの出力先へのアクセサ
+    ///
+    /// # 戻り値
+    /// CSV形式のレポートの出力先のパス（未設定の場合はNone）
+    ///
+    pub(crate) fn report_csv_path(&self) -> Option<PathBuf> {
+        self.report_csv_path.clone()
+    }
+
+    ///
+    /// トレースレポートの出力先へのアクセサ
+    ///
+    /// # 戻り値
+    /// トレースレポートの出力先のパス（未設定の場合はNone）
+    ///
+    pub(crate) fn trace_report_path(&self) -> Option<PathBuf> {
+        self.trace_report_path.clone()
+    }
+
+    ///
+    /// 見積もりモードか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--estimate` が指定されていれば`true`
+    ///
+    pub(crate) fn is_estimate(&self) -> bool {
+        self.estimate
+    }
+
+    ///
+    /// 付随ファイルのみを取り込むか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--sidecars-only` が指定されていれば`true`
+    ///
+    pub(crate) fn is_sidecars_only(&self) -> bool {
+        self.sidecars_only
+    }
+
+    ///
+    /// キャッシュ情報のみを記録するか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--mark-processed` が指定されていれば`true`
+    ///
+    pub(crate) fn is_mark_processed(&self) -> bool {
+        self.mark_processed
+    }
+
+    ///
+    /// スキップ理由出力フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--explain`が指定されていれば`true`
+    ///
+    pub(crate) fn is_explain(&self) -> bool {
+        self.explain
+    }
+
+    ///
+    /// 進捗表示抑止フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--quiet`が指定されていれば`true`
+    ///
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    ///
+    /// 予行演習フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--dry-run`が指定されていれば`true`
+    ///
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    ///
+    /// サブコマンドへのアクセサ
+    ///
+    /// # 戻り値
+    /// 指定されたサブコマンド（未指定の場合はNone）
+    ///
+    pub(crate) fn command(&self) -> Option<&Command> {
+        self.command.as_ref()
+    }
+
+    ///
+    /// キャッシュデータベースファイルのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// キャッシュデータベースファイルのパス
+    ///
+    pub(crate) fn cache_db_path(&self) -> PathBuf {
+        self.parsed_cache_db_path.as_ref().unwrap().clone()
+    }
+
+    ///
+    /// ステートディレクトリのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// ステートディレクトリのパス
+    ///
+    pub(crate) fn state_dir(&self) -> PathBuf {
+        default_state_dir(self.portable_dir(), self.user())
+    }
+
+    ///
+    /// キャッシュ評価モードへのアクセサ
+    ///
+    /// # 戻り値
+    /// キャッシュ評価モード
+    ///
+    pub(crate) fn cache_eval_mode(&self) -> CacheEvalMode {
+        self.parsed_cache_eval_mode
+    }
+
+    ///
+    /// 内容のハッシュ値による索引を用いるか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 索引を用いる場合は`true`
+    ///
+    pub(crate) fn is_hash_index(&self) -> bool {
+        self.hash_index
+    }
+
+    ///
+    /// 振り分け先全体での重複の検出を行うか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 格納済みのファイルと内容が一致するファイルをコピーしない場合は`true`
+    ///
+    pub(crate) fn is_dedup(&self) -> bool {
+        self.dedup
+    }
+
+    ///
+    /// モーションフォトの取り扱いモードへのアクセサ
+    ///
+    /// # 戻り値
+    /// モーションフォトの取り扱いモード
+    ///
+    pub(crate) fn motion_photo_mode(&self) -> MotionPhotoMode {
+        self.parsed_motion_photo
+    }
+
+    ///
+    /// 入力ファイルの処理順へのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力ファイルの処理順
+    ///
+    pub(crate) fn order(&self) -> ProcessOrder {
+        self.parsed_order
+    }
+
+    ///
+    /// GPS時刻によるカメラの時計のずれの取り扱いへのアクセサ
+    ///
+    /// # 戻り値
+    /// GPS時刻によるカメラの時計のずれの取り扱い
+    ///
+    pub(crate) fn gps_clock_mode(&self) -> GpsClockMode {
+        self.parsed_gps_clock
+    }
+
+    ///
+    /// カメラの時計がずれているとみなす閾値へのアクセサ
+    ///
+    /// # 戻り値
+    /// カメラの時計がずれているとみなす閾値（未指定の場合は300秒）
+    ///
+    pub(crate) fn gps_clock_threshold(&self) -> Duration {
+        Duration::from_secs(self.gps_clock_threshold.unwrap_or(300))
+    }
+
+    ///
+    /// 振り分け先への格納方式へのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先への格納方式
+    ///
+    pub(crate) fn layout(&self) -> StorageLayout {
+        self.parsed_layout
+    }
+
+    ///
+    /// RAWファイルの圧縮レベルへのアクセサ
+    ///
+    /// # 戻り値
+    /// 圧縮レベル（圧縮しない場合はNone）
+    ///
+    pub(crate) fn compress_raw_level(&self) -> Option<i32> {
+        self.compress_raw
+    }
+
+    ///
+    /// 暗号化の受信者へのアクセサ
+    ///
+    /// # 戻り値
+    /// 暗号化の受信者（暗号化を行わない場合はNone）
+    ///
+    pub(crate) fn encrypt_recipient(&self) -> Option<String> {
+        self.encrypt_recipient.clone()
+    }
+
+    ///
+    /// 暗号化に用いるツールへのアクセサ
+    ///
+    /// # 戻り値
+    /// 暗号化に用いるツール
+    ///
+    pub(crate) fn encrypt_tool(&self) -> EncryptTool {
+        self.parsed_encrypt_tool
+    }
+
+    ///
+    /// 暗号化名を求める際の鍵へのアクセサ
+    ///
+    /// # 戻り値
+    /// 暗号化名を求める際の鍵
+    ///
+    /// # 注記
+    /// 暗号化を行う場合(`--encrypt`を指定した場合)のみ呼び出すこと。
+    ///
+    pub(crate) fn encrypt_name_key(&self) -> [u8; 32] {
+        self.parsed_encrypt_name_key.unwrap()
+    }
+
+    ///
+    /// チェックサムマニフェストを記録するか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// チェックサムマニフェストを記録する場合は`true`
+    ///
+    pub(crate) fn is_checksums(&self) -> bool {
+        self.checksums
+    }
+
+    ///
+    /// チェックサムに用いるハッシュ関数へのアクセサ
+    ///
+    /// # 戻り値
+    /// チェックサムに用いるハッシュ関数
+    ///
+    pub(crate) fn checksum_algorithm(&self) -> ChecksumAlgorithm {
+        self.parsed_checksum_algorithm
+    }
+
+    ///
+    /// 取り込みプロファイルへのアクセサ
+    ///
+    /// # 戻り値
+    /// 取り込みプロファイル（指定されていない場合はNone）
+    ///
+    pub(crate) fn ingest_profile(&self) -> Option<IngestProfile> {
+        self.profile
+    }
+
+    ///
+    /// ロックされたファイルをシャドウコピー経由で読み出すか否かのフラグへのア
+    /// クセサ
+    ///
+    /// # 戻り値
+    /// シャドウコピー経由で読み出す場合は`true`
+    ///
+    pub(crate) fn is_vss(&self) -> bool {
+        self.vss
+    }
+
+    ///
+    /// 取り込みマーカーを用いるか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力ディレクトリの取り込みマーカーを読み書きする場合は`true`
+    ///
+    pub(crate) fn is_card_marker(&self) -> bool {
+        self.card_marker
+    }
+
+    ///
+    /// 入力ファイルを移動するか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先に格納した入力ファイルを削除する場合は`true`
+    ///
+    pub(crate) fn is_move(&self) -> bool {
+        self.move_files
+    }
+
+    ///
+    /// 入力元の読み出し専用フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力元への書き込みを伴う機能を禁止する場合は`true`
+    ///
+    pub(crate) fn is_source_read_only(&self) -> bool {
+        self.source_read_only
+    }
+
+    ///
+    /// ファイルに適用するパーミッションへのアクセサ
+    ///
+    /// # 戻り値
+    /// パーミッション（未指定の場合はNone）
+    ///
+    pub(crate) fn file_mode(&self) -> Option<u32> {
+        self.parsed_file_mode
+    }
+
+    ///
+    /// ディレクトリに適用するパーミッションへのアクセサ
+    ///
+    /// # 戻り値
+    /// パーミッション（未指定の場合はNone）
+    ///
+    pub(crate) fn dir_mode(&self) -> Option<u32> {
+        self.parsed_dir_mode
+    }
+
+    ///
+    /// 振り分け先の所有者へのアクセサ
+    ///
+    /// # 戻り値
+    /// 所有者のユーザ名またはID（未指定の場合はNone）
+    ///
+    pub(crate) fn owner(&self) -> Option<String> {
+        self.owner.clone()
+    }
+
+    ///
+    /// 振り分け先のグループへのアクセサ
+    ///
+    /// # 戻り値
+    /// グループ名またはID（未指定の場合はNone）
+    ///
+    pub(crate) fn group(&self) -> Option<String> {
+        self.group.clone()
+    }
+
+    ///
+    /// 振り分け先に追加するACLのエントリへのアクセサ
+    ///
+    /// # 戻り値
+    /// ACLのエントリ（未指定の場合はNone）
+    ///
+    pub(crate) fn acl(&self) -> Option<String> {
+        self.acl.clone()
+    }
+
+    ///
+    /// 振り分け先のファイルに付与するFinderタグへのアクセサ
+    ///
+    /// # 戻り値
+    /// Finderタグの指定の一覧（未指定の場合は空）
+    ///
+    pub(crate) fn finder_tags(&self) -> Vec<String> {
+        self.finder_tags.clone()
+    }
+
+    ///
+    /// 取り込み情報ファイルの記録フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先フォルダに取り込み情報ファイルを記録する場合は`true`
+    ///
+    pub(crate) fn is_folder_info(&self) -> bool {
+        self.folder_info
+    }
+
+    ///
+    /// PAR2リカバリファイルの冗長度へのアクセサ
+    ///
+    /// # 戻り値
+    /// 冗長度（パーセント、生成しない場合はNone）
+    ///
+    pub(crate) fn par2_redundancy(&self) -> Option<u8> {
+        self.par2
+    }
+
+    ///
+    /// スナップショットの作成方法へのアクセサ
+    ///
+    /// # 戻り値
+    /// スナップショットの作成方法
+    ///
+    pub(crate) fn snapshot_mode(&self) -> SnapshotMode {
+        self.parsed_snapshot
+    }
+
+    ///
+    /// スナップショット作成用のフックコマンドへのアクセサ
+    ///
+    /// # 戻り値
+    /// フックコマンド（未設定の場合はNone）
+    ///
+    pub(crate) fn snapshot_command(&self) -> Option<String> {
+        self.snapshot_command.clone()
+    }
+
+    ///
+    /// 入力元の回復を確認する最大回数へのアクセサ
+    ///
+    /// # 戻り値
+    /// 確認の最大回数（未指定の場合は5回）
+    ///
+    pub(crate) fn source_retries(&self) -> usize {
+        self.source_retries.unwrap_or(5)
+    }
+
+    ///
+    /// 入力元の接続の確認で応答を待つ時間へのアクセサ
+    ///
+    /// # 戻り値
+    /// 応答を待つ時間（未指定の場合は10秒）
+    ///
+    pub(crate) fn source_timeout(&self) -> Duration {
+        Duration::from_secs(self.source_timeout.unwrap_or(10))
+    }
+
+    ///
+    /// 振り分け先のドライブの状態を確認するフックコマンドへのアクセサ
+    ///
+    /// # 戻り値
+    /// フックコマンド（未設定の場合はNone）
+    ///
+    pub(crate) fn health_command(&self) -> Option<String> {
+        self.health_command.clone()
+    }
+
+    ///
+    /// 振り分け先のドライブの状態を確認する間隔へのアクセサ
+    ///
+    /// # 戻り値
+    /// 確認の間隔（未指定の場合は60秒）
+    ///
+    pub(crate) fn health_interval(&self) -> Duration {
+        Duration::from_secs(self.health_interval.unwrap_or(60))
+    }
+
+    ///
+    /// コピーした全てのファイルの内容を照合するか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 全てのファイルの内容を照合する場合は`true`
+    ///
+    pub(crate) fn is_verify(&self) -> bool {
+        self.verify
+    }
+
+    ///
+    /// コピーしたファイルのうち内容を照合するものの割合へのアクセサ
+    ///
+    /// # 戻り値
+    /// 照合する割合（百分率、未指定の場合はNone）
+    ///
+    pub(crate) fn verify_sample(&self) -> Option<f64> {
+        self.parsed_verify_sample
+    }
+
+    ///
+    /// 振り分け先に残す期間へのアクセサ
+    ///
+    /// # 戻り値
+    /// 振り分け先に残す期間（日数、未指定の場合はNone）
+    ///
+    pub(crate) fn retain_days(&self) -> Option<u32> {
+        self.retain_days
+    }
+
+    ///
+    /// 保存期間を過ぎたファイルを削除する対象のディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// 削除対象のディレクトリへのパス（未指定の場合はNone）
+    ///
+    pub(crate) fn prune_root(&self) -> Option<PathBuf> {
+        self.prune_root.clone()
+    }
+
+    ///
+    /// 監視モードフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--watch`が指定されていれば`true`
+    ///
+    pub(crate) fn is_watch(&self) -> bool {
+        self.watch
+    }
+
+    ///
+    /// デーモンモードフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--daemon`が指定されていれば`true`
+    ///
+    pub(crate) fn is_daemon(&self) -> bool {
+        self.daemon
+    }
+
+    ///
+    /// 入力ディレクトリを確認する間隔へのアクセサ
+    ///
+    /// # 戻り値
+    /// 確認間隔（未指定の場合は10秒）
+    ///
+    pub(crate) fn daemon_interval(&self) -> Duration {
+        Duration::from_secs(self.interval.unwrap_or(10))
+    }
+
+    ///
+    /// 取り込みを始めるまでの静止時間へのアクセサ
+    ///
+    /// # 戻り値
+    /// 静止時間（未指定の場合は0秒、静止を待たずに取り込む）
+    ///
+    pub(crate) fn quiet_period(&self) -> Duration {
+        Duration::from_secs(self.quiet_period.unwrap_or(0))
+    }
+
+    ///
+    /// デーモンモードでの定期取り込みの一覧へのアクセサ
+    ///
+    /// # 戻り値
+    /// 定期取り込みの設定の一覧
+    ///
+    fn schedule_entries(&self) -> Vec<Schedule> {
+        self.schedules.clone()
+    }
+
+    ///
+    /// デーモンモードでの定期取り込みのスケジュールの取得
+    ///
+    /// # 戻り値
+    /// パース済みのスケジュールと取り込み元の組を列挙するイテレータ
+    ///
+    pub(crate) fn schedules(&self)
+        -> impl Iterator<Item = (&CronExpr, &Path)>
+    {
+        self.parsed_schedules
+            .iter()
+            .map(|(cron, source)| (cron, source.as_path()))
+    }
+
+    ///
+    /// デーモンモードでのホットフォルダからの配信の一覧へのアクセサ
+    ///
+    /// # 戻り値
+    /// 配信の設定の一覧
+    ///
+    fn export_entries(&self) -> Vec<Export> {
+        self.exports.clone()
+    }
+
+    ///
+    /// ホットフォルダからの配信に用いるオプション設定の生成
+    ///
+    /// # 戻り値
+    /// 配信ごとのオプション設定を列挙するイテレータ
+    ///
+    /// # 注記
+    /// 入力ディレクトリをホットフォルダ、出力ディレクトリを配信先に差し替え、
+    /// 配信の設定のテンプレートを適用する。対象はJPEGファイルのみとし、RAW
+    /// 画像や動画の保存ディレクトリ、撮影者や撮影年ごとの出力先、シャード、
+    /// 拡張子ごとの振り分け先、ティアおよび格納方式の設定は適用しない（全て
+    /// 配信先のテンプレートに従って格納する）。
+    ///
+    pub(crate) fn exports(&self) -> impl Iterator<Item = Options> + '_ {
+        self.parsed_exports.iter().map(|(entry, path, name)| {
+            let mut opts = self.clone();
+
+            opts.input_path = Some(entry.source.clone());
+            opts.output_path = Some(entry.output.clone());
+            opts.parsed_path_template = path.clone();
+
+            if name.is_some() {
+                opts.parsed_name_template = name.clone();
+            }
+
+            opts.jpeg_only = true;
+            opts.raw_output_path = None;
+            opts.video_output_path = None;
+            opts.copy_unknown = None;
+            opts.parsed_layout = StorageLayout::Date;
+            opts.artist_roots.clear();
+            opts.year_roots.clear();
+            opts.parsed_year_roots.clear();
+            opts.shards.clear();
+            opts.ext_routes.clear();
+            opts.file_classes.clear();
+            opts.tiers.clear();
+            opts.parsed_schedules.clear();
+            opts.parsed_exports.clear();
+
+            opts
+        })
+    }
+
+    ///
+    /// JPEGファイルのみを対象とするか否かのフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// ホットフォルダからの配信の場合は`true`
+    ///
+    pub(crate) fn is_jpeg_only(&self) -> bool {
+        self.jpeg_only
+    }
+
+    ///
+    /// HTTPエンドポイントの待ち受けアドレスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 待ち受けアドレス（起動しない場合はNone）
+    ///
+    pub(crate) fn http_addr(&self) -> Option<SocketAddr> {
+        self.parsed_http_addr
+    }
+
+    ///
+    /// プレビューページ提供フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--preview`が指定されていれば`true`
+    ///
+    pub(crate) fn is_preview(&self) -> bool {
+        self.preview
+    }
+
+    ///
+    /// 制御用ソケットのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 制御用ソケットのパス（作成しない場合はNone）
+    ///
+    pub(crate) fn control_socket(&self) -> Option<PathBuf> {
+        self.control_socket.clone()
+    }
+
+    ///
+    /// FTPサーバの待ち受けアドレスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 待ち受けアドレス（起動しない場合はNone）
+    ///
+    pub(crate) fn ftp_addr(&self) -> Option<SocketAddr> {
+        self.parsed_ftp_addr
+    }
+
+    ///
+    /// FTPサーバへのログインに用いるユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ名（任意のユーザでログインできる場合はNone）
+    ///
+    pub(crate) fn ftp_user(&self) -> Option<String> {
+        self.ftp_user.clone()
+    }
+
+    ///
+    /// FTPサーバへのログインに用いるパスワードへのアクセサ
+    ///
+    /// # 戻り値
+    /// パスワード（任意のユーザでログインできる場合はNone）
+    ///
+    pub(crate) fn ftp_password(&self) -> Option<String> {
+        self.ftp_password.clone()
+    }
+
+    ///
+    /// SMTPサーバのURLへのアクセサ
+    ///
+    /// # 戻り値
+    /// SMTPサーバのURL（メールで通知しない場合はNone）
+    ///
+    pub(crate) fn smtp_url(&self) -> Option<String> {
+        self.smtp.clone()
+    }
+
+    ///
+    /// SMTPサーバの認証に用いるユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ名（認証を行わない場合はNone）
+    ///
+    pub(crate) fn smtp_user(&self) -> Option<String> {
+        self.smtp_user.clone()
+    }
+
+    ///
+    /// SMTPサーバの認証に用いるパスワードへのアクセサ
+    ///
+    /// # 戻り値
+    /// パスワード（認証を行わない場合はNone）
+    ///
+    pub(crate) fn smtp_password(&self) -> Option<String> {
+        self.smtp_password.clone()
+    }
+
+    ///
+    /// 通知メールの送信元アドレスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 送信元アドレス（未設定の場合はNone）
+    ///
+    pub(crate) fn mail_from(&self) -> Option<String> {
+        self.mail_from.clone()
+    }
+
+    ///
+    /// 通知メールの宛先アドレスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 宛先アドレスの一覧
+    ///
+    pub(crate) fn mail_to(&self) -> Vec<String> {
+        self.mail_to.clone()
+    }
+
+    ///
+    /// iOSデバイスからの取り込みフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--ios`が指定されていれば`true`
+    ///
+    pub(crate) fn is_ios(&self) -> bool {
+        self.ios
+    }
+
+    ///
+    /// 取り込み元のiOSデバイスのUDIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// デバイスのUDID（未指定の場合はNone）
+    ///
+    pub(crate) fn ios_udid(&self) -> Option<String> {
+        self.ios_udid.clone()
+    }
+
+    ///
+    /// 取り込み元のクラウドストレージのフォルダへのアクセサ
+    ///
+    /// # 戻り値
+    /// rcloneのリモートのパス（未指定の場合はNone）
+    ///
+    pub(crate) fn cloud_remote(&self) -> Option<String> {
+        self.cloud.clone()
+    }
+
+    ///
+    /// 入力ディレクトリを差し替えたオプション設定の生成
+    ///
+    /// # 引数
+    /// * `path` - 入力ディレクトリのパス
+    ///
+    /// # 戻り値
+    /// 入力ディレクトリ以外はこのオブジェクトと同じ設定のオブジェクト
+    ///
+    pub(crate) fn with_input_path(&self, path: PathBuf) -> Self {
+        let mut opts = self.clone();
+        opts.input_path = Some(path);
+        opts
+    }
+
+    ///
+    /// 入力ディレクトリと振り分け先の重なりの確認
+    ///
+    /// # 戻り値
+    /// 入力ディレクトリといずれの振り分け先も重なっていない場合は`Ok(())`を
+    /// 返す。重なっている場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 入力ディレクトリが存在しない場合は確認しない。
+    ///
+    pub(crate) fn check_input_overlap(&self) -> Result<()> {
+        if let Ok(input) = self.input_path().canonicalize() {
+            for root in self.destination_roots() {
+                check_overlap(&input, &root)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 振り分け先の基点ディレクトリの一覧の取得
+    ///
+    /// # 戻り値
+    /// 出力ディレクトリ、RAW画像保存ディレクトリ、動画保存ディレクトリ、撮影者
+    /// ごとの出力ディレクトリを重複を除いて列挙したもの
+    ///
+    pub(crate) fn destination_roots(&self) -> Vec<PathBuf> {
+        let mut roots = vec![self.output_path()];
+
+        roots.extend(self.raw_output_path());
+        roots.extend(self.video_output_path());
+        roots.extend(self.copy_unknown_dir());
+        roots.extend(
+            self.cas_dir
+                .clone()
+                .filter(|_| self.parsed_layout == StorageLayout::Cas)
+        );
+        roots.extend(self.artist_roots.values().cloned());
+        roots.extend(self.year_roots.values().cloned());
+        roots.extend(self.shards.iter().cloned());
+        roots.extend(self.tiers.iter().map(|tier| tier.path.clone()));
+        roots.extend(
+            self.route_paths()
+                .filter(|path| path.is_absolute())
+                .cloned()
+        );
+        roots.sort();
+        roots.dedup();
+
+        roots
+    }
+
+    ///
+    /// コンフィギュレーションファイルパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 確定したコンフィギュレーションファイルのパス
+    ///
+    fn config_path(&self) -> PathBuf {
+        self.parsed_config_path.clone()
+    }
+
+    ///
+    /// 設定保存フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// `--save-config` が指定されていれば`true`
+    ///
+    fn is_save_config(&self) -> bool {
+        self.save_config
+    }
+
+    ///
+    /// 設定ファイルのバックアップの保持世代数へのアクセサ
+    ///
+    /// # 戻り値
+    /// 保持する世代数（0の場合はバックアップを作成しない）
+    ///
+    pub(super) fn config_backups(&self) -> usize {
+        self.config_backups.unwrap_or(5)
+    }
+
+    ///
+    /// キャッシュデータベースオブジェクトへのアクセサ
+    ///
+    /// # 戻り値
+    /// キャッシュデータベースオブジェクト
+    ///
+    pub fn cache(&self) -> Arc<Cache> {
+        self.cache.as_ref().unwrap().clone()
+    }
+
+    ///
+    /// 存在を確認済みのディレクトリの一覧へのアクセサ
+    ///
+    /// # 戻り値
+    /// 存在を確認済みのディレクトリの一覧
+    ///
+    pub(crate) fn created_dirs(&self) -> &CreatedDirs {
+        &self.created_dirs
+    }
+
+    ///
+    /// 求めたシャードの空き容量の一覧へのアクセサ
+    ///
+    /// # 戻り値
+    /// 求めたシャードの空き容量の一覧
+    ///
+    pub(crate) fn free_spaces(&self) -> &FreeSpaces {
+        &self.free_spaces
+    }
+
+    ///
+    /// 暗号化したマニフェストの書き出しを待つ振り分け先の一覧へのアクセサ
+    ///
+    /// # 戻り値
+    /// 暗号化したマニフェストの書き出しを待つ振り分け先の一覧
+    ///
+    pub(crate) fn pending_manifests(&self) -> &PendingManifests {
+        &self.pending_manifests
+    }
+
+    ///
+    /// 取り込み処理の一時停止状態へのアクセサ
+    ///
+    /// # 戻り値
+    /// 一時停止状態(複製したオプション設定と共有する)
+    ///
+    pub(crate) fn pause_state(&self) -> &Arc<PauseState> {
+        &self.pause
+    }
+
+    ///
+    /// オプション設定内容の表示
+    ///
+    pub(crate) fn show_options(&self) {
+        println!("user:            {:?}", self.user());
+        println!("log level:       {}", self.log_level().as_ref());
+        println!("log output:      {:?}", self.log_output());
+        println!("portable dir:    {:?}", self.portable_dir());
+
+        for (origin, path) in &self.config_files {
+            println!("config file:     {:?} ({})", path, origin.as_ref());
+        }
+
+        println!("output path:     {:?}", self.output_path);
+        println!("raw output path: {:?}", self.raw_output_path());
+        println!("video output:    {:?}", self.video_output_path());
+        println!("copy unknown:    {:?}", self.copy_unknown_dir());
+        println!("cas dir:         {:?}", self.cas_dir());
+        println!("path template:   {}", self.path_template());
+        println!("name template:   {:?}", self.name_template);
+        println!("calendar:        {:?}", self.calendar_path());
+        println!("exclude list:    {:?}", self.exclude_names_from());
+        println!("history db:      {:?}", self.history_db());
+        println!("artist roots:    {:?}", self.artist_roots);
+        println!("year roots:      {:?}", self.year_roots);
+        println!("shards:          {:?}", self.shards);
+        println!("ext routes:      {:?}", self.ext_routes);
+        println!("file classes:    {:?}", self.file_classes);
+        println!(
+            "tiers:           {:?}",
+            self.tiers.iter().map(|tier| &tier.name).collect::<Vec<_>>()
+        );
+        println!("from data:       {:?}", self.from_date());
+        println!("to data:         {:?}", self.to_date());
+        println!("orientation:     {:?}", self.orientation());
+        println!("exclude cameras: {:?}", self.exclude_cameras);
+        println!("exclude serials: {:?}", self.exclude_serials);
+        println!("excludes:        {:?}", self.excludes);
+        println!("includes:        {:?}", self.includes);
+        println!("day start:       {}", self.day_start().format("%H:%M"));
+        println!("datetime format: {:?}", self.datetime_formats());
+        println!("dt fallback:     {:?}", self.datetime_fallback());
+        println!("on conflict:     {:?}", self.on_conflict());
+        println!("input path:      {:?}", self.input_path);
+        println!("cache db path:   {:?}", self.cache_db_path());
+        println!("state dir:       {:?}", self.state_dir());
+        println!("cache eval mode: {:?}", self.cache_eval_mode());
+        println!("hash index:      {:?}", self.is_hash_index());
+        println!("dedup:           {:?}", self.is_dedup());
+        println!("motion photo:    {:?}", self.motion_photo_mode());
+        println!("order:           {:?}", self.order());
+        println!("gps clock:       {:?}", self.gps_clock_mode());
+        println!("gps clock thr:   {:?}", self.gps_clock_threshold());
+        println!("layout:          {:?}", self.layout());
+        println!("compress raw:    {:?}", self.compress_raw_level());
+        println!("encrypt:         {:?}", self.encrypt_recipient());
+        println!("encrypt tool:    {:?}", self.encrypt_tool());
+        println!("checksums:       {:?}", self.is_checksums());
+        println!("checksum algo:   {:?}", self.checksum_algorithm());
+        println!("profile:         {:?}", self.ingest_profile());
+        println!("vss:             {:?}", self.is_vss());
+        println!("card marker:     {:?}", self.is_card_marker());
+        println!("move:            {:?}", self.is_move());
+        println!("source ro:       {:?}", self.is_source_read_only());
+        println!("file mode:       {:?}", self.file_mode());
+        println!("dir mode:        {:?}", self.dir_mode());
+        println!("owner:           {:?}", self.owner());
+        println!("group:           {:?}", self.group());
+        println!("acl:             {:?}", self.acl());
+        println!("finder tags:     {:?}", self.finder_tags());
+        println!("folder info:     {:?}", self.is_folder_info());
+        println!("par2:            {:?}", self.par2_redundancy());
+        println!("snapshot:        {:?}", self.snapshot_mode());
+        println!("snapshot cmd:    {:?}", self.snapshot_command());
+        println!("source retries:  {:?}", self.source_retries());
+        println!("source timeout:  {:?}", self.source_timeout());
+        println!("health command:  {:?}", self.health_command());
+        println!("health interval: {:?}", self.health_interval());
+        println!("verify:          {:?}", self.is_verify());
+        println!("verify sample:   {:?}", self.verify_sample());
+        println!("retain days:     {:?}", self.retain_days());
+        println!("prune root:      {:?}", self.prune_root());
+        println!("watch:           {:?}", self.is_watch());
+        println!("daemon:          {:?}", self.is_daemon());
+        println!("interval:        {:?}", self.daemon_interval());
+        println!("quiet period:    {:?}", self.quiet_period());
+        println!(
+            "schedules:       {:?}",
+            self.schedules
+                .iter()
+                .map(|entry| (&entry.cron, &entry.source))
+                .collect::<Vec<_>>()
+        );
+        println!(
+            "exports:         {:?}",
+            self.exports
+                .iter()
+                .map(|entry| (&entry.source, &entry.output, &entry.client))
+                .collect::<Vec<_>>()
+        );
+        println!("http:            {:?}", self.http_addr());
+        println!("preview:         {:?}", self.is_preview());
+        println!("control socket:  {:?}", self.control_socket());
+        println!("ftp:             {:?}", self.ftp_addr());
+        println!("ftp user:        {:?}", self.ftp_user());
+        println!("smtp:            {:?}", self.smtp_url());
+        println!("smtp user:       {:?}", self.smtp_user());
+        println!("mail from:       {:?}", self.mail_from());
+        println!("mail to:         {:?}", self.mail_to());
+        println!("ios:             {:?}", self.is_ios());
+        println!("ios udid:        {:?}", self.ios_udid());
+        println!("cloud:           {:?}", self.cloud_remote());
+        println!("limit:           {:?}", self.limit());
+        println!("force:           {:?}", self.force);
+        println!("force paths:     {:?}", self.force_paths);
+        println!("report path:     {:?}", self.report_path());
+        println!("report csv:      {:?}", self.report_csv_path());
+        println!("trace report:    {:?}", self.trace_report_path());
+        println!("estimate:        {:?}", self.is_estimate());
+        println!("sidecars only:   {:?}", self.is_sidecars_only());
+        println!("mark processed:  {:?}", self.is_mark_processed());
+        println!("dry run:         {:?}", self.is_dry_run());
+        println!("explain:         {:?}", self.is_explain());
+        println!("save config:     {:?}", self.is_save_config());
+        println!("config backups:  {:?}", self.config_backups());
+        println!("yes:             {:?}", self.yes);
+        println!("no input:        {:?}", self.no_input);
+        println!("quiet:           {:?}", self.is_quiet());
+        println!("config path:     {:?}", self.config_path());
+
+        println!("value origins:");
+
+        for (name, origin) in &self.origins {
+            println!("  {:<17}{}", name, origin.as_ref());
+        }
+    }
+
+    ///
+    /// コンフィギュレーションの適用
+    ///
+    /// # 注記
+    /// システム全体（/etc/imgdist/config.toml）、ユーザ（config.toml）、プロジ
+    /// ェクト（カレントディレクトリのimgdist.toml）の各コンフィギュレーション
+    /// ファイルを読み込みオプション情報に反映する。同じ項目が複数の箇所で設定さ
+    /// れている場合はコマンドライン、プロジェクト、ユーザ、システムの順に優先す
+    /// る。
+    ///
+    fn apply_config(&mut self) -> Result<()> {
+        // ユーザ名はデフォルトのパスの構築に用いるため最初に確認する
+        if let Some(user) = self.user() {
+            check_user_name(user)?;
+        }
+
+        // ポータブルモードのディレクトリも同様にデフォルトのパスの構築に用い
+        // るため最初に決定する
+        if let Some(dir) = &self.portable {
+            let dir = match dir {
+                Some(dir) => dir.clone(),
+                None => default_portable_dir()?,
+            };
+
+            if let Err(err) = std::fs::create_dir_all(&dir) {
+                return Err(anyhow!("create {} failed: {}", dir.display(), err));
+            }
+
+            self.parsed_portable_dir = Some(dir);
+        }
+
+        let user_path = if let Some(path) = &self.config_file {
+            // オプションでコンフィギュレーションファイルのパスが指定されて
+            // いる場合、そのパスに何もなければエラー
+            if !path.exists() {
+                return Err(anyhow!("{} is not exists", path.display()));
+            }
+
+            // 指定されたパスを返す
+            path.clone()
+        } else {
+            // 指定されていない場合はデフォルトのパスを返す
+            default_config_path(self.portable_dir(), self.user())
+        };
+
+        /*
+         * コマンドラインで指定された項目の記録
+         */
+        let given = [
+            ("log_level", self.log_level.is_some()),
+            ("log_output", self.log_output.is_some()),
+            ("raw_output_path", self.raw_output_path.is_some()),
+            ("video_output_path", self.video_output_path.is_some()),
+            ("copy_unknown", self.copy_unknown.is_some()),
+            ("cas_dir", self.cas_dir.is_some()),
+            ("output_path", self.output_path.is_some()),
+            ("path_template", self.path_template.is_some()),
+            ("name_template", self.name_template.is_some()),
+            ("calendar", self.calendar.is_some()),
+            ("exclude_names_from", self.exclude_names_from.is_some()),
+            ("history_db", self.history_db.is_some()),
+            ("cache_db_path", self.cache_db_path.is_some()),
+            ("cache_eval_mode", self.cache_eval_mode.is_some()),
+            ("day_start", self.day_start.is_some()),
+            ("datetime_formats", !self.datetime_formats.is_empty()),
+            ("excludes", !self.excludes.is_empty()),
+            ("includes", !self.includes.is_empty()),
+            ("datetime_fallback", self.datetime_fallback.is_some()),
+            ("on_conflict", self.on_conflict.is_some()),
+            ("motion_photo", self.motion_photo.is_some()),
+            ("order", self.order.is_some()),
+            ("gps_clock", self.gps_clock.is_some()),
+            ("gps_clock_threshold", self.gps_clock_threshold.is_some()),
+            ("layout", self.layout.is_some()),
+            ("compress_raw", self.compress_raw.is_some()),
+            ("encrypt_recipient", self.encrypt_recipient.is_some()),
+            ("encrypt_tool", self.encrypt_tool.is_some()),
+            ("checksums", self.checksums),
+            ("checksum_algorithm", self.checksum_algorithm.is_some()),
+            ("profile", self.profile.is_some()),
+            ("hash_index", self.hash_index),
+            ("dedup", self.dedup),
+            ("vss", self.vss),
+            ("card_marker", self.card_marker),
+            ("move_files", self.move_files),
+            ("source_read_only", self.source_read_only),
+            ("file_mode", self.file_mode.is_some()),
+            ("dir_mode", self.dir_mode.is_some()),
+            ("owner", self.owner.is_some()),
+            ("group", self.group.is_some()),
+            ("acl", self.acl.is_some()),
+            ("finder_tags", !self.finder_tags.is_empty()),
+            ("folder_info", self.folder_info),
+            ("par2", self.par2.is_some()),
+            ("snapshot", self.snapshot.is_some()),
+            ("snapshot_command", self.snapshot_command.is_some()),
+            ("source_retries", self.source_retries.is_some()),
+            ("source_timeout", self.source_timeout.is_some()),
+            ("health_command", self.health_command.is_some()),
+            ("health_interval", self.health_interval.is_some()),
+            ("verify", self.verify),
+            ("verify_sample", self.verify_sample.is_some()),
+            ("retain_days", self.retain_days.is_some()),
+            ("prune_root", self.prune_root.is_some()),
+            ("interval", self.interval.is_some()),
+            ("quiet_period", self.quiet_period.is_some()),
+            ("http", self.http.is_some()),
+            ("preview", self.preview),
+            ("control_socket", self.control_socket.is_some()),
+            ("ftp", self.ftp.is_some()),
+            ("ftp_user", self.ftp_user.is_some()),
+            ("ftp_password", self.ftp_password.is_some()),
+            ("smtp", self.smtp.is_some()),
+            ("smtp_user", self.smtp_user.is_some()),
+            ("smtp_password", self.smtp_password.is_some()),
+            ("mail_from", self.mail_from.is_some()),
+            ("mail_to", !self.mail_to.is_empty()),
+            ("config_backups", self.config_backups.is_some()),
+        ];
+
+        for (name, is_given) in given {
+            if is_given {
+                self.origins.insert(name, ConfigOrigin::CommandLine);
+            }
+        }
+
+        /*
+         * 優先度の高い順にコンフィギュレーションファイルを適用
+         */
+        let layers = [
+            (ConfigOrigin::Project, project_config_path()),
+            (ConfigOrigin::User, user_path),
+            (ConfigOrigin::System, system_config_path()),
+        ];
+
+        for (origin, path) in layers {
+            // パスに何も無い場合はその階層を読み飛ばす
+            if !path.exists() {
+                continue;
+            }
+
+            // 指定されたパスにあるのがファイルでなければエラー
+            if !path.is_file() {
+                return Err(anyhow!("{} is not file", path.display()));
+            }
+
+            // そのパスからコンフィギュレーションを読み取り内容を適用
+            match config::read(&path) {
+                Ok(config) => self.merge_config(&config, origin),
+                Err(err) => return Err(anyhow!("{}", err)),
+            }
+
+            self.config_files.push((origin, path));
+        }
+
+        /*
+         * ポータブルモードのログ出力先の設定
+         */
+        // ログの出力先が設定されていない場合はポータブルモードのディレクトリ
+        // 内に出力する
+        if self.log_output.is_none() {
+            if let Some(dir) = self.portable_dir() {
+                let dir = dir.join("logs");
+
+                if let Err(err) = std::fs::create_dir_all(&dir) {
+                    return Err(anyhow!(
+                        "create {} failed: {}",
+                        dir.display(),
+                        err
+                    ));
+                }
+
+                self.log_output = Some(dir);
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 1つのコンフィギュレーションファイルの内容をオプション情報に反映する
+    ///
+    /// # 引数
+    /// * `config` - コンフィギュレーションファイルの内容
+    /// * `origin` - コンフィギュレーションファイルの種別
+    ///
+    /// # 注記
+    /// より優先度の高い箇所で設定済みの項目は変更しない。
+    ///
+    fn merge_config(&mut self, config: &config::Config, origin: ConfigOrigin) {
+        // 未設定の項目にのみ値を設定し、その出所を記録する
+        macro_rules! merge {
+            ($name:ident) => {
+                if self.$name.is_none() {
+                    if let Some(value) = config.$name() {
+                        self.$name = Some(value);
+                        self.origins.insert(stringify!($name), origin);
                     }
                 }
+            };
+        }
+
+        merge!(log_level);
+        merge!(log_output);
+        merge!(raw_output_path);
+        merge!(video_output_path);
+        merge!(copy_unknown);
+        merge!(cas_dir);
+        merge!(output_path);
+        merge!(path_template);
+        merge!(name_template);
+        merge!(calendar);
+        merge!(exclude_names_from);
+        merge!(history_db);
+        merge!(cache_db_path);
+        merge!(cache_eval_mode);
+        merge!(day_start);
+        merge!(datetime_fallback);
+        merge!(on_conflict);
+        merge!(motion_photo);
+        merge!(order);
+        merge!(gps_clock);
+        merge!(gps_clock_threshold);
+        merge!(layout);
+        merge!(compress_raw);
+        merge!(encrypt_recipient);
+        merge!(encrypt_tool);
+        merge!(checksum_algorithm);
+        merge!(profile);
+        merge!(file_mode);
+        merge!(dir_mode);
+        merge!(owner);
+        merge!(group);
+        merge!(acl);
+        merge!(par2);
+        merge!(snapshot);
+        merge!(snapshot_command);
+        merge!(source_retries);
+        merge!(source_timeout);
+        merge!(health_command);
+        merge!(health_interval);
+        merge!(verify_sample);
+        merge!(retain_days);
+        merge!(prune_root);
+        merge!(interval);
+        merge!(quiet_period);
+        merge!(http);
+        merge!(control_socket);
+        merge!(ftp);
+        merge!(ftp_user);
+        merge!(ftp_password);
+        merge!(smtp);
+        merge!(smtp_user);
+        merge!(smtp_password);
+        merge!(mail_from);
+        merge!(config_backups);
+
+        // 撮影者名ごとの出力ディレクトリは表単位で置き換える
+        if !self.origins.contains_key("artist_roots") {
+            if let Some(roots) = config.artist_roots() {
+                self.artist_roots = roots;
+                self.origins.insert("artist_roots", origin);
+            }
+        }
+
+        // シャードは一覧単位で置き換える
+        if !self.origins.contains_key("shards") {
+            if let Some(shards) = config.shards() {
+                self.shards = shards;
+                self.origins.insert("shards", origin);
+            }
+        }
+
+        // 撮影年の範囲ごとの出力ディレクトリも表単位で置き換える
+        if !self.origins.contains_key("year_roots") {
+            if let Some(roots) = config.year_roots() {
+                self.year_roots = roots;
+                self.origins.insert("year_roots", origin);
+            }
+        }
+
+        // 拡張子ごとの振り分け先も表単位で置き換える（拡張子は小文字に揃え
+        // る）
+        if !self.origins.contains_key("ext_routes") {
+            if let Some(routes) = config.ext_routes() {
+                self.ext_routes = routes
+                    .into_iter()
+                    .map(|(ext, path)| {
+                        (ext.trim_start_matches('.').to_lowercase(), path)
+                    })
+                    .collect();
+                self.origins.insert("ext_routes", origin);
+            }
+        }
+
+        // 追加する形式の定義も表単位で置き換える（拡張子は小文字に揃える）
+        if !self.origins.contains_key("file_classes") {
+            if let Some(classes) = config.file_classes() {
+                self.file_classes = classes
+                    .into_iter()
+                    .map(|(ext, class)| {
+                        (ext.trim_start_matches('.').to_lowercase(), class)
+                    })
+                    .collect();
+                self.origins.insert("file_classes", origin);
+            }
+        }
+
+        // ティアも一覧単位で置き換える（分類と拡張子は小文字に揃える）
+        if !self.origins.contains_key("tiers") {
+            if let Some(tiers) = config.tiers() {
+                self.tiers = tiers
+                    .into_iter()
+                    .map(|mut tier| {
+                        tier.classes = tier
+                            .classes
+                            .iter()
+                            .map(|entry| {
+                                entry.trim_start_matches('.').to_lowercase()
+                            })
+                            .collect();
+                        tier
+                    })
+                    .collect();
+                self.origins.insert("tiers", origin);
+            }
+        }
+
+        // 定期取り込みも一覧単位で置き換える
+        if !self.origins.contains_key("schedules") {
+            if let Some(schedules) = config.schedules() {
+                self.schedules = schedules;
+                self.origins.insert("schedules", origin);
+            }
+        }
+
+        // ホットフォルダからの配信も一覧単位で置き換える
+        if !self.origins.contains_key("exports") {
+            if let Some(exports) = config.exports() {
+                self.exports = exports;
+                self.origins.insert("exports", origin);
+            }
+        }
+
+        // 通知メールの宛先は一覧単位で置き換える
+        if !self.origins.contains_key("mail_to") {
+            if let Some(mail_to) = config.mail_to() {
+                self.mail_to = mail_to;
+                self.origins.insert("mail_to", origin);
+            }
+        }
+
+        // フラグはコマンドラインでの指定が無い場合のみ設定する
+        if !self.origins.contains_key("checksums") {
+            if let Some(checksums) = config.checksums() {
+                self.checksums = checksums;
+                self.origins.insert("checksums", origin);
+            }
+        }
+
+        if !self.origins.contains_key("hash_index") {
+            if let Some(hash_index) = config.hash_index() {
+                self.hash_index = hash_index;
+                self.origins.insert("hash_index", origin);
+            }
+        }
+
+        if !self.origins.contains_key("dedup") {
+            if let Some(dedup) = config.dedup() {
+                self.dedup = dedup;
+                self.origins.insert("dedup", origin);
+            }
+        }
+
+        if !self.origins.contains_key("vss") {
+            if let Some(vss) = config.vss() {
+                self.vss = vss;
+                self.origins.insert("vss", origin);
+            }
+        }
+
+        if !self.origins.contains_key("verify") {
+            if let Some(verify) = config.verify() {
+                self.verify = verify;
+                self.origins.insert("verify", origin);
+            }
+        }
+
+        if !self.origins.contains_key("preview") {
+            if let Some(preview) = config.preview() {
+                self.preview = preview;
+                self.origins.insert("preview", origin);
+            }
+        }
+
+        if !self.origins.contains_key("card_marker") {
+            if let Some(card_marker) = config.card_marker() {
+                self.card_marker = card_marker;
+                self.origins.insert("card_marker", origin);
+            }
+        }
+
+        if !self.origins.contains_key("move_files") {
+            if let Some(move_files) = config.move_files() {
+                self.move_files = move_files;
+                self.origins.insert("move_files", origin);
+            }
+        }
+
+        if !self.origins.contains_key("source_read_only") {
+            if let Some(source_read_only) = config.source_read_only() {
+                self.source_read_only = source_read_only;
+                self.origins.insert("source_read_only", origin);
+            }
+        }
+
+        if !self.origins.contains_key("folder_info") {
+            if let Some(folder_info) = config.folder_info() {
+                self.folder_info = folder_info;
+                self.origins.insert("folder_info", origin);
+            }
+        }
+
+        // 撮影日時の書式は一覧単位で置き換える
+        if !self.origins.contains_key("datetime_formats") {
+            if let Some(formats) = config.datetime_formats() {
+                self.datetime_formats = formats;
+                self.origins.insert("datetime_formats", origin);
+            }
+        }
+
+        // 走査のグロブパターンも一覧単位で置き換える
+        if !self.origins.contains_key("excludes") {
+            if let Some(patterns) = config.excludes() {
+                self.excludes = patterns;
+                self.origins.insert("excludes", origin);
+            }
+        }
+
+        if !self.origins.contains_key("includes") {
+            if let Some(patterns) = config.includes() {
+                self.includes = patterns;
+                self.origins.insert("includes", origin);
+            }
+        }
+
+        // Finderタグは一覧単位で置き換える
+        if !self.origins.contains_key("finder_tags") {
+            if let Some(tags) = config.finder_tags() {
+                self.finder_tags = tags;
+                self.origins.insert("finder_tags", origin);
+            }
+        }
+    }
+
+    ///
+    /// 設定情報のバリデーションとキャッシュの構築
+    ///
+    /// # 戻り値
+    /// 設定情報に問題が無い場合は`Ok(())`を返す。問題があった場合はエラー情報
+    /// を`Err()`でラップして返す。
+    fn validate(&mut self) -> Result<()> {
+        /*
+         * 入出力ディレクトリの確認（振り分け処理を行う場合のみ）
+         */
+        if self.command.is_none() || self.is_import_all() {
+            self.validate_paths()?;
+        }
+
+        /*
+         * 追加する形式の登録（振り分け先の索引付けでも参照する）
+         */
+        let classes = ClassRegistry::new(&self.file_classes)?;
+        self.parsed_classes = Arc::new(classes);
+
+        /*
+         * 処理件数の上限の確認
+         */
+        if self.limit == Some(0) {
+            return Err(anyhow!("limit must be greater than 0"));
+        }
+
+        /*
+         * PAR2リカバリファイルの冗長度の確認
+         */
+        if self.par2.is_some_and(|pct| pct == 0 || pct > 100) {
+            return Err(anyhow!("par2 redundancy must be between 1 and 100"));
+        }
+
+        /*
+         * RAWファイルの圧縮レベルの確認
+         */
+        if let Some(level) = self.compress_raw {
+            if !(1..=22).contains(&level) {
+                return Err(anyhow!("compress level must be between 1 and 22"));
+            }
+
+            // 暗号化ファイルは圧縮が効かないため併用は認めない
+            if self.encrypt_recipient.is_some() {
+                return Err(anyhow!(
+                    "--compress-raw cannot be used with --encrypt"
+                ));
+            }
+        }
 
-                if self.log_output.is_none() {
-                    if let Some(path) = config.log_output() {
-                        self.log_output = Some(path);
-                    }
-                }
+        // 暗号化したファイルは内容を入力ファイルと照合できず、指定が全く効か
+        // ないため併用は認めない
+        if self.encrypt_recipient.is_some()
+            && (self.verify || self.verify_sample.is_some())
+        {
+            return Err(anyhow!(
+                "--verify and --verify-sample cannot be used with --encrypt"
+            ));
+        }
 
-                if self.raw_output_path.is_none() {
-                    if let Some(path) = config.raw_output_path() {
-                        self.raw_output_path = Some(path);
-                    }
-                }
+        /*
+         * 日付形式の確認とキャッシュの構築
+         */
+        if let Some(ref from_date) = self.from_date {
+            self.parsed_from_date = Some(parse_datetime(from_date)?);
+        }
 
-                if self.output_path.is_none() {
-                    if let Some(path) = config.output_path() {
-                        self.output_path = Some(path);
-                    }
+        if let Some(ref to_date) = self.to_date {
+            self.parsed_to_date = Some(parse_datetime(to_date)?);
+        }
+
+        /*
+         * パステンプレートの確認
+         */
+        if let Some(ref template) = self.path_template {
+            self.parsed_path_template = PathTemplate::parse(template)?;
+        }
+
+        if let Some(ref template) = self.name_template {
+            self.parsed_name_template = Some(NameTemplate::parse(template)?);
+        }
+
+        /*
+         * カレンダーの読み込み
+         */
+        if let Some(ref path) = self.calendar {
+            self.parsed_calendar = Some(Arc::new(Calendar::load(path)?));
+        }
+
+        /*
+         * 除外リストの読み込み
+         */
+        if let Some(ref path) = self.exclude_names_from {
+            self.parsed_exclude_list =
+                Some(Arc::new(ExcludeList::load(path)?));
+        }
+
+        /*
+         * 撮影年の範囲ごとの出力ディレクトリの解析（範囲の重なりはエラー）
+         */
+        let mut year_roots = Vec::new();
+
+        for (range, path) in &self.year_roots {
+            let (start, end) = parse_year_range(range)?;
+
+            for (other_start, other_end, _) in &year_roots {
+                if start <= *other_end && *other_start <= end {
+                    return Err(anyhow!("year range {} overlaps", range));
                 }
+            }
 
-                if self.cache_db_path.is_none() {
-                    if let Some(path) = config.cache_db_path() {
-                        self.cache_db_path = Some(path);
-                    }
+            year_roots.push((start, end, path.clone()));
+        }
+
+        self.parsed_year_roots = year_roots;
+
+        /*
+         * 撮影日の区切り時刻の確認
+         */
+        if let Some(ref day_start) = self.day_start {
+            self.parsed_day_start = parse_time(day_start)?;
+        }
+
+        /*
+         * 撮影日時の書式の確認
+         */
+        for format in &self.datetime_formats {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return Err(anyhow!("invalid datetime format: {}", format));
+            }
+        }
+
+        /*
+         * 走査のグロブパターンの解析
+         */
+        self.parsed_excludes = parse_globs(&self.excludes)?;
+        self.parsed_includes = parse_globs(&self.includes)?;
+
+        /*
+         * キャッシュデータベースパスの設定
+         */
+        self.parsed_cache_db_path = Some(if let Some(path) = &self.cache_db_path {
+            path.clone()
+        } else {
+            default_cache_db_path(self.portable_dir(), self.user())
+        });
+
+        /*
+         * 履歴データベースの読み込み (自身のキャッシュは指定できない)
+         */
+        if let Some(ref path) = self.history_db {
+            let cache_db_path = self.cache_db_path();
+
+            if path.exists()
+                && cache_db_path.exists()
+                && path.canonicalize()? == cache_db_path.canonicalize()?
+            {
+                return Err(anyhow!(
+                    "history database must differ from the cache database"
+                ));
+            }
+
+            self.parsed_history = Some(Arc::new(History::load(path)?));
+        }
+
+        /*
+         * キャッシュ評価モードの設定
+         */
+        self.parsed_cache_eval_mode = if let Some(mode) = self.cache_eval_mode {
+            mode
+        } else {
+            CacheEvalMode::Shallow
+        };
+
+        /*
+         * モーションフォトの取り扱いモードの設定
+         */
+        self.parsed_motion_photo = if let Some(mode) = self.motion_photo {
+            mode
+        } else {
+            MotionPhotoMode::Ignore
+        };
+
+        /*
+         * 入力ファイルの処理順の設定
+         */
+        self.parsed_order = self.order.unwrap_or(ProcessOrder::Path);
+
+        /*
+         * 撮影日時が無いファイルの取り扱いの設定
+         */
+        self.parsed_datetime_fallback = self
+            .datetime_fallback
+            .unwrap_or(DatetimeFallback::Skip);
+
+        /*
+         * GPS時刻によるカメラの時計のずれの取り扱いの設定
+         */
+        self.parsed_gps_clock = self.gps_clock.unwrap_or(GpsClockMode::Warn);
+
+        if self.gps_clock_threshold == Some(0) {
+            return Err(anyhow!("gps clock threshold must be greater than 0"));
+        }
+
+        /*
+         * 振り分け先への格納方式の設定
+         */
+        self.parsed_layout = self.layout.unwrap_or(StorageLayout::Date);
+
+        // 暗号化・圧縮したファイルは内容が元のものと異なるため併用は認めない
+        if self.parsed_layout == StorageLayout::Cas
+            && (self.encrypt_recipient.is_some() || self.compress_raw.is_some())
+        {
+            return Err(anyhow!(
+                "--layout cas cannot be used with --encrypt or --compress-raw"
+            ));
+        }
+
+        /*
+         * 入力ディレクトリと振り分け先の重なりの確認
+         */
+        // 振り分け先が入力ディレクトリの中にあると自身の出力を再び取り込んで
+        // しまい、逆の場合は取り込み中に入力ディレクトリが書き換わるため、い
+        // ずれも認めない(取り込み元が未確定の場合とデーモンモードで入力ディ
+        // レクトリが未作成の場合は確認できないので対象外とする)
+        if self.command.is_none() && !self.ios && self.cloud.is_none() {
+            self.check_input_overlap()?;
+        }
+
+        /*
+         * 暗号化に用いるツールの設定
+         */
+        self.parsed_encrypt_tool = if let Some(tool) = self.encrypt_tool {
+            tool
+        } else {
+            EncryptTool::Age
+        };
+
+        if self.encrypt_recipient.is_some() {
+            let key = encrypt::load_name_key(&self.state_dir())?;
+            self.parsed_encrypt_name_key = Some(key);
+        }
+
+        /*
+         * チェックサムに用いるハッシュ関数の設定
+         */
+        self.parsed_checksum_algorithm = self
+            .checksum_algorithm
+            .unwrap_or(ChecksumAlgorithm::Sha256);
+
+        /*
+         * スナップショットの作成方法の設定
+         */
+        self.parsed_snapshot = if let Some(mode) = self.snapshot {
+            mode
+        } else {
+            SnapshotMode::Off
+        };
+
+        if self.parsed_snapshot == SnapshotMode::Command
+            && self.snapshot_command.is_none()
+        {
+            return Err(anyhow!(
+                "--snapshot-command is required for the command snapshot mode"
+            ));
+        }
+
+        /*
+         * デーモンモードの設定の確認
+         */
+        if self.daemon && self.estimate {
+            return Err(anyhow!("--daemon cannot be used with --estimate"));
+        }
+
+        /*
+         * 監視モードの設定の確認
+         */
+        if self.watch {
+            let exclusive = self.daemon
+                || self.estimate
+                || self.ios
+                || self.cloud.is_some()
+                || self.sidecars_only
+                || self.mark_processed;
+
+            if exclusive {
+                return Err(anyhow!(
+                    "--watch cannot be used with --daemon, --estimate, --ios, \
+                     --cloud, --sidecars-only or --mark-processed"
+                ));
+            }
+
+            // 取り込みマーカーの書き込み自体が変更として通知されるため併用
+            // できない
+            if self.card_marker {
+                return Err(anyhow!(
+                    "--watch cannot be used with --card-marker"
+                ));
+            }
+        }
+
+        /*
+         * 付随ファイルのみの取り込みの設定の確認
+         */
+        let exclusive = self.daemon
+            || self.estimate
+            || self.ios
+            || self.cloud.is_some();
+
+        if self.sidecars_only && exclusive {
+            return Err(anyhow!(
+                "--sidecars-only cannot be used with --daemon, --estimate, \
+                 --ios or --cloud"
+            ));
+        }
+
+        /*
+         * キャッシュ情報のみの記録の設定の確認
+         */
+        let exclusive = self.daemon || self.estimate || self.sidecars_only;
+
+        if self.mark_processed && exclusive {
+            return Err(anyhow!(
+                "--mark-processed cannot be used with --daemon, --estimate or \
+                 --sidecars-only"
+            ));
+        }
+
+        if self.interval == Some(0) {
+            return Err(anyhow!("interval must be greater than 0"));
+        }
+
+        if self.source_timeout == Some(0) {
+            return Err(anyhow!("source timeout must be greater than 0"));
+        }
+
+        if self.health_interval == Some(0) {
+            return Err(anyhow!("health interval must be greater than 0"));
+        }
+
+        if self.retain_days == Some(0) {
+            return Err(anyhow!("retain days must be greater than 0"));
+        }
+
+        if self.retain_days.is_some() && self.prune_root.is_none() {
+            return Err(anyhow!("--retain-days requires --prune-root"));
+        }
+
+        // 常駐する場合や複数のカードから取り込む場合は削除の確認に答えられな
+        // いため、明示的な同意を求める
+        let unattended = self.daemon
+            || self.watch
+            || self.no_input
+            || self.is_import_all();
+
+        if self.prune_root.is_some() && unattended && !self.yes {
+            return Err(anyhow!(
+                "--prune-root with --daemon, --watch, --no-input or \
+                 import-all requires --yes"
+            ));
+        }
+
+        /*
+         * 照合の割合の設定
+         */
+        if let Some(ref sample) = self.verify_sample {
+            self.parsed_verify_sample = Some(parse_percent(sample)?);
+        }
+
+        /*
+         * 振り分け先の権限の設定
+         */
+        if let Some(ref mode) = self.file_mode {
+            self.parsed_file_mode = Some(permission::parse_mode(mode)?);
+        }
+
+        if let Some(ref mode) = self.dir_mode {
+            self.parsed_dir_mode = Some(permission::parse_mode(mode)?);
+        }
+
+        if cfg!(not(unix))
+            && (self.file_mode.is_some()
+                || self.dir_mode.is_some()
+                || self.owner.is_some()
+                || self.group.is_some()
+                || self.acl.is_some())
+        {
+            return Err(anyhow!(
+                "permission policy is not supported on this platform"
+            ));
+        }
+
+        if cfg!(not(target_os = "macos")) && !self.finder_tags.is_empty() {
+            return Err(anyhow!(
+                "--finder-tag is not supported on this platform"
+            ));
+        }
+
+        if cfg!(not(windows)) && self.vss {
+            return Err(anyhow!("--vss is not supported on this platform"));
+        }
+
+        if cfg!(not(unix)) && !self.shards.is_empty() {
+            return Err(anyhow!("shards are not supported on this platform"));
+        }
+
+        if cfg!(not(unix)) && self.control_socket.is_some() {
+            return Err(anyhow!(
+                "--control-socket is not supported on this platform"
+            ));
+        }
+
+        if let Some(ref addr) = self.http {
+            self.parsed_http_addr = match addr.parse::<SocketAddr>() {
+                Ok(addr) => Some(addr),
+                Err(err) => {
+                    return Err(anyhow!("invalid address {}: {}", addr, err));
                 }
+            };
+        }
 
-                if self.cache_eval_mode.is_none() {
-                    if let Some(mode) = config.cache_eval_mode() {
-                        self.cache_eval_mode = Some(mode);
-                    }
+        if self.preview && self.http.is_none() {
+            return Err(anyhow!("--preview requires --http"));
+        }
+
+        if let Some(ref addr) = self.ftp {
+            self.parsed_ftp_addr = match addr.parse::<SocketAddr>() {
+                Ok(addr) => Some(addr),
+                Err(err) => {
+                    return Err(anyhow!("invalid address {}: {}", addr, err));
                 }
+            };
+        }
+
+        if self.ftp_user.is_some() != self.ftp_password.is_some() {
+            return Err(anyhow!(
+                "--ftp-user and --ftp-password must be specified together"
+            ));
+        }
+
+        // TLSに対応しないため、平文のFTPサーバを外部に公開しない
+        if let Some(addr) = self.parsed_ftp_addr {
+            if !addr.ip().is_loopback() {
+                return Err(anyhow!(
+                    "--ftp must listen on a loopback address (plain FTP, \
+                     TLS is not supported)"
+                ));
+            }
+        }
+
+        let mut schedules = Vec::new();
+
+        for entry in &self.schedules {
+            let cron = CronExpr::parse(&entry.cron)?;
+            schedules.push((cron, entry.source.clone()));
+        }
+
+        self.parsed_schedules = schedules;
+
+        let mut exports = Vec::new();
+
+        for entry in &self.exports {
+            // クライアント名はパスの区切り文字を含まないものに限る
+            if entry.client.is_empty()
+                || entry.client.contains(['/', '\\'])
+                || entry.client == ".."
+            {
+                return Err(anyhow!(
+                    "invalid export client name: {:?}",
+                    entry.client
+                ));
+            }
+
+            // `{client}`はパースの前に置き換える
+            let path = match entry.path_template {
+                Some(ref template) => template.clone(),
+                None => format!("{{client}}/{}", DEFAULT_TEMPLATE),
+            };
+            let path = PathTemplate::parse(
+                &path.replace("{client}", &entry.client)
+            )?;
+
+            let name = match entry.name_template {
+                Some(ref template) => Some(NameTemplate::parse(template)?),
+                None => None,
+            };
+
+            exports.push((entry.clone(), path, name));
+        }
+
+        self.parsed_exports = exports;
+
+        /*
+         * メールによる通知の設定の確認
+         */
+        if let Some(ref url) = self.smtp {
+            if !url.starts_with("smtp://") && !url.starts_with("smtps://") {
+                return Err(anyhow!("invalid smtp url: {}", url));
+            }
+
+            if self.mail_from.is_none() || self.mail_to.is_empty() {
+                return Err(anyhow!(
+                    "--smtp requires --mail-from and --mail-to"
+                ));
+            }
+        }
+
+        if self.smtp_user.is_some() != self.smtp_password.is_some() {
+            return Err(anyhow!(
+                "--smtp-user and --smtp-password must be specified together"
+            ));
+        }
+
+        /*
+         * iOSデバイスからの取り込みの設定の確認
+         */
+        if self.ios {
+            if self.input_path.is_some() && self.command.is_none() {
+                return Err(anyhow!("--ios cannot be used with input path"));
+            }
+
+            if self.daemon || self.estimate {
+                return Err(anyhow!(
+                    "--ios cannot be used with --daemon or --estimate"
+                ));
+            }
+        }
+
+        // iOSデバイスやクラウドストレージは入力元に書き込めないため、取り込
+        // みマーカーは用いない
+        if self.card_marker && (self.ios || self.cloud.is_some()) {
+            return Err(anyhow!(
+                "--card-marker cannot be used with --ios or --cloud"
+            ));
+        }
+
+        // 移動はローカルの入力ディレクトリから直接読み出す場合に限る(シャド
+        // ウコピーやiOSデバイス、クラウドストレージの一時ディレクトリから読
+        // み出したファイルを削除しても入力元からは削除されない)
+        if self.move_files && (self.ios || self.cloud.is_some() || self.vss) {
+            return Err(anyhow!(
+                "--move cannot be used with --ios, --cloud or --vss"
+            ));
+        }
+
+        // 予行演習は入力ディレクトリからの取り込みに限る(デーモンモードや
+        // iOSデバイス、クラウドストレージからの取り込みでは取り込み元の準備
+        // の段階で書き込みを伴う)
+        if self.dry_run
+            && (self.daemon
+                || self.ios
+                || self.cloud.is_some()
+                || self.sidecars_only)
+        {
+            return Err(anyhow!(
+                "--dry-run cannot be used with --daemon, --ios, --cloud or \
+                 --sidecars-only"
+            ));
+        }
+
+        if self.source_read_only {
+            self.validate_source_read_only()?;
+        }
+
+        if let Some(ref udid) = self.ios_udid {
+            ios::check_udid(udid)?;
+        }
+
+        /*
+         * クラウドストレージからの取り込みの設定の確認
+         */
+        if let Some(ref remote) = self.cloud {
+            if !remote.contains(':') {
+                return Err(anyhow!("invalid remote {}", remote));
+            }
+
+            if self.input_path.is_some() && self.command.is_none() {
+                return Err(anyhow!("--cloud cannot be used with input path"));
+            }
 
-                Ok(())
+            if self.daemon || self.estimate {
+                return Err(anyhow!(
+                    "--cloud cannot be used with --daemon or --estimate"
+                ));
             }
+        }
 
-            // エラーが出たらエラー
-            Err(err) => Err(anyhow!("{}", err))
+        /*
+         * キャッシュの初期化（振り分け処理を行う場合のみ）
+         */
+        // デーモンモードでは取り込みのたびにキャッシュをオープンする。iOSデバ
+        // イスやクラウドストレージからの取り込みでは取り込み元の準備ができた
+        // 後にオープンする。
+        if self.command.is_none()
+            && !self.daemon
+            && !self.ios
+            && self.cloud.is_none()
+        {
+            let cache = if self.dry_run {
+                Cache::open_dry_run(
+                    self.parsed_cache_db_path.clone().unwrap(),
+                    self.parsed_cache_eval_mode,
+                    self.input_path(),
+                )?
+            } else {
+                Cache::open(
+                    self.parsed_cache_db_path.clone().unwrap(),
+                    self.parsed_cache_eval_mode,
+                    self.input_path(),
+                )?
+            };
+            self.cache = Some(Arc::new(cache));
         }
+
+        /*
+         * コンフィギュレーションファイルのパス設定
+         */
+        self.parsed_config_path = if let Some(path) = &self.config_file {
+            path.clone()
+        } else {
+            default_config_path(self.portable_dir(), self.user())
+        };
+
+        Ok(())
     }
 
     ///
-    /// 設定情報のバリデーションとキャッシュの構築
+    /// 複数のカードからの取り込みを行うか否かを判定する
     ///
     /// # 戻り値
-    /// 設定情報に問題が無い場合は`Ok(())`を返す。問題があった場合はエラー情報
-    /// を`Err()`でラップして返す。
-    fn validate(&mut self) -> Result<()> {
+    /// `import-all`サブコマンドが指定されている場合は`true`
+    ///
+    fn is_import_all(&self) -> bool {
+        matches!(self.command, Some(Command::ImportAll(_)))
+    }
+
+    ///
+    /// 入出力ディレクトリのバリデーション
+    ///
+    /// # 戻り値
+    /// 入出力ディレクトリの指定に問題が無い場合は`Ok(())`を返す。問題があった
+    /// 場合はエラー情報を`Err()`でラップして返す。
+    ///
+    fn validate_paths(&self) -> Result<()> {
         /*
          * 入力ディレクトリの確認
          */
-        if !self.input_path.is_dir() {
-            return Err(anyhow!(
-                "{} is not directory",
-                self.input_path.display()
-            ));
+        // iOSデバイスやクラウドストレージからの取り込みでは取り込み元の準備が
+        // できた後に、複数のカードからの取り込みではカードごとに決定する
+        if !self.ios && self.cloud.is_none() && !self.is_import_all() {
+            let input_path = self.input_path();
+
+            // デーモンモードでは起動時に入力ディレクトリが無くても良い
+            if !self.daemon && !input_path.is_dir() {
+                return Err(anyhow!(
+                    "{} is not directory",
+                    input_path.display()
+                ));
+            }
         }
 
         /*
@@ -482,59 +3982,282 @@ impl Options {
         }
 
         /*
-         * 日付形式の確認とキャッシュの構築
+         * 動画ディレクトリの確認（指定された場合）
          */
-        if let Some(ref from_date) = self.from_date {
-            self.parsed_from_date = Some(parse_datetime(from_date)?);
+        if let Some(path) = &self.video_output_path {
+            if !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
         }
 
-        if let Some(ref to_date) = self.to_date {
-            self.parsed_to_date = Some(parse_datetime(to_date)?);
+        /*
+         * 画像以外のファイルの保全先の確認（指定された場合）
+         */
+        if let Some(path) = &self.copy_unknown {
+            if !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
         }
 
         /*
-         * キャッシュデータベースパスの設定
+         * オブジェクトストアの確認（指定された場合、存在しなければ格納時に作
+         * 成する）
          */
-        let default_cache_db = BaseDirs::new()
-            .unwrap()
-            .cache_dir()
-            .join(env!("CARGO_PKG_NAME"))
-            .join("cache.redb");
+        if let Some(path) = &self.cas_dir {
+            if path.exists() && !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
+        }
 
-        self.parsed_cache_db_path = Some(if let Some(path) = &self.cache_db_path {
-            path.clone()
-        } else {
-            default_cache_db
-        });
+        /*
+         * 保存期間を過ぎたファイルを削除する対象のディレクトリの確認
+         */
+        if let Some(path) = &self.prune_root {
+            if !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
+        }
 
         /*
-         * キャッシュ評価モードの設定
+         * 撮影者ごとの出力ディレクトリの確認
          */
-        self.parsed_cache_eval_mode = if let Some(mode) = self.cache_eval_mode {
-            mode
-        } else {
-            CacheEvalMode::Shallow
-        };
+        for path in self.artist_roots.values() {
+            if !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
+        }
 
         /*
-         * キャッシュの初期化
+         * シャードの確認
          */
-        let cache = Cache::open(
-            self.parsed_cache_db_path.as_ref().unwrap(),
-            self.parsed_cache_eval_mode,
-            &self.input_path,
-        )?;
-        self.cache = Some(Arc::new(cache));
+        for path in &self.shards {
+            if !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
+        }
 
         /*
-         * コンフィギュレーションファイルのパス設定
+         * 撮影年の範囲ごとの出力ディレクトリの確認
          */
-        self.parsed_config_path = if let Some(path) = &self.config_file {
-            path.clone()
-        } else {
-            default_config_path()
+        for path in self.year_roots.values() {
+            if !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
+        }
+
+        /*
+         * 拡張子ごとの振り分け先の確認（絶対パスで指定された場合のみ）
+         */
+        for path in self.route_paths() {
+            if path.is_absolute() && !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
+        }
+
+        /*
+         * ティアの確認
+         */
+        for tier in &self.tiers {
+            if tier.classes.is_empty() {
+                return Err(anyhow!("tier {} has no classes", tier.name));
+            }
+
+            if tier.classes.iter().any(|entry| entry == "other") {
+                return Err(anyhow!(
+                    "tier {}: list extensions instead of \"other\"",
+                    tier.name
+                ));
+            }
+
+            if !tier.path.is_dir() {
+                return Err(anyhow!(
+                    "{} is not directory (tier {})",
+                    tier.path.display(),
+                    tier.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 入力元の読み出し専用指定のバリデーション
+    ///
+    /// # 戻り値
+    /// 入力元への書き込みを伴う指定が無い場合は`Ok(())`を返す。ある場合はエラ
+    /// ー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 取り込みマーカーの書き込み、入力ファイルの削除(移動)、シャドウコピー
+    /// の作成、FTPサーバのスプールディレクトリへの書き込みを禁止する。また、
+    /// 出力先やキャッシュデータベースが入力ディレクトリの中に置かれている場合
+    /// もエラーとする。
+    ///
+    fn validate_source_read_only(&self) -> Result<()> {
+        if self.card_marker || self.move_files || self.vss {
+            return Err(anyhow!(
+                "--source-read-only cannot be used with --card-marker, \
+                 --move or --vss"
+            ));
+        }
+
+        if self.ftp.is_some() {
+            return Err(anyhow!("--source-read-only cannot be used with --ftp"));
+        }
+
+        // iOSデバイスやクラウドストレージからの取り込みでは入力ディレクトリ
+        // が一時的なものとなるため確認しない
+        let input_path = match self.input_path {
+            Some(ref path) if !self.ios && self.cloud.is_none() => {
+                path.canonicalize().unwrap_or_else(|_| path.clone())
+            }
+            _ => return Ok(()),
         };
 
+        let outputs = self.output_path
+            .iter()
+            .chain(self.raw_output_path.iter())
+            .chain(self.video_output_path.iter())
+            .chain(self.copy_unknown.iter())
+            .chain(self.cas_dir.iter())
+            .chain(self.artist_roots.values())
+            .chain(self.year_roots.values())
+            .chain(self.shards.iter())
+            .chain(self.tiers.iter().map(|tier| &tier.path))
+            .chain(self.parsed_cache_db_path.iter())
+            .chain(self.report_path.iter())
+            .chain(self.report_csv_path.iter())
+            .chain(self.trace_report_path.iter());
+
+        for path in outputs {
+            // レポート等のまだ存在しないファイルは親ディレクトリで解決する
+            let path = match path.canonicalize() {
+                Ok(path) => path,
+                Err(_) => {
+                    let parent = match path.parent() {
+                        Some(dir) if !dir.as_os_str().is_empty() => dir,
+                        _ => Path::new("."),
+                    };
+
+                    match (parent.canonicalize(), path.file_name()) {
+                        (Ok(dir), Some(name)) => dir.join(name),
+                        _ => path.clone(),
+                    }
+                }
+            };
+
+            if path.starts_with(&input_path) {
+                return Err(anyhow!(
+                    "--source-read-only: {} is inside the input directory",
+                    path.display()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// 撮影年の範囲の指定を解析する
+///
+/// # 引数
+/// * `range` - 範囲の指定（"2015-2019"、"2020-"、"-2014"、"2018"の形式）
+///
+/// # 戻り値
+/// 解析に成功した場合は始点と終点（いずれも範囲に含む、省略した側は上限ま
+/// たは下限）の組を`Ok()`でラップして返す。失敗した場合はエラー情報を
+/// `Err()`でラップして返す。
+///
+fn parse_year_range(range: &str) -> Result<(i32, i32)> {
+    let parse = |s: &str, default: i32| -> Result<i32> {
+        let s = s.trim();
+
+        if s.is_empty() {
+            return Ok(default);
+        }
+
+        s.parse::<i32>()
+            .map_err(|_| anyhow!("invalid year range: {}", range))
+    };
+
+    if matches!(range.trim(), "" | "-") {
+        return Err(anyhow!("invalid year range: {}", range));
+    }
+
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => {
+            (parse(start, i32::MIN)?, parse(end, i32::MAX)?)
+        }
+
+        None => {
+            let year = parse(range, 0)?;
+            (year, year)
+        }
+    };
+
+    if start > end {
+        return Err(anyhow!("invalid year range: {}", range));
+    }
+
+    Ok((start, end))
+}
+
+///
+/// 入力ディレクトリと振り分け先ディレクトリの重なりの確認
+///
+/// # 引数
+/// * `input` - 入力ディレクトリのパス(正規化済み)
+/// * `root` - 振り分け先の基点ディレクトリのパス
+///
+/// # 戻り値
+/// 二つのディレクトリが重なっていない場合は`Ok(())`を返す。一方が他方を含む
+/// 場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 未作成のディレクトリ(オブジェクトストア等)は存在する祖先ディレクトリま
+/// でを正規化して比較する。
+///
+fn check_overlap(input: &Path, root: &Path) -> Result<()> {
+    /*
+     * 振り分け先のパスの正規化
+     */
+    let mut rest = Vec::new();
+    let mut base = root.to_path_buf();
+
+    let root = loop {
+        if let Ok(path) = base.canonicalize() {
+            break rest.iter().rev().fold(path, |path, name| path.join(name));
+        }
+
+        match (base.file_name(), base.parent()) {
+            (Some(name), Some(parent)) => {
+                rest.push(name.to_os_string());
+                base = parent.to_path_buf();
+            }
+
+            // 正規化できない場合は確認しない
+            _ => return Ok(()),
+        }
+    };
+
+    /*
+     * 包含関係の判定
+     */
+    if root.starts_with(input) {
+        Err(anyhow!(
+            "{} is inside the input directory {}",
+            root.display(),
+            input.display()
+        ))
+    } else if input.starts_with(&root) {
+        Err(anyhow!(
+            "input directory {} is inside {}",
+            input.display(),
+            root.display()
+        ))
+    } else {
         Ok(())
     }
 }
@@ -574,22 +4297,23 @@ pub(super) fn parse() -> Result<Arc<Options>> {
         let cfg_path = opts.config_path();
 
         if cfg_path.exists() {
-            print!(
-                "config file {} already exists. overwrite? [y/N]: ",
+            let message = format!(
+                "config file {} already exists. overwrite?",
                 cfg_path.display()
             );
-            io::stdout().flush().ok();
-
-            let mut answer = String::new();
-            io::stdin().read_line(&mut answer).ok();
-            let answer = answer.trim().to_lowercase();
 
-            if answer != "y" && answer != "yes" {
+            if !opts.confirm(&message)? {
                 println!("save-config aborted");
                 std::process::exit(0);
             }
         }
 
+        match config::backup(&cfg_path, opts.config_backups()) {
+            Ok(Some(path)) => println!("backup saved to {}", path.display()),
+            Ok(None) => {}
+            Err(err) => return Err(anyhow!("backup config failed: {}", err)),
+        }
+
         if let Err(err) = config::write(&cfg_path, &opts) {
             return Err(anyhow!("{}", err));
         }
@@ -612,6 +4336,53 @@ pub(super) fn parse() -> Result<Arc<Options>> {
     Ok(Arc::new(opts))
 }
 
+///
+/// オプション設定の再読み込み
+///
+/// # 戻り値
+/// 処理に成功した場合はオプション設定をパックしたオブジェクトを`Ok()`でラップ
+/// して返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 起動時と同じコマンドラインオプションにコンフィギュレーションファイルを再度
+/// 適用する。ログ機能の設定は再読み込みの対象としない。
+///
+pub(crate) fn reload() -> Result<Arc<Options>> {
+    let mut opts = Options::parse();
+
+    opts.apply_config()?;
+    opts.validate()?;
+
+    Ok(Arc::new(opts))
+}
+
+///
+/// 引数の列からのオプション設定の構築
+///
+/// # 引数
+/// * `args` - コマンドライン引数の列(先頭はプログラム名)
+///
+/// # 戻り値
+/// 処理に成功した場合はオプション設定をパックしたオブジェクトを`Ok()`でラップ
+/// して返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// ライブラリとして組み込んだ場合に用いる。ログ機能の初期化と、設定の保存・
+/// 表示のモードの処理は行わない。
+///
+pub(super) fn from_args<I, T>(args: I) -> Result<Arc<Options>>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut opts = Options::try_parse_from(args)?;
+
+    opts.apply_config()?;
+    opts.validate()?;
+
+    Ok(Arc::new(opts))
+}
+
 ///
 /// 日付文字列をパースしてDateTime<Local>に変換する
 ///
@@ -641,3 +4412,135 @@ fn parse_datetime(date_string: &str) -> Result<DateTime<Local>> {
         }
     }
 }
+
+///
+/// 時刻文字列をパースしてNaiveTimeに変換する
+///
+/// # 引数
+/// * `time_string` - HH:MM形式の時刻文字列
+///
+/// # 戻り値
+/// パースが成功した場合は`Ok(NaiveTime)`を返す。失敗した場合はエラー情報を
+/// `Err()`でラップして返す。
+fn parse_time(time_string: &str) -> Result<NaiveTime> {
+    match NaiveTime::parse_from_str(time_string, "%H:%M") {
+        Ok(time) => Ok(time),
+        Err(_) => {
+            Err(anyhow!(
+                "invalid time format: {} (expected HH:MM)",
+                time_string
+            ))
+        }
+    }
+}
+
+///
+/// 百分率の文字列をパースする
+///
+/// # 引数
+/// * `percent` - 百分率の文字列（"5%"、"0.5"等、末尾の"%"は省略可）
+///
+/// # 戻り値
+/// パースが成功した場合は百分率の値を`Ok()`でラップして返す。失敗した場合や
+/// 0より大きく100以下の範囲に無い場合はエラー情報を`Err()`でラップして返す。
+///
+fn parse_percent(percent: &str) -> Result<f64> {
+    let value = percent
+        .trim()
+        .trim_end_matches('%')
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| *value > 0.0 && *value <= 100.0);
+
+    value.ok_or_else(|| {
+        anyhow!("invalid percentage: {} (expected 0% < N <= 100%)", percent)
+    })
+}
+
+///
+/// グロブパターンの一覧のパース
+///
+/// # 引数
+/// * `patterns` - グロブパターンの文字列の一覧
+///
+/// # 戻り値
+/// パースが成功した場合はパターンの一覧を`Ok()`でラップして返す。失敗した場合
+/// はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// Windowsのパス区切りで記述されたパターンも受け付けるよう、`\`は`/`に置き
+/// 換えてからパースする。
+///
+fn parse_globs(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Pattern::new(&pattern.replace('\\', "/")).map_err(|err| {
+                anyhow!("invalid glob pattern {}: {}", pattern, err)
+            })
+        })
+        .collect()
+}
+
+///
+/// ユーザ名の確認
+///
+/// # 引数
+/// * `user` - ユーザ名
+///
+/// # 戻り値
+/// ディレクトリ名として使用可能な場合は`Ok(())`を返す。使用できない場合はエラー
+/// 情報を`Err()`でラップして返す。
+///
+fn check_user_name(user: &str) -> Result<()> {
+    let valid = !user.is_empty()
+        && user != "."
+        && user != ".."
+        && !user.chars().any(|c| matches!(c, '/' | '\\') || c.is_control());
+
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("invalid user name: {}", user))
+    }
+}
+
+#[cfg(test)]
+impl Options {
+    ///
+    /// 撮影日の区切り時刻のみを指定したオプション設定を作成する(テスト用)
+    ///
+    /// # 引数
+    /// * `day_start` - 撮影日の区切り時刻 (HH:MM形式)
+    ///
+    /// # 戻り値
+    /// 作成したオプション設定
+    ///
+    pub(crate) fn with_day_start(day_start: &str) -> Self {
+        let mut opts = Self::with_args(&["--day-start", day_start]);
+        opts.parsed_day_start = parse_time(day_start).unwrap();
+        opts
+    }
+
+    ///
+    /// コマンドラインオプションを指定したオプション設定を作成する(テスト用)
+    ///
+    /// # 引数
+    /// * `args` - コマンドラインオプション(入力ディレクトリは`.`とする)
+    ///
+    /// # 戻り値
+    /// 作成したオプション設定
+    ///
+    /// # 注記
+    /// バリデーションは行わないため、解析を要するオプションの値は反映されな
+    /// い。
+    ///
+    pub(crate) fn with_args(args: &[&str]) -> Self {
+        let args = std::iter::once("imgdist")
+            .chain(args.iter().copied())
+            .chain(std::iter::once("."));
+
+        Self::parse_from(args)
+    }
+}
@@ -0,0 +1,412 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 付随ファイル(テレメトリ、ボイスメモ、XMP等)を親のファイルと共に取り込むモ
+//! ジュール
+//!
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use log::info;
+
+use crate::checksum;
+use crate::class_registry::ClassRegistry;
+use crate::cmd_args::{Collision, FileKind, Options};
+use crate::encrypt;
+use crate::error::ImportError;
+use crate::permission;
+use crate::{
+    apply_collision, file_stem, is_jpeg_file, is_raw_file, is_video_file,
+};
+
+///
+/// 付随ファイルの種別ごとの規則
+///
+struct Rule {
+    /// 付随ファイルの拡張子(小文字)
+    exts: &'static [&'static str],
+
+    /// 親のファイルの拡張子か否かを判定する関数
+    is_parent: fn(&str, &ClassRegistry) -> bool,
+}
+
+/// 付随ファイルの規則の一覧
+const RULES: [Rule; 3] = [
+    // DJIのドローンのテレメトリの字幕(SRT)と低解像度のプロキシ動画(LRF)
+    Rule {
+        exts: &["srt", "lrf"],
+        is_parent: is_drone_file,
+    },
+
+    // カメラで記録したボイスメモ
+    Rule {
+        exts: &["wav", "mp3"],
+        is_parent: is_image_file,
+    },
+
+    // 現像ソフトの編集内容(XMP)、iOSの編集内容(AAE)、カメラが記録したサム
+    // ネイル(THM)
+    Rule {
+        exts: &["xmp", "aae", "thm"],
+        is_parent: is_media_file,
+    },
+];
+
+///
+/// ドローンの付随ファイルの親となる形式か否かを判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `_classes` - 設定で追加した形式の登録内容(参照しない)
+///
+/// # 戻り値
+/// MP4またはDNGファイルの場合は`true`
+///
+fn is_drone_file(ext: &str, _classes: &ClassRegistry) -> bool {
+    matches!(ext.to_lowercase().as_str(), "mp4" | "dng")
+}
+
+///
+/// ボイスメモの親となる形式か否かを判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// JPEGまたはRAWファイルの場合は`true`
+///
+fn is_image_file(ext: &str, classes: &ClassRegistry) -> bool {
+    is_jpeg_file(ext, classes) || is_raw_file(ext, classes)
+}
+
+///
+/// サイドカーの親となる形式か否かを判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// JPEG、RAWまたは動画ファイルの場合は`true`
+///
+fn is_media_file(ext: &str, classes: &ClassRegistry) -> bool {
+    is_image_file(ext, classes) || is_video_file(ext, classes)
+}
+
+///
+/// ファイルの拡張子を小文字で取り出す
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+///
+/// # 戻り値
+/// 小文字に揃えた拡張子(拡張子が無い場合は空文字列)
+///
+fn ext_of(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+///
+/// 同じディレクトリにある拡張子以外が同名のファイルを列挙する
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `accept` - 列挙する拡張子か否かを判定する関数
+///
+/// # 戻り値
+/// 見つかったファイルのパスの一覧(名前順)
+///
+fn siblings<F>(path: &Path, accept: F) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> bool,
+{
+    match path.file_stem() {
+        Some(stem) => named(path, stem, accept),
+        None => Vec::new(),
+    }
+}
+
+///
+/// 同じディレクトリにある"IMG_0001.CR2.xmp"の形式のファイルを列挙する
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `accept` - 列挙する拡張子か否かを判定する関数
+///
+/// # 戻り値
+/// 対象ファイルの名前に拡張子を加えた名前のファイルのパスの一覧(名前順)
+///
+fn suffixed<F>(path: &Path, accept: F) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> bool,
+{
+    match path.file_name() {
+        Some(name) => named(path, name, accept),
+        None => Vec::new(),
+    }
+}
+
+///
+/// 同じディレクトリにある拡張子を除いた名前が指定のものであるファイルを列挙
+/// する
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `stem` - 拡張子を除いた名前
+/// * `accept` - 列挙する拡張子か否かを判定する関数
+///
+/// # 戻り値
+/// 見つかったファイルのパスの一覧(名前順、対象ファイル自身は含まない)
+///
+fn named<F>(path: &Path, stem: &OsStr, accept: F) -> Vec<PathBuf>
+where
+    F: Fn(&str) -> bool,
+{
+    let dir = match path.parent() {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+
+    let mut found = match dir.read_dir() {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|candidate| {
+                candidate != path
+                    && candidate.file_stem() == Some(stem)
+                    && accept(&ext_of(candidate))
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => Vec::new(),
+    };
+
+    found.sort();
+    found
+}
+
+///
+/// 付随ファイルか否かを判定する
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 付随ファイルの規則の一覧にある拡張子、または設定で`sidecar`の分類として
+/// 追加した形式の拡張子を持つ場合は`true`
+///
+pub(crate) fn is_companion(path: &Path, opts: &Options) -> bool {
+    let ext = ext_of(path);
+
+    RULES.iter().any(|rule| rule.exts.contains(&ext.as_str()))
+        || opts.classes().is_kind(&ext, FileKind::Sidecar)
+}
+
+///
+/// 付随ファイルの振り分け先でのファイル名を求める
+///
+/// # 引数
+/// * `path` - 付随ファイルのパス
+/// * `parent` - 親のファイルのパス
+/// * `name` - 親のファイルの振り分け先でのファイル名
+///
+/// # 戻り値
+/// 親のファイル名の拡張子を自身のものに置き換えた名前("IMG_0001.CR2.xmp"の
+/// 形式のものは親のファイル名に自身の拡張子を加えた名前)
+///
+pub(crate) fn companion_name(path: &Path, parent: &Path, name: &str)
+    -> String
+{
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy())
+        .unwrap_or_default();
+
+    if path.file_stem() == parent.file_name() {
+        format!("{}.{}", name, ext)
+    } else {
+        format!("{}.{}", file_stem(Path::new(name)), ext)
+    }
+}
+
+///
+/// 付随ファイルの親のファイルを探す
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 付随ファイルであり、同じディレクトリに親のファイル(拡張子以外が同名の親と
+/// なる形式のファイル)がある場合はそのパス
+///
+/// # 注記
+/// "IMG_0001.CR2.xmp"の形式の場合は拡張子を除いた名前のファイル
+/// ("IMG_0001.CR2")を優先して親とする。
+///
+pub(crate) fn parent(path: &Path, opts: &Options) -> Option<PathBuf> {
+    let classes = opts.classes();
+    let ext = ext_of(path);
+
+    // 設定で追加した付随ファイルの親は静止画とする
+    if classes.is_kind(&ext, FileKind::Sidecar) {
+        return siblings(path, |ext| is_image_file(ext, classes))
+            .into_iter()
+            .next();
+    }
+
+    let rule = RULES.iter().find(|rule| rule.exts.contains(&ext.as_str()))?;
+    let is_parent = |ext: &str| (rule.is_parent)(ext, classes);
+
+    let base = path.with_extension("");
+    if is_parent(&ext_of(&base)) && base.is_file() {
+        return Some(base);
+    }
+
+    siblings(path, is_parent).into_iter().next()
+}
+
+///
+/// 親のファイルの付随ファイルを振り分け先にコピーする
+///
+/// # 引数
+/// * `path` - 親のファイルのパス
+/// * `dir` - 親のファイルの格納先ディレクトリ
+/// * `name` - 親のファイルの振り分け先でのファイル名
+/// * `collision` - 親のファイルに適用した同名のファイルの取り扱い
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は格納した付随ファイルのパスの一覧を`Ok()`でラップして
+/// 返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 付随ファイルは親のファイル名の拡張子を自身のものに置き換えた名前で格納す
+/// る("IMG_0001.CR2.xmp"の形式のものは親のファイル名に自身の拡張子を加えた名
+/// 前とする)。
+///
+pub(crate) fn copy_all(
+    path: &Path,
+    dir: &Path,
+    name: &str,
+    collision: Collision,
+    opts: &Options,
+) -> Result<Vec<PathBuf>> {
+    let classes = opts.classes();
+    let ext = ext_of(path);
+    let mut exts = RULES
+        .iter()
+        .filter(|rule| (rule.is_parent)(&ext, classes))
+        .flat_map(|rule| rule.exts.iter().map(|ext| ext.to_string()))
+        .collect::<Vec<_>>();
+
+    if is_image_file(&ext, classes) {
+        exts.extend(classes.exts_of(FileKind::Sidecar));
+    }
+
+    if exts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let accept = |ext: &str| exts.iter().any(|x| x == ext);
+    let mut copied = Vec::new();
+
+    let targets = siblings(path, accept)
+        .into_iter()
+        .chain(suffixed(path, accept))
+        .collect::<Vec<_>>();
+
+    for src in targets {
+        let name = companion_name(&src, path, name);
+
+        if let Some(dst) = store(&src, dir, &name, collision, opts)? {
+            copied.push(dst);
+        }
+    }
+
+    Ok(copied)
+}
+
+///
+/// 付随ファイル1件を振り分け先に格納する
+///
+/// # 引数
+/// * `src` - 付随ファイルのパス
+/// * `dir` - 格納先ディレクトリ
+/// * `name` - 振り分け先でのファイル名(暗号化前のもの)
+/// * `collision` - 同名のファイルが存在する場合の取り扱い
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は格納したファイルのパス(同名のファイルを残してスキップ
+/// した場合はNone)を`Ok()`でラップして返す。失敗した場合はエラー情報を
+/// `Err()`でラップして返す。
+///
+/// # 注記
+/// 同名のファイルは親のファイルと同じ規則で取り扱う。暗号化を指定した場合は
+/// 平文を残さないよう親のファイルと同様に暗号化して格納する(ドローンのテレ
+/// メトリには位置情報も含まれる)。圧縮は行わない。
+///
+pub(crate) fn store(
+    src: &Path,
+    dir: &Path,
+    name: &str,
+    collision: Collision,
+    opts: &Options,
+) -> Result<Option<PathBuf>> {
+    let encrypt = opts.encrypt_recipient().is_some();
+    let stored = |name: &str| {
+        if encrypt {
+            encrypt::encrypted_name(name, opts)
+        } else {
+            name.to_string()
+        }
+    };
+
+    /*
+     * 同名のファイルの取り扱い
+     */
+    let exists = dir.join(stored(name)).exists();
+
+    let Some(name) =
+        apply_collision(name.to_string(), exists, collision, dir, stored)?
+    else {
+        info!("skip existing companion: {}", src.display());
+        return Ok(None);
+    };
+
+    /*
+     * 格納
+     */
+    let dst = if encrypt {
+        encrypt::distribute(src, dir, &name, opts)?
+    } else {
+        let dst = dir.join(&name);
+
+        if let Err(err) = std::fs::copy(src, &dst) {
+            let context = format!("copy to {} failed", dst.display());
+            return Err(ImportError::io(context, err).into());
+        }
+
+        dst
+    };
+
+    info!("copied companion {} to {}", src.display(), dst.display());
+
+    permission::apply(&dst, &[], opts)?;
+
+    if opts.is_checksums() {
+        checksum::record(&dst, opts.checksum_algorithm())?;
+    }
+
+    Ok(Some(dst))
+}
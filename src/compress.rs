@@ -0,0 +1,111 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先へのファイルの圧縮格納を行うモジュール
+//!
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use crate::cmd_args::Options;
+use crate::mkdir;
+
+/// 圧縮ファイルに付与する拡張子
+const ZSTD_EXT: &str = "zst";
+
+///
+/// ファイルをzstd形式で圧縮して振り分け先に格納する
+///
+/// # 引数
+/// * `src` - 格納するファイルのパス
+/// * `dir` - 振り分け先のディレクトリ
+/// * `name` - 振り分け先でのファイル名(圧縮前のもの)
+/// * `level` - 圧縮レベル
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は格納した圧縮ファイルのパスを`Ok()`でラップして返す。失
+/// 敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 圧縮ファイルの名前は元のファイル名に`.zst`を付与したものとする。後から
+/// `zstd -t`で検証できるよう、フレームにはチェックサムを含める。
+///
+pub(crate) fn distribute(
+    src: &Path,
+    dir: &Path,
+    name: &str,
+    level: i32,
+    opts: &Options,
+) -> Result<PathBuf> {
+    /*
+     * 振り分け先ディレクトリの作成
+     */
+    mkdir::ensure(dir, opts)?;
+
+    /*
+     * 圧縮(一時ファイルに書き出してからリネームする)
+     */
+    let dst = dir.join(compressed_name(name));
+    let tmp = dir.join(format!("{}.tmp", compressed_name(name)));
+
+    if let Err(err) = compress_file(src, &tmp, level) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(anyhow!("compress to {} failed: {}", dst.display(), err));
+    }
+
+    std::fs::rename(&tmp, &dst)?;
+
+    info!("compressed {} to {}", src.display(), dst.display());
+
+    Ok(dst)
+}
+
+///
+/// 圧縮ファイルの名前を求める
+///
+/// # 引数
+/// * `name` - 元のファイル名
+///
+/// # 戻り値
+/// 元のファイル名に`.zst`を付与した名前
+///
+pub(crate) fn compressed_name(name: &str) -> String {
+    format!("{}.{}", name, ZSTD_EXT)
+}
+
+///
+/// ファイルをzstd形式で圧縮する
+///
+/// # 引数
+/// * `src` - 圧縮元のファイルのパス
+/// * `dst` - 圧縮先のファイルのパス
+/// * `level` - 圧縮レベル
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn compress_file(src: &Path, dst: &Path, level: i32) -> Result<()> {
+    let mut reader = BufReader::new(File::open(src)?);
+    let writer = BufWriter::new(File::create(dst)?);
+
+    let mut encoder = zstd::Encoder::new(writer, level)?;
+    encoder.include_checksum(true)?;
+
+    std::io::copy(&mut reader, &mut encoder)?;
+
+    let mut writer = encoder.finish()?;
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! デーモンを制御するUnixドメインソケットを提供するモジュール
+//!
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+
+use super::state::State;
+use super::Request;
+
+///
+/// 制御用ソケットを作成し、コマンドの受け付けを開始する
+///
+/// # 引数
+/// * `path` - ソケットファイルのパス
+/// * `state` - デーモンの状態
+/// * `sender` - 取り込みスレッドへの要求の送信口
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 受け付けるコマンドは1行1コマンドのテキスト形式とし、1行で応答する（成功時
+/// は`ok`で始まり、失敗時は`error`で始まる）。
+///
+/// * `status` - 現在の状態（JSON形式）
+/// * `pause` - 取り込みの一時停止
+/// * `resume` - 取り込みの再開
+/// * `trigger <DIR>` - 指定ディレクトリからの取り込みの実行
+/// * `reload-config` - 設定の再読み込み
+///
+pub(super) fn spawn(path: &Path, state: Arc<State>, sender: Sender<Request>)
+    -> Result<()>
+{
+    /*
+     * 残っているソケットファイルの削除
+     */
+    if path.exists() {
+        // 接続できる場合は他のデーモンが使用中
+        if UnixStream::connect(path).is_ok() {
+            return Err(anyhow!("{} is already in use", path.display()));
+        }
+
+        std::fs::remove_file(path)?;
+    }
+
+    /*
+     * ソケットの作成（所有者のみアクセス可能とする）
+     */
+    let listener = match UnixListener::bind(path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            return Err(anyhow!("bind {} failed: {}", path.display(), err));
+        }
+    };
+
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+    info!("control socket listening on {}", path.display());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                // 取り込みの完了を待つ接続があっても他の接続から再開等の操作
+                // ができるよう、接続ごとにスレッドを起こす
+                Ok(stream) => {
+                    let state = state.clone();
+                    let sender = sender.clone();
+
+                    thread::spawn(move || {
+                        if let Err(err) = serve(stream, &state, &sender) {
+                            warn!("control connection failed: {}", err);
+                        }
+                    });
+                }
+
+                Err(err) => warn!("control accept failed: {}", err),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+///
+/// 1つの接続からのコマンドを処理する
+///
+/// # 引数
+/// * `stream` - 接続
+/// * `state` - デーモンの状態
+/// * `sender` - 取り込みスレッドへの要求の送信口
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn serve(stream: UnixStream, state: &State, sender: &Sender<Request>)
+    -> Result<()>
+{
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let reply = match execute(line, state, sender) {
+            Ok(reply) => reply,
+            Err(err) => format!("error {}", err),
+        };
+
+        writeln!(writer, "{}", reply)?;
+    }
+
+    Ok(())
+}
+
+///
+/// コマンドを実行する
+///
+/// # 引数
+/// * `line` - 受信したコマンド行
+/// * `state` - デーモンの状態
+/// * `sender` - 取り込みスレッドへの要求の送信口
+///
+/// # 戻り値
+/// 処理が成功した場合は応答行を`Ok()`でラップして返す。失敗した場合はエラー情
+/// 報を`Err()`でラップして返す。
+///
+fn execute(line: &str, state: &State, sender: &Sender<Request>)
+    -> Result<String>
+{
+    let (command, arg) = match line.split_once(char::is_whitespace) {
+        Some((command, arg)) => (command, arg.trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        "status" => Ok(format!("ok {}", state.status_json()?)),
+
+        "pause" => {
+            state.pause_state().pause();
+            Ok("ok paused".to_string())
+        }
+
+        "resume" => {
+            state.pause_state().resume();
+            Ok("ok resumed".to_string())
+        }
+
+        "trigger" => {
+            if arg.is_empty() {
+                return Err(anyhow!("source directory is not specified"));
+            }
+
+            if state.pause_state().is_paused() {
+                return Err(anyhow!("daemon is paused"));
+            }
+
+            let path = PathBuf::from(arg);
+
+            if !path.is_dir() {
+                return Err(anyhow!("{} is not directory", path.display()));
+            }
+
+            send(sender, Request::Trigger(path))?;
+            Ok("ok triggered".to_string())
+        }
+
+        "reload-config" => {
+            let (reply, result) = mpsc::channel();
+            send(sender, Request::ReloadConfig(reply))?;
+
+            // 再読み込みは取り込みの合間に行われるため、その完了を待つ
+            match result.recv() {
+                Ok(Ok(())) => Ok("ok reloaded".to_string()),
+                Ok(Err(err)) => Err(anyhow!("{}", err)),
+                Err(_) => Err(anyhow!("daemon is not responding")),
+            }
+        }
+
+        _ => Err(anyhow!("unknown command: {}", command)),
+    }
+}
+
+///
+/// 取り込みスレッドへ要求を送信する
+///
+/// # 引数
+/// * `sender` - 取り込みスレッドへの要求の送信口
+/// * `request` - 送信する要求
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn send(sender: &Sender<Request>, request: Request) -> Result<()> {
+    match sender.send(request) {
+        Ok(()) => Ok(()),
+        Err(_) => Err(anyhow!("daemon is not responding")),
+    }
+}
@@ -0,0 +1,913 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! カメラからのアップロードを受け付けるFTPサーバを提供するモジュール
+//!
+//! FTPS(TLS)には対応しないため、パスワードとファイルは平文で転送される。そ
+//! のため待ち受けはループバックアドレスに限り、外部からの接続はSSHのポート転
+//! 送等の暗号化された経路を経由させる。
+//!
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+
+use super::Request;
+
+/// データ接続の確立を待つ時間
+const DATA_TIMEOUT: Duration = Duration::from_secs(30);
+
+///
+/// ログインに用いる認証情報
+///
+#[derive(Debug, Clone)]
+pub(super) struct Credential {
+    /// ユーザ名
+    pub(super) user: String,
+
+    /// パスワード
+    pub(super) password: String,
+}
+
+///
+/// データ接続の方法を表す列挙子
+///
+enum DataChannel {
+    /// パッシブモード（サーバ側で待ち受ける）
+    Passive(TcpListener),
+
+    /// アクティブモード（クライアント側へ接続する）
+    Active(SocketAddr),
+}
+
+///
+/// 1つの接続のセッション情報
+///
+struct Session {
+    /// スプールディレクトリのパス
+    spool: PathBuf,
+
+    /// 認証情報（Noneの場合は任意のユーザでログインできる）
+    credential: Option<Credential>,
+
+    /// 取り込みスレッドへの要求の送信口
+    sender: Sender<Request>,
+
+    /// 制御接続
+    stream: TcpStream,
+
+    /// USERコマンドで指定されたユーザ名
+    user: Option<String>,
+
+    /// ログイン済みか否か
+    logged_in: bool,
+
+    /// カレントディレクトリ（スプールディレクトリからの相対パス）
+    cwd: PathBuf,
+
+    /// 次のデータ転送に用いるデータ接続
+    data: Option<DataChannel>,
+
+    /// RNFRコマンドで指定された名前変更元のパス
+    rename_from: Option<PathBuf>,
+}
+
+///
+/// FTPサーバを起動する
+///
+/// # 引数
+/// * `addr` - 待ち受けアドレス
+/// * `spool` - アップロードされたファイルを格納するスプールディレクトリ
+/// * `credential` - 認証情報（Noneの場合は任意のユーザでログインできる）
+/// * `sender` - 取り込みスレッドへの要求の送信口
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 接続の処理は接続ごとに専用のスレッドで行う。ファイルの受信が完了するたびに
+/// スプールディレクトリからの取り込みを要求する。受信中のファイルは隠しファイ
+/// ル(`.<NAME>.part`)として書き込み、受信の完了後に本来の名前に変更する。平
+/// 文のFTPであるため、ループバックアドレス以外での待ち受けは拒否する。
+///
+pub(super) fn spawn(
+    addr: SocketAddr,
+    spool: PathBuf,
+    credential: Option<Credential>,
+    sender: Sender<Request>,
+) -> Result<()> {
+    if !addr.ip().is_loopback() {
+        return Err(anyhow!(
+            "ftp server must listen on loopback (no TLS support), not {}",
+            addr
+        ));
+    }
+
+    if let Err(err) = fs::create_dir_all(&spool) {
+        return Err(anyhow!("create {} failed: {}", spool.display(), err));
+    }
+
+    let listener = match TcpListener::bind(addr) {
+        Ok(listener) => listener,
+        Err(err) => return Err(anyhow!("bind {} failed: {}", addr, err)),
+    };
+
+    info!("ftp server listening on {}", addr);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let session = Session::new(
+                        spool.clone(),
+                        credential.clone(),
+                        sender.clone(),
+                        stream,
+                    );
+
+                    thread::spawn(move || {
+                        if let Err(err) = session.serve() {
+                            warn!("ftp connection failed: {}", err);
+                        }
+                    });
+                }
+
+                Err(err) => warn!("ftp accept failed: {}", err),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+impl Session {
+    ///
+    /// セッションを生成する
+    ///
+    /// # 引数
+    /// * `spool` - スプールディレクトリのパス
+    /// * `credential` - 認証情報
+    /// * `sender` - 取り込みスレッドへの要求の送信口
+    /// * `stream` - 制御接続
+    ///
+    /// # 戻り値
+    /// 生成したセッション
+    ///
+    fn new(
+        spool: PathBuf,
+        credential: Option<Credential>,
+        sender: Sender<Request>,
+        stream: TcpStream,
+    ) -> Self {
+        Self {
+            spool,
+            credential,
+            sender,
+            stream,
+            user: None,
+            logged_in: false,
+            cwd: PathBuf::new(),
+            data: None,
+            rename_from: None,
+        }
+    }
+
+    ///
+    /// 接続を閉じるまでコマンドを処理する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn serve(mut self) -> Result<()> {
+        let peer = self.stream.peer_addr()?;
+        let mut reader = BufReader::new(self.stream.try_clone()?);
+        let mut line = String::new();
+
+        debug!("ftp connected from {}", peer);
+        self.reply(220, "imgdist ready")?;
+
+        loop {
+            line.clear();
+
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (verb, arg) = match line.split_once(' ') {
+                Some((verb, arg)) => (verb.to_ascii_uppercase(), arg),
+                None => (line.to_ascii_uppercase(), ""),
+            };
+
+            // パスワードはログに残さない
+            if verb == "PASS" {
+                debug!("ftp {}: PASS ****", peer);
+            } else {
+                debug!("ftp {}: {}", peer, line);
+            }
+
+            if verb == "QUIT" {
+                self.reply(221, "bye")?;
+                break;
+            }
+
+            self.dispatch(&verb, arg)?;
+        }
+
+        debug!("ftp disconnected from {}", peer);
+
+        Ok(())
+    }
+
+    ///
+    /// 1つのコマンドを処理する
+    ///
+    /// # 引数
+    /// * `verb` - コマンド名（大文字）
+    /// * `arg` - コマンドの引数
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    /// # 注記
+    /// コマンド自体の失敗はエラー応答として返し、`Err()`は制御接続の入出力に失
+    /// 敗した場合のみ返す。
+    ///
+    fn dispatch(&mut self, verb: &str, arg: &str) -> Result<()> {
+        /*
+         * ログイン前でも受け付けるコマンド
+         */
+        match verb {
+            "USER" => {
+                self.user = Some(arg.to_string());
+                self.logged_in = false;
+                return self.reply(331, "password required");
+            }
+
+            "PASS" => return self.login(arg),
+            "SYST" => return self.reply(215, "UNIX Type: L8"),
+            "NOOP" => return self.reply(200, "ok"),
+            "AUTH" => return self.reply(502, "TLS not supported"),
+
+            "FEAT" => {
+                return self.write(
+                    "211-Features:\r\n EPSV\r\n PASV\r\n SIZE\r\n UTF8\r\n\
+                     211 End\r\n"
+                );
+            }
+
+            "OPTS" => return self.reply(200, "ok"),
+            _ => {}
+        }
+
+        if !self.logged_in {
+            return self.reply(530, "not logged in");
+        }
+
+        /*
+         * ログイン後に受け付けるコマンド
+         */
+        match verb {
+            "PWD" | "XPWD" => {
+                let cwd = format!("\"{}\" is current directory", self.pwd());
+                self.reply(257, &cwd)
+            }
+
+            "CWD" | "XCWD" => self.change_dir(arg),
+            "CDUP" | "XCUP" => self.change_dir(".."),
+            "MKD" | "XMKD" => self.make_dir(arg),
+            "TYPE" | "MODE" | "STRU" => self.reply(200, "ok"),
+            "PASV" => self.passive(false),
+            "EPSV" => self.passive(true),
+            "PORT" => self.active(arg),
+            "LIST" | "NLST" => self.list(arg, verb == "LIST"),
+            "SIZE" => self.size(arg),
+            "STOR" => self.store(arg),
+            "RNFR" => self.rename_from(arg),
+            "RNTO" => self.rename_to(arg),
+            _ => self.reply(502, "command not implemented"),
+        }
+    }
+
+    ///
+    /// ログインを行う
+    ///
+    /// # 引数
+    /// * `password` - PASSコマンドで指定されたパスワード
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn login(&mut self, password: &str) -> Result<()> {
+        let user = match self.user.take() {
+            Some(user) => user,
+            None => return self.reply(503, "send USER first"),
+        };
+
+        // 一致した文字数が応答時間から推測されないよう、ユーザ名とパスワード
+        // の両方を常に最後まで比較する
+        self.logged_in = match self.credential {
+            Some(ref credential) => {
+                let user_ok = constant_time_eq(&credential.user, &user);
+                let password_ok =
+                    constant_time_eq(&credential.password, password);
+
+                user_ok & password_ok
+            }
+
+            None => true,
+        };
+
+        if self.logged_in {
+            info!("ftp login from {} as {}", self.stream.peer_addr()?, user);
+            self.reply(230, "logged in")
+        } else {
+            warn!("ftp login failed from {}", self.stream.peer_addr()?);
+            self.reply(530, "login incorrect")
+        }
+    }
+
+    ///
+    /// カレントディレクトリを変更する
+    ///
+    /// # 引数
+    /// * `arg` - 移動先のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn change_dir(&mut self, arg: &str) -> Result<()> {
+        let path = self.resolve(arg);
+
+        if self.spool.join(&path).is_dir() {
+            self.cwd = path;
+            self.reply(250, "ok")
+        } else {
+            self.reply(550, "no such directory")
+        }
+    }
+
+    ///
+    /// ディレクトリを作成する
+    ///
+    /// # 引数
+    /// * `arg` - 作成するディレクトリのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn make_dir(&mut self, arg: &str) -> Result<()> {
+        let path = self.resolve(arg);
+
+        match fs::create_dir_all(self.spool.join(&path)) {
+            Ok(()) => {
+                let path = format!("\"/{}\" created", slashed(&path));
+                self.reply(257, &path)
+            }
+
+            Err(err) => self.reply(550, &err.to_string()),
+        }
+    }
+
+    ///
+    /// パッシブモードのデータ接続を待ち受ける
+    ///
+    /// # 引数
+    /// * `extended` - EPSVコマンドによる要求か否か
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn passive(&mut self, extended: bool) -> Result<()> {
+        let ip = self.stream.local_addr()?.ip();
+        let listener = TcpListener::bind((ip, 0))?;
+        let port = listener.local_addr()?.port();
+
+        self.data = Some(DataChannel::Passive(listener));
+
+        if extended {
+            let text = format!("entering extended passive mode (|||{}|)", port);
+            return self.reply(229, &text);
+        }
+
+        match ip {
+            IpAddr::V4(ip) => {
+                let [h1, h2, h3, h4] = ip.octets();
+                let text = format!(
+                    "entering passive mode ({},{},{},{},{},{})",
+                    h1, h2, h3, h4,
+                    port >> 8,
+                    port & 0xff
+                );
+
+                self.reply(227, &text)
+            }
+
+            IpAddr::V6(_) => {
+                self.data = None;
+                self.reply(522, "use EPSV for IPv6")
+            }
+        }
+    }
+
+    ///
+    /// アクティブモードの接続先を設定する
+    ///
+    /// # 引数
+    /// * `arg` - PORTコマンドの引数（`h1,h2,h3,h4,p1,p2`の形式）
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    /// # 注記
+    /// 第三者のホストへの接続に悪用されないよう(FTPバウンス攻撃)、制御接続の
+    /// 接続元と異なるアドレスは拒否する。
+    ///
+    fn active(&mut self, arg: &str) -> Result<()> {
+        let values = arg
+            .split(',')
+            .map(|value| value.trim().parse::<u8>())
+            .collect::<Result<Vec<_>, _>>();
+
+        match values.as_deref() {
+            Ok([h1, h2, h3, h4, p1, p2]) => {
+                let ip = IpAddr::from([*h1, *h2, *h3, *h4]);
+                let port = u16::from(*p1) << 8 | u16::from(*p2);
+
+                if ip != self.stream.peer_addr()?.ip().to_canonical() {
+                    warn!("ftp PORT to foreign address {} rejected", ip);
+                    return self.reply(504, "address must match the client");
+                }
+
+                self.data = Some(
+                    DataChannel::Active(SocketAddr::new(ip, port))
+                );
+                self.reply(200, "ok")
+            }
+
+            _ => self.reply(501, "invalid argument"),
+        }
+    }
+
+    ///
+    /// ディレクトリの一覧を送信する
+    ///
+    /// # 引数
+    /// * `arg` - 対象のパス（オプション指定は無視する）
+    /// * `detail` - 詳細形式（LIST）で送信するか否か
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn list(&mut self, arg: &str, detail: bool) -> Result<()> {
+        let arg = arg
+            .split_whitespace()
+            .find(|arg| !arg.starts_with('-'))
+            .unwrap_or("");
+        let dir = self.spool.join(self.resolve(arg));
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => return self.reply(550, &err.to_string()),
+        };
+
+        let mut text = String::new();
+
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // 受信中のファイルは表示しない
+            if name.starts_with('.') {
+                continue;
+            }
+
+            if !detail {
+                text.push_str(&format!("{}\r\n", name));
+                continue;
+            }
+
+            let (mode, size) = match entry.metadata() {
+                Ok(meta) if meta.is_dir() => ("drwxr-xr-x", 0),
+                Ok(meta) => ("-rw-r--r--", meta.len()),
+                Err(_) => continue,
+            };
+
+            text.push_str(&format!(
+                "{} 1 ftp ftp {} Jan 01 00:00 {}\r\n",
+                mode, size, name
+            ));
+        }
+
+        let mut data = match self.open_data() {
+            Ok(data) => data,
+            Err(err) => return self.reply(425, &err.to_string()),
+        };
+
+        self.reply(150, "sending directory list")?;
+
+        match data.write_all(text.as_bytes()) {
+            Ok(()) => {
+                drop(data);
+                self.reply(226, "transfer complete")
+            }
+
+            Err(err) => self.reply(426, &err.to_string()),
+        }
+    }
+
+    ///
+    /// ファイルのサイズを返す
+    ///
+    /// # 引数
+    /// * `arg` - 対象のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn size(&mut self, arg: &str) -> Result<()> {
+        let path = self.spool.join(self.resolve(arg));
+
+        match path.metadata() {
+            Ok(meta) if meta.is_file() => {
+                self.reply(213, &meta.len().to_string())
+            }
+
+            _ => self.reply(550, "no such file"),
+        }
+    }
+
+    ///
+    /// ファイルを受信する
+    ///
+    /// # 引数
+    /// * `arg` - 格納先のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn store(&mut self, arg: &str) -> Result<()> {
+        let path = self.spool.join(self.resolve(arg));
+        let (dir, name) = match (path.parent(), path.file_name()) {
+            (Some(dir), Some(name)) if dir.is_dir() => {
+                (dir.to_path_buf(), name.to_string_lossy().to_string())
+            }
+
+            _ => return self.reply(553, "invalid file name"),
+        };
+
+        let tmp = dir.join(format!(".{}.part", name));
+
+        let mut data = match self.open_data() {
+            Ok(data) => data,
+            Err(err) => return self.reply(425, &err.to_string()),
+        };
+
+        self.reply(150, "ready to receive")?;
+
+        let result = File::create(&tmp)
+            .and_then(|mut file| {
+                io::copy(&mut data, &mut file)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp, &path));
+
+        match result {
+            Ok(()) => {
+                info!("ftp received {}", path.display());
+                self.reply(226, "transfer complete")?;
+                self.notify();
+                Ok(())
+            }
+
+            Err(err) => {
+                warn!("ftp receive {} failed: {}", path.display(), err);
+                let _ = fs::remove_file(&tmp);
+                self.reply(451, &err.to_string())
+            }
+        }
+    }
+
+    ///
+    /// 名前変更元のパスを設定する
+    ///
+    /// # 引数
+    /// * `arg` - 名前変更元のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn rename_from(&mut self, arg: &str) -> Result<()> {
+        let path = self.spool.join(self.resolve(arg));
+
+        if path.exists() {
+            self.rename_from = Some(path);
+            self.reply(350, "ready for RNTO")
+        } else {
+            self.reply(550, "no such file")
+        }
+    }
+
+    ///
+    /// 名前の変更を行う
+    ///
+    /// # 引数
+    /// * `arg` - 名前変更先のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    /// # 注記
+    /// 一時的な名前でアップロードした後に名前を変更するカメラがあるため、名前
+    /// の変更後にも取り込みを要求する。
+    ///
+    fn rename_to(&mut self, arg: &str) -> Result<()> {
+        let from = match self.rename_from.take() {
+            Some(from) => from,
+            None => return self.reply(503, "send RNFR first"),
+        };
+
+        let to = self.spool.join(self.resolve(arg));
+
+        match fs::rename(&from, &to) {
+            Ok(()) => {
+                self.reply(250, "ok")?;
+                self.notify();
+                Ok(())
+            }
+
+            Err(err) => self.reply(550, &err.to_string()),
+        }
+    }
+
+    ///
+    /// データ接続を確立する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はデータ接続を`Ok()`でラップして返す。失敗した場合は
+    /// エラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// パッシブモードでは、第三者がデータ接続を横取りしないよう、制御接続の接
+    /// 続元と異なるアドレスからの接続は切断して待ち受けを続ける。
+    ///
+    fn open_data(&mut self) -> Result<TcpStream> {
+        match self.data.take() {
+            Some(DataChannel::Passive(listener)) => {
+                let client = self.stream.peer_addr()?.ip().to_canonical();
+                let limit = Instant::now() + DATA_TIMEOUT;
+
+                listener.set_nonblocking(true)?;
+
+                loop {
+                    match listener.accept() {
+                        Ok((stream, peer)) => {
+                            if peer.ip().to_canonical() != client {
+                                warn!(
+                                    "ftp data connection from foreign \
+                                     address {} rejected",
+                                    peer
+                                );
+                                continue;
+                            }
+
+                            stream.set_nonblocking(false)?;
+                            return Ok(stream);
+                        }
+
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            if Instant::now() >= limit {
+                                return Err(anyhow!("data connection timeout"));
+                            }
+
+                            thread::sleep(Duration::from_millis(50));
+                        }
+
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+            }
+
+            Some(DataChannel::Active(addr)) => {
+                Ok(TcpStream::connect_timeout(&addr, DATA_TIMEOUT)?)
+            }
+
+            None => Err(anyhow!("use PASV or PORT first")),
+        }
+    }
+
+    ///
+    /// 取り込みスレッドにスプールディレクトリからの取り込みを要求する
+    ///
+    fn notify(&self) {
+        if self.sender.send(Request::Trigger(self.spool.clone())).is_err() {
+            warn!("ftp import request failed");
+        }
+    }
+
+    ///
+    /// クライアントが指定したパスを解決する
+    ///
+    /// # 引数
+    /// * `arg` - クライアントが指定したパス
+    ///
+    /// # 戻り値
+    /// スプールディレクトリからの相対パス
+    ///
+    /// # 注記
+    /// `..`によってスプールディレクトリの外を指すことはできない（ルートより上
+    /// への移動はルートに留まる）。
+    ///
+    fn resolve(&self, arg: &str) -> PathBuf {
+        resolve(&self.cwd, arg)
+    }
+
+    ///
+    /// カレントディレクトリを表す文字列を返す
+    ///
+    /// # 戻り値
+    /// `/`で始まるカレントディレクトリのパス
+    ///
+    fn pwd(&self) -> String {
+        format!("/{}", slashed(&self.cwd))
+    }
+
+    ///
+    /// 応答を送信する
+    ///
+    /// # 引数
+    /// * `code` - 応答コード
+    /// * `text` - 応答メッセージ
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn reply(&mut self, code: u16, text: &str) -> Result<()> {
+        self.write(&format!("{} {}\r\n", code, text))
+    }
+
+    ///
+    /// 制御接続に文字列を書き込む
+    ///
+    /// # 引数
+    /// * `text` - 書き込む文字列
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    fn write(&mut self, text: &str) -> Result<()> {
+        self.stream.write_all(text.as_bytes())?;
+        Ok(())
+    }
+}
+
+///
+/// クライアントが指定したパスをカレントディレクトリを起点に解決する
+///
+/// # 引数
+/// * `cwd` - カレントディレクトリ（スプールディレクトリからの相対パス）
+/// * `arg` - クライアントが指定したパス
+///
+/// # 戻り値
+/// スプールディレクトリからの相対パス
+///
+fn resolve(cwd: &Path, arg: &str) -> PathBuf {
+    let mut path = if arg.starts_with('/') {
+        PathBuf::new()
+    } else {
+        cwd.to_path_buf()
+    };
+
+    for component in Path::new(arg).components() {
+        match component {
+            Component::Normal(name) => path.push(name),
+            Component::ParentDir => {
+                path.pop();
+            }
+            _ => {}
+        }
+    }
+
+    path
+}
+
+///
+/// 文字列を比較に要する時間が内容に依存しない方法で比較する
+///
+/// # 引数
+/// * `expect` - 期待する値
+/// * `actual` - 比較する値
+///
+/// # 戻り値
+/// 一致した場合は`true`
+///
+/// # 注記
+/// 長さが異なる場合は即座に`false`を返す（長さは秘匿しない）。
+///
+fn constant_time_eq(expect: &str, actual: &str) -> bool {
+    let (expect, actual) = (expect.as_bytes(), actual.as_bytes());
+
+    if expect.len() != actual.len() {
+        return false;
+    }
+
+    let diff = expect
+        .iter()
+        .zip(actual)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b));
+
+    std::hint::black_box(diff) == 0
+}
+
+///
+/// 相対パスを`/`区切りの文字列に変換する
+///
+/// # 引数
+/// * `path` - 変換する相対パス
+///
+/// # 戻り値
+/// `/`区切りの文字列
+///
+fn slashed(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_follows_current_directory() {
+        let cwd = Path::new("DCIM");
+
+        assert_eq!(resolve(cwd, "100CANON"), Path::new("DCIM/100CANON"));
+        assert_eq!(resolve(cwd, "a/./b.jpg"), Path::new("DCIM/a/b.jpg"));
+        assert_eq!(resolve(cwd, ""), Path::new("DCIM"));
+    }
+
+    #[test]
+    fn resolve_treats_absolute_path_as_spool_relative() {
+        let cwd = Path::new("DCIM");
+
+        assert_eq!(resolve(cwd, "/"), Path::new(""));
+        assert_eq!(resolve(cwd, "/etc/passwd"), Path::new("etc/passwd"));
+        assert_eq!(resolve(cwd, "//x"), Path::new("x"));
+    }
+
+    #[test]
+    fn resolve_cannot_leave_spool() {
+        let cwd = Path::new("DCIM");
+
+        assert_eq!(resolve(cwd, ".."), Path::new(""));
+        assert_eq!(resolve(cwd, "../.."), Path::new(""));
+        assert_eq!(resolve(cwd, "../../etc/passwd"), Path::new("etc/passwd"));
+        assert_eq!(resolve(cwd, "/../../x"), Path::new("x"));
+        assert_eq!(resolve(cwd, "a/../../../x"), Path::new("x"));
+    }
+
+    #[test]
+    fn resolve_cdup_stays_at_root() {
+        let root = resolve(Path::new(""), "..");
+
+        assert_eq!(root, Path::new(""));
+        assert_eq!(resolve(&root, ".."), Path::new(""));
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_value() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(constant_time_eq("", ""));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "Secret"));
+        assert!(!constant_time_eq("secret", "secrets"));
+        assert!(!constant_time_eq("secret", ""));
+    }
+}
@@ -0,0 +1,74 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 書き出し用のホットフォルダの変化を検出するモジュール
+//!
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::quiet::{signature, Signature};
+
+///
+/// ホットフォルダごとの確認状況
+///
+#[derive(Debug, Default)]
+struct Watch {
+    /// 前回の確認で得た要約
+    observed: Option<Signature>,
+
+    /// 最後に配信を行った時点の要約
+    delivered: Option<Signature>,
+}
+
+///
+/// ホットフォルダの変化を追跡する構造体
+///
+/// # 注記
+/// 現像ソフトが書き出し中のファイルを配信しないよう、内容が変化した後、連続
+/// する2回の確認で内容が変化していないことを確かめてから配信を行う。
+///
+#[derive(Debug, Default)]
+pub(super) struct HotFolders {
+    /// ホットフォルダのパスごとの確認状況
+    watches: HashMap<PathBuf, Watch>,
+}
+
+impl HotFolders {
+    ///
+    /// ホットフォルダを確認し、配信を行うべきか否かを判定する
+    ///
+    /// # 引数
+    /// * `path` - ホットフォルダのパス
+    ///
+    /// # 戻り値
+    /// 前回の配信以降に内容が変化しており、かつ前回の確認から変化が止まって
+    /// いる場合は`true`
+    ///
+    /// # 注記
+    /// `true`を返した時点の内容を配信済みとして記録する。ホットフォルダが存
+    /// 在しない場合や空の場合は`false`を返す。
+    ///
+    pub(super) fn is_ready(&mut self, path: &Path) -> bool {
+        let watch = self.watches.entry(path.to_path_buf()).or_default();
+        let current = signature(path);
+        let previous = std::mem::replace(&mut watch.observed, current);
+
+        match current {
+            Some(sig)
+                if sig.count > 0
+                    && previous == current
+                    && watch.delivered != current =>
+            {
+                watch.delivered = current;
+                true
+            }
+
+            _ => false,
+        }
+    }
+}
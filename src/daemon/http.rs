@@ -0,0 +1,166 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! デーモンの状態を返すHTTPエンドポイントを提供するモジュール
+//!
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::preview;
+use super::state::State;
+
+///
+/// HTTPエンドポイントを起動する
+///
+/// # 引数
+/// * `addr` - 待ち受けアドレス
+/// * `state` - デーモンの状態
+/// * `preview` - プレビューページを提供するか否か
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// リクエストの処理は専用のスレッドで行う。提供するエンドポイントは以下の通り
+/// （いずれもGETのみ、プレビューページ以外はJSON形式で応答する）。
+///
+/// * `/status` - 現在の活動状況と直近の実行結果
+/// * `/runs` - 実行履歴（新しい順）
+/// * `/errors` - エラー履歴（新しい順）
+/// * `/preview` - 直近の取り込みのサムネイル一覧（`--preview`指定時のみ）
+///
+pub(super) fn spawn(
+    addr: SocketAddr,
+    state: Arc<State>,
+    preview: bool,
+) -> Result<()> {
+    let server = match Server::http(addr) {
+        Ok(server) => server,
+        Err(err) => return Err(anyhow!("http server start failed: {}", err)),
+    };
+
+    info!("http endpoint listening on {}", addr);
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            if let Err(err) = handle(request, &state, preview) {
+                warn!("http response failed: {}", err);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+///
+/// リクエストを処理する
+///
+/// # 引数
+/// * `request` - 受信したリクエスト
+/// * `state` - デーモンの状態
+/// * `preview` - プレビューページを提供するか否か
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn handle(request: Request, state: &State, preview: bool) -> Result<()> {
+    if *request.method() != Method::Get {
+        return respond(request, 405, r#"{"error":"method not allowed"}"#);
+    }
+
+    // クエリ文字列は無視する
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+
+    if preview {
+        if path == "/preview" {
+            let html = preview::page(state);
+            return send(request, 200, "text/html; charset=utf-8", html);
+        }
+
+        if let Some(index) = path.strip_prefix("/preview/") {
+            let image = index
+                .parse::<usize>()
+                .map_err(anyhow::Error::from)
+                .and_then(|index| preview::thumbnail(state, index));
+
+            return match image {
+                Ok(image) => send(request, 200, "image/jpeg", image),
+                Err(_) => respond(request, 404, r#"{"error":"not found"}"#),
+            };
+        }
+    }
+
+    let body = match path.as_str() {
+        "/status" => state.status_json(),
+        "/runs" => state.runs_json(),
+        "/errors" => state.errors_json(),
+        _ => return respond(request, 404, r#"{"error":"not found"}"#),
+    };
+
+    match body {
+        Ok(body) => respond(request, 200, &body),
+
+        Err(err) => {
+            error!("http response build failed: {}", err);
+            respond(request, 500, r#"{"error":"internal error"}"#)
+        }
+    }
+}
+
+///
+/// JSON形式の応答を返す
+///
+/// # 引数
+/// * `request` - 応答するリクエスト
+/// * `status` - HTTPステータスコード
+/// * `body` - 応答本体
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn respond(request: Request, status: u16, body: &str) -> Result<()> {
+    send(request, status, "application/json", body)
+}
+
+///
+/// 指定の形式の応答を返す
+///
+/// # 引数
+/// * `request` - 応答するリクエスト
+/// * `status` - HTTPステータスコード
+/// * `content_type` - 応答本体の形式
+/// * `body` - 応答本体
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn send<B>(request: Request, status: u16, content_type: &str, body: B)
+    -> Result<()>
+where
+    B: Into<Vec<u8>>,
+{
+    let header = Header::from_bytes("Content-Type", content_type)
+        .map_err(|_| anyhow!("invalid header"))?;
+
+    let response = Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header);
+
+    request.respond(response)?;
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! デーモンモードの処理をまとめたモジュール
+//!
+
+#[cfg(unix)]
+mod control;
+mod ftp;
+mod hot_folder;
+mod http;
+mod preview;
+mod quiet;
+mod schedule;
+mod state;
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::Result;
+use chrono::Local;
+use log::{error, info, warn};
+
+use crate::cache::Cache;
+use crate::cmd_args::{self, Options};
+use crate::mail;
+use crate::{finalize, import, prune_expired};
+use self::hot_folder::HotFolders;
+use self::quiet::Quiescence;
+use self::state::State;
+
+pub(crate) use self::schedule::CronExpr;
+
+///
+/// 制御用ソケットから取り込みスレッドへの要求を表す列挙子
+///
+enum Request {
+    /// 指定ディレクトリからの取り込みの実行
+    Trigger(PathBuf),
+
+    /// 設定の再読み込み（結果の返信先）
+    ReloadConfig(Sender<Result<(), String>>),
+}
+
+///
+/// デーモンモードの実行関数
+///
+/// # 引数
+/// * `opts` - オプション設定をパックしたオブジェクト
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 概要
+/// 入力ディレクトリの有無を一定間隔で確認し、入力ディレクトリが出現する（カー
+/// ドが挿入される）たびに取り込みを行う。起動時に入力ディレクトリが存在する場
+/// 合は直ちに取り込みを行う。確認の合間には制御用ソケットからの要求を処理す
+/// る。また、スケジュールが設定されている場合は、その時刻になるたびに指定の
+/// 取り込み元から取り込みを行う。ホットフォルダからの配信が設定されている場
+/// 合は、確認のたびにホットフォルダの内容の変化を調べ、書き出しが完了したも
+/// のを配信先に振り分ける。
+///
+/// 静止時間(`--quiet-period`)が指定されている場合は、入力ディレクトリの出現
+/// や取り込み要求の後、内容の変化と要求が静止時間の間途絶えるのを待ってから
+/// まとめて1回の取り込みを行う。
+///
+pub(crate) fn run(opts: Arc<Options>) -> Result<()> {
+    let mut opts = opts;
+    let state = Arc::new(State::new(
+        opts.input_path(),
+        opts.pause_state().clone(),
+    ));
+    let (sender, receiver) = mpsc::channel();
+
+    /*
+     * HTTPエンドポイントの起動（指定された場合のみ）
+     */
+    if let Some(addr) = opts.http_addr() {
+        http::spawn(addr, state.clone(), opts.is_preview())?;
+    }
+
+    /*
+     * FTPサーバの起動（指定された場合のみ）
+     */
+    if let Some(addr) = opts.ftp_addr() {
+        let credential = opts.ftp_user().zip(opts.ftp_password())
+            .map(|(user, password)| ftp::Credential {user, password});
+
+        ftp::spawn(addr, opts.input_path(), credential, sender.clone())?;
+    }
+
+    /*
+     * 制御用ソケットの作成（指定された場合のみ）
+     */
+    #[cfg(unix)]
+    if let Some(path) = opts.control_socket() {
+        control::spawn(&path, state.clone(), sender)?;
+    }
+
+    #[cfg(not(unix))]
+    drop(sender);
+
+    info!("daemon started (watching {})", opts.input_path().display());
+
+    /*
+     * 入力ディレクトリの監視
+     */
+    let mut available = false;
+    let mut last_check = Local::now().naive_local();
+    let mut hot_folders = HotFolders::default();
+    let mut quiescence = Quiescence::default();
+    let mut settling = false;
+    let mut pending = BTreeSet::<PathBuf>::new();
+
+    loop {
+        let current = opts.input_path().is_dir();
+        state.set_input_available(current);
+
+        // 入力ディレクトリが出現した時のみ取り込みを行う（一時停止中は出現の
+        // 判定を保留し、再開後に取り込みを行う）。書き込みが続いている間は
+        // 静止するまで取り込みを待つ
+        if !state.pause_state().is_paused() {
+            if current && !available {
+                quiescence.touch(&opts.input_path());
+                settling = true;
+            }
+
+            if !current {
+                settling = false;
+            }
+
+            available = current;
+
+            let period = opts.quiet_period();
+
+            if settling && quiescence.is_quiet(&opts.input_path(), period) {
+                settling = false;
+                quiescence.forget(&opts.input_path());
+                import_once(&opts, &state);
+            }
+        }
+
+        /*
+         * 静止を待っている取り込み要求の実行
+         */
+        let ready = pending
+            .iter()
+            .filter(|path| {
+                quiescence.is_quiet(path, opts.quiet_period())
+            })
+            .cloned()
+            .collect::<Vec<PathBuf>>();
+
+        for path in ready {
+            pending.remove(&path);
+            quiescence.forget(&path);
+            import_once(&opts.with_input_path(path), &state);
+        }
+
+        /*
+         * スケジュールされた取り込みの実行
+         */
+        // 前回の確認以降に到来した時刻を対象とする（一時停止中は保留し、再
+        // 開後に取り込みを行う）
+        if !state.pause_state().is_paused() {
+            let now = Local::now().naive_local();
+
+            for (cron, source) in opts.schedules() {
+                if cron.is_due(last_check, now) {
+                    sweep(&opts, &state, source);
+                }
+            }
+
+            last_check = now;
+        }
+
+        /*
+         * ホットフォルダからの書き出し済みファイルの配信
+         */
+        if !state.pause_state().is_paused() {
+            for export in opts.exports() {
+                if hot_folders.is_ready(&export.input_path()) {
+                    info!(
+                        "export delivery from {}",
+                        export.input_path().display()
+                    );
+                    import_once(&export, &state);
+                }
+            }
+        }
+
+        /*
+         * 制御用ソケットからの要求の処理
+         */
+        match receiver.recv_timeout(opts.daemon_interval()) {
+            // 静止時間が指定されている場合は、同じディレクトリへの要求を
+            // まとめて静止後に1回だけ取り込む
+            Ok(Request::Trigger(path)) => {
+                if opts.quiet_period().is_zero() {
+                    import_once(&opts.with_input_path(path), &state);
+                } else {
+                    quiescence.touch(&path);
+                    pending.insert(path);
+                }
+            }
+
+            Ok(Request::ReloadConfig(reply)) => {
+                let result = match cmd_args::reload() {
+                    Ok(new_opts) => {
+                        opts = new_opts;
+                        info!("configuration reloaded");
+                        Ok(())
+                    }
+
+                    Err(err) => {
+                        error!("configuration reload failed: {}", err);
+                        Err(err.to_string())
+                    }
+                };
+
+                let _ = reply.send(result);
+            }
+
+            Err(RecvTimeoutError::Timeout) => {}
+
+            // 制御用ソケットを使用しない場合
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(opts.daemon_interval());
+            }
+        }
+    }
+}
+
+///
+/// スケジュールされた取り込み元から取り込みを行う
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `state` - デーモンの状態
+/// * `source` - 取り込み元のディレクトリ
+///
+/// # 注記
+/// 取り込み元が存在しない(ネットワーク越しのボリュームがマウントされていな
+/// い等の)場合は警告を記録し、次回の時刻まで取り込みを行わない。
+///
+fn sweep(opts: &Options, state: &State, source: &Path) {
+    if !source.is_dir() {
+        warn!("scheduled source {} is not available", source.display());
+        return;
+    }
+
+    info!("scheduled import from {}", source.display());
+    import_once(&opts.with_input_path(source.to_path_buf()), state);
+}
+
+///
+/// 1回分の取り込みを行う
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `state` - デーモンの状態
+///
+/// # 注記
+/// キャッシュデータベースは取り込みのたびにオープンし、終了後にクローズする
+/// （常駐中に他のプロセスからキャッシュを操作できるようにするため）。
+///
+fn import_once(opts: &Options, state: &State) {
+    info!("import started from {}", opts.input_path().display());
+    state.begin_run();
+
+    let result = Cache::open(
+        opts.cache_db_path(),
+        opts.cache_eval_mode(),
+        opts.input_path(),
+    ).and_then(|cache| {
+        let report = import(opts, &cache)?;
+        Ok((cache, report))
+    });
+
+    match result {
+        Ok((cache, report)) => {
+            let result = finalize(opts, &report)
+                .and_then(|_| prune_expired(opts, &cache, &report));
+
+            if let Err(ref err) = result {
+                error!("{}", err);
+            }
+
+            state.finish_run(Some(&report), result.err());
+        }
+
+        Err(err) => {
+            error!("import failed: {}", err);
+            mail::notify_failure(opts, &err);
+            state.finish_run(None, Some(err));
+        }
+    }
+}
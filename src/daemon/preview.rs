@@ -0,0 +1,157 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 直近の取り込みのプレビューページを生成するモジュール
+//!
+
+use std::fmt::Write;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use exif::{In, Tag};
+
+use super::state::State;
+
+///
+/// プレビューページのHTMLを生成する
+///
+/// # 引数
+/// * `state` - デーモンの状態
+///
+/// # 戻り値
+/// 直近の取り込みでコピーしたファイルのサムネイルを並べたHTML文字列
+///
+/// # 注記
+/// サムネイルは`/preview/<番号>`から取得させる。番号はプレビューの対象の一覧
+/// 中の位置で、次の取り込みが終わるまで同じファイルを指す。
+///
+pub(super) fn page(state: &State) -> String {
+    let previews = state.previews();
+    let mut html = String::new();
+
+    html.push_str(concat!(
+        "<!DOCTYPE html>\n",
+        "<html><head><meta charset=\"utf-8\">",
+        "<meta name=\"viewport\" content=\"width=device-width\">",
+        "<title>imgdist preview</title>",
+        "<style>",
+        "body{font-family:sans-serif;margin:8px}",
+        "figure{display:inline-block;margin:4px;width:160px;",
+        "vertical-align:top}",
+        "img{max-width:160px;max-height:120px}",
+        "figcaption{font-size:small;word-break:break-all}",
+        "</style></head><body>\n",
+    ));
+
+    match state.last_started() {
+        Some(started) => {
+            let _ = writeln!(
+                html,
+                "<p>{} ({} files)</p>",
+                escape(&started),
+                previews.len(),
+            );
+        }
+
+        None => html.push_str("<p>no import yet</p>\n"),
+    }
+
+    for (index, path) in previews.iter().enumerate() {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let _ = writeln!(
+            html,
+            "<figure><img src=\"/preview/{}\" alt=\"\" loading=\"lazy\">\
+             <figcaption>{}</figcaption></figure>",
+            index,
+            escape(&name),
+        );
+    }
+
+    html.push_str("</body></html>\n");
+    html
+}
+
+///
+/// プレビューの対象のファイルのサムネイルを取得する
+///
+/// # 引数
+/// * `state` - デーモンの状態
+/// * `index` - プレビューの対象の一覧中の位置
+///
+/// # 戻り値
+/// 処理が成功した場合はJPEG形式のサムネイルを`Ok()`でラップして返す。該当す
+/// るファイルが無い場合やサムネイルが埋め込まれていない場合はエラー情報を
+/// `Err()`でラップして返す。
+///
+/// # 注記
+/// 振り分け先のファイルのExif情報に埋め込まれたサムネイル(IFD1)をそのまま
+/// 返す。画像のデコードや縮小は行わない。
+///
+pub(super) fn thumbnail(state: &State, index: usize) -> Result<Vec<u8>> {
+    let previews = state.previews();
+    let path = previews
+        .get(index)
+        .ok_or_else(|| anyhow!("no preview at {}", index))?;
+
+    embedded_thumbnail(path)
+}
+
+///
+/// Exif情報に埋め込まれたサムネイルを読み出す
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合はサムネイルのデータを`Ok()`でラップして返す。失敗した
+/// 場合はエラー情報を`Err()`でラップして返す。
+///
+fn embedded_thumbnail(path: &Path) -> Result<Vec<u8>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let exif = exif::Reader::new().read_from_container(&mut reader)?;
+
+    let field = |tag| {
+        exif.get_field(tag, In::THUMBNAIL)
+            .and_then(|field| field.value.get_uint(0))
+            .map(|value| value as usize)
+    };
+
+    let (Some(offset), Some(length)) = (
+        field(Tag::JPEGInterchangeFormat),
+        field(Tag::JPEGInterchangeFormatLength),
+    ) else {
+        return Err(anyhow!("no thumbnail in {}", path.display()));
+    };
+
+    // オフセットはTIFFヘッダの先頭からの位置
+    exif.buf()
+        .get(offset..offset.saturating_add(length))
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| anyhow!("broken thumbnail in {}", path.display()))
+}
+
+///
+/// HTMLに埋め込む文字列をエスケープする
+///
+/// # 引数
+/// * `s` - 対象の文字列
+///
+/// # 戻り値
+/// HTMLの特殊文字を実体参照に置き換えた文字列
+///
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
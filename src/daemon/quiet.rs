@@ -0,0 +1,148 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 取り込み元のディレクトリへの書き込みが止まったことを検出するモジュール
+//!
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use walkdir::WalkDir;
+
+///
+/// ディレクトリの内容の要約
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Signature {
+    /// ファイル数
+    pub(super) count: usize,
+
+    /// 合計サイズ(バイト)
+    size: u64,
+
+    /// 最も新しい更新日時(ファイルが無い場合はNone)
+    modified: Option<SystemTime>,
+}
+
+///
+/// ディレクトリごとの最後の活動の記録
+///
+#[derive(Debug)]
+struct Activity {
+    /// 最後に確認した内容の要約
+    signature: Option<Signature>,
+
+    /// 最後に内容の変化または取り込み要求を確認した時刻
+    since: Instant,
+}
+
+///
+/// 取り込み元のディレクトリの静止を判定する構造体
+///
+/// # 注記
+/// カメラやFTPクライアントが多数のファイルを書き込んでいる間に取り込みを繰り
+/// 返さないよう、内容の変化と取り込み要求が一定時間途絶えたことを確かめてか
+/// ら取り込みを行うために用いる。
+///
+#[derive(Debug, Default)]
+pub(super) struct Quiescence {
+    /// ディレクトリのパスごとの最後の活動
+    activities: HashMap<PathBuf, Activity>,
+}
+
+impl Quiescence {
+    ///
+    /// ディレクトリへの活動(取り込み要求の受信等)を記録する
+    ///
+    /// # 引数
+    /// * `path` - 対象のディレクトリのパス
+    ///
+    pub(super) fn touch(&mut self, path: &Path) {
+        self.activities.insert(path.to_path_buf(), Activity {
+            signature: signature(path),
+            since: Instant::now(),
+        });
+    }
+
+    ///
+    /// ディレクトリが静止しているか否かを判定する
+    ///
+    /// # 引数
+    /// * `path` - 対象のディレクトリのパス
+    /// * `period` - 静止とみなすまでの時間
+    ///
+    /// # 戻り値
+    /// 最後の活動から`period`以上経過している場合は`true`
+    ///
+    /// # 注記
+    /// `period`が0の場合は内容を確認せずに`true`を返す。前回の確認から内容が
+    /// 変化している場合はその時点を最後の活動とする。
+    ///
+    pub(super) fn is_quiet(&mut self, path: &Path, period: Duration) -> bool {
+        if period.is_zero() {
+            return true;
+        }
+
+        let current = signature(path);
+        let activity = self
+            .activities
+            .entry(path.to_path_buf())
+            .or_insert_with(|| Activity {
+                signature: current,
+                since: Instant::now(),
+            });
+
+        if activity.signature != current {
+            activity.signature = current;
+            activity.since = Instant::now();
+        }
+
+        activity.since.elapsed() >= period
+    }
+
+    ///
+    /// ディレクトリの活動の記録を破棄する
+    ///
+    /// # 引数
+    /// * `path` - 対象のディレクトリのパス
+    ///
+    pub(super) fn forget(&mut self, path: &Path) {
+        self.activities.remove(path);
+    }
+}
+
+///
+/// ディレクトリの内容の要約を求める
+///
+/// # 引数
+/// * `path` - 対象のディレクトリのパス
+///
+/// # 戻り値
+/// 内容の要約(ディレクトリが存在しない場合はNone)
+///
+pub(super) fn signature(path: &Path) -> Option<Signature> {
+    if !path.is_dir() {
+        return None;
+    }
+
+    let mut sig = Signature {count: 0, size: 0, modified: None};
+
+    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        if let Ok(meta) = entry.metadata() {
+            sig.count += 1;
+            sig.size += meta.len();
+            sig.modified = sig.modified.max(meta.modified().ok());
+        }
+    }
+
+    Some(sig)
+}
@@ -0,0 +1,214 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! デーモンモードの定期取り込みのスケジュール(cron形式)を扱うモジュール
+//!
+
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+
+///
+/// パース済みのcron形式のスケジュール
+///
+#[derive(Debug, Clone)]
+pub(crate) struct CronExpr {
+    /// 分(0〜59)ごとの一致の可否
+    minutes: Vec<bool>,
+
+    /// 時(0〜23)ごとの一致の可否
+    hours: Vec<bool>,
+
+    /// 日(1〜31)ごとの一致の可否
+    days: Vec<bool>,
+
+    /// 月(1〜12)ごとの一致の可否
+    months: Vec<bool>,
+
+    /// 曜日(0〜6、0が日曜日)ごとの一致の可否
+    weekdays: Vec<bool>,
+
+    /// 日の指定が`*`か否か
+    any_day: bool,
+
+    /// 曜日の指定が`*`か否か
+    any_weekday: bool,
+}
+
+impl CronExpr {
+    ///
+    /// cron形式の文字列をパースする
+    ///
+    /// # 引数
+    /// * `expr` - "分 時 日 月 曜日"の5フィールドの文字列、または`@hourly`、
+    ///   `@daily`、`@weekly`、`@monthly`
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はパース結果を`Ok()`でラップして返す。失敗した場合は
+    /// エラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 各フィールドには`*`、数値、範囲(`1-5`)、列挙(`1,15`)、間隔(`*/10`、
+    /// `0-30/5`)を指定できる。曜日の7は日曜日として扱う。
+    ///
+    pub(crate) fn parse(expr: &str) -> Result<Self> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expr => expr,
+        };
+
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 5 {
+            return Err(anyhow!("invalid schedule \"{}\"", expr));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+
+        // 7は0と同じく日曜日を表す
+        if weekdays[7] {
+            weekdays[0] = true;
+        }
+        weekdays.truncate(7);
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    ///
+    /// 指定の日時(分単位)がスケジュールに一致するか否かを判定する
+    ///
+    /// # 引数
+    /// * `time` - 判定する日時
+    ///
+    /// # 戻り値
+    /// 一致する場合は`true`
+    ///
+    /// # 注記
+    /// cronと同様に、日と曜日の両方が指定されている場合は何れかに一致すれば
+    /// よいものとする。
+    ///
+    fn matches(&self, time: &NaiveDateTime) -> bool {
+        let day = self.days[time.day() as usize];
+        let weekday =
+            self.weekdays[time.weekday().num_days_from_sunday() as usize];
+
+        let date_hit = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        };
+
+        self.minutes[time.minute() as usize]
+            && self.hours[time.hour() as usize]
+            && self.months[time.month() as usize]
+            && date_hit
+    }
+
+    ///
+    /// 指定の期間内に実行すべき時刻があるか否かを判定する
+    ///
+    /// # 引数
+    /// * `from` - 期間の始点(この時刻を含まない)
+    /// * `to` - 期間の終点(この時刻を含む)
+    ///
+    /// # 戻り値
+    /// 期間内にスケジュールに一致する時刻(分単位)がある場合は`true`
+    ///
+    pub(crate) fn is_due(&self, from: NaiveDateTime, to: NaiveDateTime)
+        -> bool
+    {
+        let mut time = match from.with_second(0)
+            .and_then(|time| time.with_nanosecond(0))
+        {
+            Some(time) => time + Duration::minutes(1),
+            None => return false,
+        };
+
+        while time <= to {
+            if self.matches(&time) {
+                return true;
+            }
+
+            time += Duration::minutes(1);
+        }
+
+        false
+    }
+}
+
+///
+/// cron形式の1フィールドをパースする
+///
+/// # 引数
+/// * `field` - フィールドの文字列
+/// * `min` - フィールドの最小値
+/// * `max` - フィールドの最大値
+///
+/// # 戻り値
+/// 処理が成功した場合は値(0〜`max`)ごとの一致の可否を`Ok()`でラップして返す。
+/// 失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>> {
+    let mut table = vec![false; max as usize + 1];
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, parse_value(step, 1, max)?),
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, min, max)?, parse_value(end, min, max)?)
+        } else {
+            let value = parse_value(range, min, max)?;
+
+            // 間隔を指定した場合は最大値までを範囲とする
+            (value, if part.contains('/') { max } else { value })
+        };
+
+        if start > end {
+            return Err(anyhow!("invalid schedule field \"{}\"", field));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            table[value as usize] = true;
+        }
+    }
+
+    Ok(table)
+}
+
+///
+/// cron形式のフィールド中の数値をパースする
+///
+/// # 引数
+/// * `value` - 数値の文字列
+/// * `min` - 許容する最小値
+/// * `max` - 許容する最大値
+///
+/// # 戻り値
+/// 処理が成功した場合は数値を`Ok()`でラップして返す。失敗した場合はエラー情
+/// 報を`Err()`でラップして返す。
+///
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32> {
+    match value.parse::<u32>() {
+        Ok(value) if (min..=max).contains(&value) => Ok(value),
+        _ => Err(anyhow!("invalid schedule value \"{}\"", value)),
+    }
+}
@@ -0,0 +1,398 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! デーモンの稼働状態と実行履歴を管理するモジュール
+//!
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{Error, Result};
+use chrono::Local;
+use serde::Serialize;
+
+use crate::pause::PauseState;
+use crate::report::{Action, Report};
+
+/// 保持する実行履歴の最大件数
+const MAX_RUNS: usize = 50;
+
+/// 保持するエラーの最大件数
+const MAX_ERRORS: usize = 200;
+
+/// プレビューの対象として保持するファイルの最大件数
+const MAX_PREVIEWS: usize = 200;
+
+///
+/// デーモンの現在の活動状況を表す列挙子
+///
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+enum Activity {
+    /// 入力ディレクトリの出現待ち
+    Idle,
+
+    /// 取り込み中（開始日時）
+    Importing { since: String },
+}
+
+///
+/// 1回分の取り込みの実行結果を表す構造体
+///
+#[derive(Debug, Clone, Serialize)]
+struct RunRecord {
+    /// 開始日時(ISO8601)
+    started: String,
+
+    /// 終了日時(ISO8601)
+    finished: String,
+
+    /// コピーしたファイル数
+    copied: usize,
+
+    /// 処理を行わなかったファイル数
+    skipped: usize,
+
+    /// 処理に失敗したファイル数
+    failed: usize,
+
+    /// コピーしたバイト数
+    copied_bytes: u64,
+
+    /// 取り込み自体が失敗した場合のエラーメッセージ
+    error: Option<String>,
+}
+
+///
+/// 発生したエラーを表す構造体
+///
+#[derive(Debug, Clone, Serialize)]
+struct ErrorRecord {
+    /// 発生日時(ISO8601)
+    time: String,
+
+    /// エラーの原因となった入力ファイル（ファイル単位のエラーの場合のみ）
+    source: Option<PathBuf>,
+
+    /// エラーメッセージ
+    message: String,
+}
+
+///
+/// `/status`で返す情報をまとめた構造体
+///
+#[derive(Debug, Serialize)]
+struct Status<'a> {
+    /// デーモンの起動日時(ISO8601)
+    started: &'a str,
+
+    /// 監視している入力ディレクトリ
+    input_path: &'a PathBuf,
+
+    /// 入力ディレクトリの有無
+    input_available: bool,
+
+    /// 一時停止中か否か
+    paused: bool,
+
+    /// 現在の活動状況
+    activity: &'a Activity,
+
+    /// 実行回数
+    runs: usize,
+
+    /// 直近の実行結果
+    last_run: Option<&'a RunRecord>,
+}
+
+///
+/// 排他制御下で管理する状態をまとめた構造体
+///
+#[derive(Debug)]
+struct Inner {
+    /// デーモンの起動日時(ISO8601)
+    started: String,
+
+    /// 監視している入力ディレクトリ
+    input_path: PathBuf,
+
+    /// 入力ディレクトリの有無
+    input_available: bool,
+
+    /// 現在の活動状況
+    activity: Activity,
+
+    /// 実行回数
+    run_count: usize,
+
+    /// 実行履歴（新しいものが末尾）
+    runs: VecDeque<RunRecord>,
+
+    /// エラー履歴（新しいものが末尾）
+    errors: VecDeque<ErrorRecord>,
+
+    /// 直近の取り込みでコピーしたファイルの振り分け先（コピーした順）
+    previews: Vec<PathBuf>,
+}
+
+///
+/// デーモンの状態を表す構造体
+///
+/// # 注記
+/// 取り込みスレッドとHTTPスレッドから共有されるため、内部状態は排他制御下で
+/// 管理する。
+///
+#[derive(Debug)]
+pub(super) struct State {
+    inner: Mutex<Inner>,
+
+    /// 取り込み処理の一時停止状態(オプション設定と共有する)
+    pause: Arc<PauseState>,
+}
+
+impl State {
+    ///
+    /// インスタンスを構築する
+    ///
+    /// # 引数
+    /// * `input_path` - 監視する入力ディレクトリ
+    /// * `pause` - 取り込み処理の一時停止状態
+    ///
+    /// # 戻り値
+    /// 待機状態で初期化したインスタンス
+    ///
+    pub(super) fn new(input_path: PathBuf, pause: Arc<PauseState>) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                started: Local::now().to_rfc3339(),
+                input_path,
+                input_available: false,
+                activity: Activity::Idle,
+                run_count: 0,
+                runs: VecDeque::new(),
+                errors: VecDeque::new(),
+                previews: Vec::new(),
+            }),
+            pause,
+        }
+    }
+
+    ///
+    /// 取り込み処理の一時停止状態へのアクセサ
+    ///
+    /// # 戻り値
+    /// 一時停止状態
+    ///
+    pub(super) fn pause_state(&self) -> &PauseState {
+        &self.pause
+    }
+
+    ///
+    /// 内部状態のロック
+    ///
+    /// # 戻り値
+    /// 内部状態のガードオブジェクト
+    ///
+    /// # 注記
+    /// 他のスレッドがパニックした場合でも状態の参照は継続できるよう、ポイズン
+    /// 状態は無視する。
+    ///
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    ///
+    /// 入力ディレクトリの有無を記録する
+    ///
+    /// # 引数
+    /// * `available` - 入力ディレクトリが存在する場合は`true`
+    ///
+    pub(super) fn set_input_available(&self, available: bool) {
+        self.lock().input_available = available;
+    }
+
+    ///
+    /// 取り込みの開始を記録する
+    ///
+    pub(super) fn begin_run(&self) {
+        self.lock().activity = Activity::Importing {
+            since: Local::now().to_rfc3339(),
+        };
+    }
+
+    ///
+    /// 取り込みの終了を記録する
+    ///
+    /// # 引数
+    /// * `report` - 取り込みの実行結果（取り込み自体が失敗した場合はNone）
+    /// * `error` - 取り込みまたは後処理で発生したエラー
+    ///
+    pub(super) fn finish_run(
+        &self,
+        report: Option<&Report>,
+        error: Option<Error>,
+    ) {
+        let mut inner = self.lock();
+        let now = Local::now().to_rfc3339();
+
+        let started = match &inner.activity {
+            Activity::Importing { since } => since.clone(),
+            Activity::Idle => now.clone(),
+        };
+
+        /*
+         * エラー履歴の更新
+         */
+        if let Some(report) = report {
+            for entry in report.failures() {
+                inner.push_error(ErrorRecord {
+                    time: now.clone(),
+                    source: Some(entry.source.clone()),
+                    message: entry.error.clone().unwrap_or_default(),
+                });
+            }
+        }
+
+        /*
+         * プレビューの対象の更新
+         */
+        // 取り込み自体が失敗した場合は前回の内容を残す
+        if let Some(report) = report {
+            inner.previews = report
+                .entries()
+                .filter(|entry| entry.action == Action::Copied)
+                .filter_map(|entry| entry.destination.clone())
+                .take(MAX_PREVIEWS)
+                .collect();
+        }
+
+        if let Some(ref err) = error {
+            inner.push_error(ErrorRecord {
+                time: now.clone(),
+                source: None,
+                message: err.to_string(),
+            });
+        }
+
+        /*
+         * 実行履歴の更新
+         */
+        let record = RunRecord {
+            started: report
+                .map(|report| report.started().to_string())
+                .unwrap_or(started),
+            finished: report
+                .and_then(|report| report.finished())
+                .map(str::to_string)
+                .unwrap_or(now),
+            copied: report.map_or(0, Report::copied),
+            skipped: report.map_or(0, Report::skipped),
+            failed: report.map_or(0, Report::failed),
+            copied_bytes: report.map_or(0, Report::copied_bytes),
+            error: error.map(|err| err.to_string()),
+        };
+
+        if inner.runs.len() >= MAX_RUNS {
+            inner.runs.pop_front();
+        }
+
+        inner.runs.push_back(record);
+        inner.run_count += 1;
+        inner.activity = Activity::Idle;
+    }
+
+    ///
+    /// 現在の状態をJSON文字列で取得する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はJSON文字列を`Ok()`でラップして返す。失敗した場合は
+    /// エラー情報を`Err()`でラップして返す。
+    ///
+    pub(super) fn status_json(&self) -> Result<String> {
+        let inner = self.lock();
+
+        let status = Status {
+            started: &inner.started,
+            input_path: &inner.input_path,
+            input_available: inner.input_available,
+            paused: self.pause.is_paused(),
+            activity: &inner.activity,
+            runs: inner.run_count,
+            last_run: inner.runs.back(),
+        };
+
+        Ok(serde_json::to_string(&status)?)
+    }
+
+    ///
+    /// 実行履歴をJSON文字列で取得する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は新しい順に並べた実行履歴のJSON文字列を`Ok()`でラッ
+    /// プして返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    pub(super) fn runs_json(&self) -> Result<String> {
+        let inner = self.lock();
+        let runs = inner.runs.iter().rev().collect::<Vec<_>>();
+
+        Ok(serde_json::to_string(&runs)?)
+    }
+
+    ///
+    /// エラー履歴をJSON文字列で取得する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は新しい順に並べたエラー履歴のJSON文字列を`Ok()`で
+    /// ラップして返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    pub(super) fn errors_json(&self) -> Result<String> {
+        let inner = self.lock();
+        let errors = inner.errors.iter().rev().collect::<Vec<_>>();
+
+        Ok(serde_json::to_string(&errors)?)
+    }
+
+    ///
+    /// 直近の取り込みの開始日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 開始日時(ISO8601、取り込みを行っていない場合はNone)
+    ///
+    pub(super) fn last_started(&self) -> Option<String> {
+        self.lock().runs.back().map(|run| run.started.clone())
+    }
+
+    ///
+    /// プレビューの対象のファイルの一覧を取得する
+    ///
+    /// # 戻り値
+    /// 直近の取り込みでコピーしたファイルの振り分け先の一覧（コピーした順）
+    ///
+    pub(super) fn previews(&self) -> Vec<PathBuf> {
+        self.lock().previews.clone()
+    }
+}
+
+impl Inner {
+    ///
+    /// エラー履歴にエラーを追加する
+    ///
+    /// # 引数
+    /// * `record` - 追加するエラー
+    ///
+    /// # 注記
+    /// 保持件数の上限を超えた場合は古いものから破棄する。
+    ///
+    fn push_error(&mut self, record: ErrorRecord) {
+        if self.errors.len() >= MAX_ERRORS {
+            self.errors.pop_front();
+        }
+
+        self.errors.push_back(record);
+    }
+}
@@ -0,0 +1,124 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 入力ディレクトリとキャッシュの差分表示を行うモジュール
+//!
+
+use anyhow::{anyhow, Result};
+use log::error;
+
+use crate::cache::{Cache, DiffStatus, ExifCache};
+use crate::cmd_args::{DiffArgs, Options};
+use crate::{input_files, is_target_file};
+
+///
+/// 差分の件数を集計する構造体
+///
+#[derive(Debug, Default)]
+struct DiffCount {
+    /// キャッシュに記録されていないファイル数
+    new: usize,
+
+    /// キャッシュの記録と異なるファイル数
+    changed: usize,
+
+    /// キャッシュの記録と一致するファイル数
+    unchanged: usize,
+
+    /// 判定に失敗したファイル数
+    failed: usize,
+}
+
+///
+/// `diff`サブコマンドの実行関数
+///
+/// # 引数
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// ファイルのコピーおよびキャッシュの更新は一切行わない。ファイルごとの判定結
+/// 果を入力ディレクトリからの相対パスとともに表示し、最後に件数を表示する。
+///
+pub(crate) fn run(args: &DiffArgs, opts: &Options) -> Result<()> {
+    if !args.input_path.is_dir() {
+        return Err(anyhow!(
+            "{} is not directory",
+            args.input_path.display()
+        ));
+    }
+
+    let opts = opts.with_input_path(args.input_path.clone());
+    let cache = Cache::open(
+        opts.cache_db_path(),
+        opts.cache_eval_mode(),
+        opts.input_path(),
+    )?;
+
+    let mut count = DiffCount::default();
+    let exif_cache = ExifCache::new(&opts);
+
+    for entry in input_files(&opts, &exif_cache) {
+        let path = entry.path();
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        // 取り込み対象外の形式は表示しない
+        if !is_target_file(&ext, &opts) {
+            continue;
+        }
+
+        let name = path.strip_prefix(&args.input_path).unwrap_or(path);
+
+        let status = entry
+            .metadata()
+            .map_err(|err| anyhow!("{}", err))
+            .and_then(|meta| cache.diff(path, &meta, &exif_cache));
+
+        match status {
+            Ok(DiffStatus::New) => {
+                println!("new        {}", name.display());
+                count.new += 1;
+            }
+
+            Ok(DiffStatus::Changed(reasons)) => {
+                println!(
+                    "changed    {} ({})",
+                    name.display(),
+                    reasons.join(", ")
+                );
+                count.changed += 1;
+            }
+
+            Ok(DiffStatus::Unchanged) => {
+                println!("unchanged  {}", name.display());
+                count.unchanged += 1;
+            }
+
+            Err(err) => {
+                error!("diff failed {}: {}", path.display(), err);
+                count.failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "new: {}, changed: {}, unchanged: {}, failed: {}",
+        count.new,
+        count.changed,
+        count.unchanged,
+        count.failed
+    );
+
+    Ok(())
+}
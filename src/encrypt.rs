@@ -0,0 +1,453 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先へのファイルの暗号化格納を行うモジュール
+//!
+
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use crate::cmd_args::{EncryptTool, Options};
+use crate::external::execute;
+use crate::mkdir;
+
+/// 暗号化名と元のファイル名の対応を記録するマニフェストファイルの名前(暗号
+/// 化前のもの)
+const MANIFEST_NAME: &str = "ENCRYPTED.tsv";
+
+/// 暗号化名を求める際の鍵を格納するファイルの名前
+const KEY_NAME: &str = "encrypt-name.key";
+
+/// 振り分け先ごとのマニフェストの控えを格納するディレクトリの名前
+const MANIFEST_DIR: &str = "encrypted";
+
+/// 暗号化名に用いるハッシュ値の桁数
+const NAME_LEN: usize = 32;
+
+///
+/// 今回の取り込みでマニフェストに行を追加した振り分け先の一覧
+///
+#[derive(Debug, Default)]
+pub(crate) struct PendingManifests {
+    /// 暗号化したマニフェストの書き出しを待つ振り分け先のディレクトリ
+    dirs: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl PendingManifests {
+    ///
+    /// 振り分け先の一覧のロック
+    ///
+    /// # 戻り値
+    /// 振り分け先の一覧のガードオブジェクト
+    ///
+    fn lock(&self) -> MutexGuard<'_, BTreeSet<PathBuf>> {
+        self.dirs.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+///
+/// ファイルを暗号化して振り分け先に格納する
+///
+/// # 引数
+/// * `src` - 格納するファイルのパス
+/// * `dir` - 振り分け先のディレクトリ
+/// * `name` - 振り分け先でのファイル名(暗号化前のもの)
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は格納した暗号化ファイルのパスを`Ok()`でラップして返す。
+/// 失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 平文が振り分け先に書き込まれることが無いよう、入力ファイルから直接暗号化す
+/// る。暗号化名は元のファイル名から鍵付きハッシュで決定的に求めるため、同じ
+/// ファイルを再処理した場合は同じ暗号化ファイルを置き換える。
+///
+pub(crate) fn distribute(src: &Path, dir: &Path, name: &str, opts: &Options)
+    -> Result<PathBuf>
+{
+    let recipient = match opts.encrypt_recipient() {
+        Some(recipient) => recipient,
+        None => return Err(anyhow!("encryption recipient is not specified")),
+    };
+
+    /*
+     * 振り分け先ディレクトリの作成
+     */
+    mkdir::ensure(dir, opts)?;
+
+    /*
+     * 暗号化(一時ファイルに書き出してからリネームする)
+     */
+    let tool = opts.encrypt_tool();
+    let enc_name = encrypted_name(name, opts);
+    let dst = dir.join(&enc_name);
+
+    encrypt_to(src, &dst, tool, &recipient)?;
+
+    /*
+     * マニフェストへの記録(暗号化は取り込みの終了時にまとめて行う)
+     */
+    update_manifest(dir, &enc_name, name, opts)?;
+
+    info!("encrypted {} to {}", src.display(), dst.display());
+
+    Ok(dst)
+}
+
+///
+/// 暗号化ファイルの名前を求める
+///
+/// # 引数
+/// * `name` - 元のファイル名
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 元のファイル名の鍵付きハッシュ値にツールごとの拡張子を付与した名前
+///
+/// # 注記
+/// 鍵はステートディレクトリに保持する秘密の値のため、ファイル名の候補から暗
+/// 号化名を求めて照合することはできない。
+///
+pub(crate) fn encrypted_name(name: &str, opts: &Options) -> String {
+    let hash = keyed_hash(&opts.encrypt_name_key(), name.as_bytes());
+
+    format!("{}.{}", &hash[..NAME_LEN], extension(opts.encrypt_tool()))
+}
+
+///
+/// 暗号化名を求める際の鍵を読み出す
+///
+/// # 引数
+/// * `state_dir` - ステートディレクトリのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は鍵を`Ok()`でラップして返す。失敗した場合はエラー情報
+/// を`Err()`でラップして返す。
+///
+/// # 注記
+/// 鍵のファイルが無い場合は乱数で生成して作成する(Unixでは所有者のみ読み書
+/// きできるようにする)。鍵を失うと既存の暗号化ファイルを再処理した際に別の
+/// 名前で格納されるため、ステートディレクトリごと保全すること。
+///
+pub(crate) fn load_name_key(state_dir: &Path) -> Result<[u8; 32]> {
+    let path = state_dir.join(KEY_NAME);
+
+    /*
+     * 鍵の生成(既にある場合は読み出す)
+     */
+    if !path.exists() {
+        let mut key = [0u8; 32];
+
+        if let Err(err) = getrandom::getrandom(&mut key) {
+            return Err(anyhow!("generate name key failed: {}", err));
+        }
+
+        fs::create_dir_all(state_dir)?;
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        match options.open(&path) {
+            Ok(mut file) => {
+                writeln!(file, "{}", to_hex(&key))?;
+                file.sync_all()?;
+                info!("created name key {}", path.display());
+                return Ok(key);
+            }
+
+            // 他のプロセスが先に作成した場合はそちらを用いる
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+
+            Err(err) => {
+                return Err(anyhow!(
+                    "create {} failed: {}",
+                    path.display(),
+                    err
+                ));
+            }
+        }
+    }
+
+    /*
+     * 鍵の読み出し
+     */
+    let text = fs::read_to_string(&path)?;
+    let text = text.trim();
+    let mut key = [0u8; 32];
+
+    if text.len() != key.len() * 2 || !text.is_ascii() {
+        return Err(anyhow!("{} is not valid name key", path.display()));
+    }
+
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = match u8::from_str_radix(&text[i * 2..i * 2 + 2], 16) {
+            Ok(byte) => byte,
+            Err(_) => {
+                return Err(anyhow!("{} is not valid name key", path.display()));
+            }
+        };
+    }
+
+    Ok(key)
+}
+
+///
+/// ファイルを暗号化して書き出す
+///
+/// # 引数
+/// * `src` - 暗号化するファイルのパス
+/// * `dst` - 暗号化ファイルのパス
+/// * `tool` - 暗号化に用いるツール
+/// * `recipient` - 暗号化の受信者
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 一時ファイルに書き出してから名前を変更する。gpgでは受信者の鍵が信頼されて
+/// いない場合は暗号化に失敗する(信頼モデルの指定は変更しない)。
+///
+fn encrypt_to(src: &Path, dst: &Path, tool: EncryptTool, recipient: &str)
+    -> Result<()>
+{
+    let mut tmp = dst.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut command = match tool {
+        EncryptTool::Age => {
+            let mut command = Command::new("age");
+            command
+                .arg("--encrypt")
+                .arg("--recipient")
+                .arg(recipient)
+                .arg("--output")
+                .arg(&tmp)
+                .arg(src);
+            command
+        }
+
+        EncryptTool::Gpg => {
+            let mut command = Command::new("gpg");
+            command
+                .args(["--batch", "--yes"])
+                .arg("--recipient")
+                .arg(recipient)
+                .arg("--output")
+                .arg(&tmp)
+                .arg("--encrypt")
+                .arg(src);
+            command
+        }
+    };
+
+    if let Err(err) = execute(&mut command) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+
+    fs::rename(&tmp, dst)?;
+
+    Ok(())
+}
+
+///
+/// 暗号化名と元のファイル名の対応をマニフェストの控えに記録する
+///
+/// # 引数
+/// * `dir` - 振り分け先のディレクトリ
+/// * `enc_name` - 暗号化ファイルの名前
+/// * `name` - 元のファイル名
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// マニフェストは1行1ファイルのタブ区切り形式(暗号化名、元のファイル名)とす
+/// る。平文の控えはステートディレクトリに置いて行を追記するのみとし、振り分
+/// け先の暗号化したマニフェストは`finalize()`でまとめて書き出す。
+///
+fn update_manifest(dir: &Path, enc_name: &str, name: &str, opts: &Options)
+    -> Result<()>
+{
+    let copy = manifest_copy(dir, opts);
+
+    // 並行して処理するファイルの行が混ざらないよう、追記の間はロックを保持
+    // する
+    let mut pending = opts.pending_manifests().lock();
+
+    if let Some(store) = copy.parent() {
+        fs::create_dir_all(store)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&copy)?;
+    writeln!(file, "{}\t{}", enc_name, name)?;
+
+    pending.insert(dir.to_path_buf());
+
+    Ok(())
+}
+
+///
+/// 今回の取り込みで更新したマニフェストを暗号化して振り分け先に書き出す
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 取り込みの終了時に振り分け先ごとに1回だけ呼び出すツールを起動する。控え
+/// は同じ暗号化名の行を最後のものだけに詰めてから暗号化し、一時ファイルに書
+/// き出した上でリネームする(途中で中断しても書きかけのマニフェストが残らな
+/// い)。書き出せなかった振り分け先の行は控えに残り、次にその振り分け先へ格
+/// 納した際に改めて書き出す。
+///
+pub(crate) fn finalize(opts: &Options) -> Result<()> {
+    let dirs = std::mem::take(&mut *opts.pending_manifests().lock());
+
+    let recipient = match opts.encrypt_recipient() {
+        Some(recipient) => recipient,
+        None => return Ok(()),
+    };
+
+    let tool = opts.encrypt_tool();
+
+    for dir in dirs {
+        let copy = manifest_copy(&dir, opts);
+
+        /*
+         * 控えの整理
+         */
+        let mut lines = Vec::<&str>::new();
+        let text = fs::read_to_string(&copy)?;
+
+        for line in text.lines().filter(|line| !line.is_empty()) {
+            let enc_name = line.split('\t').next();
+            lines.retain(|known| known.split('\t').next() != enc_name);
+            lines.push(line);
+        }
+
+        let mut body = lines.join("\n");
+        body.push('\n');
+
+        let mut tmp = copy.as_os_str().to_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        fs::write(&tmp, body)?;
+        fs::rename(&tmp, &copy)?;
+
+        /*
+         * 暗号化したマニフェストの書き出し
+         */
+        let name = format!("{}.{}", MANIFEST_NAME, extension(tool));
+        let manifest = dir.join(name);
+
+        encrypt_to(&copy, &manifest, tool, &recipient)?;
+
+        info!("manifest written to {}", manifest.display());
+    }
+
+    Ok(())
+}
+
+///
+/// 今回の取り込みでマニフェストに行を追加した振り分け先の一覧を破棄する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 注記
+/// 取り込みの開始時に呼び出す(前回の取り込みが`finalize()`に至らずに終わっ
+/// た場合の一覧を持ち越さないため)。
+///
+pub(crate) fn reset(opts: &Options) {
+    opts.pending_manifests().lock().clear();
+}
+
+///
+/// 振り分け先のマニフェストの平文の控えのパスを求める
+///
+/// # 引数
+/// * `dir` - 振り分け先のディレクトリ
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// ステートディレクトリ内の控えのパス(振り分け先のパスの鍵付きハッシュ値を
+/// 名前とする)
+///
+fn manifest_copy(dir: &Path, opts: &Options) -> PathBuf {
+    let key = opts.encrypt_name_key();
+    let dir_hash = keyed_hash(&key, dir.as_os_str().as_encoded_bytes());
+
+    opts.state_dir()
+        .join(MANIFEST_DIR)
+        .join(format!("{}.tsv", dir_hash))
+}
+
+///
+/// 暗号化ファイルに付与する拡張子を求める
+///
+/// # 引数
+/// * `tool` - 暗号化に用いるツール
+///
+/// # 戻り値
+/// ツールごとの拡張子
+///
+fn extension(tool: EncryptTool) -> &'static str {
+    match tool {
+        EncryptTool::Age => "age",
+        EncryptTool::Gpg => "gpg",
+    }
+}
+
+///
+/// 鍵付きハッシュ値を求める
+///
+/// # 引数
+/// * `key` - 鍵
+/// * `data` - 対象のデータ
+///
+/// # 戻り値
+/// BLAKE3の鍵付きハッシュ値の16進表記
+///
+fn keyed_hash(key: &[u8; 32], data: &[u8]) -> String {
+    blake3::keyed_hash(key, data).to_hex().to_string()
+}
+
+///
+/// バイト列を16進表記の文字列に変換する
+///
+/// # 引数
+/// * `bytes` - 変換するバイト列
+///
+/// # 戻り値
+/// 16進表記の文字列
+///
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,256 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! ファイルの取り込みの失敗を種別ごとに表すエラー型を定義するモジュール
+//!
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+///
+/// 取り込みの失敗の種別
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    /// ファイルの読み書きに失敗した
+    Io,
+
+    /// Exif情報の読み込みに失敗した
+    Exif,
+
+    /// キャッシュデータベースの操作に失敗した
+    Cache,
+
+    /// 振り分け先の空き容量が不足している
+    DestinationFull,
+
+    /// 振り分け先に同名のファイルが存在する
+    Conflict,
+
+    /// 上記以外
+    Other,
+}
+
+impl ErrorKind {
+    ///
+    /// 種別の名称を返す
+    ///
+    /// # 戻り値
+    /// レポートに記録するものと同じ名称
+    ///
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Io => "io",
+            Self::Exif => "exif",
+            Self::Cache => "cache",
+            Self::DestinationFull => "destination-full",
+            Self::Conflict => "conflict",
+            Self::Other => "other",
+        }
+    }
+}
+
+// Displayトレイトの実装
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+///
+/// ファイルの取り込みの失敗を表すエラー
+///
+#[derive(Debug)]
+pub enum ImportError {
+    /// ファイルの読み書きの失敗
+    Io {
+        /// 失敗した操作の説明
+        context: String,
+
+        /// 元のエラー
+        source: io::Error,
+    },
+
+    /// Exif情報の読み込みの失敗
+    Exif {
+        /// 対象ファイルのパス
+        path: PathBuf,
+
+        /// 元のエラーのメッセージ
+        message: String,
+    },
+
+    /// キャッシュデータベースの操作の失敗
+    Cache(String),
+
+    /// 振り分け先の空き容量の不足
+    DestinationFull {
+        /// 失敗した操作の説明
+        context: String,
+
+        /// 元のエラー
+        source: io::Error,
+    },
+
+    /// 振り分け先に同名のファイルが存在する
+    Conflict(PathBuf),
+}
+
+impl ImportError {
+    ///
+    /// 入出力のエラーから構築する
+    ///
+    /// # 引数
+    /// * `context` - 失敗した操作の説明
+    /// * `source` - 元のエラー
+    ///
+    /// # 戻り値
+    /// 空き容量の不足によるものであれば`DestinationFull`、それ以外は`Io`
+    ///
+    pub(crate) fn io<S>(context: S, source: io::Error) -> Self
+    where
+        S: Into<String>,
+    {
+        let context = context.into();
+
+        if is_storage_full(&source) {
+            Self::DestinationFull { context, source }
+        } else {
+            Self::Io { context, source }
+        }
+    }
+
+    ///
+    /// 失敗の種別を返す
+    ///
+    /// # 戻り値
+    /// 失敗の種別
+    ///
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io { .. } => ErrorKind::Io,
+            Self::Exif { .. } => ErrorKind::Exif,
+            Self::Cache(_) => ErrorKind::Cache,
+            Self::DestinationFull { .. } => ErrorKind::DestinationFull,
+            Self::Conflict(_) => ErrorKind::Conflict,
+        }
+    }
+}
+
+// Displayトレイトの実装
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { context, source } => {
+                write!(f, "{}: {}", context, source)
+            }
+
+            Self::Exif { path, message } => {
+                write!(f, "read exif failed {}: {}", path.display(), message)
+            }
+
+            Self::Cache(message) => write!(f, "cache error: {}", message),
+
+            Self::DestinationFull { context, source } => {
+                write!(f, "{}: destination full ({})", context, source)
+            }
+
+            Self::Conflict(path) => {
+                write!(f, "destination already exists: {}", path.display())
+            }
+        }
+    }
+}
+
+// Errorトレイトの実装
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::DestinationFull { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+///
+/// 空き容量の不足によるエラーか否かを判定する
+///
+/// # 引数
+/// * `err` - 入出力のエラー
+///
+/// # 戻り値
+/// 空き容量またはクォータの不足によるものであれば`true`
+///
+fn is_storage_full(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded
+    )
+}
+
+///
+/// エラーの種別を判定する
+///
+/// # 引数
+/// * `err` - 判定するエラー
+///
+/// # 戻り値
+/// 失敗の種別
+///
+/// # 注記
+/// `ImportError`で返されたものはその種別とする。それ以外はエラーの連鎖を辿
+/// り、キャッシュデータベース(redb)、Exif情報の読み込み、入出力のエラーを
+/// `?`で伝搬したものをそれぞれの種別とし、いずれでもなければ`Other`とする。
+///
+pub(crate) fn classify(err: &anyhow::Error) -> ErrorKind {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<ImportError>() {
+            return err.kind();
+        }
+
+        if is_cache_error(cause) {
+            return ErrorKind::Cache;
+        }
+
+        if cause.is::<exif::Error>() {
+            return ErrorKind::Exif;
+        }
+
+        if let Some(err) = cause.downcast_ref::<io::Error>() {
+            return if is_storage_full(err) {
+                ErrorKind::DestinationFull
+            } else {
+                ErrorKind::Io
+            };
+        }
+    }
+
+    ErrorKind::Other
+}
+
+///
+/// キャッシュデータベースのエラーか否かを判定する
+///
+/// # 引数
+/// * `err` - 判定するエラー
+///
+/// # 戻り値
+/// redbのエラー型であれば`true`
+///
+fn is_cache_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    err.is::<redb::Error>()
+        || err.is::<redb::DatabaseError>()
+        || err.is::<redb::TransactionError>()
+        || err.is::<redb::TableError>()
+        || err.is::<redb::StorageError>()
+        || err.is::<redb::CommitError>()
+}
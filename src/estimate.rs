@@ -0,0 +1,313 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 取り込み内容の見積もりを行うモジュール
+//!
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use log::{debug, error};
+
+use crate::cache::{Cache, CacheDecision, ExifCache};
+use crate::cmd_args::Options;
+use crate::exif_view::ExifView;
+use crate::marker;
+use crate::report::FileTimings;
+use crate::template::PathContext;
+use crate::{
+    build_file_type, input_files, is_date_in_range, is_excluded_camera,
+    is_target_file, original_dir, resolve_datetime, shooting_date, src_dir,
+};
+
+///
+/// ファイル数とサイズの集計値
+///
+#[derive(Debug, Default, Clone, Copy)]
+struct Tally {
+    /// ファイル数
+    files: u64,
+
+    /// 合計サイズ(バイト)
+    bytes: u64,
+}
+
+impl Tally {
+    ///
+    /// ファイルを1件集計に加える
+    ///
+    /// # 引数
+    /// * `bytes` - ファイルサイズ(バイト)
+    ///
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+///
+/// 見積もり結果を集約する構造体
+///
+#[derive(Debug, Default)]
+struct Estimate {
+    /// 撮影日とファイルタイプごとの取り込み予定
+    planned: BTreeMap<(NaiveDate, &'static str), Tally>,
+
+    /// 処理済み(キャッシュヒットまたは取り込みマーカー以前)のファイル
+    processed: Tally,
+
+    /// 取り込み対象外(日付範囲外・撮影日時なし・除外リスト・向き)のファイル
+    skipped: Tally,
+}
+
+///
+/// 見積もりモードの実行関数
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// ファイルのコピーおよびキャッシュの更新は一切行わない。Exif情報の読み出しは
+/// キャッシュにヒットしなかったファイルに限られる。
+///
+pub(crate) fn run(opts: &Options) -> Result<()> {
+    let cache = opts.cache();
+    let mut estimate = Estimate::default();
+    let high_water = if opts.is_card_marker() {
+        marker::read(&opts.input_path())?
+    } else {
+        None
+    };
+    let exif_cache = ExifCache::new(opts);
+
+    for entry in input_files(opts, &exif_cache) {
+        let meta = entry.metadata()?;
+
+        if let Err(err) = tally_file(
+            entry.path(),
+            meta,
+            opts,
+            cache.as_ref(),
+            &exif_cache,
+            high_water,
+            &mut estimate,
+        ) {
+            error!("{}", err);
+        }
+    }
+
+    print_estimate(&estimate);
+
+    Ok(())
+}
+
+///
+/// ファイル1件分の見積もりを集計に加える
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `meta` - 対象ファイルのメタデータ
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュデータベース
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+/// * `high_water` - 取り込みマーカーの更新日時の上限(マーカーが無い場合は
+///   None)
+/// * `estimate` - 集計先
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn tally_file(
+    path: &Path,
+    meta: std::fs::Metadata,
+    opts: &Options,
+    cache: &Cache,
+    exif_cache: &ExifCache,
+    high_water: Option<SystemTime>,
+    estimate: &mut Estimate,
+) -> Result<()> {
+    let size = meta.len();
+    let ext = match path.extension() {
+        Some(ext) => ext.to_string_lossy().to_string(),
+        None => return Ok(()),
+    };
+
+    // 対応していない形式はExifを読む前に除外する
+    if !is_target_file(&ext, opts) {
+        return Ok(());
+    }
+
+    if let Some(list) = opts.exclude_list() {
+        if list.contains(path)? {
+            estimate.skipped.add(size);
+            return Ok(());
+        }
+    }
+
+    if high_water.is_some_and(|high_water| {
+        meta.modified().is_ok_and(|mtime| mtime <= high_water)
+    }) {
+        estimate.processed.add(size);
+        return Ok(());
+    }
+
+    match cache.evaluate(path, meta, exif_cache, &mut FileTimings::default())? {
+        CacheDecision::Hit => estimate.processed.add(size),
+
+        CacheDecision::Miss { mut handle, exif } => {
+            if opts.history().is_some_and(|history| history.contains(&handle)) {
+                estimate.processed.add(size);
+                return Ok(());
+            }
+
+            let algo = opts.checksum_algorithm();
+
+            if opts.is_hash_index()
+                && cache.find_by_hash(&mut handle, path, algo)?.is_some()
+            {
+                estimate.processed.add(size);
+                return Ok(());
+            }
+
+            if opts.is_dedup()
+                && cache.find_in_archive(&mut handle, path, algo)?.is_some()
+            {
+                estimate.processed.add(size);
+                return Ok(());
+            }
+
+            let datetime = match resolve_datetime(&exif, path, opts)? {
+                Some(datetime) => datetime,
+
+                None => {
+                    estimate.skipped.add(size);
+                    return Ok(());
+                }
+            };
+
+            let view = ExifView::from(&*exif);
+
+            if opts.orientation().is_some_and(|wanted| {
+                view.orientation() != Some(wanted)
+            }) {
+                estimate.skipped.add(size);
+                return Ok(());
+            }
+
+            if is_excluded_camera(&view, opts) {
+                estimate.skipped.add(size);
+                return Ok(());
+            }
+
+            let date = shooting_date(&datetime, opts);
+
+            if !is_date_in_range(&date, opts) {
+                debug!("out of range: {} ({})", path.display(), date);
+                estimate.skipped.add(size);
+                return Ok(());
+            }
+
+            let ctx = PathContext {
+                date,
+                artist: view.artist.as_deref(),
+                event: opts.event_at(&datetime),
+                camera: view.camera_name(),
+                ext: &ext,
+                original_dir: original_dir(path, opts),
+                src_dir: src_dir(path),
+                volume_label: cache.volume_label(),
+            };
+
+            if let Some(file_type) = build_file_type(&ext, &ctx, opts) {
+                estimate
+                    .planned
+                    .entry((date, file_type.label()))
+                    .or_default()
+                    .add(size);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// 見積もり結果を標準出力に表示する
+///
+/// # 引数
+/// * `estimate` - 表示する見積もり結果
+///
+fn print_estimate(estimate: &Estimate) {
+    let mut total = Tally::default();
+
+    println!("{:<10}  {:<5}  {:>8}  {:>10}", "date", "type", "files", "size");
+
+    for ((date, label), tally) in &estimate.planned {
+        println!(
+            "{:<10}  {:<5}  {:>8}  {:>10}",
+            date.format("%Y-%m-%d"),
+            label,
+            tally.files,
+            format_size(tally.bytes),
+        );
+
+        total.files += tally.files;
+        total.bytes += tally.bytes;
+    }
+
+    println!();
+    println!(
+        "to be imported:  {} files ({})",
+        total.files,
+        format_size(total.bytes)
+    );
+    println!(
+        "already done:    {} files ({})",
+        estimate.processed.files,
+        format_size(estimate.processed.bytes)
+    );
+    println!(
+        "out of target:   {} files ({})",
+        estimate.skipped.files,
+        format_size(estimate.skipped.bytes)
+    );
+}
+
+///
+/// バイト数を人間が読みやすい形式に整形する
+///
+/// # 引数
+/// * `bytes` - バイト数
+///
+/// # 戻り値
+/// 2進接頭辞付きの文字列
+///
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
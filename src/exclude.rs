@@ -0,0 +1,118 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 受け渡し済みのファイルの一覧(除外リスト)を取り扱うモジュール
+//!
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::checksum;
+use crate::cmd_args::ChecksumAlgorithm;
+
+///
+/// 読み込んだ除外リスト
+///
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExcludeList {
+    /// 除外するファイル名(小文字に揃えたもの)
+    names: HashSet<String>,
+
+    /// 除外するファイルの内容のハッシュ値(SHA-256の16進表記、小文字)
+    hashes: HashSet<String>,
+}
+
+impl ExcludeList {
+    ///
+    /// 除外リストのファイルを読み込む
+    ///
+    /// # 引数
+    /// * `path` - 除外リストのファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は読み込んだ除外リストを`Ok()`でラップして返す。失敗
+    /// した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 1行に1件、ファイル名またはSHA-256のハッシュ値を記述する。空行と`#`で始
+    /// まる行は無視する。ディレクトリを含むパスが記述された場合はファイル名の
+    /// 部分のみを用いる。
+    ///
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                return Err(anyhow!("read {} failed: {}", path.display(), err));
+            }
+        };
+
+        let mut list = Self::default();
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if is_sha256(line) {
+                list.hashes.insert(line.to_lowercase());
+            } else if let Some(name) = Path::new(line).file_name() {
+                list.names.insert(name.to_string_lossy().to_lowercase());
+            }
+        }
+
+        Ok(list)
+    }
+
+    ///
+    /// ファイルが除外リストに含まれるか否かを判定する
+    ///
+    /// # 引数
+    /// * `path` - 対象ファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は、ファイル名またはハッシュ値が一致すれば`true`を
+    /// `Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラップして返
+    /// す。
+    ///
+    /// # 注記
+    /// ファイル名の比較では大文字と小文字を区別しない。ハッシュ値の計算はファ
+    /// イルの全体を読み出すため、リストにハッシュ値が含まれる場合のみ行う。
+    ///
+    pub(crate) fn contains(&self, path: &Path) -> Result<bool> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if self.names.contains(&name) {
+            return Ok(true);
+        }
+
+        if self.hashes.is_empty() {
+            return Ok(false);
+        }
+
+        let hash = checksum::digest_file(path, ChecksumAlgorithm::Sha256)?;
+
+        Ok(self.hashes.contains(&hash))
+    }
+}
+
+///
+/// SHA-256のハッシュ値の16進表記か否かを判定する
+///
+/// # 引数
+/// * `s` - 判定する文字列
+///
+/// # 戻り値
+/// 64桁の16進数の場合は`true`
+///
+fn is_sha256(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
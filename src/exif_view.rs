@@ -0,0 +1,399 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! Exif情報から振り分けやフィルタに用いる項目を型付きで取り出すモジュール
+//!
+
+use std::cmp::Ordering;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use exif::{Context, Exif, Field, In, Tag, Value};
+
+use crate::cmd_args::Orientation;
+
+/// 所有者名のタグ（標準タグ定義に含まれないため個別に定義）
+const TAG_OWNER_NAME: Tag = Tag(Context::Exif, 0xfde8);
+
+///
+/// GPSによる撮影位置
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GpsPosition {
+    /// 緯度(度、北緯が正)
+    pub(crate) latitude: f64,
+
+    /// 経度(度、東経が正)
+    pub(crate) longitude: f64,
+
+    /// 高度(メートル、海面下が負、記録されていない場合はNone)
+    pub(crate) altitude: Option<f64>,
+}
+
+///
+/// Exif情報から取り出した項目をまとめた構造体
+///
+/// # 注記
+/// テンプレート、フィルタ、レポート等で用いる項目の取り出しをここに集約する。
+/// 文字列の項目は表示用の引用符を含まず、前後の空白とNUL文字を取り除いたもの
+/// とする(空の場合はNone)。
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ExifView {
+    /// 撮影時刻の秒未満(SubSecTimeOriginal)
+    pub(crate) subsec: Option<String>,
+
+    /// 撮影日時のタイムゾーン(OffsetTimeOriginal)
+    pub(crate) offset_time: Option<FixedOffset>,
+
+    /// メーカー名(Make)
+    pub(crate) make: Option<String>,
+
+    /// 機種名(Model)
+    pub(crate) model: Option<String>,
+
+    /// カメラのシリアル番号(BodySerialNumber)
+    pub(crate) serial: Option<String>,
+
+    /// レンズのメーカー名(LensMake)
+    pub(crate) lens_make: Option<String>,
+
+    /// レンズの機種名(LensModel)
+    pub(crate) lens_model: Option<String>,
+
+    /// ISO感度(PhotographicSensitivity)
+    pub(crate) iso: Option<u32>,
+
+    /// 露出時間(秒、ExposureTimeの分子と分母)
+    pub(crate) exposure_time: Option<(u32, u32)>,
+
+    /// F値(FNumber)
+    pub(crate) f_number: Option<f64>,
+
+    /// 焦点距離(ミリメートル、FocalLength)
+    pub(crate) focal_length: Option<f64>,
+
+    /// 35mm換算の焦点距離(ミリメートル、FocalLengthIn35mmFilm)
+    pub(crate) focal_length_35mm: Option<u32>,
+
+    /// 撮影位置(GPSLatitude/GPSLongitude/GPSAltitude)
+    pub(crate) gps: Option<GpsPosition>,
+
+    /// GPS時刻(GPSDateStamp/GPSTimeStamp、UTC)
+    pub(crate) gps_time: Option<DateTime<Utc>>,
+
+    /// 撮影者名(Artist、OwnerName、CameraOwnerNameの順で最初に得られたもの)
+    pub(crate) artist: Option<String>,
+
+    /// 画素数(幅と高さ、Orientationによる回転を適用したもの)
+    pub(crate) dimensions: Option<(u32, u32)>,
+}
+
+impl ExifView {
+    ///
+    /// メーカー名と機種名を連結した文字列を返す
+    ///
+    /// # 戻り値
+    /// "Make/Model"の形式の文字列(いずれかのみ記録されている場合はその値、何
+    /// れも記録されていない場合はNone)
+    ///
+    pub(crate) fn camera(&self) -> Option<String> {
+        match (&self.make, &self.model) {
+            (Some(make), Some(model)) => Some(format!("{}/{}", make, model)),
+            (Some(make), None) => Some(make.clone()),
+            (None, Some(model)) => Some(model.clone()),
+            (None, None) => None,
+        }
+    }
+
+    ///
+    /// パステンプレートに用いるカメラの機種名を返す
+    ///
+    /// # 戻り値
+    /// Modelを優先し、記録されていない場合はMakeを返す(何れも記録されていな
+    /// い場合はNone)
+    ///
+    pub(crate) fn camera_name(&self) -> Option<&str> {
+        self.model.as_deref().or(self.make.as_deref())
+    }
+
+    ///
+    /// レンズ名を返す
+    ///
+    /// # 戻り値
+    /// LensModelを優先し、記録されていない場合はLensMakeを返す(何れも記録さ
+    /// れていない場合はNone)
+    ///
+    pub(crate) fn lens(&self) -> Option<&str> {
+        self.lens_model.as_deref().or(self.lens_make.as_deref())
+    }
+
+    ///
+    /// 露出時間を表示用の文字列で返す
+    ///
+    /// # 戻り値
+    /// 1秒未満は"1/250"、1秒以上は"2"や"2.5"の形式の文字列(記録されていない
+    /// 場合はNone)
+    ///
+    pub(crate) fn exposure(&self) -> Option<String> {
+        let (num, denom) = self.exposure_time?;
+
+        if num == 0 || denom == 0 {
+            return None;
+        }
+
+        if num < denom {
+            Some(format!("1/{}", (denom as f64 / num as f64).round()))
+        } else {
+            let secs = num as f64 / denom as f64;
+            Some(format!("{}", (secs * 10.0).round() / 10.0))
+        }
+    }
+
+    ///
+    /// 画像の向きを返す
+    ///
+    /// # 戻り値
+    /// 画像の向き。画素数が記録されていない場合や正方形の場合はNone。
+    ///
+    pub(crate) fn orientation(&self) -> Option<Orientation> {
+        let (width, height) = self.dimensions?;
+
+        match width.cmp(&height) {
+            Ordering::Less => Some(Orientation::Portrait),
+            Ordering::Greater => Some(Orientation::Landscape),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+// トレイトFrom<&Exif>の実装
+impl From<&Exif> for ExifView {
+    fn from(exif: &Exif) -> Self {
+        let offset_time = ascii(exif, Tag::OffsetTimeOriginal)
+            .and_then(|offset| offset.parse::<FixedOffset>().ok());
+
+        let exposure_time = field(exif, Tag::ExposureTime)
+            .and_then(|field| match field.value {
+                Value::Rational(ref values) => values
+                    .first()
+                    .map(|value| (value.num, value.denom)),
+                _ => None,
+            });
+
+        Self {
+            subsec: ascii(exif, Tag::SubSecTimeOriginal),
+            offset_time,
+            make: ascii(exif, Tag::Make),
+            model: ascii(exif, Tag::Model),
+            serial: ascii(exif, Tag::BodySerialNumber),
+            lens_make: ascii(exif, Tag::LensMake),
+            lens_model: ascii(exif, Tag::LensModel),
+            iso: uint(exif, Tag::PhotographicSensitivity),
+            exposure_time,
+            f_number: rational(exif, Tag::FNumber),
+            focal_length: rational(exif, Tag::FocalLength),
+            focal_length_35mm: uint(exif, Tag::FocalLengthIn35mmFilm),
+            gps: gps_position(exif),
+            gps_time: gps_time(exif),
+            artist: [Tag::Artist, TAG_OWNER_NAME, Tag::CameraOwnerName]
+                .into_iter()
+                .find_map(|tag| ascii(exif, tag)),
+            dimensions: dimensions(exif),
+        }
+    }
+}
+
+///
+/// フィールドを取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+/// * `tag` - 取得するフィールドのタグ
+///
+/// # 戻り値
+/// 主画像のフィールド(記録されていない場合はNone)
+///
+fn field(exif: &Exif, tag: Tag) -> Option<&Field> {
+    exif.get_field(tag, In::PRIMARY)
+}
+
+///
+/// ASCII形式のフィールドの値を取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+/// * `tag` - 取得するフィールドのタグ
+///
+/// # 戻り値
+/// 前後の空白とNUL文字を取り除いた最初の空でない値(得られない場合はNone)
+///
+fn ascii(exif: &Exif, tag: Tag) -> Option<String> {
+    match field(exif, tag)?.value {
+        Value::Ascii(ref values) => values
+            .iter()
+            .map(|value| {
+                String::from_utf8_lossy(value)
+                    .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+                    .to_string()
+            })
+            .find(|value| !value.is_empty()),
+
+        _ => None,
+    }
+}
+
+///
+/// 整数形式のフィールドの値を取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+/// * `tag` - 取得するフィールドのタグ
+///
+/// # 戻り値
+/// 最初の値(記録されていない場合はNone)
+///
+fn uint(exif: &Exif, tag: Tag) -> Option<u32> {
+    field(exif, tag)?.value.get_uint(0)
+}
+
+///
+/// 有理数形式のフィールドの値を取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+/// * `tag` - 取得するフィールドのタグ
+///
+/// # 戻り値
+/// 最初の値を実数に変換したもの(記録されていないか有限の値でない場合はNone)
+///
+fn rational(exif: &Exif, tag: Tag) -> Option<f64> {
+    let value = match field(exif, tag)?.value {
+        Value::Rational(ref values) => values.first()?.to_f64(),
+        Value::SRational(ref values) => values.first()?.to_f64(),
+        _ => return None,
+    };
+
+    Some(value).filter(|value| value.is_finite())
+}
+
+///
+/// 度・分・秒の3つの有理数で記録された角度を取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+/// * `tag` - 取得するフィールドのタグ
+///
+/// # 戻り値
+/// 角度(度、記録されていない場合はNone)
+///
+fn degrees(exif: &Exif, tag: Tag) -> Option<f64> {
+    match field(exif, tag)?.value {
+        Value::Rational(ref values) if values.len() == 3 => {
+            let value = values[0].to_f64()
+                + values[1].to_f64() / 60.0
+                + values[2].to_f64() / 3600.0;
+
+            Some(value).filter(|value| value.is_finite())
+        }
+
+        _ => None,
+    }
+}
+
+///
+/// 撮影位置を取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+///
+/// # 戻り値
+/// 撮影位置(緯度と経度が記録されていない場合はNone)
+///
+fn gps_position(exif: &Exif) -> Option<GpsPosition> {
+    let mut latitude = degrees(exif, Tag::GPSLatitude)?;
+    let mut longitude = degrees(exif, Tag::GPSLongitude)?;
+
+    if ascii(exif, Tag::GPSLatitudeRef).as_deref() == Some("S") {
+        latitude = -latitude;
+    }
+
+    if ascii(exif, Tag::GPSLongitudeRef).as_deref() == Some("W") {
+        longitude = -longitude;
+    }
+
+    // GPSAltitudeRefが1の場合は海面下
+    let altitude = rational(exif, Tag::GPSAltitude).map(|altitude| {
+        match uint(exif, Tag::GPSAltitudeRef) {
+            Some(1) => -altitude,
+            _ => altitude,
+        }
+    });
+
+    Some(GpsPosition {latitude, longitude, altitude})
+}
+
+///
+/// GPS時刻を取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+///
+/// # 戻り値
+/// GPSDateStampとGPSTimeStampから求めたUTCの日時(記録されていない場合はNone)
+///
+fn gps_time(exif: &Exif) -> Option<DateTime<Utc>> {
+    let date = ascii(exif, Tag::GPSDateStamp)?;
+    let date = NaiveDate::parse_from_str(&date, "%Y:%m:%d").ok()?;
+
+    let secs = match field(exif, Tag::GPSTimeStamp)?.value {
+        Value::Rational(ref values) if values.len() == 3 => {
+            values[0].to_f64() * 3600.0
+                + values[1].to_f64() * 60.0
+                + values[2].to_f64()
+        }
+
+        _ => return None,
+    };
+
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+
+    let datetime = date.and_hms_opt(0, 0, 0)?
+        + chrono::Duration::milliseconds((secs * 1000.0).round() as i64);
+
+    Some(Utc.from_utc_datetime(&datetime))
+}
+
+///
+/// 画素数を取得する
+///
+/// # 引数
+/// * `exif` - Exif情報
+///
+/// # 戻り値
+/// 幅と高さの組(記録されていない場合はNone)
+///
+/// # 注記
+/// 画素数はPixelXDimension/PixelYDimensionを優先し、記録されていない場合は
+/// ImageWidth/ImageLengthを用いる。Orientationが90度の回転を示す場合(5〜8)は
+/// 幅と高さを入れ替える。
+///
+fn dimensions(exif: &Exif) -> Option<(u32, u32)> {
+    let (width, height) = match (
+        uint(exif, Tag::PixelXDimension),
+        uint(exif, Tag::PixelYDimension),
+    ) {
+        (Some(width), Some(height)) => (width, height),
+        _ => (uint(exif, Tag::ImageWidth)?, uint(exif, Tag::ImageLength)?),
+    };
+
+    match uint(exif, Tag::Orientation) {
+        Some(5..=8) => Some((height, width)),
+        _ => Some((width, height)),
+    }
+}
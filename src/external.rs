@@ -0,0 +1,129 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 外部コマンドの呼び出しをまとめたモジュール
+//!
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use anyhow::{anyhow, Result};
+
+///
+/// 外部コマンドを実行する
+///
+/// # 引数
+/// * `command` - 実行するコマンド
+///
+/// # 戻り値
+/// コマンドが正常終了した場合は標準出力の内容を`Ok()`でラップして返す。失敗し
+/// た場合はエラー情報を`Err()`でラップして返す。
+///
+pub(crate) fn execute(command: &mut Command) -> Result<String> {
+    let program = command.get_program().to_string_lossy().to_string();
+
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(err) => {
+            return Err(anyhow!("execute {} failed: {}", program, err));
+        }
+    };
+
+    check_output(&program, output)
+}
+
+///
+/// 標準入力にデータを与えて外部コマンドを実行する
+///
+/// # 引数
+/// * `command` - 実行するコマンド
+/// * `input` - 標準入力に書き込むデータ
+///
+/// # 戻り値
+/// コマンドが正常終了した場合は標準出力の内容を`Ok()`でラップして返す。失敗し
+/// た場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// パスワード等のコマンドライン引数に載せたくない値を渡すために用いる(コマン
+/// ドライン引数は他のユーザからも参照できるため)。
+///
+pub(crate) fn execute_with_input(command: &mut Command, input: &[u8])
+    -> Result<String>
+{
+    let program = command.get_program().to_string_lossy().to_string();
+
+    let result = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            // 書き込み後に標準入力を閉じてEOFを伝える
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(input)?;
+            }
+
+            child.wait_with_output()
+        });
+
+    let output = match result {
+        Ok(output) => output,
+        Err(err) => {
+            return Err(anyhow!("execute {} failed: {}", program, err));
+        }
+    };
+
+    check_output(&program, output)
+}
+
+///
+/// 外部コマンドの終了状態を確認する
+///
+/// # 引数
+/// * `program` - 実行したコマンドの名前
+/// * `output` - 実行結果
+///
+/// # 戻り値
+/// コマンドが正常終了した場合は標準出力の内容を`Ok()`でラップして返す。失敗し
+/// た場合はエラー情報を`Err()`でラップして返す。
+///
+fn check_output(program: &str, output: Output) -> Result<String> {
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed ({}): {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+///
+/// シェル経由で実行するコマンドを構築する
+///
+/// # 引数
+/// * `command` - 実行するコマンド文字列
+///
+/// # 戻り値
+/// プラットフォームのシェルでコマンド文字列を実行するコマンド
+///
+pub(crate) fn shell(command: &str) -> Command {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.arg("/C");
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-c");
+        process
+    };
+
+    process.arg(command);
+    process
+}
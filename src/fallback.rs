@@ -0,0 +1,121 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! Exif情報に撮影日時が無いファイルの日時を補うモジュール
+//!
+
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+use crate::cmd_args::DatetimeFallback;
+
+///
+/// 撮影日時の代わりに用いる日時を求める
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `mode` - 撮影日時が無い場合の取り扱い
+///
+/// # 戻り値
+/// 代わりに用いる日時。`mode`が`skip`の場合や日時を得られない場合はNone。
+///
+pub(crate) fn datetime(path: &Path, mode: DatetimeFallback)
+    -> Option<DateTime<Local>>
+{
+    match mode {
+        DatetimeFallback::Mtime => from_mtime(path),
+        DatetimeFallback::Filename => from_filename(path),
+        DatetimeFallback::Skip => None,
+    }
+}
+
+///
+/// ファイルの更新日時を求める
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+///
+/// # 戻り値
+/// ファイルの更新日時(取得できない場合はNone)
+///
+fn from_mtime(path: &Path) -> Option<DateTime<Local>> {
+    let mtime = std::fs::metadata(path).ok()?.modified().ok()?;
+
+    Some(DateTime::<Local>::from(mtime))
+}
+
+///
+/// ファイル名に含まれる日時を求める
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+///
+/// # 戻り値
+/// ファイル名から読み取った日時(読み取れない場合はNone)
+///
+/// # 注記
+/// 拡張子を除いたファイル名の数字の並びを区切り文字を取り除いて連結し、その
+/// 先頭から年月日(8桁)と時分秒(6桁)を読み取る。`IMG_20240312_142530`、
+/// `PXL_20240312_142530123`、`Screenshot 2024-03-12 at 14.25.30`等の形式に
+/// 対応する。時分秒が読み取れない場合はその日の0時とし、日時はローカル時刻
+/// として扱う。年月日として読み取れない数字の並びは読み飛ばす。
+///
+fn from_filename(path: &Path) -> Option<DateTime<Local>> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let groups = stem
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|group| !group.is_empty())
+        .collect::<Vec<_>>();
+
+    for start in 0..groups.len() {
+        let digits = groups[start..].concat();
+
+        if let Some(datetime) = parse_digits(&digits) {
+            return Local.from_local_datetime(&datetime).earliest();
+        }
+    }
+
+    None
+}
+
+///
+/// 数字の並びを日時として読み取る
+///
+/// # 引数
+/// * `digits` - 数字のみからなる文字列
+///
+/// # 戻り値
+/// 読み取った日時(年月日として読み取れない場合はNone)
+///
+fn parse_digits(digits: &str) -> Option<NaiveDateTime> {
+    let number = |range: std::ops::Range<usize>| {
+        digits.get(range)?.parse::<u32>().ok()
+    };
+
+    let year = number(0..4)?;
+
+    // 連番等を日付と誤認しないよう、年の範囲を限定する
+    if !(1970..=2099).contains(&year) {
+        return None;
+    }
+
+    let date = NaiveDate::from_ymd_opt(
+        year as i32,
+        number(4..6)?,
+        number(6..8)?,
+    )?;
+
+    let time = match (number(8..10), number(10..12), number(12..14)) {
+        (Some(hour), Some(min), Some(sec)) => {
+            NaiveTime::from_hms_opt(hour, min, sec)
+        }
+        _ => None,
+    };
+
+    Some(date.and_time(time.unwrap_or(NaiveTime::MIN)))
+}
@@ -0,0 +1,243 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! macOSのFinderタグの付与を行うモジュール
+//!
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Result;
+use log::debug;
+
+use crate::cmd_args::Options;
+use crate::external::execute;
+use crate::template::PathContext;
+
+/// Finderタグを格納する拡張属性の名前
+const TAG_ATTR: &str = "com.apple.metadata:_kMDItemUserTags";
+
+///
+/// 振り分け先のファイルにFinderタグを付与する
+///
+/// # 引数
+/// * `path` - 振り分け先に格納したファイルのパス
+/// * `ctx` - タグの展開に用いる値
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// タグは拡張属性にバイナリ形式のプロパティリスト(文字列の配列)として書き込
+/// む(`xattr -wx`を使用)。既存のタグは置き換える。
+///
+pub(crate) fn tag(path: &Path, ctx: &PathContext, opts: &Options)
+    -> Result<()>
+{
+    let tags = expand_tags(&opts.finder_tags(), ctx);
+
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    let hex = encode_plist(&tags)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    execute(
+        Command::new("xattr")
+            .arg("-wx")
+            .arg(TAG_ATTR)
+            .arg(hex)
+            .arg(path)
+    )?;
+
+    debug!("tagged {} with {:?}", path.display(), tags);
+
+    Ok(())
+}
+
+///
+/// タグの指定を展開する
+///
+/// # 引数
+/// * `tags` - タグの指定(`{event}`、`{artist}`を含むことができる)
+/// * `ctx` - タグの展開に用いる値
+///
+/// # 戻り値
+/// 展開後のタグの一覧
+///
+/// # 注記
+/// 値の無い変数を含むタグ(イベントに該当しない場合の`{event}`等)は付与しな
+/// い。同じ名前のタグは1つにまとめる。
+///
+fn expand_tags(tags: &[String], ctx: &PathContext) -> Vec<String> {
+    let mut expanded = Vec::<String>::new();
+
+    for tag in tags {
+        let tag = match expand_variable(tag, "{event}", ctx.event) {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        let tag = match expand_variable(&tag, "{artist}", ctx.artist) {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        let tag = tag.trim().to_string();
+
+        if !tag.is_empty() && !expanded.contains(&tag) {
+            expanded.push(tag);
+        }
+    }
+
+    expanded
+}
+
+///
+/// タグの指定に含まれる変数を展開する
+///
+/// # 引数
+/// * `tag` - タグの指定
+/// * `variable` - 展開する変数(`{event}`等)
+/// * `value` - 変数の値
+///
+/// # 戻り値
+/// 展開後のタグ。変数を含むが値が無い場合はNone。
+///
+fn expand_variable(tag: &str, variable: &str, value: Option<&str>)
+    -> Option<String>
+{
+    if !tag.contains(variable) {
+        return Some(tag.to_string());
+    }
+
+    value.map(|value| tag.replace(variable, value))
+}
+
+///
+/// 文字列の配列をバイナリ形式のプロパティリストに変換する
+///
+/// # 引数
+/// * `values` - 格納する文字列
+///
+/// # 戻り値
+/// `bplist00`形式のバイト列
+///
+/// # 注記
+/// オブジェクト0を配列、1以降を各文字列とする。ASCIIのみの文字列はASCII形式、
+/// それ以外はUTF-16BE形式で格納する。
+///
+fn encode_plist(values: &[String]) -> Vec<u8> {
+    let count = values.len() + 1;
+    let ref_size = if count < 0x100 { 1 } else { 2 };
+
+    let mut out = b"bplist00".to_vec();
+    let mut offsets = Vec::with_capacity(count);
+
+    /*
+     * オブジェクトの書き出し
+     */
+    offsets.push(out.len());
+    push_marker(&mut out, 0xa0, values.len());
+
+    for index in 1..count {
+        push_sized(&mut out, index as u64, ref_size);
+    }
+
+    for value in values {
+        offsets.push(out.len());
+
+        if value.is_ascii() {
+            push_marker(&mut out, 0x50, value.len());
+            out.extend_from_slice(value.as_bytes());
+        } else {
+            let units = value.encode_utf16().collect::<Vec<_>>();
+            push_marker(&mut out, 0x60, units.len());
+
+            for unit in units {
+                out.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+    }
+
+    /*
+     * オフセットテーブルとトレイラの書き出し
+     */
+    let table_offset = out.len();
+    let offset_size = match table_offset {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        _ => 4,
+    };
+
+    for offset in offsets {
+        push_sized(&mut out, offset as u64, offset_size);
+    }
+
+    out.extend_from_slice(&[0; 6]);
+    out.push(offset_size as u8);
+    out.push(ref_size as u8);
+    out.extend_from_slice(&(count as u64).to_be_bytes());
+    out.extend_from_slice(&0u64.to_be_bytes());
+    out.extend_from_slice(&(table_offset as u64).to_be_bytes());
+
+    out
+}
+
+///
+/// オブジェクトのマーカーを書き出す
+///
+/// # 引数
+/// * `out` - 書き出し先
+/// * `kind` - オブジェクトの種別を示す上位4ビット
+/// * `len` - 要素数
+///
+/// # 注記
+/// 要素数が15以上の場合は続けて整数オブジェクトとして書き出す。
+///
+fn push_marker(out: &mut Vec<u8>, kind: u8, len: usize) {
+    if len < 0x0f {
+        out.push(kind | len as u8);
+        return;
+    }
+
+    out.push(kind | 0x0f);
+
+    match len {
+        0..=0xff => {
+            out.push(0x10);
+            push_sized(out, len as u64, 1);
+        }
+
+        0x100..=0xffff => {
+            out.push(0x11);
+            push_sized(out, len as u64, 2);
+        }
+
+        _ => {
+            out.push(0x12);
+            push_sized(out, len as u64, 4);
+        }
+    }
+}
+
+///
+/// 整数を指定したバイト数のビッグエンディアンで書き出す
+///
+/// # 引数
+/// * `out` - 書き出し先
+/// * `value` - 書き出す値
+/// * `size` - バイト数
+///
+fn push_sized(out: &mut Vec<u8>, value: u64, size: usize) {
+    out.extend_from_slice(&value.to_be_bytes()[8 - size..]);
+}
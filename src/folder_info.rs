@@ -0,0 +1,161 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先フォルダごとの取り込み情報ファイルを取り扱うモジュール
+//!
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::report::{Action, Report};
+
+/// 取り込み情報ファイルの名前
+pub(crate) const INFO_NAME: &str = ".imgdist-info.toml";
+
+///
+/// フォルダごとの取り込み情報
+///
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct FolderInfo {
+    /// 取り込んだファイル数の合計
+    files: usize,
+
+    /// 取り込んだバイト数の合計
+    bytes: u64,
+
+    /// ファイルを取り込んだ実行の一覧
+    #[serde(default)]
+    runs: Vec<RunInfo>,
+}
+
+///
+/// 1回の実行でフォルダに取り込んだファイルの情報
+///
+#[derive(Debug, Deserialize, Serialize)]
+struct RunInfo {
+    /// 実行開始日時(ISO8601)
+    started: String,
+
+    /// 実行終了日時(ISO8601)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished: Option<String>,
+
+    /// 入力元(入力ディレクトリのパスまたはクラウドストレージのリモート)
+    source: String,
+
+    /// 入力元のボリュームID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume_id: Option<String>,
+
+    /// 取り込んだファイル数
+    files: usize,
+
+    /// 取り込んだバイト数
+    bytes: u64,
+}
+
+///
+/// 実行結果を振り分け先フォルダの取り込み情報ファイルに記録する
+///
+/// # 引数
+/// * `report` - 取り込みの実行結果
+/// * `source` - 入力元を表す文字列
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// ファイルをコピーしたフォルダごとに、既存の情報ファイルを読み込んで今回の実
+/// 行分を追記する(同じ実行の記録が既にある場合は置き換える)。合計は記録され
+/// ている実行の分から求め直す。
+///
+pub(crate) fn update(report: &Report, source: &str) -> Result<()> {
+    /*
+     * フォルダごとの集計
+     */
+    let mut counts = BTreeMap::<PathBuf, (usize, u64)>::new();
+
+    for entry in report.entries() {
+        if entry.action != Action::Copied {
+            continue;
+        }
+
+        let dir = match entry.destination.as_deref().and_then(Path::parent) {
+            Some(dir) => dir,
+            None => continue,
+        };
+
+        let count = counts.entry(dir.to_path_buf()).or_default();
+        count.0 += 1;
+        count.1 += entry.size;
+    }
+
+    /*
+     * 情報ファイルの更新
+     */
+    for (dir, (files, bytes)) in counts {
+        let run = RunInfo {
+            started: report.started().to_string(),
+            finished: report.finished().map(str::to_string),
+            source: source.to_string(),
+            volume_id: report.volume_id().map(str::to_string),
+            files,
+            bytes,
+        };
+
+        update_dir(&dir, run)?;
+    }
+
+    Ok(())
+}
+
+///
+/// 1つのフォルダの取り込み情報ファイルを更新する
+///
+/// # 引数
+/// * `dir` - 対象のフォルダ
+/// * `run` - 今回の実行分の情報
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn update_dir(dir: &Path, run: RunInfo) -> Result<()> {
+    let path = dir.join(INFO_NAME);
+
+    let mut info = if path.exists() {
+        let text = std::fs::read_to_string(&path)?;
+
+        match toml::from_str::<FolderInfo>(&text) {
+            Ok(info) => info,
+            Err(err) => {
+                return Err(anyhow!("parse {} failed: {}", path.display(), err));
+            }
+        }
+    } else {
+        FolderInfo::default()
+    };
+
+    info.runs.retain(|entry| entry.started != run.started);
+    info.runs.push(run);
+    info.files = info.runs.iter().map(|run| run.files).sum();
+    info.bytes = info.runs.iter().map(|run| run.bytes).sum();
+
+    // 一時ファイルに書き出してから置き換える
+    let tmp = dir.join(format!("{}.tmp", INFO_NAME));
+    std::fs::write(&tmp, toml::to_string(&info)?)?;
+    std::fs::rename(&tmp, &path)?;
+
+    debug!("folder info updated: {}", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先のドライブの状態に応じてコピーを調整するモジュール
+//!
+
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+use crate::cmd_args::Options;
+use crate::external::shell;
+
+/// 減速時にコピーの前に挟む待ち時間
+const THROTTLE_WAIT: Duration = Duration::from_secs(10);
+
+///
+/// フックコマンドが報告したドライブの状態
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Health {
+    /// 正常(終了コード0)
+    Normal,
+
+    /// 減速が必要(終了コード1)
+    Throttle,
+
+    /// 停止が必要(終了コード2以上)
+    Halt,
+}
+
+///
+/// ドライブの状態の確認状況
+///
+struct Monitor {
+    /// 最後にフックコマンドを実行した時刻(未実行の場合はNone)
+    checked: Option<Instant>,
+
+    /// 最後に確認したドライブの状態
+    health: Health,
+}
+
+/// ドライブの状態の確認状況
+static MONITOR: Mutex<Monitor> = Mutex::new(Monitor {
+    checked: None,
+    health: Health::Normal,
+});
+
+///
+/// ドライブの状態の確認状況のロック
+///
+/// # 戻り値
+/// 確認状況のガードオブジェクト
+///
+fn lock() -> MutexGuard<'static, Monitor> {
+    MONITOR.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+///
+/// ドライブの状態に応じてコピーの開始を待つ
+///
+/// # 引数
+/// * `dir` - これからコピーを行う振り分け先のディレクトリ
+/// * `opts` - オプション設定の参照
+///
+/// # 注記
+/// ファイルのコピーの直前に呼び出す。`--health-command`が指定されていない場
+/// 合は何もしない。フックコマンドは前回の実行から`--health-interval`の間隔
+/// が経過している場合にのみ実行し、それ以外は前回の結果を用いる。減速が必要
+/// な場合は一定時間待ってから戻り、停止が必要な場合は正常または減速の状態に
+/// 戻るまで間隔ごとにフックコマンドを再実行して待つ。
+///
+pub(crate) fn wait(dir: &Path, opts: &Options) {
+    let command = match opts.health_command() {
+        Some(command) => command,
+        None => return,
+    };
+
+    let mut monitor = lock();
+
+    loop {
+        let due = monitor
+            .checked
+            .is_none_or(|at| at.elapsed() >= opts.health_interval());
+
+        if due {
+            let health = check(&command, dir, opts);
+
+            if health == Health::Normal && monitor.health != Health::Normal {
+                info!("destination drive recovered, copies resumed");
+            }
+
+            monitor.health = health;
+            monitor.checked = Some(Instant::now());
+        }
+
+        match monitor.health {
+            Health::Normal => return,
+
+            Health::Throttle => {
+                thread::sleep(THROTTLE_WAIT);
+                return;
+            }
+
+            // 停止中も一時停止の指示には従う
+            Health::Halt => {
+                thread::sleep(opts.health_interval());
+                opts.pause_state().wait();
+            }
+        }
+    }
+}
+
+///
+/// フックコマンドを実行してドライブの状態を確認する
+///
+/// # 引数
+/// * `command` - フックコマンド
+/// * `dir` - これからコピーを行う振り分け先のディレクトリ
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// フックコマンドの終了コードから判定したドライブの状態
+///
+/// # 注記
+/// コマンドはシェル経由で実行し、振り分け先のディレクトリと出力ディレクトリ
+/// を環境変数で渡す。コマンドを実行できなかった場合や、シグナルにより終了し
+/// た場合は警告を記録して正常として扱う(フックコマンドの不備で取り込みが止ま
+/// らないようにするため)。
+///
+fn check(command: &str, dir: &Path, opts: &Options) -> Health {
+    let output = shell(command)
+        .env("IMGDIST_DESTINATION", dir)
+        .env("IMGDIST_OUTPUT", opts.output_path())
+        .output();
+
+    let output = match output {
+        Ok(output) => output,
+        Err(err) => {
+            warn!("health command failed: {}", err);
+            return Health::Normal;
+        }
+    };
+
+    // 標準出力の先頭行は状態の説明(温度等)としてログに出力する
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let detail = stdout.lines().next().unwrap_or("").trim();
+
+    match output.status.code() {
+        Some(0) => Health::Normal,
+
+        Some(1) => {
+            warn!("destination drive needs throttling: {}", detail);
+            Health::Throttle
+        }
+
+        Some(_) => {
+            warn!("destination drive needs halting: {}", detail);
+            Health::Halt
+        }
+
+        None => {
+            warn!("health command terminated ({})", output.status);
+            Health::Normal
+        }
+    }
+}
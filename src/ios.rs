@@ -0,0 +1,178 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! iOSデバイスからの取り込みを行うモジュール
+//!
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+
+use crate::cache::Cache;
+use crate::cmd_args::Options;
+use crate::external::execute;
+use crate::mail;
+use crate::{finalize, import, prune_expired};
+
+/// ボリュームIDの接頭辞
+const VOLUME_PREFIX: &str = "ios-";
+
+///
+/// マウントしたデバイスのファイルシステム
+///
+/// # 注記
+/// スコープを抜ける際にアンマウントし、マウントポイントを削除する。
+///
+struct Mount {
+    /// マウントポイント
+    path: PathBuf,
+}
+
+impl Mount {
+    ///
+    /// デバイスのメディア領域をマウントする
+    ///
+    /// # 引数
+    /// * `udid` - デバイスのUDID
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はマウントしたファイルシステムを`Ok()`でラップして返
+    /// す。失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    fn new(udid: &str) -> Result<Self> {
+        let path = std::env::temp_dir()
+            .join(format!("{}-{}", env!("CARGO_PKG_NAME"), udid));
+
+        std::fs::create_dir_all(&path)?;
+
+        if let Err(err) = execute(
+            Command::new("ifuse").arg("--udid").arg(udid).arg(&path)
+        ) {
+            let _ = std::fs::remove_dir(&path);
+            return Err(err);
+        }
+
+        Ok(Self { path })
+    }
+}
+
+// Dropトレイトの実装
+impl Drop for Mount {
+    fn drop(&mut self) {
+        let result = if cfg!(target_os = "linux") {
+            execute(Command::new("fusermount").arg("-u").arg(&self.path))
+        } else {
+            execute(Command::new("umount").arg(&self.path))
+        };
+
+        match result {
+            Ok(_) => {
+                let _ = std::fs::remove_dir(&self.path);
+            }
+
+            Err(err) => {
+                warn!("unmount {} failed: {}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+///
+/// iOSデバイスからの取り込みを実行する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 概要
+/// libimobiledeviceのifuseでデバイスのメディア領域(AFC)をマウントし、その中の
+/// DCIMディレクトリを入力ディレクトリとして通常の取り込みを行う。キャッシュの
+/// ボリュームIDにはデバイスのUDIDを用いる。
+///
+pub(crate) fn run(opts: &Options) -> Result<()> {
+    let udid = match opts.ios_udid() {
+        Some(udid) => udid,
+        None => find_device()?,
+    };
+
+    info!("importing from iOS device {}", udid);
+
+    let mount = Mount::new(&udid)?;
+    let input_path = mount.path.join("DCIM");
+
+    if !input_path.is_dir() {
+        return Err(anyhow!("DCIM not found on device {}", udid));
+    }
+
+    let opts = opts.with_input_path(input_path);
+    let cache = Cache::open_volume(
+        opts.cache_db_path(),
+        opts.cache_eval_mode(),
+        format!("{}{}", VOLUME_PREFIX, udid),
+        mount.path.clone(),
+    )?;
+
+    let report = import(&opts, &cache).inspect_err(|err| {
+        mail::notify_failure(&opts, err);
+    })?;
+    finalize(&opts, &report)?;
+    prune_expired(&opts, &cache, &report)
+}
+
+///
+/// 接続されているデバイスを検出する
+///
+/// # 戻り値
+/// 接続されているデバイスが1台のみの場合はそのUDIDを`Ok()`でラップして返す。
+/// それ以外の場合はエラー情報を`Err()`でラップして返す。
+///
+fn find_device() -> Result<String> {
+    let output = execute(Command::new("idevice_id").arg("-l"))?;
+    let devices = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+
+    match devices.as_slice() {
+        [] => Err(anyhow!("no iOS device connected")),
+        [udid] => Ok(udid.to_string()),
+        _ => Err(anyhow!(
+            "multiple iOS devices connected ({}), specify --ios-udid",
+            devices.join(", ")
+        )),
+    }
+}
+
+///
+/// UDIDの書式を確認する
+///
+/// # 引数
+/// * `udid` - 確認するUDID
+///
+/// # 戻り値
+/// 英数字とハイフンのみで構成されている場合は`Ok(())`を返す。それ以外の場合は
+/// エラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// UDIDはマウントポイントの名前とボリュームIDに用いるため、パス区切り文字等を
+/// 含むものは受け付けない。
+///
+pub(crate) fn check_udid(udid: &str) -> Result<()> {
+    if !udid.is_empty()
+        && udid.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        Ok(())
+    } else {
+        Err(anyhow!("invalid UDID: {}", udid))
+    }
+}
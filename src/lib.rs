@@ -0,0 +1,3252 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Kuwagata HIROSHI <kgt9221@gmail.com>
+//
+
+//!
+//! 画像ファイルの振り分け処理を提供するライブラリ
+//!
+//! 入力ディレクトリの走査、撮影日時の取得、キャッシュデータベース、振り分け
+//! 先へのコピーまでの一連の処理を、コマンドラインツール以外(GUIのフロントエ
+//! ンド等)から呼び出せるよう公開する。公開する機能は以下の通り。
+//!
+//! * [`parse_args()`]、[`options_from_args()`] - オプション設定の構築
+//! * [`run()`] - コマンドラインツールと同じ処理の実行
+//! * [`install_signal_handler()`] - シグナルによる一時停止と再開の有効化
+//! * [`scan()`] - 取り込み対象のファイルの列挙
+//! * [`shooting_datetime()`] - 撮影日時の取得
+//! * [`import()`]、[`finalize()`] - 取り込みとその後処理
+//! * [`prune_expired()`] - 保存期間を過ぎたファイルの削除
+//!
+
+mod cmd_args;
+mod audit;
+mod batch;
+mod cache;
+mod calendar;
+mod class_registry;
+mod cas;
+mod checksum;
+mod clock;
+mod cloud;
+mod companion;
+mod compress;
+mod daemon;
+mod diff;
+mod encrypt;
+mod error;
+mod estimate;
+mod exclude;
+mod exif_view;
+mod external;
+mod fallback;
+mod finder;
+mod folder_info;
+mod health;
+mod ios;
+mod mail;
+mod marker;
+mod mkdir;
+mod motion_photo;
+mod parity;
+mod pause;
+mod permission;
+mod profile;
+mod progress;
+mod prune;
+mod rebuild;
+mod report;
+mod sidecar;
+mod shard;
+mod snapshot;
+mod source;
+mod state;
+mod stats;
+mod template;
+mod video;
+mod vss;
+mod watch;
+mod why;
+
+use std::ffi::OsString;
+use std::fs::{File, Metadata};
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, Error, Result};
+use chrono::TimeZone;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use exif::{Exif, Field, Tag};
+use glob::{MatchOptions, Pattern};
+use walkdir::{DirEntry, WalkDir};
+
+use crate::cache::{CacheDecision, ExifCache, TxnHandle};
+use crate::class_registry::ClassRegistry;
+use crate::clock::ClockTracker;
+use crate::cmd_args::{
+    ChecksumAlgorithm, Collision, Command, DatetimeFallback, FileKind,
+    GpsClockMode, MotionPhotoMode, ProcessOrder, SnapshotMode, StorageLayout,
+};
+use crate::exif_view::ExifView;
+use crate::progress::Progress;
+use crate::report::TraceWriter;
+use crate::state::{LastRun, Parked, StateStore};
+use crate::template::{NameContext, PathContext, Sequencer};
+use crate::vss::Shadow;
+
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+pub use crate::cache::Cache;
+pub use crate::cmd_args::Options;
+pub use crate::error::{ErrorKind, ImportError};
+pub use crate::report::{Action, FileEntry, Report, SkipReason};
+
+/// コピー中に入力ファイルが更新された場合にコピーを試みる回数
+const COPY_ATTEMPTS: usize = 3;
+
+/// コピー中に入力ファイルが更新された場合に再試行するまでの待ち時間
+const COPY_RETRY_WAIT: Duration = Duration::from_secs(1);
+
+/// 撮影日時の解析に用いる標準の書式(Exif情報の表示用の値と、Exifの規定の
+/// コロン区切りの値、およびそれらの変種)
+const DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y:%m:%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+];
+
+/// ファイルタイプと保存先パスを表す列挙型
+#[derive(Debug, Clone, PartialEq)]
+enum FileType {
+    /// JPEGファイル（保存先パス）
+    Jpeg(PathBuf),
+
+    /// RAWファイル（保存先パス）
+    Raw(PathBuf),
+
+    /// 動画ファイル（保存先パス）
+    Video(PathBuf),
+
+    /// 拡張子ごとの振り分け先が設定されたその他のファイル（保存先パス）
+    Other(PathBuf),
+}
+
+impl FileType {
+    ///
+    /// ファイルタイプの名称を返す
+    ///
+    /// # 戻り値
+    /// ログやレポートに用いるファイルタイプの名称
+    ///
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Jpeg(_) => "jpeg",
+            Self::Raw(_) => "raw",
+            Self::Video(_) => "video",
+            Self::Other(_) => "other",
+        }
+    }
+
+    ///
+    /// 保存先パスへのアクセサ
+    ///
+    /// # 戻り値
+    /// ファイルの保存先ディレクトリのパス
+    ///
+    fn dir(&self) -> &Path {
+        match self {
+            Self::Jpeg(path)
+                | Self::Raw(path)
+                | Self::Video(path)
+                | Self::Other(path) => path,
+        }
+    }
+}
+
+///
+/// 1回の取り込みの間、入力ファイルの処理で共有する状態
+///
+struct ImportSession<'a> {
+    /// オプション設定
+    opts: &'a Options,
+
+    /// キャッシュデータベース
+    cache: &'a Cache,
+
+    /// 読み出したExif情報のキャッシュ
+    exif_cache: &'a ExifCache,
+
+    /// 取り込みマーカーの更新日時の上限(マーカーが無い場合はNone)
+    high_water: Option<SystemTime>,
+
+    /// ファイル名の通し番号のカウンタ
+    sequencer: Sequencer,
+
+    /// カメラの時計のずれの集計
+    clocks: ClockTracker,
+}
+
+///
+/// 拡張子からJPEGファイルかどうかを判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// JPEGファイルの場合は`true`、そうでなければ`false`
+///
+/// # 注記
+/// Insta360の静止画(insp)はJPEG形式であるためJPEGファイルとして扱う。設定
+/// で`image`の分類として追加した形式もJPEGファイルとして扱う。
+///
+fn is_jpeg_file(ext: &str, classes: &ClassRegistry) -> bool {
+    matches!(ext.to_lowercase().as_str(), "jpg" | "jpeg" | "insp")
+        || classes.is_kind(ext, FileKind::Image)
+}
+
+///
+/// 拡張子からRAWファイルかどうかを判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// RAWファイルの場合は`true`、そうでなければ`false`
+///
+/// # 注記
+/// 設定で`raw`の分類として追加した形式も含む。
+///
+fn is_raw_file(ext: &str, classes: &ClassRegistry) -> bool {
+    matches!(ext.to_lowercase().as_str(), 
+        "dng" |
+        "gpr" |
+        "nef" |
+        "cr2" |
+        "arw" |
+        "orf" |
+        "rw2" |
+        "pef" |
+        "srw" |
+        "raf" |
+        "3fr" |
+        "fff" |
+        "x3f"
+    ) || classes.is_kind(ext, FileKind::Raw)
+}
+
+///
+/// 拡張子から動画ファイルかどうかを判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// 撮影日時を読み出せる動画ファイルの場合は`true`、そうでなければ`false`
+///
+/// # 注記
+/// 対象はInsta360の動画(insv)、MP4形式の動画(DJIのドローン等)、QuickTime
+/// 形式の動画(mov)およびAVI形式の動画とする。設定で`video`の分類として追加
+/// した形式も含む。
+///
+fn is_video_file(ext: &str, classes: &ClassRegistry) -> bool {
+    matches!(ext.to_lowercase().as_str(), "insv" | "mp4" | "mov" | "avi")
+        || classes.is_kind(ext, FileKind::Video)
+}
+
+///
+/// 拡張子からファイルの分類を判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `classes` - 設定で追加した形式の登録内容
+///
+/// # 戻り値
+/// ティアの選択に用いる分類の名称(jpeg、raw、video、other)
+///
+fn file_class(ext: &str, classes: &ClassRegistry) -> &'static str {
+    if is_jpeg_file(ext, classes) {
+        "jpeg"
+    } else if is_raw_file(ext, classes) {
+        "raw"
+    } else if is_video_file(ext, classes) {
+        "video"
+    } else {
+        "other"
+    }
+}
+
+///
+/// 拡張子から取り込み対象のファイルかどうかを判定する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// JPEGファイル、RAWファイル、動画ファイル、または拡張子ごとの振り分け先やテ
+/// ィアが設定されたファイルの場合は`true`、そうでなければ`false`（ホットフォ
+/// ルダからの配信の場合はJPEGファイルのみ`true`）
+///
+fn is_target_file(ext: &str, opts: &Options) -> bool {
+    let classes = opts.classes();
+
+    if opts.is_jpeg_only() {
+        return is_jpeg_file(ext, classes);
+    }
+
+    is_jpeg_file(ext, classes)
+        || is_raw_file(ext, classes)
+        || is_video_file(ext, classes)
+        || opts.ext_route(ext).is_some()
+        || opts.tier_for(file_class(ext, classes), ext).is_some()
+}
+
+///
+/// 拡張子からファイルタイプと保存先パスを構築する
+///
+/// # 引数
+/// * `ext` - ファイルの拡張子
+/// * `ctx` - パステンプレートの展開に用いる値
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 判定されたファイルタイプと保存先パス、または`None`（サポートされていない形式）
+///
+/// # 注記
+/// 撮影者ごとの出力先が設定されている場合は、ファイルタイプによらずそちらを基
+/// 点とする。次いで撮影年の範囲ごとの出力先、ファイルの分類に対応するティア
+/// の順に、設定されていればその基点ディレクトリを用いる。拡張子ごとの振り分
+/// け先が設定されている場合は、JPEG/RAW/動画の既定の規則よりもそちらを優先す
+/// る(相対パスの場合は既定の基点からの相対パスとする)。拡張子ごとの振り分け
+/// 先が無く、取り込みプロファイルのフォルダの規則で振り分け先が定められてい
+/// る場合は同様にそちらを用いる。出力ディレクトリを基点とする場合、シャード
+/// が設定されていればその中から選んだものを用いる。
+///
+fn build_file_type(ext: &str, ctx: &PathContext, opts: &Options)
+    -> Option<FileType>
+{
+    let classes = opts.classes();
+    let ext_lower = ext.to_lowercase();
+    let is_jpeg = is_jpeg_file(&ext_lower, classes);
+    let is_raw = is_raw_file(&ext_lower, classes);
+    let is_video = is_video_file(&ext_lower, classes);
+    let sub_path = opts.path_template().expand(ctx);
+    let route = opts.ext_route(&ext_lower);
+    let tier_root = opts
+        .tier_for(file_class(&ext_lower, classes), &ext_lower)
+        .map(|tier| tier.path.clone());
+    let tiered = tier_root.is_some();
+    let root = ctx
+        .artist
+        .and_then(|artist| opts.artist_root(artist))
+        .or_else(|| opts.year_root(ctx.date.year()))
+        .or(tier_root);
+
+    // 既定の基点ディレクトリの決定
+    let base = match ext_lower.as_str() {
+        _ if is_raw => {
+            root
+                .or_else(|| opts.raw_output_path())
+                .unwrap_or_else(|| shard::output_root(&sub_path, opts))
+        },
+
+        _ if is_video => {
+            root
+                .or_else(|| opts.video_output_path())
+                .unwrap_or_else(|| shard::output_root(&sub_path, opts))
+        },
+
+        _ if is_jpeg || route.is_some() || tiered => {
+            root.unwrap_or_else(|| shard::output_root(&sub_path, opts))
+        },
+
+        _ => return None,
+    };
+
+    // 拡張子ごとの振り分け先の適用(絶対パスの場合は基点を置き換える)
+    let route = route.or_else(|| profile::route(ctx.original_dir, opts));
+    let path = match route {
+        Some(route) => base.join(route).join(sub_path),
+        None => base.join(sub_path),
+    };
+
+    match ext_lower.as_str() {
+        _ if is_jpeg => Some(FileType::Jpeg(path)),
+        _ if is_raw => Some(FileType::Raw(path)),
+        _ if is_video => Some(FileType::Video(path)),
+        _ => Some(FileType::Other(path)),
+    }
+}
+
+///
+/// コマンドラインオプションのパース
+///
+/// # 戻り値
+/// 処理に成功した場合はオプション設定をパックしたオブジェクトを`Ok()`でラップ
+/// して返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// プロセスのコマンドライン引数を解析し、コンフィギュレーションファイルの適
+/// 用とログ機能の初期化までを行う。`--save-config`、`--show-options`の指定
+/// があった場合はその処理を行ってプロセスを終了する。
+///
+pub fn parse_args() -> Result<Arc<Options>> {
+    cmd_args::parse()
+}
+
+///
+/// 引数の列からオプション設定を構築する
+///
+/// # 引数
+/// * `args` - コマンドライン引数の列(先頭はプログラム名)
+///
+/// # 戻り値
+/// 処理に成功した場合はオプション設定をパックしたオブジェクトを`Ok()`でラップ
+/// して返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 他のプログラムに組み込んで用いるためのもの。コンフィギュレーションファイ
+/// ルの適用とバリデーションは行うが、ログ機能の初期化は行わない(ログは呼び
+/// 出し側で`log`クレートのロガーを設定して受け取る)。
+///
+pub fn options_from_args<I, T>(args: I) -> Result<Arc<Options>>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    cmd_args::from_args(args)
+}
+
+///
+/// 入力ディレクトリ中の取り込み対象のファイルを列挙する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理順に並べたファイルのパスの一覧
+///
+/// # 注記
+/// 振り分け処理と同じ条件(除外・包含の指定、処理順)で列挙する。取り込み対
+/// 象の形式か否かの判定は行わない。
+///
+pub fn scan(opts: &Options) -> Vec<PathBuf> {
+    let exif_cache = ExifCache::new(opts);
+
+    input_files(opts, &exif_cache)
+        .map(DirEntry::into_path)
+        .collect()
+}
+
+///
+/// ファイルの撮影日時を求める
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は撮影日時(求められない場合はNone)を`Ok()`でラップして
+/// 返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// Exif情報(動画ファイルの場合はコンテナに記録された作成日時)から求め、記
+/// 録されていない場合は振り分け処理と同じ方法で補う。
+///
+pub fn shooting_datetime(path: &Path, opts: &Options)
+    -> Result<Option<DateTime<Local>>>
+{
+    let (exif, _) = cache::read_exif(path, opts.classes())?;
+
+    resolve_datetime(&exif, path, opts)
+}
+
+///
+/// 一時停止と再開を行うシグナルハンドラを登録する
+///
+/// # 引数
+/// * `opts` - シグナルで一時停止・再開する取り込みのオプション設定
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// SIGUSR1で一時停止し、SIGUSR2で再開する(Unix系のみ)。シグナルを待つスレッ
+/// ドを起動するため、プロセスにつき1回だけ呼び出す。ライブラリとして組み込む
+/// 場合、シグナルの扱いは呼び出し側に委ね、`run()`では登録しない。
+///
+pub fn install_signal_handler(opts: &Options) -> Result<()> {
+    pause::install_signal_handler(opts.pause_state().clone())
+}
+
+///
+/// プログラムの実行関数
+///
+/// # 引数
+/// * `opts` - オプション情報をパックしたオブジェクト
+///
+/// # 戻り値
+/// プログラムが正常狩猟した場合は、`Ok(())`を返す。失敗した場合はエラー情報を
+/// `Err()`でラップして返す。
+///
+pub fn run(opts: Arc<Options>) -> Result<()> {
+    /*
+     * サブコマンドが指定されている場合はそれを実行して終了
+     */
+    if let Some(command) = opts.command() {
+        return run_command(command, &opts);
+    }
+
+    /*
+     * 見積もりモードの場合は見積もりのみを行って終了
+     */
+    if opts.is_estimate() {
+        return estimate::run(&opts);
+    }
+
+    /*
+     * 付随ファイルのみの取り込みの場合は修復処理のみを行って終了
+     */
+    if opts.is_sidecars_only() {
+        return sidecar::run(&opts);
+    }
+
+    /*
+     * iOSデバイスからの取り込みの場合はデバイスをマウントして取り込む
+     */
+    if opts.is_ios() {
+        return ios::run(&opts);
+    }
+
+    /*
+     * クラウドストレージからの取り込みの場合は新規のファイルを取得して取り込む
+     */
+    if opts.cloud_remote().is_some() {
+        return cloud::run(&opts);
+    }
+
+    /*
+     * デーモンモードの場合は常駐して取り込みを繰り返す
+     */
+    if opts.is_daemon() {
+        return daemon::run(opts);
+    }
+
+    /*
+     * 監視モードの場合は常駐して追加されたファイルの取り込みを繰り返す
+     */
+    if opts.is_watch() {
+        return watch::run(&opts);
+    }
+
+    /*
+     * 取り込みの実行
+     */
+    let report = import(&opts, opts.cache().as_ref()).inspect_err(|err| {
+        // 中断までに格納したファイルの対応は残しておく
+        if let Err(err) = encrypt::finalize(&opts) {
+            error!("{}", err);
+        }
+
+        mail::notify_failure(&opts, err);
+    })?;
+
+    finalize(&opts, &report)?;
+    prune_expired(&opts, opts.cache().as_ref(), &report)
+}
+
+///
+/// 入力ディレクトリ中のファイルの取り込みを行う
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュデータベース
+///
+/// # 戻り値
+/// 処理が成功した場合は実行結果のレポートを`Ok()`でラップして返す。失敗した場
+/// 合は種別を持つエラー情報(`ImportError`)を`Err()`でラップして返す。
+///
+/// # 注記
+/// ファイル単位の処理の失敗はレポートに記録し、処理は継続する。ただし入力元
+/// との接続が失われて回復しない場合は、残りのファイルの処理を打ち切り中断し
+/// たことをレポートとステートディレクトリに記録する。
+///
+pub fn import(opts: &Options, cache: &Cache) -> Result<Report, ImportError> {
+    /*
+     * 前回の実行結果の出力
+     */
+    let state = StateStore::open(opts.state_dir())?;
+
+    match state.last_run() {
+        Ok(Some(last)) => info!(
+            "last run {}: copied {}, skipped {}, failed {} (from {})",
+            last.finished.as_deref().unwrap_or(&last.started),
+            last.copied,
+            last.skipped,
+            last.failed,
+            last.input_path.display(),
+        ),
+
+        Ok(None) => {}
+
+        // 前回の記録が読めなくても取り込みは継続する
+        Err(err) => warn!("{}", err),
+    }
+
+    match state.parked() {
+        Ok(Some(parked)) if parked.input_path == opts.input_path() => info!(
+            "resuming run parked at {} ({})",
+            parked.parked_at,
+            parked.reason,
+        ),
+
+        Ok(_) => {}
+        Err(err) => warn!("{}", err),
+    }
+
+    /*
+     * 入力ディレクトリ中のファイルを順に処理
+     */
+    mkdir::reset(opts);
+    shard::reset(opts);
+    encrypt::reset(opts);
+
+    let mut report = Report::new();
+    report.set_volume_id(cache.volume_id());
+
+    if opts.is_dry_run() {
+        report.set_dry_run();
+    }
+
+    if opts.is_source_read_only() {
+        report.set_source_read_only();
+    }
+
+    let mut copied = 0;
+    let mut trace = match opts.trace_report_path() {
+        Some(path) => Some(TraceWriter::create(path)?),
+        None => None,
+    };
+    let input_path = opts.input_path();
+    let mut parked = None;
+    let mut shadow = None;
+    let mut limited = false;
+    let mut deferred = false;
+
+    // 並べ替え、キャッシュ評価、撮影日時の取得でExif情報の解析を1回で済ませ
+    // るため、実行中はExif情報のキャッシュを共有する
+    let exif_cache = ExifCache::new(opts);
+
+    // 取り込みマーカーが読めない場合はマーカー無しとして全てのファイルを評
+    // 価する
+    let high_water = if opts.is_card_marker() {
+        marker::read(&input_path).unwrap_or_else(|err| {
+            warn!("{}", err);
+            None
+        })
+    } else {
+        None
+    };
+    let mut new_high_water = high_water;
+    let mut session = ImportSession {
+        opts,
+        cache,
+        exif_cache: &exif_cache,
+        high_water,
+        sequencer: Sequencer::default(),
+        clocks: ClockTracker::default(),
+    };
+
+    // 進捗を表示する場合は残り時間を見積もるため、先に入力ファイルを全て
+    // 列挙して総量を求めておく
+    let mut progress = Progress::new(opts);
+    let entries = match progress.as_mut() {
+        Some(progress) => {
+            let entries = input_files(opts, &exif_cache).collect::<Vec<_>>();
+            progress.set_total(&entries);
+            Box::new(entries.into_iter())
+        }
+
+        None => input_files(opts, &exif_cache),
+    };
+
+    for entry in entries {
+        // 一時停止中は再開されるまで次のファイルの処理を待つ
+        opts.pause_state().wait();
+
+        let (mut record, mut result) = import_file(&entry, &mut session);
+
+        // 入力元との接続が失われた場合は回復を待って1回だけ再試行し、回復し
+        // なければ残りのファイルの処理を打ち切る
+        if let Err(ref err) = result {
+            if source::is_lost(err, &input_path, opts.source_timeout()) {
+                if source::wait(
+                    &input_path,
+                    opts.source_retries(),
+                    opts.source_timeout(),
+                ) {
+                    (record, result) = import_file(&entry, &mut session);
+                } else {
+                    let path = entry.path().to_path_buf();
+                    parked = Some((path, err.to_string()));
+                    break;
+                }
+            }
+        }
+
+        // 他のアプリケーションにロックされていて読み出せない場合は、シャドウ
+        // コピー経由で再試行する
+        if let Err(ref err) = result {
+            if opts.is_vss() && vss::is_sharing_violation(err) {
+                (record, result) =
+                    import_shadowed(&entry, &mut session, &mut shadow);
+            }
+        }
+
+        if let Err(err) = result {
+            record_failure(&mut record, err);
+        }
+
+        if record.action == Action::Copied {
+            copied += 1;
+        }
+
+        // コピー中に更新されたファイルや向き・カメラにより除外したファイル
+        // は後から取り込むため、マーカーを進めない
+        if matches!(
+            record.reason,
+            Some(
+                SkipReason::ModifiedDuringCopy
+                    | SkipReason::Orientation
+                    | SkipReason::ExcludedCamera
+            )
+        ) {
+            deferred = true;
+        }
+
+        // 処理を終えたファイルの更新日時の最大値を取り込みマーカーに記録する
+        if opts.is_card_marker() && record.action != Action::Failed {
+            let mtime = std::fs::metadata(entry.path())
+                .and_then(|meta| meta.modified());
+
+            if let Ok(mtime) = mtime {
+                new_high_water = new_high_water.max(Some(mtime));
+            }
+        }
+
+        // 移動の指定時はコピーを終えたファイルを入力ディレクトリから削除す
+        // る(削除に失敗しても振り分け自体は成功として扱う)
+        move_source(entry.path(), &mut record, opts);
+
+        // スキップ理由の出力(--explain指定時のみ)
+        if opts.is_explain() {
+            if let Some(reason) = record.reason {
+                info!("skipped {} ({})", record.source.display(), reason);
+            }
+        }
+
+        // 判定の経過の書き出し(--trace-report指定時のみ)
+        if let Some(trace) = trace.as_mut() {
+            trace.write(&record)?;
+        }
+
+        if let Some(progress) = progress.as_mut() {
+            progress.update(&record);
+        }
+
+        report.push(record);
+
+        // 処理件数の上限に達した場合はそこで打ち切る
+        if opts.limit().is_some_and(|limit| copied >= limit) {
+            info!("reached the limit of {} files", copied);
+            limited = true;
+            break;
+        }
+    }
+
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+
+    if let Some(trace) = trace.as_mut() {
+        trace.flush()?;
+    }
+
+    /*
+     * キャッシュ評価の件数の記録
+     */
+    if let Err(err) = cache.flush_stats() {
+        warn!("cache stats not recorded: {}", err);
+    }
+
+    /*
+     * シャドウコピーの削除
+     */
+    if shadow.is_some() {
+        cache.set_shadow_root(None);
+        drop(shadow);
+    }
+
+    /*
+     * カメラの時計のずれの警告
+     */
+    if opts.gps_clock_mode() != GpsClockMode::Off {
+        session.clocks.warn(opts.gps_clock_threshold());
+    }
+
+    /*
+     * 中断の記録
+     */
+    // 走査中に接続が失われた場合はファイルの列挙が途中で終わるため、最後に
+    // 入力元に到達できるかを確認する
+    if parked.is_none()
+        && !source::is_reachable(&input_path, opts.source_timeout())
+    {
+        parked = Some((input_path.clone(), "input is unreachable".to_string()));
+    }
+
+    if let Some((last_file, reason)) = parked {
+        report.park(reason.clone());
+
+        if opts.is_dry_run() {
+            return Ok(report);
+        }
+
+        state.set_parked(&Parked {
+            parked_at: Local::now().to_rfc3339(),
+            input_path: input_path.clone(),
+            last_file: Some(last_file),
+            reason,
+        })?;
+    }
+
+    /*
+     * サマリの出力
+     */
+    report.finish();
+    report.log_summary();
+
+    /*
+     * 取り込みマーカーの更新
+     */
+    // 処理しなかったファイルが残っている場合に後から取り込めなくならないよ
+    // う、全てのファイルを処理し終えた場合のみ更新する
+    if let Some(high_water) = new_high_water.filter(|_| !opts.is_dry_run()) {
+        if report.failed() == 0
+            && report.parked().is_none()
+            && !limited
+            && !deferred
+        {
+            // 書き込み禁止のカードでも取り込み自体は成功として扱う
+            if let Err(err) = marker::write(&input_path, high_water) {
+                warn!("card marker not written: {}", err);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+///
+/// 取り込み後の後処理を行う
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `report` - 取り込みの実行結果
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 概要
+/// 実行結果の記録、レポートの書き出し、暗号化したマニフェストの書き出し、PAR2
+/// リカバリファイルの生成、出力先のスナップショットの作成を順に行う。取り込み
+/// を中断していた場合は、スナップショットの作成を行わずにエラーを返す。SMTP
+/// サーバが設定されている場合は、最後に実行結果をメールで通知する。
+///
+pub fn finalize(opts: &Options, report: &Report) -> Result<()> {
+    let result = postprocess(opts, report);
+
+    if !opts.is_dry_run() {
+        mail::notify(opts, report, result.as_ref().err());
+    }
+
+    result
+}
+
+///
+/// 保存期間を過ぎたファイルを振り分け先から削除する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `cache` - 取り込みに用いたキャッシュオブジェクトの参照
+/// * `report` - 取り込みの実行結果
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// `--retain-days`が指定されている場合のみ、[`finalize()`]の後に呼び出す(削
+/// 除の前にスナップショット等の後処理を終えておくため)。取り込みを中断した場
+/// 合は削除を行わない。削除の前に確認を求めるため、常駐する場合や複数のカー
+/// ドからの取り込みでは`--yes`の指定が必要となる(オプション設定のバリデーシ
+/// ョンで確認する)。
+///
+pub fn prune_expired(opts: &Options, cache: &Cache, report: &Report)
+    -> Result<()>
+{
+    if opts.retain_days().is_none() || report.parked().is_some() {
+        return Ok(());
+    }
+
+    prune::run(opts, cache)?;
+
+    Ok(())
+}
+
+///
+/// 取り込み後の後処理の本体
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `report` - 取り込みの実行結果
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn postprocess(opts: &Options, report: &Report) -> Result<()> {
+    /*
+     * 実行結果の記録
+     */
+    if !opts.is_dry_run() {
+        let state = StateStore::open(opts.state_dir())?;
+
+        state.set_last_run(&LastRun::new(report, &opts.input_path()))?;
+
+        // 最後まで処理できた場合は同じ入力元の中断の記録を消す
+        if report.parked().is_none() {
+            if let Some(parked) = state.parked()? {
+                if parked.input_path == opts.input_path() {
+                    state.clear_parked()?;
+                }
+            }
+        }
+    }
+
+    /*
+     * レポートの書き出し
+     */
+    if let Some(path) = opts.report_path() {
+        report.write_json(&path)?;
+        info!("report written to {}", path.display());
+    }
+
+    if let Some(path) = opts.report_csv_path() {
+        report.write_csv(&path)?;
+        info!("csv report written to {}", path.display());
+    }
+
+    // 予行演習の場合は振り分け先に対する後処理を行わない
+    if opts.is_dry_run() {
+        return Ok(());
+    }
+
+    /*
+     * 暗号化したマニフェストの書き出し
+     */
+    encrypt::finalize(opts)?;
+
+    /*
+     * 取り込み情報ファイルの記録
+     */
+    if opts.is_folder_info() {
+        let source = opts
+            .cloud_remote()
+            .unwrap_or_else(|| opts.input_path().display().to_string());
+
+        folder_info::update(report, &source)?;
+    }
+
+    /*
+     * PAR2リカバリファイルの生成
+     */
+    if let Some(redundancy) = opts.par2_redundancy() {
+        parity::generate(report.destination_dirs(), redundancy)?;
+    }
+
+    /*
+     * 出力先のスナップショットの作成（失敗無く終了した場合のみ）
+     */
+    if opts.snapshot_mode() != SnapshotMode::Off {
+        if report.parked().is_some() {
+            warn!("snapshot skipped (run parked)");
+        } else if report.failed() > 0 {
+            warn!("snapshot skipped ({} files failed)", report.failed());
+        } else if report.copied() == 0 {
+            info!("snapshot skipped (no files copied)");
+        } else {
+            snapshot::take(opts)?;
+        }
+    }
+
+    /*
+     * 中断した場合はエラーとして終了する
+     */
+    if let Some(reason) = report.parked() {
+        return Err(anyhow!(
+            "run parked ({}), run again to resume when {} is reachable",
+            reason,
+            opts.input_path().display()
+        ));
+    }
+
+    Ok(())
+}
+
+///
+/// サブコマンドの実行関数
+///
+/// # 引数
+/// * `command` - 実行するサブコマンド
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn run_command(command: &Command, opts: &Options) -> Result<()> {
+    match command {
+        Command::Cache(command) => cache::run_command(command, opts),
+        Command::Diff(args) => diff::run(args, opts),
+        Command::Why(args) => why::run(args, opts),
+        Command::Stats(args) => stats::run(args, opts),
+        Command::AuditNames(args) => audit::run(args, opts),
+        Command::ImportAll(args) => batch::run(args, opts),
+    }
+}
+
+///
+/// 入力ファイル1件分の取り込みを行う
+///
+/// # 引数
+/// * `entry` - 入力ファイルのエントリ
+/// * `session` - 取り込みの間共有する状態
+///
+/// # 戻り値
+/// 処理結果の記録と、処理の成否の組
+///
+fn import_file(entry: &DirEntry, session: &mut ImportSession)
+    -> (FileEntry, Result<()>)
+{
+    let meta = match entry.metadata() {
+        Ok(meta) => meta,
+        Err(err) => {
+            return (FileEntry::new(entry.path(), 0), Err(err.into()));
+        }
+    };
+
+    let mut record = FileEntry::new(entry.path(), meta.len());
+    let result = process_file(entry.path(), meta, session, &mut record);
+
+    (record, result)
+}
+
+///
+/// 入力ファイル1件分の取り込みをシャドウコピー経由で行う
+///
+/// # 引数
+/// * `entry` - 入力ファイルのエントリ
+/// * `session` - 取り込みの間共有する状態
+/// * `shadow` - 作成済みのシャドウコピー(未作成の場合はNone)
+///
+/// # 戻り値
+/// 処理結果の記録と、処理の成否の組
+///
+/// # 注記
+/// シャドウコピーは最初に必要となった時点で入力元のボリュームに対して1回だけ
+/// 作成し、以降のファイルでも共有する。ファイル情報と内容はいずれもシャドウコ
+/// ピー作成時点のものを用いる。
+///
+fn import_shadowed(
+    entry: &DirEntry,
+    session: &mut ImportSession,
+    shadow: &mut Option<Shadow>,
+) -> (FileEntry, Result<()>) {
+    let cache = session.cache;
+
+    if shadow.is_none() {
+        match Shadow::create(cache.volume_prefix()) {
+            Ok(created) => {
+                cache.set_shadow_root(Some(created.root().to_path_buf()));
+                *shadow = Some(created);
+            }
+
+            Err(err) => return (FileEntry::new(entry.path(), 0), Err(err)),
+        }
+    }
+
+    let path = match shadow.as_ref().unwrap().map(entry.path()) {
+        Ok(path) => path,
+        Err(err) => return (FileEntry::new(entry.path(), 0), Err(err)),
+    };
+
+    let meta = match path.metadata() {
+        Ok(meta) => meta,
+        Err(err) => {
+            return (FileEntry::new(entry.path(), 0), Err(err.into()));
+        }
+    };
+
+    let mut record = FileEntry::new(entry.path(), meta.len());
+    record.check("vss", path.display(), true);
+    info!("reading {} through shadow copy", entry.path().display());
+
+    let result = process_file(&path, meta, session, &mut record);
+
+    (record, result)
+}
+
+///
+/// 入力ディレクトリ中の処理対象のファイルを列挙する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+///
+/// # 戻り値
+/// 処理順に並べたファイルのイテレータ
+///
+/// # 注記
+/// ファイルシステムが返す順序によらず実行ごとに同じ順序となるよう、ディレク
+/// トリ内のエントリは名前順に走査する。撮影日時の順が指定された場合は全ての
+/// ファイルのExif情報を先に読み出して並べ替える(撮影日時を読み出せないもの
+/// はパスの順で末尾に置く)。読み出したExif情報はキャッシュに保持され、その
+/// 後の処理で再利用される。
+///
+fn input_files(opts: &Options, exif_cache: &ExifCache)
+    -> Box<dyn Iterator<Item = DirEntry>>
+{
+    let root = opts.input_path();
+    let excludes = opts.excludes().to_vec();
+    let includes = opts.includes().to_vec();
+
+    let entries = WalkDir::new(&root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry({
+            let root = root.clone();
+            move |e| !is_shadow(e) && !is_glob_excluded(e, &root, &excludes)
+        })
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some())
+        .filter(move |e| is_glob_included(e, &root, &includes))
+        .filter(|e| !marker::is_marker(&e.file_name().to_string_lossy()));
+
+    match opts.order() {
+        ProcessOrder::Path => Box::new(entries),
+
+        ProcessOrder::Capture => {
+            let mut entries = entries
+                .map(|entry| {
+                    (capture_time(entry.path(), exif_cache, opts), entry)
+                })
+                .collect::<Vec<_>>();
+
+            // 安定ソートのため撮影日時が同じものはパスの順を保つ
+            entries.sort_by(|(a, _), (b, _)| {
+                a.is_none().cmp(&b.is_none()).then_with(|| a.cmp(b))
+            });
+
+            Box::new(entries.into_iter().map(|(_, entry)| entry))
+        }
+    }
+}
+
+///
+/// 入力ファイルのディレクトリの入力ディレクトリからの相対パスを求める
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 入力ディレクトリからの相対パス(入力ディレクトリの外のファイルの場合は
+/// None)
+///
+/// # 注記
+/// パステンプレートの`{original_dir}`の展開に用いる。
+///
+fn original_dir<'a>(path: &'a Path, opts: &Options) -> Option<&'a Path> {
+    path.parent()?.strip_prefix(opts.input_path()).ok()
+}
+
+///
+/// 入力ファイルのあるディレクトリの名前を求める
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+///
+/// # 戻り値
+/// ディレクトリの名前(`100CANON`等、求められない場合はNone)
+///
+/// # 注記
+/// テンプレートの`{src_dir}`の展開に用いる。
+///
+fn src_dir(path: &Path) -> Option<&str> {
+    path.parent()?.file_name()?.to_str()
+}
+
+///
+/// 並べ替えに用いる撮影日時を読み出す
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 撮影日時と秒未満の値(3桁に揃えたもの)の組。Exif情報や撮影日時を読み出せ
+/// ない場合はNone。
+///
+fn capture_time(path: &Path, exif_cache: &ExifCache, opts: &Options)
+    -> Option<(NaiveDateTime, String)>
+{
+    let (exif, _) = exif_cache.read(path).ok()?;
+    let datetime = resolve_datetime(&exif, path, opts)
+        .ok()??
+        .naive_local();
+    let view = ExifView::from(&*exif);
+
+    Some((datetime, template::format_subsec(view.subsec.as_deref())))
+}
+
+fn is_shadow(entry: &DirEntry) -> bool {
+    if let Some(name) = entry.file_name().to_str() {
+        return name.starts_with("._") || matches!(name, 
+            ".DS_Store"       |
+            ".AppleDouble"    |
+            ".Trashes"        |
+            ".Spotlight-V100" |
+            ".fseventsd"      |
+            ".TemporaryItems"
+        );
+    }
+
+    false
+}
+
+///
+/// 走査で除外するグロブパターンに一致するか否かを判定する
+///
+/// # 引数
+/// * `entry` - 走査で得たファイルまたはディレクトリ
+/// * `root` - 入力ディレクトリのパス
+/// * `patterns` - `--exclude`で指定されたパターンの一覧
+///
+/// # 戻り値
+/// 何れかのパターンに一致する場合は`true`
+///
+/// # 注記
+/// ディレクトリが一致した場合はその下を走査しない。
+///
+fn is_glob_excluded(entry: &DirEntry, root: &Path, patterns: &[Pattern])
+    -> bool
+{
+    entry.depth() > 0
+        && patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern, entry.path(), root))
+}
+
+///
+/// 走査で処理対象とするグロブパターンに一致するか否かを判定する
+///
+/// # 引数
+/// * `entry` - 走査で得たファイル
+/// * `root` - 入力ディレクトリのパス
+/// * `patterns` - `--include`で指定されたパターンの一覧
+///
+/// # 戻り値
+/// 何れかのパターンに一致する場合、またはパターンが指定されていない場合は
+/// `true`
+///
+fn is_glob_included(entry: &DirEntry, root: &Path, patterns: &[Pattern])
+    -> bool
+{
+    patterns.is_empty()
+        || patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern, entry.path(), root))
+}
+
+///
+/// 走査で得たファイルまたはディレクトリがグロブパターンに一致するか否かを判
+/// 定する
+///
+/// # 引数
+/// * `pattern` - グロブパターン
+/// * `path` - 走査で得たファイルまたはディレクトリのパス
+/// * `root` - 入力ディレクトリのパス
+///
+/// # 戻り値
+/// 一致する場合は`true`
+///
+/// # 注記
+/// `/`を含むパターンは入力ディレクトリからの相対パスと、含まないパターンは
+/// 名前と照合する。大文字と小文字は区別せず、`*`はパスの区切りに一致しない
+/// (複数の階層に一致させる場合は`**`を用いる)。相対パスの区切りはプラット
+/// フォームによらず`/`で照合する。
+///
+fn glob_matches(pattern: &Pattern, path: &Path, root: &Path) -> bool {
+    let options = MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    if !pattern.as_str().contains('/') {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        return pattern.matches_with(&name, options);
+    }
+
+    let Ok(rel) = path.strip_prefix(root) else {
+        return false;
+    };
+
+    let rel = rel
+        .components()
+        .map(|comp| comp.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    pattern.matches_with(&rel, options)
+}
+
+/// ファイルを処理する（ファイルタイプ判定とパス構築を含む）
+///
+/// # 引数
+/// * `path` - 処理するファイルのパス
+/// * `session` - 取り込みの間共有する状態
+/// * `record` - 処理結果の記録先
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を `Err()`でラップして
+/// 返す
+fn process_file<P>(
+    path: P,
+    meta: Metadata,
+    session: &mut ImportSession,
+    record: &mut FileEntry,
+) -> Result<()>
+where 
+    P: AsRef<Path>
+{
+    let path = path.as_ref();
+    let opts = session.opts;
+    let cache = session.cache;
+    let exif_cache = session.exif_cache;
+    let high_water = session.high_water;
+    let sequencer = &mut session.sequencer;
+    let clocks = &mut session.clocks;
+    
+    let ext = match path.extension() {
+        Some(ext) => ext.to_string_lossy(),
+        None => return Ok(()), // 拡張子がない場合はスキップ
+    };
+
+    // 付随ファイル(テレメトリ、ボイスメモ)は親のファイルと共にコピーするた
+    // め単独では処理しない
+    if let Some(parent) = companion::parent(path, opts) {
+        record.check("companion", parent.display(), true);
+        debug!("skip companion file: {}", path.display());
+        record.reason = Some(SkipReason::Companion);
+        return Ok(());
+    }
+
+    // 対応していない形式はExifを読む前に除外する
+    let supported = is_target_file(&ext, opts);
+    record.check("supported-ext", &ext, supported);
+
+    let unknown_dir = opts.copy_unknown_dir().filter(|_| !supported);
+
+    if !supported && unknown_dir.is_none() {
+        record.reason = Some(SkipReason::UnsupportedExt);
+        return Ok(());
+    }
+
+    /*
+     * 除外リストの評価 (他の経路で受け渡し済みのファイルか否かの判定)
+     */
+    if let Some(list) = opts.exclude_list() {
+        let excluded = list.contains(path)?;
+        record.check("exclude-list", path.display(), excluded);
+
+        if excluded {
+            info!("skip excluded file: {}", path.display());
+            record.reason = Some(SkipReason::Excluded);
+            return Ok(());
+        }
+    }
+
+    /*
+     * 取り込みマーカーの評価 (前回までに取り込んだファイルか否かの判定)
+     */
+    if let Some(high_water) = high_water {
+        let marked = meta.modified()? <= high_water
+            && !opts.is_force_target(path);
+        record.check(
+            "card-marker",
+            DateTime::<Local>::from(high_water).to_rfc3339(),
+            marked,
+        );
+
+        if marked {
+            debug!("skip file before card marker: {}", path.display());
+            record.reason = Some(SkipReason::BeforeMarker);
+            return Ok(());
+        }
+    }
+
+    // 画像以外のファイルの保全が指定されている場合はそちらで処理する
+    if let Some(dir) = unknown_dir {
+        return copy_unknown(path, meta, &dir, opts, cache, record);
+    }
+
+    /*
+     * キャッシュの評価 (処理済みか否かの判定)
+     */
+    // コピー中の更新を検出できるよう、評価時点のサイズと更新日時を控えておく
+    let stamp = (meta.len(), meta.modified()?);
+    let eval_start = Instant::now();
+    let forced = opts.is_force_target(path);
+    record.check("force-target", path.display(), forced);
+
+    let decision = if forced {
+        // 強制再処理の対象の場合はキャッシュ情報を無視する
+        cache.evaluate_forced(path, meta, exif_cache, &mut record.timings)?
+    } else {
+        cache.evaluate(path, meta, exif_cache, &mut record.timings)?
+    };
+
+    // Exif情報の読み出し時間はキャッシュ評価の時間から除いて記録する
+    record.timings.cache_eval = Some(
+        eval_start
+            .elapsed()
+            .saturating_sub(record.timings.exif_read.unwrap_or_default())
+    );
+
+    record.check(
+        "cache-hit",
+        format!("{:?}", opts.cache_eval_mode()).to_lowercase(),
+        matches!(decision, CacheDecision::Hit),
+    );
+
+    match decision {
+        // キャッシュにヒットする場合は処理済みのファイル(または、以前処理したと
+        // きからファイルの状態は変化無し)なのでスキップ
+        CacheDecision::Hit => {
+            info!("skip processed file: {}", path.display());
+            record.reason = Some(SkipReason::CacheHit);
+        }
+
+        // キャッシュにミスした場合は未処理ファイル(または、以前処理したときから
+        // ファイルの状態は変化あり)なので処理対象とする。
+        CacheDecision::Miss {mut handle, exif} => {
+            // RAWファイルのExif情報をペアのJPEGファイルで代用した場合はその
+            // 旨を記録する
+            if let Some(jpeg) = handle.paired_exif() {
+                record.check("paired-exif", jpeg, true);
+            }
+
+            /*
+             * 履歴データベースの照会 (他のマシンで取り込み済みか否かの判定)
+             */
+            // 強制再処理の対象の場合は照会しない
+            if let Some(history) = opts.history().filter(|_| !forced) {
+                let archived = history.contains(&handle);
+                record.check("history", path.display(), archived);
+
+                if archived {
+                    info!("skip archived file: {}", path.display());
+                    record.reason = Some(SkipReason::Archived);
+                    return Ok(());
+                }
+            }
+
+            /*
+             * 内容のハッシュ値の照会 (名前の変更・移動のみのファイルか否かの
+             * 判定)
+             */
+            // 強制再処理の対象の場合もハッシュ値は索引に登録する
+            if opts.is_hash_index() {
+                let algo = opts.checksum_algorithm();
+                let known = cache.find_by_hash(&mut handle, path, algo)?;
+                let renamed = known.is_some() && !forced;
+                record.check(
+                    "hash-index",
+                    known.unwrap_or_default(),
+                    renamed,
+                );
+
+                // 新しいパスでもキャッシュにヒットするようコミットしておく
+                if renamed {
+                    info!("skip renamed file: {}", path.display());
+                    record.reason = Some(SkipReason::CacheHit);
+                    cache.commit(handle)?;
+                    return Ok(());
+                }
+            }
+
+            /*
+             * 振り分け先全体での重複の照会 (内容が一致するファイルが格納済み
+             * か否かの判定)
+             */
+            if opts.is_dedup() && !forced {
+                let algo = opts.checksum_algorithm();
+                let stored = cache.find_in_archive(&mut handle, path, algo)?;
+                record.check(
+                    "dedup",
+                    stored.as_deref().unwrap_or(Path::new("")).display(),
+                    stored.is_some(),
+                );
+
+                // 再度照会しないよう格納済みのファイルを振り分け先として記録
+                // しておく
+                if let Some(stored) = stored {
+                    info!(
+                        "skip duplicate file: {} (stored as {})",
+                        path.display(),
+                        stored.display()
+                    );
+                    record.reason = Some(SkipReason::Duplicate);
+                    record.destination = Some(stored.clone());
+                    handle.set_destination(&stored);
+                    cache.commit(handle)?;
+                    return Ok(());
+                }
+            }
+
+            /*
+             * 撮影日時を取得
+             */
+            let field = get_datetime_field(&exif);
+            let (datetime, exact) = if let Some(field) = field {
+                let value = field.display_value().to_string();
+                record.check("has-datetime", &value, true);
+                (parse_datetime(&value, opts.datetime_formats())?, true)
+            } else {
+                record.check("has-datetime", "", false);
+
+                let mode = datetime_fallback(path, opts);
+                let fallback = fallback::datetime(path, mode);
+                record.check(
+                    "datetime-fallback",
+                    format!("{:?}", mode).to_lowercase(),
+                    fallback.is_some(),
+                );
+
+                match fallback {
+                    Some(datetime) => {
+                        info!(
+                            "datetime of {} taken from {:?}: {}",
+                            path.display(),
+                            mode,
+                            datetime.to_rfc3339(),
+                        );
+                        (datetime, false)
+                    }
+
+                    None => {
+                        warn!(
+                            "not contained datetime info in {}",
+                            path.display()
+                        );
+                        record.reason = Some(SkipReason::NoExif);
+                        return Ok(());
+                    }
+                }
+            };
+
+            /*
+             * GPS時刻によるカメラの時計のずれの確認
+             */
+            // 撮影日時を補った場合はずれの集計を乱さないよう確認しない
+            let view = ExifView::from(&*exif);
+            let datetime = if exact {
+                check_clock(&view, datetime, opts, clocks, record)
+            } else {
+                datetime
+            };
+
+            // レポートに記録する撮影日とカメラの機種名
+            record.date = Some(shooting_date(&datetime, opts).to_string());
+            record.camera = view.camera();
+
+            /*
+             * カメラによる除外のチェック
+             */
+            // 除外したファイルは後から取り込めるよう、キャッシュを更新せずに
+            // スキップする
+            if !opts.exclude_cameras().is_empty()
+                || !opts.exclude_serials().is_empty()
+            {
+                let excluded = is_excluded_camera(&view, opts);
+                record.check(
+                    "exclude-camera",
+                    format!(
+                        "{} ({})",
+                        record.camera.as_deref().unwrap_or(""),
+                        view.serial.as_deref().unwrap_or("")
+                    ),
+                    excluded,
+                );
+
+                if excluded {
+                    debug!("skipping {} (excluded camera)", path.display());
+                    record.reason = Some(SkipReason::ExcludedCamera);
+                    return Ok(());
+                }
+            }
+
+            /*
+             * 画像の向きのチェック
+             */
+            // 向きの異なるファイルは後から別の指定で取り込めるよう、キャッシュ
+            // を更新せずにスキップする
+            if let Some(wanted) = opts.orientation() {
+                let actual = view.orientation();
+                record.check(
+                    "orientation",
+                    format!("{:?}", actual).to_lowercase(),
+                    actual == Some(wanted),
+                );
+
+                if actual != Some(wanted) {
+                    debug!(
+                        "skipping {} (orientation {:?})",
+                        path.display(),
+                        actual
+                    );
+                    record.reason = Some(SkipReason::Orientation);
+                    return Ok(());
+                }
+            }
+
+            /*
+             * 日付範囲のチェック
+             */
+            let date = shooting_date(&datetime, opts);
+            let in_range = is_date_in_range(&date, opts);
+            record.check("in-date-range", date, in_range);
+
+            if in_range && opts.is_mark_processed() {
+                // コピーを行わずにキャッシュ情報のみを記録する
+                info!("mark processed: {}", path.display());
+                record.reason = Some(SkipReason::MarkedProcessed);
+            } else if in_range {
+                // ファイルタイプと保存先パスを構築(シャドウコピー経由で読み
+                // 出した場合も元のファイルのディレクトリを用いる)
+                let source = record.source.clone();
+                let ctx = PathContext {
+                    date,
+                    artist: view.artist.as_deref(),
+                    event: opts.event_at(&datetime),
+                    camera: view.camera_name(),
+                    ext: &ext,
+                    original_dir: original_dir(&source, opts),
+                    src_dir: src_dir(&source),
+                    volume_label: cache.volume_label(),
+                };
+
+                if let Some(file_type) = build_file_type(
+                    &ext,
+                    &ctx,
+                    opts
+                ) {
+                    record.check("file-type", file_type.label(), true);
+                    let is_jpeg = matches!(file_type, FileType::Jpeg(_));
+
+                    // 振り分け先でのファイル名を決定
+                    let name_ctx = NameContext {
+                        datetime: datetime.naive_local(),
+                        subsec: view.subsec.as_deref(),
+                        artist: ctx.artist,
+                        event: ctx.event,
+                        stem: &file_stem(path),
+                        src_dir: ctx.src_dir,
+                        volume_label: ctx.volume_label,
+                    };
+                    let name = build_name(
+                        path,
+                        &file_type,
+                        &name_ctx,
+                        opts,
+                        sequencer,
+                    );
+
+                    /*
+                     * 同名のファイルの取り扱い (ティアまたは--on-conflictの
+                     * 指定による)
+                     */
+                    let tier = opts.tier_for(file_type.label(), &ext);
+                    let collision = opts.collision_for(tier);
+                    let exists = file_type
+                        .dir()
+                        .join(stored_name(&name, &file_type, opts))
+                        .exists();
+                    record.check(
+                        "collision",
+                        format!("{:?}", collision).to_lowercase(),
+                        exists,
+                    );
+
+                    let name = apply_collision(
+                        name,
+                        exists,
+                        collision,
+                        file_type.dir(),
+                        |name| stored_name(name, &file_type, opts),
+                    )?;
+
+                    // 既存のファイルを残す場合は処理済みとして扱う
+                    let Some(name) = name else {
+                        info!("skip existing file: {}", path.display());
+                        record.reason = Some(SkipReason::Collision);
+                        cache.commit(handle)?;
+                        return Ok(());
+                    };
+
+                    // 予行演習の場合はコピーの予定のみを記録する
+                    if opts.is_dry_run() {
+                        let dst = file_type
+                            .dir()
+                            .join(stored_name(&name, &file_type, opts));
+                        return plan_copy(path, dst, handle, cache, record);
+                    }
+
+                    // 振り分け先のドライブの状態によりコピーを待つ
+                    health::wait(file_type.dir(), opts);
+
+                    let copy_start = Instant::now();
+                    let new_dirs = permission::missing_dirs(file_type.dir());
+                    let compress_level = opts
+                        .compress_raw_level()
+                        .filter(|_| matches!(file_type, FileType::Raw(_)));
+
+                    // --verifyまたはティアの指定、あるいは抜き取りの対象と
+                    // なった場合は内容を照合する(暗号化・圧縮した場合は内容
+                    // が異なるため行わない)
+                    let verify = opts.is_verify()
+                        || tier.is_some_and(|tier| tier.verify);
+                    let sampled = !verify && is_sampled(opts);
+                    let stored_as_is = opts.encrypt_recipient().is_none()
+                        && compress_level.is_none();
+
+                    if (verify || sampled) && !stored_as_is {
+                        warn!(
+                            "verification skipped for {}: stored content \
+                             differs from the source",
+                            path.display()
+                        );
+                    }
+
+                    // 照合に用いる入力ファイルのハッシュ値はコピーと同時に
+                    // 求める
+                    let algo = ((verify || sampled) && stored_as_is)
+                        .then(|| opts.checksum_algorithm());
+                    let mut digest = None;
+
+                    let dst = copy_stable(path, stamp, || {
+                        if opts.encrypt_recipient().is_some() {
+                            encrypt::distribute(
+                                path,
+                                file_type.dir(),
+                                &name,
+                                opts
+                            )
+                        } else if let Some(level) = compress_level {
+                            compress::distribute(
+                                path,
+                                file_type.dir(),
+                                &name,
+                                level,
+                                opts
+                            )
+                        } else if opts.layout() == StorageLayout::Cas {
+                            let (dst, hash) = cas::distribute(
+                                path,
+                                file_type.dir(),
+                                &name,
+                                opts
+                            )?;
+                            digest = Some((ChecksumAlgorithm::Sha256, hash));
+                            Ok(dst)
+                        } else {
+                            let (dst, hash) = distribute(
+                                path,
+                                file_type.clone(),
+                                &name,
+                                algo,
+                                opts
+                            )?;
+                            digest = algo.zip(hash);
+                            Ok(dst)
+                        }
+                    })?;
+
+                    // 更新が収まらない場合はキャッシュを更新せずに次回の取
+                    // り込みに回す
+                    let dst = match dst {
+                        Some(dst) => dst,
+                        None => {
+                            record.reason =
+                                Some(SkipReason::ModifiedDuringCopy);
+                            return Ok(());
+                        }
+                    };
+
+                    record.timings.copy = Some(copy_start.elapsed());
+
+                    if let Some(digest) = algo.and(digest.as_ref()) {
+                        verify_copy(path, &dst, digest)?;
+                        record.check(
+                            if verify { "verify" } else { "verify-sample" },
+                            dst.display(),
+                            true,
+                        );
+                        record.verified = true;
+                    }
+
+                    record.destination = Some(dst.clone());
+                    record.action = Action::Copied;
+                    handle.set_destination(&dst);
+
+                    permission::apply(&dst, &new_dirs, opts)?;
+                    finder::tag(&dst, &ctx, opts)?;
+
+                    if opts.is_checksums() {
+                        checksum::record(&dst, opts.checksum_algorithm())?;
+                    }
+
+                    // 付随ファイル(テレメトリ、ボイスメモ)を隣にコピーする
+                    record.companions = companion::copy_all(
+                        path,
+                        file_type.dir(),
+                        &name,
+                        collision,
+                        opts
+                    )?;
+
+                    // JPEGの場合はモーションフォトの処理を行う(暗号化した
+                    // 場合は内容を参照できないため行わない)
+                    if is_jpeg && opts.encrypt_recipient().is_none() {
+                        process_motion_photo(&dst, opts)?;
+                    }
+                }
+
+            } else {
+                debug!(
+                    "skipping {} (date {} is out of range)",
+                    path.display(),
+                    date
+                );
+                record.reason = Some(SkipReason::OutOfRange);
+            }
+
+            /*
+             * キャッシュデータをコミット
+             */
+            cache.commit(handle)?;
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// GPS時刻によりカメラの時計のずれを確認する
+///
+/// # 引数
+/// * `exif` - Exif情報
+/// * `datetime` - 撮影日時(DateTimeOriginal)
+/// * `opts` - オプション設定の参照
+/// * `clocks` - カメラの時計のずれの集計
+/// * `record` - 処理結果の記録先
+///
+/// # 戻り値
+/// 振り分け先の決定に用いる撮影日時(補正を適用する場合は補正後のもの)
+///
+/// # 注記
+/// GPS時刻が記録されているファイルのずれをカメラごとに集計し、補正を適用する
+/// 指定の場合は、ずれが一定で閾値以上であると判断できたカメラのファイル(GPS
+/// 時刻を持たないものも含む)の撮影日時からそのずれを差し引く。
+///
+fn check_clock(
+    view: &ExifView,
+    datetime: DateTime<Local>,
+    opts: &Options,
+    clocks: &mut ClockTracker,
+    record: &mut FileEntry,
+) -> DateTime<Local> {
+    let mode = opts.gps_clock_mode();
+
+    if mode == GpsClockMode::Off {
+        return datetime;
+    }
+
+    let camera = clock::camera_key(view);
+    let threshold = opts.gps_clock_threshold().as_secs();
+
+    if let Some(offset) = clock::gps_offset(view, &datetime) {
+        record.check(
+            "gps-clock-offset",
+            format!("{}s", offset),
+            offset.unsigned_abs() < threshold,
+        );
+        clocks.add(&camera, offset);
+    }
+
+    if mode != GpsClockMode::Fix {
+        return datetime;
+    }
+
+    match clocks.offset(&camera) {
+        Some(offset) if offset.unsigned_abs() >= threshold => {
+            record.check("clock-corrected", format!("{}s", offset), true);
+            datetime - chrono::Duration::seconds(offset)
+        }
+
+        _ => datetime,
+    }
+}
+
+///
+/// 画像以外のファイルを保全用のディレクトリにコピーする
+///
+/// # 引数
+/// * `path` - 処理するファイルのパス
+/// * `meta` - ファイルのメタデータ
+/// * `dir` - 保全用のディレクトリ
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュデータベース
+/// * `record` - 処理結果の記録先
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// Exif情報を持たないため、撮影日時の代わりにファイルの更新日時を用いて振り分
+/// け先を決定する。
+///
+fn copy_unknown(
+    path: &Path,
+    meta: Metadata,
+    dir: &Path,
+    opts: &Options,
+    cache: &Cache,
+    record: &mut FileEntry,
+) -> Result<()> {
+    /*
+     * キャッシュの評価
+     */
+    let forced = opts.is_force_target(path);
+    record.check("force-target", path.display(), forced);
+
+    let mut handle = match cache.evaluate_plain(path, &meta, forced)? {
+        Some(handle) => handle,
+        None => {
+            record.check("cache-hit", "plain", true);
+            info!("skip processed file: {}", path.display());
+            record.reason = Some(SkipReason::CacheHit);
+            return Ok(());
+        }
+    };
+
+    record.check("cache-hit", "plain", false);
+
+    /*
+     * 更新日時による日付範囲のチェック
+     */
+    let stamp = (meta.len(), meta.modified()?);
+    let datetime = DateTime::<Local>::from(meta.modified()?);
+    let date = shooting_date(&datetime, opts);
+    let in_range = is_date_in_range(&date, opts);
+    record.date = Some(date.to_string());
+    record.check("in-date-range", date, in_range);
+
+    if in_range && opts.is_mark_processed() {
+        info!("mark processed: {}", path.display());
+        record.reason = Some(SkipReason::MarkedProcessed);
+    } else if in_range {
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy())
+            .unwrap_or_default();
+        let ctx = PathContext {
+            date,
+            artist: None,
+            event: opts.event_at(&datetime),
+            camera: None,
+            ext: &ext,
+            original_dir: original_dir(path, opts),
+            src_dir: src_dir(path),
+            volume_label: cache.volume_label(),
+        };
+
+        let file_type = FileType::Other(
+            dir.join(opts.path_template().expand(&ctx))
+        );
+        record.check("file-type", "unknown", true);
+
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+        /*
+         * 同名のファイルの取り扱い (--on-conflictの指定による)
+         */
+        let collision = opts.on_conflict();
+        let exists = file_type.dir().join(&name).exists();
+        record.check(
+            "collision",
+            format!("{:?}", collision).to_lowercase(),
+            exists,
+        );
+
+        let name = apply_collision(
+            name,
+            exists,
+            collision,
+            file_type.dir(),
+            str::to_string,
+        )?;
+
+        let Some(name) = name else {
+            info!("skip existing file: {}", path.display());
+            record.reason = Some(SkipReason::Collision);
+            cache.commit(handle)?;
+            return Ok(());
+        };
+
+        // 予行演習の場合はコピーの予定のみを記録する
+        if opts.is_dry_run() {
+            let dst = file_type.dir().join(&name);
+            return plan_copy(path, dst, handle, cache, record);
+        }
+
+        health::wait(file_type.dir(), opts);
+
+        let copy_start = Instant::now();
+        let new_dirs = permission::missing_dirs(file_type.dir());
+
+        // 照合に用いる入力ファイルのハッシュ値はコピーと同時に求める
+        let verify = opts.is_verify();
+        let sampled = !verify && is_sampled(opts);
+        let algo = (verify || sampled).then(|| opts.checksum_algorithm());
+        let mut digest = None;
+
+        let dst = copy_stable(path, stamp, || {
+            if opts.layout() == StorageLayout::Cas {
+                let (dst, hash) =
+                    cas::distribute(path, file_type.dir(), &name, opts)?;
+                digest = Some((ChecksumAlgorithm::Sha256, hash));
+                Ok(dst)
+            } else {
+                let (dst, hash) =
+                    distribute(path, file_type.clone(), &name, algo, opts)?;
+                digest = algo.zip(hash);
+                Ok(dst)
+            }
+        })?;
+
+        let dst = match dst {
+            Some(dst) => dst,
+            None => {
+                record.reason = Some(SkipReason::ModifiedDuringCopy);
+                return Ok(());
+            }
+        };
+
+        record.timings.copy = Some(copy_start.elapsed());
+
+        if let Some(digest) = algo.and(digest.as_ref()) {
+            verify_copy(path, &dst, digest)?;
+            record.check(
+                if verify { "verify" } else { "verify-sample" },
+                dst.display(),
+                true,
+            );
+            record.verified = true;
+        }
+
+        record.destination = Some(dst.clone());
+        record.action = Action::Copied;
+        handle.set_destination(&dst);
+
+        permission::apply(&dst, &new_dirs, opts)?;
+        finder::tag(&dst, &ctx, opts)?;
+
+        if opts.is_checksums() {
+            checksum::record(&dst, opts.checksum_algorithm())?;
+        }
+    } else {
+        record.reason = Some(SkipReason::OutOfRange);
+    }
+
+    cache.commit(handle)
+}
+
+///
+/// 予行演習の場合にコピーの予定を記録する
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+/// * `dst` - コピーする予定の振り分け先のパス
+/// * `handle` - キャッシュの評価で得たコミット用ハンドル
+/// * `cache` - キャッシュデータベース
+/// * `record` - 処理結果の記録先
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 振り分け先のディレクトリの作成やファイルのコピーは行わず、コピーの予定を
+/// ログに出力してコピーしたものとして記録する。キャッシュのコミットも予行演
+/// 習用のキャッシュに渡し、コミットする予定のキーの出力のみを行う。
+///
+fn plan_copy(
+    path: &Path,
+    dst: PathBuf,
+    mut handle: TxnHandle,
+    cache: &Cache,
+    record: &mut FileEntry,
+) -> Result<()> {
+    info!("would copy {} -> {}", path.display(), dst.display());
+    record.check("dry-run", dst.display(), true);
+
+    handle.set_destination(&dst);
+    record.destination = Some(dst);
+    record.action = Action::Copied;
+
+    cache.commit(handle)
+}
+
+///
+/// 入力ファイルが更新されていないことを確認しながらコピーを行う
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+/// * `stamp` - キャッシュ評価時点の入力ファイルのサイズと更新日時
+/// * `copy` - 振り分け先へのコピーを行うクロージャ
+///
+/// # 戻り値
+/// コピーが成功した場合は振り分け先のパスを`Ok(Some())`でラップして返す。入
+/// 力ファイルの更新が収まらなかった場合は`Ok(None)`を返す。失敗した場合はエ
+/// ラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// カメラが書き込み中のファイルや同期ツールが触れたファイルを中途半端な内容
+/// のまま格納しないよう、コピーの前後でサイズと更新日時が変わった場合はコピー
+/// したファイルを破棄し、時間をおいて再試行する。
+///
+fn copy_stable<F>(path: &Path, stamp: (u64, SystemTime), mut copy: F)
+    -> Result<Option<PathBuf>>
+where
+    F: FnMut() -> Result<PathBuf>
+{
+    let mut stamp = stamp;
+
+    for attempt in 1..=COPY_ATTEMPTS {
+        let dst = copy()?;
+
+        let meta = std::fs::metadata(path)?;
+        let current = (meta.len(), meta.modified()?);
+
+        if current == stamp {
+            return Ok(Some(dst));
+        }
+
+        /*
+         * 更新された場合はコピーしたファイルを破棄
+         */
+        // オブジェクトストアの場合は振り分け先のリンクのみを削除する(オブジェ
+        // クト自体は内容とハッシュ値が一致しているため残しても問題無い)
+        std::fs::remove_file(&dst)?;
+
+        warn!(
+            "{} was modified during copy (attempt {}/{})",
+            path.display(),
+            attempt,
+            COPY_ATTEMPTS
+        );
+
+        stamp = current;
+
+        if attempt < COPY_ATTEMPTS {
+            std::thread::sleep(COPY_RETRY_WAIT);
+        }
+    }
+
+    Ok(None)
+}
+
+///
+/// ファイルの処理の失敗を処理結果に記録する
+///
+/// # 引数
+/// * `record` - 処理結果の記録先
+/// * `err` - 処理で発生したエラー
+///
+/// # 注記
+/// 振り分け先へのコピーを終えた後(照合やキャッシュのコミット)で失敗した場合
+/// も処理種別を`Failed`に置き換えるため、入力ファイルは移動の対象とならない。
+///
+fn record_failure(record: &mut FileEntry, err: Error) {
+    error!("{}", err);
+    record.action = Action::Failed;
+    record.error = Some(err.to_string());
+    record.error_kind = Some(error::classify(&err));
+}
+
+///
+/// 移動の指定時に、処理を終えた入力ファイルを入力ディレクトリから削除する
+///
+/// # 引数
+/// * `src` - 入力ファイルのパス
+/// * `record` - 入力ファイルの処理結果
+/// * `opts` - オプション設定の参照
+///
+/// # 注記
+/// 削除するのは処理種別が`Copied`のもの(コピー、照合、キャッシュのコミット
+/// を全て終えたもの)に限る。予行演習の場合は削除の予定をログに出力するのみ
+/// とする。
+///
+fn move_source(src: &Path, record: &mut FileEntry, opts: &Options) {
+    if !opts.is_move() || record.action != Action::Copied {
+        return;
+    }
+
+    if opts.is_dry_run() {
+        info!("would remove {}", src.display());
+        return;
+    }
+
+    if let Some(dst) = record.destination.clone() {
+        let result = remove_moved(src, &dst);
+
+        if let Err(ref err) = result {
+            warn!("{}", err);
+        }
+
+        record.check("move", src.display(), result.is_ok());
+    }
+}
+
+///
+/// 移動の指定時に、振り分け先への格納を終えた入力ファイルを削除する
+///
+/// # 引数
+/// * `src` - 入力ファイルのパス
+/// * `dst` - 振り分け先のファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 照合や付随ファイルのコピー、モーションフォトの処理で入力ファイルを参照す
+/// るため、移動は同一ボリューム内であってもリネームは用いず、コピーを終えた
+/// 後に入力ファイルを削除する形で行う。電源断等で両方を失うことの無いよう、
+/// 削除の前に振り分け先のファイル(Unixではそのディレクトリも)の内容をスト
+/// レージに書き出す。
+///
+fn remove_moved(src: &Path, dst: &Path) -> Result<()> {
+    File::open(dst)?.sync_all()?;
+
+    #[cfg(unix)]
+    if let Some(dir) = dst.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+
+    if let Err(err) = std::fs::remove_file(src) {
+        return Err(anyhow!("remove {} failed: {}", src.display(), err));
+    }
+
+    info!("moved {} to {}", src.display(), dst.display());
+
+    Ok(())
+}
+
+///
+/// 振り分け先の決定に用いる撮影日時を求める
+///
+/// # 引数
+/// * `exif` - Exif情報を格納したオブジェクトの参照
+/// * `path` - 対象ファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 撮影日時(撮影日時が無く、`--datetime-fallback`によっても補えない場合は
+/// None)を`Ok()`でラップして返す。撮影日時の解析に失敗した場合はエラー情報を
+/// `Err()`でラップして返す。
+///
+/// # 注記
+/// 振り分けを行わずに振り分け先を求める処理(見積もり、名前の衝突の検査等)で
+/// 用いる。
+///
+fn resolve_datetime(exif: &Exif, path: &Path, opts: &Options)
+    -> Result<Option<DateTime<Local>>>
+{
+    match get_datetime_field(exif) {
+        Some(field) => Ok(Some(parse_datetime(
+            &field.display_value().to_string(),
+            opts.datetime_formats(),
+        )?)),
+
+        None => Ok(fallback::datetime(path, datetime_fallback(path, opts))),
+    }
+}
+
+///
+/// 撮影日時が無い場合の補い方を求める
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 設定で撮影日時の取得元を指定した形式の場合はその方法、取り込みプロファイ
+/// ルのフォルダの規則で定められている場合はその方法、それ以外の場合は
+/// `--datetime-fallback`で指定された方法
+///
+fn datetime_fallback(path: &Path, opts: &Options) -> DatetimeFallback {
+    path.extension()
+        .and_then(|ext| opts.classes().date_source(&ext.to_string_lossy()))
+        .or_else(|| profile::datetime_fallback(original_dir(path, opts), opts))
+        .unwrap_or(opts.datetime_fallback())
+}
+
+/// Exif情報から撮影日時フィールドを取得する
+///
+/// # 引数
+/// * `exif` - Exif情報を格納したオブジェクトの参照
+///
+/// # 戻り値
+/// 撮影日時フィールドが存在する場合は`Some(&Field)`を返す。存在しない場合は
+/// `None`を返す。
+fn get_datetime_field(exif: &Exif) -> Option<&Field> {
+    exif.get_field(Tag::DateTimeOriginal, exif::In::PRIMARY)
+}
+
+///
+/// 除外するカメラで撮影されたファイルか否かを判定する
+///
+/// # 引数
+/// * `view` - Exif情報から取り出した項目
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 機種名またはシリアル番号が除外の指定に一致する場合は`true`
+///
+/// # 注記
+/// 機種名は`Model`のみ、または`Make/Model`の形式の何れかと大文字小文字を区別
+/// せずに比較する。シリアル番号は完全に一致するもののみとする。
+///
+fn is_excluded_camera(view: &ExifView, opts: &Options) -> bool {
+    if let Some(camera) = view.camera() {
+        let model = view.model.as_deref().unwrap_or_default();
+        let hit = opts.exclude_cameras().iter().any(|name| {
+            name.eq_ignore_ascii_case(&camera)
+                || (!model.is_empty() && name.eq_ignore_ascii_case(model))
+        });
+
+        if hit {
+            return true;
+        }
+    }
+
+    match view.serial.as_ref() {
+        Some(serial) => opts.exclude_serials().contains(serial),
+        None => false,
+    }
+}
+
+///
+/// パスから拡張子を除いたファイル名を取得する
+///
+/// # 引数
+/// * `path` - ファイルのパス
+///
+/// # 戻り値
+/// 拡張子を除いたファイル名
+///
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+///
+/// 振り分け先でのファイル名を決定する
+///
+/// # 引数
+/// * `path` - 処理するファイルのパス
+/// * `file_type` - ファイルタイプと保存先パス
+/// * `ctx` - ファイル名テンプレートの展開に用いる値
+/// * `opts` - オプション設定の参照
+/// * `sequencer` - ファイル名の通し番号のカウンタ
+///
+/// # 戻り値
+/// 振り分け先でのファイル名(暗号化・圧縮による名前の変更を適用する前のもの)
+///
+/// # 注記
+/// ファイル名テンプレートが指定されていない場合は元のファイル名をそのまま用い
+/// る。拡張子は元のファイルのものを引き継ぐ。テンプレートに`{seq}`を含む場合
+/// は、保存先に同名のファイルが存在しない番号が得られるまで通し番号を進める。
+///
+fn build_name(
+    path: &Path,
+    file_type: &FileType,
+    ctx: &NameContext,
+    opts: &Options,
+    sequencer: &mut Sequencer,
+) -> String {
+    let template = match opts.name_template() {
+        Some(template) => template,
+        None => return path.file_name().unwrap().to_string_lossy().to_string(),
+    };
+
+    let ext = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    if !template.has_sequence() {
+        return format!("{}{}", template.expand(ctx, 0), ext);
+    }
+
+    let dir = file_type.dir();
+    let key = dir.join(format!("{}{}", template.expand(ctx, 0), ext));
+
+    loop {
+        let seq = sequencer.next(key.clone());
+        let name = format!("{}{}", template.expand(ctx, seq), ext);
+
+        if !dir.join(stored_name(&name, file_type, opts)).exists() {
+            return name;
+        }
+    }
+}
+
+///
+/// 同名のファイルが振り分け先に存在する場合の取り扱いを適用する
+///
+/// # 引数
+/// * `name` - 振り分け先でのファイル名
+/// * `exists` - 同名のファイルが振り分け先に存在するか否か
+/// * `collision` - 同名のファイルの取り扱い
+/// * `dir` - 格納先のディレクトリ
+/// * `stored` - ファイル名から格納される際の名前を求めるクロージャ
+///
+/// # 戻り値
+/// 格納に用いるファイル名を`Ok()`でラップして返す(既存のファイルを残す場合は
+/// None)。エラーとする指定の場合は衝突したパスを示すエラー情報を`Err()`でラ
+/// ップして返す。
+///
+pub(crate) fn apply_collision<F>(
+    name: String,
+    exists: bool,
+    collision: Collision,
+    dir: &Path,
+    stored: F,
+) -> Result<Option<String>>
+where
+    F: Fn(&str) -> String,
+{
+    if !exists {
+        return Ok(Some(name));
+    }
+
+    match collision {
+        Collision::Skip => Ok(None),
+        Collision::Rename => Ok(Some(unique_name(&name, dir, stored))),
+        Collision::Error => {
+            Err(ImportError::Conflict(dir.join(stored(&name))).into())
+        }
+        Collision::Overwrite => Ok(Some(name)),
+    }
+}
+
+///
+/// 振り分け先に存在しないファイル名を求める
+///
+/// # 引数
+/// * `name` - 振り分け先でのファイル名
+/// * `dir` - 格納先のディレクトリ
+/// * `stored` - ファイル名から格納される際の名前を求めるクロージャ
+///
+/// # 戻り値
+/// 拡張子の前に`_1`、`_2`…を付けたファイル名のうち、格納される際の名前が振
+/// り分け先に存在しない最初のもの
+///
+fn unique_name<F>(name: &str, dir: &Path, stored: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let stem = file_stem(Path::new(name));
+    let ext = Path::new(name)
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut number = 1;
+
+    loop {
+        let candidate = format!("{}_{}{}", stem, number, ext);
+        if !dir.join(stored(&candidate)).exists() {
+            return candidate;
+        }
+
+        number += 1;
+    }
+}
+
+///
+/// コピーしたファイルを抜き取りによる照合の対象とするか否かを判定する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// `--verify-sample`の割合で無作為に抽選し、当選した場合は`true`(指定が無
+/// い場合は常に`false`)
+///
+/// # 注記
+/// 乱数はOSの乱数生成器から得る。得られなかった場合は照合の対象とする(照合
+/// を取りこぼさない側に倒す)。
+///
+fn is_sampled(opts: &Options) -> bool {
+    let percent = match opts.verify_sample() {
+        Some(percent) => percent,
+        None => return false,
+    };
+
+    let mut buf = [0u8; 8];
+
+    if let Err(err) = getrandom::getrandom(&mut buf) {
+        warn!("random number generation failed: {}", err);
+        return true;
+    }
+
+    is_drawn(percent, u64::from_le_bytes(buf))
+}
+
+///
+/// 乱数の値が抽選の割合に収まるか否かを判定する
+///
+/// # 引数
+/// * `percent` - 当選とする割合(百分率)
+/// * `value` - 一様に分布する乱数の値
+///
+/// # 戻り値
+/// 当選した場合は`true`
+///
+/// # 注記
+/// 割合は0.0001%単位で扱う。0%では常に落選し、100%では常に当選する。
+///
+fn is_drawn(percent: f64, value: u64) -> bool {
+    let threshold = (percent * 10_000.0).round() as u64;
+
+    value % 1_000_000 < threshold
+}
+
+///
+/// コピーしたファイルの内容を入力ファイルと照合する
+///
+/// # 引数
+/// * `src` - 入力ファイルのパス
+/// * `dst` - 振り分け先にコピーしたファイルのパス
+/// * `digest` - コピーの際に求めた入力ファイルのハッシュ関数とハッシュ値
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 入力ファイルは読み直さず、コピーのために読み出した内容から求めたハッシュ
+/// 値と振り分け先のファイルのハッシュ値を比較する(通常は
+/// `--checksum-algorithm`で指定したハッシュ関数、オブジェクトストアに格納した
+/// 場合はSHA-256)。一致しない場合は、次回の取り込みでコピーし直せるよう振り
+/// 分け先のファイルを削除してからエラーを返す。
+///
+fn verify_copy(
+    src: &Path,
+    dst: &Path,
+    digest: &(ChecksumAlgorithm, String),
+) -> Result<()> {
+    let (algo, expect) = digest;
+
+    if checksum::digest_file(dst, *algo)? == *expect {
+        return Ok(());
+    }
+
+    let _ = std::fs::remove_file(dst);
+
+    Err(anyhow!(
+        "verify failed: {} differs from {}",
+        dst.display(),
+        src.display()
+    ))
+}
+
+///
+/// 保存先に格納される際のファイル名を求める
+///
+/// # 引数
+/// * `name` - 振り分け先でのファイル名
+/// * `file_type` - ファイルタイプと保存先パス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 暗号化または圧縮を行う場合はそれらによる名前の変更を適用したファイル名
+///
+fn stored_name(name: &str, file_type: &FileType, opts: &Options) -> String {
+    if opts.encrypt_recipient().is_some() {
+        encrypt::encrypted_name(name, opts)
+    } else if opts.compress_raw_level().is_some()
+        && matches!(file_type, FileType::Raw(_))
+    {
+        compress::compressed_name(name)
+    } else {
+        name.to_string()
+    }
+}
+
+///
+/// 撮影日時から撮影日を求める
+///
+/// # 引数
+/// * `datetime` - 撮影日時
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 撮影日の区切り時刻を適用した撮影日（区切り時刻より前の撮影は前日となる）
+fn shooting_date(datetime: &DateTime<Local>, opts: &Options) -> NaiveDate {
+    let shift = opts.day_start().signed_duration_since(NaiveTime::MIN);
+    (datetime.naive_local() - shift).date()
+}
+
+/// 撮影日が指定された日付範囲内かどうかを判定する
+///
+/// # 引数
+/// * `date` - 撮影日（区切り時刻を適用済みのもの）
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 日付範囲内の場合は`true`、範囲外の場合は`false`
+fn is_date_in_range(date: &NaiveDate, opts: &Options) -> bool {
+    // 始点のチェック
+    if let Some(from_date) = opts.from_date() {
+        if *date < from_date.date_naive() {
+            return false;
+        }
+    }
+    
+    // 終点のチェック
+    if let Some(to_date) = opts.to_date() {
+        if *date >= to_date.date_naive() {
+            return false;
+        }
+    }
+    
+    true
+}
+
+/// ファイルを指定されたパスにコピーする
+///
+/// # 引数
+/// * `src` - コピー元ファイルのパス
+/// * `file_type` - ファイルタイプと保存先パス
+/// * `name` - 保存先でのファイル名
+/// * `algo` - コピーと同時にハッシュ値を求める場合は用いるハッシュ関数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合はコピー先のファイルのパスと、読み出した内容のハッシュ値
+/// (`algo`を指定した場合のみ)の組を`Ok()`でラップして返す。失敗した場合は
+/// エラー情報を `Err()`でラップして返す
+fn distribute(
+    src: impl AsRef<Path>,
+    file_type: FileType,
+    name: &str,
+    algo: Option<ChecksumAlgorithm>,
+    opts: &Options,
+) -> Result<(PathBuf, Option<String>)> {
+    let src = src.as_ref();
+    
+    // 保存先パスを取得
+    let target_path = file_type.dir();
+    
+    let dst = target_path.join(name);
+
+    // ディレクトリが存在しない場合は作成
+    mkdir::ensure(target_path, opts)?;
+
+    // ファイルをコピー
+    let result = match algo {
+        Some(algo) => checksum::copy_with_digest(src, &dst, algo).map(Some),
+        None => std::fs::copy(src, &dst).map(|_| None),
+    };
+
+    let hash = match result {
+        Ok(hash) => hash,
+        Err(err) => {
+            let context = format!("copy to {} failed", dst.display());
+            return Err(ImportError::io(context, err).into());
+        }
+    };
+
+    info!("copied {} to {}", src.display(), target_path.display());
+
+    Ok((dst, hash))
+}
+
+///
+/// モーションフォトの検出と動画部分の切り出しを行う
+///
+/// # 引数
+/// * `jpeg` - 振り分け先にコピーしたJPEGファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を `Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 入力元(メモリカード)を再度読み出さずに済むよう、検出と切り出しはコピー済み
+/// のファイルに対して行う。
+///
+fn process_motion_photo(jpeg: &Path, opts: &Options) -> Result<()> {
+    let mode = opts.motion_photo_mode();
+
+    if mode == MotionPhotoMode::Ignore {
+        return Ok(());
+    }
+
+    if let Some(motion) = motion_photo::detect(jpeg)? {
+        match mode {
+            MotionPhotoMode::Tag => {
+                info!(
+                    "motion photo detected: {} (video {} bytes)",
+                    jpeg.display(),
+                    motion.length()
+                );
+            }
+
+            MotionPhotoMode::Extract => {
+                let video = motion_photo::video_path(jpeg);
+                motion_photo::extract(jpeg, &motion, &video)?;
+                info!("extracted motion video to {}", video.display());
+                permission::apply(&video, &[], opts)?;
+
+                if opts.is_checksums() {
+                    checksum::record(&video, opts.checksum_algorithm())?;
+                }
+            }
+
+            MotionPhotoMode::Ignore => {}
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// 撮影日時の文字列を解析する
+///
+/// # 引数
+/// * `s` - 撮影日時の文字列（Exif情報の表示用の値）
+/// * `formats` - 標準の書式に加えて用いる書式（strftime形式）
+///
+/// # 戻り値
+/// 解析に成功した場合は撮影日時を`Ok()`でラップして返す。失敗した場合はエラー
+/// 情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 標準の書式(`DATETIME_FORMATS`)、`formats`の順に試し、最初に解析できたも
+/// のを採用する。何れの書式も秒未満の値と末尾のタイムゾーン(`+09:00`、
+/// `+0900`、`Z`)を省略可能として扱う。タイムゾーンが付与されている場合も
+/// 撮影地の時刻(記録された時刻そのもの)を撮影日時とし、実行環境のタイムゾー
+/// ンへの変換は行わない。
+///
+fn parse_datetime(s: &str, formats: &[String]) -> Result<DateTime<Local>> {
+    // 標準の書式で解析できない値は引用符で囲まれて表示される
+    let value = s.trim_matches(|c: char| {
+        c == '"' || c == '\0' || c.is_whitespace()
+    });
+    let value = match value.strip_suffix(['Z', 'z']) {
+        Some(head) => format!("{}+00:00", head),
+        None => value.to_string(),
+    };
+
+    let datetime = DATETIME_FORMATS
+        .into_iter()
+        .chain(formats.iter().map(String::as_str))
+        .find_map(|format| {
+            NaiveDateTime::parse_from_str(&value, format).ok().or_else(|| {
+                ["%:z", "%z"].into_iter().find_map(|zone| {
+                    let format = format!("{} {}", format, zone);
+                    DateTime::parse_from_str(&value, &format)
+                        .ok()
+                        .map(|datetime| datetime.naive_local())
+                })
+            })
+        })
+        .ok_or_else(|| anyhow!("datetime parse failed: {}", s))?;
+
+    Local.from_local_datetime(&datetime)
+        .earliest()
+        .ok_or_else(|| anyhow!("nonexistent local datetime: {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// 撮影日時の文字列を解析し、記録された時刻をそのまま取り出す
+    ///
+    fn parse(s: &str, formats: &[String]) -> Option<NaiveDateTime> {
+        parse_datetime(s, formats)
+            .ok()
+            .map(|datetime| datetime.naive_local())
+    }
+
+    ///
+    /// 日時の値を作成する
+    ///
+    fn datetime(h: u32, m: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(h, m, s)
+            .unwrap()
+    }
+
+    ///
+    /// テスト用の作業ディレクトリを作成する
+    ///
+    fn work_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("imgdist-lib-{}-{}", name, std::process::id()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    ///
+    /// 入力ファイルと振り分け先のファイルを作成する
+    ///
+    fn copied_pair(dir: &Path) -> (PathBuf, PathBuf) {
+        let src = dir.join("src.jpg");
+        let dst = dir.join("dst.jpg");
+
+        std::fs::write(&src, b"image").unwrap();
+        std::fs::write(&dst, b"image").unwrap();
+
+        (src, dst)
+    }
+
+    #[test]
+    fn unique_name_appends_first_free_number() {
+        let dir = work_dir("unique");
+
+        std::fs::write(dir.join("a.jpg"), b"").unwrap();
+        assert_eq!(unique_name("a.jpg", &dir, str::to_string), "a_1.jpg");
+
+        std::fs::write(dir.join("a_1.jpg"), b"").unwrap();
+        std::fs::write(dir.join("a_2.jpg"), b"").unwrap();
+        assert_eq!(unique_name("a.jpg", &dir, str::to_string), "a_3.jpg");
+
+        assert_eq!(unique_name("README", &dir, str::to_string), "README_1");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unique_name_appends_to_existing_suffix() {
+        let dir = work_dir("unique-suffix");
+
+        // 既に`_N`で終わる名前も番号を進めず、さらに番号を付ける
+        std::fs::write(dir.join("a_1.jpg"), b"").unwrap();
+        assert_eq!(unique_name("a_1.jpg", &dir, str::to_string), "a_1_1.jpg");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unique_name_checks_stored_name() {
+        let dir = work_dir("unique-stored");
+        let stored = |name: &str| format!("{}.gpg", name);
+
+        std::fs::write(dir.join("a_1.jpg.gpg"), b"").unwrap();
+        std::fs::write(dir.join("a_2.jpg"), b"").unwrap();
+        assert_eq!(unique_name("a.jpg", &dir, stored), "a_2.jpg");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn apply_collision_follows_policy() {
+        let dir = work_dir("collision");
+        let name = || "a.jpg".to_string();
+
+        std::fs::write(dir.join("a.jpg"), b"").unwrap();
+
+        let apply = |exists, collision| {
+            apply_collision(name(), exists, collision, &dir, str::to_string)
+                .unwrap()
+        };
+
+        assert_eq!(apply(true, Collision::Skip), None);
+        assert_eq!(apply(true, Collision::Overwrite), Some(name()));
+        assert_eq!(apply(true, Collision::Rename), Some("a_1.jpg".into()));
+
+        // 存在しない場合は指定によらず元の名前を用いる
+        let policies = [Collision::Skip, Collision::Rename, Collision::Error];
+
+        for collision in policies {
+            assert_eq!(apply(false, collision), Some(name()));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn apply_collision_reports_conflict() {
+        let dir = work_dir("conflict");
+        let stored = |name: &str| format!("{}.gpg", name);
+
+        let err = apply_collision(
+            "a.jpg".to_string(),
+            true,
+            Collision::Error,
+            &dir,
+            stored,
+        ).unwrap_err();
+
+        match err.downcast_ref::<ImportError>() {
+            Some(ImportError::Conflict(path)) => {
+                assert_eq!(path, &dir.join("a.jpg.gpg"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        assert_eq!(error::classify(&err), error::ErrorKind::Conflict);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn move_removes_source_after_commit() {
+        let dir = work_dir("move-ok");
+        let (src, dst) = copied_pair(&dir);
+        let opts = Options::with_args(&["--move"]);
+        let mut record = FileEntry::new(&src, 5);
+
+        record.action = Action::Copied;
+        record.destination = Some(dst.clone());
+        move_source(&src, &mut record, &opts);
+
+        assert!(!src.exists());
+        assert!(dst.is_file());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn move_keeps_source_when_copy_failed() {
+        let dir = work_dir("move-copy");
+        let src = dir.join("src.jpg");
+        let opts = Options::with_args(&["--move"]);
+        let mut record = FileEntry::new(&src, 5);
+
+        std::fs::write(&src, b"image").unwrap();
+        record_failure(&mut record, anyhow!("copy failed"));
+        move_source(&src, &mut record, &opts);
+
+        assert!(src.is_file());
+        assert_eq!(record.action, Action::Failed);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn move_keeps_source_when_verify_failed() {
+        let dir = work_dir("move-verify");
+        let (src, _) = copied_pair(&dir);
+        let opts = Options::with_args(&["--move"]);
+        let mut record = FileEntry::new(&src, 5);
+
+        // 照合の失敗は振り分け先を記録する前に返る
+        record_failure(&mut record, anyhow!("verify failed"));
+        move_source(&src, &mut record, &opts);
+
+        assert!(src.is_file());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn move_keeps_source_when_commit_failed() {
+        let dir = work_dir("move-commit");
+        let (src, dst) = copied_pair(&dir);
+        let opts = Options::with_args(&["--move"]);
+        let mut record = FileEntry::new(&src, 5);
+
+        // コミットの失敗はコピーを記録した後に返る
+        record.action = Action::Copied;
+        record.destination = Some(dst);
+        record_failure(&mut record, anyhow!("commit failed"));
+        move_source(&src, &mut record, &opts);
+
+        assert!(src.is_file());
+        assert_eq!(record.action, Action::Failed);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn move_keeps_source_in_dry_run_and_copy_mode() {
+        let dir = work_dir("move-dry-run");
+        let (src, dst) = copied_pair(&dir);
+
+        for args in [&["--move", "--dry-run"][..], &[]] {
+            let opts = Options::with_args(args);
+            let mut record = FileEntry::new(&src, 5);
+
+            record.action = Action::Copied;
+            record.destination = Some(dst.clone());
+            move_source(&src, &mut record, &opts);
+
+            assert!(src.is_file());
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn is_drawn_never_at_zero_percent() {
+        for value in [0, 1, 999_999, 1_000_000, u64::MAX] {
+            assert!(!is_drawn(0.0, value));
+        }
+    }
+
+    #[test]
+    fn is_drawn_always_at_hundred_percent() {
+        for value in [0, 1, 999_999, 1_000_000, u64::MAX] {
+            assert!(is_drawn(100.0, value));
+        }
+    }
+
+    #[test]
+    fn is_drawn_follows_percentage() {
+        assert!(is_drawn(5.0, 49_999));
+        assert!(!is_drawn(5.0, 50_000));
+        assert!(is_drawn(0.0001, 0));
+        assert!(!is_drawn(0.0001, 1));
+    }
+
+    #[test]
+    fn is_sampled_without_option_is_false() {
+        let opts = Options::with_day_start("00:00");
+
+        for _ in 0..100 {
+            assert!(!is_sampled(&opts));
+        }
+    }
+
+    ///
+    /// 入力ディレクトリからの相対パス(`/`区切り)をグロブパターンと照合する
+    ///
+    fn glob_rel(pattern: &str, rel: &str) -> bool {
+        let root = std::env::temp_dir().join("in");
+        let path = rel.split('/').fold(root.clone(), |path, name| {
+            path.join(name)
+        });
+
+        glob_matches(&Pattern::new(pattern).unwrap(), &path, &root)
+    }
+
+    #[test]
+    fn glob_name_pattern_matches_at_any_depth() {
+        assert!(glob_rel("*.tmp", "a.tmp"));
+        assert!(glob_rel("*.tmp", "x/y/a.tmp"));
+        assert!(glob_rel("raw", "x/raw"));
+        assert!(!glob_rel("*.tmp", "a.tmp/b.jpg"));
+    }
+
+    #[test]
+    fn glob_path_pattern_matches_relative_path() {
+        assert!(glob_rel("raw/*.jpg", "raw/a.jpg"));
+        assert!(!glob_rel("raw/*.jpg", "x/raw/a.jpg"));
+        assert!(glob_rel("**/raw/*.jpg", "raw/a.jpg"));
+        assert!(glob_rel("**/raw/*.jpg", "x/y/raw/a.jpg"));
+        assert!(glob_rel("x/**", "x/y/a.jpg"));
+    }
+
+    #[test]
+    fn glob_star_does_not_cross_separator() {
+        assert!(!glob_rel("raw/*.jpg", "raw/sub/a.jpg"));
+        assert!(!glob_rel("*/a.jpg", "x/y/a.jpg"));
+        assert!(glob_rel("raw/**/*.jpg", "raw/sub/a.jpg"));
+    }
+
+    #[test]
+    fn glob_ignores_case_on_every_platform() {
+        assert!(glob_rel("*.JPG", "a.jpg"));
+        assert!(glob_rel("*.jpg", "A.JPG"));
+        assert!(glob_rel("RAW/*.jpg", "raw/A.JPG"));
+        assert!(glob_rel("dcim/**", "DCIM/100CANON/a.jpg"));
+    }
+
+    #[test]
+    fn glob_path_pattern_ignores_path_outside_root() {
+        let root = std::env::temp_dir().join("in");
+        let path = std::env::temp_dir().join("other").join("raw").join("a.jpg");
+        let pattern = Pattern::new("raw/*.jpg").unwrap();
+
+        assert!(!glob_matches(&pattern, &path, &root));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn glob_matches_windows_separators() {
+        let pattern = Pattern::new("raw/*.jpg").unwrap();
+        let root = Path::new(r"C:\In");
+
+        assert!(glob_matches(&pattern, Path::new(r"C:\In\raw\a.jpg"), root));
+        assert!(glob_matches(&pattern, Path::new(r"C:\In\RAW\A.JPG"), root));
+        assert!(!glob_matches(&pattern, Path::new(r"C:\In\x\raw\a.jpg"), root));
+    }
+
+    #[test]
+    fn glob_filters_input_walk() {
+        let dir = work_dir("glob-walk");
+
+        for rel in ["a.jpg", "B.JPG", "c.png", "raw/d.jpg", "x/Raw/e.jpg"] {
+            let path = dir.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"").unwrap();
+        }
+
+        let excludes = vec![Pattern::new("RAW").unwrap()];
+        let includes = vec![Pattern::new("*.jpg").unwrap()];
+
+        let names = WalkDir::new(&dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !is_glob_excluded(e, &dir, &excludes))
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| is_glob_included(e, &dir, &includes))
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["B.JPG", "a.jpg"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_datetime_accepts_standard_formats() {
+        let expect = Some(datetime(12, 34, 56));
+
+        assert_eq!(parse("2024-05-01 12:34:56", &[]), expect);
+        assert_eq!(parse("2024:05:01 12:34:56", &[]), expect);
+        assert_eq!(parse("2024-05-01T12:34:56", &[]), expect);
+        assert_eq!(parse("2024/05/01 12:34:56", &[]), expect);
+    }
+
+    #[test]
+    fn parse_datetime_strips_quotes_and_padding() {
+        let expect = Some(datetime(12, 34, 56));
+
+        assert_eq!(parse("\"2024:05:01 12:34:56\"", &[]), expect);
+        assert_eq!(parse("2024:05:01 12:34:56\0", &[]), expect);
+        assert_eq!(parse("  2024:05:01 12:34:56 ", &[]), expect);
+    }
+
+    #[test]
+    fn parse_datetime_keeps_recorded_time_with_zone() {
+        let expect = Some(datetime(12, 34, 56));
+
+        assert_eq!(parse("2024-05-01T12:34:56+09:00", &[]), expect);
+        assert_eq!(parse("2024-05-01T12:34:56+0900", &[]), expect);
+        assert_eq!(parse("2024-05-01T12:34:56Z", &[]), expect);
+    }
+
+    #[test]
+    fn parse_datetime_accepts_subseconds() {
+        let expect = datetime(12, 34, 56)
+            + chrono::Duration::milliseconds(250);
+
+        assert_eq!(parse("2024:05:01 12:34:56.25", &[]), Some(expect));
+    }
+
+    #[test]
+    fn parse_datetime_uses_additional_formats() {
+        let formats = vec!["%d.%m.%Y %H:%M:%S".to_string()];
+
+        assert_eq!(parse("01.05.2024 12:34:56", &[]), None);
+        assert_eq!(
+            parse("01.05.2024 12:34:56", &formats),
+            Some(datetime(12, 34, 56))
+        );
+    }
+
+    #[test]
+    fn shooting_date_defaults_to_midnight() {
+        let opts = Options::with_day_start("00:00");
+        let date = |h, m| {
+            let datetime = Local.from_local_datetime(&datetime(h, m, 0));
+            shooting_date(&datetime.earliest().unwrap(), &opts)
+        };
+
+        assert_eq!(date(0, 0), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        assert_eq!(date(23, 59), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+    }
+
+    #[test]
+    fn shooting_date_applies_day_start() {
+        let opts = Options::with_day_start("04:00");
+        let date = |h, m| {
+            let datetime = Local.from_local_datetime(&datetime(h, m, 0));
+            shooting_date(&datetime.earliest().unwrap(), &opts)
+        };
+
+        // 区切り時刻より前の撮影は前日として扱う
+        assert_eq!(date(2, 0), NaiveDate::from_ymd_opt(2024, 4, 30).unwrap());
+        assert_eq!(date(3, 59), NaiveDate::from_ymd_opt(2024, 4, 30).unwrap());
+        assert_eq!(date(4, 0), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        assert_eq!(date(23, 59), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+    }
+
+    #[test]
+    fn parse_datetime_rejects_invalid_values() {
+        assert_eq!(parse("", &[]), None);
+        assert_eq!(parse("0000:00:00 00:00:00", &[]), None);
+        assert_eq!(parse("2024:05:01", &[]), None);
+    }
+}
@@ -0,0 +1,330 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 実行結果をメール(SMTP)で通知するモジュール
+//!
+
+use std::fmt::{self, Write as _};
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{anyhow, Error, Result};
+use chrono::Local;
+use log::{info, warn};
+
+use crate::cmd_args::Options;
+use crate::estimate::format_size;
+use crate::external::execute_with_input;
+use crate::report::Report;
+
+/// 本文に列挙する失敗したファイルの上限件数
+const MAX_FAILURES: usize = 100;
+
+///
+/// 取り込みの実行結果をメールで通知する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `report` - 取り込みの実行結果
+/// * `error` - 後処理で発生したエラー(無い場合はNone)
+///
+/// # 注記
+/// SMTPサーバが設定されていない場合は何もしない。送信に失敗しても取り込みの
+/// 結果には影響させず、警告を記録するのみとする。
+///
+pub(crate) fn notify(opts: &Options, report: &Report, error: Option<&Error>) {
+    if opts.smtp_url().is_none() {
+        return;
+    }
+
+    let failed = report.failed() > 0
+        || report.parked().is_some()
+        || error.is_some();
+
+    let subject = format!(
+        "imgdist: {} (copied {}, skipped {}, failed {})",
+        if failed { "run finished with errors" } else { "run finished" },
+        report.copied(),
+        report.skipped(),
+        report.failed(),
+    );
+
+    /*
+     * 本文の構築
+     */
+    let mut body = String::new();
+
+    let _ = writeln!(body, "input:    {}", source_name(opts));
+    let _ = writeln!(body, "started:  {}", report.started());
+    let _ = writeln!(
+        body,
+        "finished: {}",
+        report.finished().unwrap_or("(unfinished)")
+    );
+    let _ = writeln!(
+        body,
+        "files:    copied {}, skipped {}, failed {} ({} copied)",
+        report.copied(),
+        report.skipped(),
+        report.failed(),
+        format_size(report.copied_bytes()),
+    );
+
+    if let Some(reason) = report.parked() {
+        let _ = writeln!(body, "parked:   {}", reason);
+    }
+
+    if let Some(err) = error {
+        let _ = writeln!(body, "error:    {}", err);
+    }
+
+    if report.failed() > 0 {
+        let _ = writeln!(body);
+        let _ = writeln!(body, "failed files:");
+
+        for entry in report.failures().take(MAX_FAILURES) {
+            let _ = writeln!(
+                body,
+                "  {}: {}",
+                entry.source.display(),
+                entry.error.as_deref().unwrap_or("unknown error")
+            );
+        }
+
+        if report.failed() > MAX_FAILURES {
+            let _ = writeln!(
+                body,
+                "  ... and {} more",
+                report.failed() - MAX_FAILURES
+            );
+        }
+    }
+
+    send_or_warn(opts, &subject, &body);
+}
+
+///
+/// 取り込みの失敗をメールで通知する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `error` - 取り込みを中止したエラー
+///
+/// # 注記
+/// 実行結果のレポートを得られずに取り込みが失敗した場合に用いる。SMTPサーバ
+/// が設定されていない場合は何もしない。
+///
+pub(crate) fn notify_failure(opts: &Options, error: &dyn fmt::Display) {
+    if opts.smtp_url().is_none() {
+        return;
+    }
+
+    let subject = "imgdist: run failed".to_string();
+    let body = format!(
+        "input:    {}\nfailed:   {}\nerror:    {}\n",
+        source_name(opts),
+        Local::now().to_rfc3339(),
+        error
+    );
+
+    send_or_warn(opts, &subject, &body);
+}
+
+///
+/// 通知に記載する取り込み元の名前を求める
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// クラウドストレージの場合はリモートのパス、それ以外は入力ディレクトリのパス
+///
+fn source_name(opts: &Options) -> String {
+    opts.cloud_remote()
+        .unwrap_or_else(|| opts.input_path().display().to_string())
+}
+
+///
+/// メールを送信し、失敗した場合は警告を記録する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `subject` - 件名
+/// * `body` - 本文
+///
+fn send_or_warn(opts: &Options, subject: &str, body: &str) {
+    match send(opts, subject, body) {
+        Ok(()) => info!("report mailed to {}", opts.mail_to().join(", ")),
+        Err(err) => warn!("send mail failed: {}", err),
+    }
+}
+
+///
+/// メールを送信する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `subject` - 件名
+/// * `body` - 本文
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// SMTPによる送信はcurlに任せる。メッセージは一時ファイルに書き出してから
+/// 渡し、送信後に削除する。`smtp://`の場合はサーバが対応していればSTARTTLS
+/// で暗号化する。認証情報を用いる場合は平文での送信を避けるためTLSを必須と
+/// し、パスワードがプロセスの一覧に現れないよう認証情報は標準入力から設定ファ
+/// イルとして渡す。
+///
+fn send(opts: &Options, subject: &str, body: &str) -> Result<()> {
+    let Some(url) = opts.smtp_url() else {
+        return Err(anyhow!("smtp server is not configured"));
+    };
+
+    let Some(from) = opts.mail_from() else {
+        return Err(anyhow!("--smtp requires --mail-from"));
+    };
+
+    let to = opts.mail_to();
+
+    /*
+     * メッセージの書き出し
+     */
+    let message = build_message(&from, &to, subject, body);
+    let path = message_path();
+
+    if let Err(err) = std::fs::write(&path, message) {
+        return Err(anyhow!("write {} failed: {}", path.display(), err));
+    }
+
+    /*
+     * curlによる送信
+     */
+    let credential = opts.smtp_user().zip(opts.smtp_password());
+    let mut command = Command::new("curl");
+
+    command
+        .arg("--silent")
+        .arg("--show-error")
+        .arg(if credential.is_some() { "--ssl-reqd" } else { "--ssl" })
+        .arg("--url")
+        .arg(&url)
+        .arg("--mail-from")
+        .arg(&from);
+
+    for rcpt in &to {
+        command.arg("--mail-rcpt").arg(rcpt);
+    }
+
+    let config = match credential {
+        Some((user, password)) => {
+            command.arg("--config").arg("-");
+            curl_config("user", &format!("{}:{}", user, password))
+        }
+
+        None => String::new(),
+    };
+
+    command.arg("--upload-file").arg(&path);
+
+    let result = execute_with_input(&mut command, config.as_bytes());
+    let _ = std::fs::remove_file(&path);
+
+    result.map(|_| ())
+}
+
+///
+/// curlの設定ファイルの1行を構築する
+///
+/// # 引数
+/// * `name` - オプション名
+/// * `value` - オプションの値
+///
+/// # 戻り値
+/// 値を二重引用符で囲み、必要な文字をエスケープした設定行
+///
+fn curl_config(name: &str, value: &str) -> String {
+    let mut line = format!("{} = \"", name);
+
+    for ch in value.chars() {
+        match ch {
+            '\\' => line.push_str("\\\\"),
+            '"' => line.push_str("\\\""),
+            '\t' => line.push_str("\\t"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            _ => line.push(ch),
+        }
+    }
+
+    line.push_str("\"\n");
+    line
+}
+
+///
+/// メッセージ(RFC 5322形式)を構築する
+///
+/// # 引数
+/// * `from` - 送信元アドレス
+/// * `to` - 宛先アドレスの一覧
+/// * `subject` - 件名(ASCII文字のみ)
+/// * `body` - 本文
+///
+/// # 戻り値
+/// ヘッダと本文を連結したメッセージ
+///
+fn build_message(from: &str, to: &[String], subject: &str, body: &str)
+    -> String
+{
+    let mut message = String::new();
+
+    let _ = write!(message, "From: {}\r\n", from);
+    let _ = write!(message, "To: {}\r\n", to.join(", "));
+    let _ = write!(message, "Subject: {}\r\n", subject);
+    let _ = write!(message, "Date: {}\r\n", Local::now().to_rfc2822());
+    let _ = write!(message, "MIME-Version: 1.0\r\n");
+    let _ = write!(message, "Content-Type: text/plain; charset=UTF-8\r\n");
+    let _ = write!(message, "Content-Transfer-Encoding: 8bit\r\n");
+    let _ = write!(message, "\r\n");
+
+    for line in body.lines() {
+        let _ = write!(message, "{}\r\n", line);
+    }
+
+    message
+}
+
+///
+/// メッセージを書き出す一時ファイルのパスを求める
+///
+/// # 戻り値
+/// プロセスごとに異なる一時ファイルのパス
+///
+fn message_path() -> PathBuf {
+    let name = format!("imgdist-mail-{}.eml", std::process::id());
+    std::env::temp_dir().join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curl_config_quotes_value() {
+        assert_eq!(curl_config("user", "me:pass"), "user = \"me:pass\"\n");
+    }
+
+    #[test]
+    fn curl_config_escapes_special_characters() {
+        assert_eq!(
+            curl_config("user", "me:p\"a\\s s\n#"),
+            "user = \"me:p\\\"a\\\\s s\\n#\"\n"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 入力元(メモリカード)に置く取り込みマーカーを取り扱うモジュール
+//!
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+/// 取り込みマーカーファイルの名前
+pub(crate) const MARKER_NAME: &str = ".imgdist-marker.toml";
+
+///
+/// 取り込みマーカーの内容
+///
+#[derive(Debug, Deserialize, Serialize)]
+struct Marker {
+    /// 最後に取り込みを行った日時(ISO8601)
+    imported_at: String,
+
+    /// 取り込み済みとみなすファイルの更新日時の上限(ISO8601)
+    high_water: String,
+}
+
+///
+/// 取り込みマーカーを読み込む
+///
+/// # 引数
+/// * `dir` - 入力ディレクトリのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は取り込み済みとみなす更新日時の上限を`Ok()`でラップし
+/// て返す(マーカーが無い場合はNone)。失敗した場合はエラー情報を`Err()`でラッ
+/// プして返す。
+///
+pub(crate) fn read(dir: &Path) -> Result<Option<SystemTime>> {
+    let path = dir.join(MARKER_NAME);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let marker = match toml::from_str::<Marker>(
+        &std::fs::read_to_string(&path)?
+    ) {
+        Ok(marker) => marker,
+        Err(err) => {
+            return Err(anyhow!("parse {} failed: {}", path.display(), err));
+        }
+    };
+
+    match DateTime::parse_from_rfc3339(&marker.high_water) {
+        Ok(high_water) => {
+            info!(
+                "card marker found: imported at {}, high water {}",
+                marker.imported_at,
+                marker.high_water
+            );
+
+            Ok(Some(SystemTime::from(high_water)))
+        }
+
+        Err(err) => Err(anyhow!("parse {} failed: {}", path.display(), err)),
+    }
+}
+
+///
+/// 取り込みマーカーを書き込む
+///
+/// # 引数
+/// * `dir` - 入力ディレクトリのパス
+/// * `high_water` - 取り込み済みとみなすファイルの更新日時の上限
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 書き込み途中でカードが抜かれても壊れたマーカーが残らないよう、一時ファイ
+/// ルに書き込んだ後にリネームする。
+///
+pub(crate) fn write(dir: &Path, high_water: SystemTime) -> Result<()> {
+    let marker = Marker {
+        imported_at: Local::now().to_rfc3339(),
+        high_water: DateTime::<Local>::from(high_water).to_rfc3339(),
+    };
+
+    let path = dir.join(MARKER_NAME);
+    let tmp = dir.join(format!("{}.tmp", MARKER_NAME));
+
+    std::fs::write(&tmp, toml::to_string(&marker)?)?;
+    std::fs::rename(&tmp, &path)?;
+
+    debug!("card marker updated: {}", path.display());
+
+    Ok(())
+}
+
+///
+/// 取り込みマーカーのファイルか否かを判定する
+///
+/// # 引数
+/// * `name` - ファイル名
+///
+/// # 戻り値
+/// 取り込みマーカーまたはその一時ファイルの場合は`true`
+///
+pub(crate) fn is_marker(name: &str) -> bool {
+    name.starts_with(MARKER_NAME)
+}
@@ -0,0 +1,92 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先ディレクトリの作成を管理するモジュール
+//!
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+
+use crate::cmd_args::Options;
+
+///
+/// 今回の取り込みで存在を確認済みのディレクトリの一覧
+///
+#[derive(Debug, Default)]
+pub(crate) struct CreatedDirs {
+    /// 確認済みのディレクトリ
+    dirs: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl CreatedDirs {
+    ///
+    /// 確認済みのディレクトリの一覧のロック
+    ///
+    /// # 戻り値
+    /// 確認済みのディレクトリの一覧のガードオブジェクト
+    ///
+    fn lock(&self) -> MutexGuard<'_, BTreeSet<PathBuf>> {
+        self.dirs.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+///
+/// 振り分け先ディレクトリが存在することを保証する
+///
+/// # 引数
+/// * `dir` - 振り分け先ディレクトリのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 存在しない場合は作成する。一度確認したディレクトリは`reset()`を呼ぶまで
+/// 再確認しない(日付ごとの深いツリーに多数のファイルを振り分ける際に、ネット
+/// ワーク越しの振り分け先へ同じ問い合わせを繰り返さないため)。確認済みの一覧
+/// はオプション設定ごとに保持する。ファイルシステムへの操作の間はロックを保
+/// 持しないため、複数のスレッドから同時に呼び出してもよい。
+///
+pub(crate) fn ensure(dir: &Path, opts: &Options) -> Result<()> {
+    let created = opts.created_dirs();
+
+    if created.lock().contains(dir) {
+        return Ok(());
+    }
+
+    if !dir.exists() {
+        if let Err(err) = std::fs::create_dir_all(dir) {
+            return Err(anyhow!("create directory failed: {}", err));
+        }
+    }
+
+    if !dir.is_dir() {
+        return Err(anyhow!("{} is not directory", dir.display()));
+    }
+
+    created.lock().insert(dir.to_path_buf());
+
+    Ok(())
+}
+
+///
+/// 確認済みのディレクトリの一覧を破棄する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 注記
+/// 取り込みの開始時に呼び出す(デーモンモード等で前回の取り込みの後に削除さ
+/// れたディレクトリを確認済みとして扱わないため)。
+///
+pub(crate) fn reset(opts: &Options) {
+    opts.created_dirs().lock().clear();
+}
@@ -0,0 +1,256 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! モーションフォト(動画を埋め込んだJPEG)を取り扱うモジュール
+//!
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+/// ftypボックスとして許容する最大サイズ(バイト)
+const MAX_FTYP_SIZE: u32 = 256;
+
+///
+/// 検出したモーションフォトの動画部分の位置情報
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct MotionPhoto {
+    /// ファイル先頭から動画部分までのオフセット(バイト)
+    offset: u64,
+
+    /// 動画部分の長さ(バイト)
+    length: u64,
+}
+
+impl MotionPhoto {
+    ///
+    /// 動画部分の長さへのアクセサ
+    ///
+    /// # 戻り値
+    /// 動画部分の長さ(バイト)
+    ///
+    pub(crate) fn length(&self) -> u64 {
+        self.length
+    }
+}
+
+///
+/// JPEGファイルにMP4動画が埋め込まれているかを検出する
+///
+/// # 引数
+/// * `path` - 検査対象のJPEGファイルのパス
+///
+/// # 戻り値
+/// モーションフォトであった場合は動画部分の位置情報を`Some()`でラップして返す。
+/// モーションフォトでなかった場合は`None`を返す。
+///
+/// # 注記
+/// Samsung/Pixelのいずれの形式も、JPEGデータの後ろにMP4のボックス列がそのまま
+/// 連結されているため、ftypボックスを探索しそこからボックス列を辿ることで動画部
+/// 分の範囲を特定している(末尾に付与されるSEF等のトレイラは範囲に含めない)。
+///
+pub(crate) fn detect<P>(path: P) -> Result<Option<MotionPhoto>>
+where
+    P: AsRef<Path>,
+{
+    let data = std::fs::read(path)?;
+
+    // JPEGでなければ対象外
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return Ok(None);
+    }
+
+    let mut pos = 4;
+
+    while let Some(found) = find(&data[pos..], b"ftyp") {
+        let ftyp = pos + found;
+
+        if let Some(length) = measure_mp4(&data, ftyp - 4) {
+            return Ok(Some(MotionPhoto {
+                offset: (ftyp - 4) as u64,
+                length,
+            }));
+        }
+
+        pos = ftyp + 4;
+    }
+
+    Ok(None)
+}
+
+///
+/// モーションフォトから動画部分を切り出してファイルに書き出す
+///
+/// # 引数
+/// * `src` - モーションフォトのパス
+/// * `motion` - 動画部分の位置情報
+/// * `dst` - 書き出し先のパス
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+pub(crate) fn extract<P, Q>(src: P, motion: &MotionPhoto, dst: Q) -> Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut reader = File::open(src)?;
+    reader.seek(SeekFrom::Start(motion.offset))?;
+
+    let mut writer = BufWriter::new(File::create(&dst)?);
+    let copied = io::copy(&mut reader.take(motion.length), &mut writer)?;
+
+    if copied != motion.length {
+        return Err(anyhow!(
+            "motion video truncated: {}",
+            dst.as_ref().display()
+        ));
+    }
+
+    Ok(())
+}
+
+///
+/// 切り出した動画の保存先パスを生成する
+///
+/// # 引数
+/// * `jpeg` - 振り分け先に保存したJPEGファイルのパス
+///
+/// # 戻り値
+/// JPEGファイルと同じディレクトリに置く動画ファイルのパス
+///
+pub(crate) fn video_path<P>(jpeg: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let jpeg = jpeg.as_ref();
+    let stem = jpeg
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    jpeg.with_file_name(format!("{}_motion.mp4", stem))
+}
+
+///
+/// バイト列中のパターンの出現位置を探す
+///
+/// # 引数
+/// * `data` - 探索対象のバイト列
+/// * `pattern` - 探索するパターン
+///
+/// # 戻り値
+/// 最初に見つかった位置(見つからなければ`None`)
+///
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len()).position(|w| w == pattern)
+}
+
+///
+/// 指定位置から始まるMP4のボックス列を辿りその長さを求める
+///
+/// # 引数
+/// * `data` - ファイル全体のバイト列
+/// * `start` - ftypボックスの先頭位置
+///
+/// # 戻り値
+/// 妥当なMP4と判断できた場合はボックス列の長さ(バイト)を返す。そうでなければ
+/// `None`を返す。
+///
+/// # 概要
+/// 先頭のftypボックスのサイズとブランドを検査した後、トップレベルのボックスを
+/// 順に辿る。moovボックスとmdatボックスの両方が見つかった場合のみ妥当なMP4と判
+/// 断し、最後に辿れたボックスの終端までを動画部分とする。
+///
+fn measure_mp4(data: &[u8], start: usize) -> Option<u64> {
+    let ftyp_size = read_u32(data, start)?;
+    if !(16..=MAX_FTYP_SIZE).contains(&ftyp_size) {
+        return None;
+    }
+
+    // メジャーブランドは英数字(と空白)のみで構成される
+    let brand = data.get(start + 8..start + 12)?;
+    if !brand.iter().all(|c| c.is_ascii_alphanumeric() || *c == b' ') {
+        return None;
+    }
+
+    let mut pos = start;
+    let mut has_moov = false;
+    let mut has_mdat = false;
+
+    /*
+     * トップレベルのボックスを辿る
+     */
+    while pos + 8 <= data.len() {
+        let kind = &data[pos + 4..pos + 8];
+        if !kind.iter().all(|c| c.is_ascii_graphic() || *c == b' ') {
+            break;
+        }
+
+        let size = match read_u32(data, pos)? {
+            // サイズ0はファイル終端までのボックス
+            0 => (data.len() - pos) as u64,
+
+            // サイズ1は64bitの拡張サイズ
+            1 => read_u64(data, pos + 8)?,
+
+            size => size as u64,
+        };
+
+        if size < 8 || pos as u64 + size > data.len() as u64 {
+            break;
+        }
+
+        match kind {
+            b"moov" => has_moov = true,
+            b"mdat" => has_mdat = true,
+            _ => {}
+        }
+
+        pos += size as usize;
+    }
+
+    if has_moov && has_mdat {
+        Some((pos - start) as u64)
+    } else {
+        None
+    }
+}
+
+///
+/// ビッグエンディアンの32bit値を読み出す
+///
+/// # 引数
+/// * `data` - バイト列
+/// * `pos` - 読み出し位置
+///
+/// # 戻り値
+/// 読み出した値(範囲外の場合は`None`)
+///
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+///
+/// ビッグエンディアンの64bit値を読み出す
+///
+/// # 引数
+/// * `data` - バイト列
+/// * `pos` - 読み出し位置
+///
+/// # 戻り値
+/// 読み出した値(範囲外の場合は`None`)
+///
+fn read_u64(data: &[u8], pos: usize) -> Option<u64> {
+    let bytes = data.get(pos..pos + 8)?;
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
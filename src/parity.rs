@@ -0,0 +1,163 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先フォルダのPAR2リカバリファイルの生成を行うモジュール
+//!
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use log::{error, info};
+
+use crate::external::execute;
+
+/// リカバリファイルのベース名
+const RECOVERY_NAME: &str = "recovery";
+
+/// リカバリファイルの拡張子
+const PAR2_EXT: &str = "par2";
+
+///
+/// フォルダごとにPAR2リカバリファイルを生成する
+///
+/// # 引数
+/// * `dirs` - 対象とするフォルダの列
+/// * `redundancy` - 冗長度(パーセント)
+///
+/// # 戻り値
+/// 全てのフォルダで生成に成功した場合は`Ok(())`を返す。失敗したフォルダがあっ
+/// た場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 一部のフォルダで失敗しても残りのフォルダの処理は継続する。
+///
+pub(crate) fn generate<I>(dirs: I, redundancy: u8) -> Result<()>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    let mut failed = 0;
+
+    for dir in dirs {
+        if let Err(err) = generate_dir(&dir, redundancy) {
+            error!("par2 generation failed for {}: {}", dir.display(), err);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("par2 generation failed for {} folders", failed));
+    }
+
+    Ok(())
+}
+
+///
+/// 1つのフォルダのPAR2リカバリファイルを生成する
+///
+/// # 引数
+/// * `dir` - 対象のフォルダ
+/// * `redundancy` - 冗長度(パーセント)
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// フォルダの内容が変化しているため、既存のリカバリファイルは削除してから作り
+/// 直す。
+///
+fn generate_dir(dir: &Path, redundancy: u8) -> Result<()> {
+    /*
+     * 既存のリカバリファイルの削除と対象ファイルの列挙
+     */
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+
+        if is_par2_file(&path) {
+            if is_recovery_file(&path) {
+                std::fs::remove_file(&path)?;
+            }
+            continue;
+        }
+
+        files.push(path);
+    }
+
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    files.sort();
+
+    /*
+     * リカバリファイルの生成
+     */
+    let recovery = dir.join(format!("{}.{}", RECOVERY_NAME, PAR2_EXT));
+
+    execute(
+        Command::new("par2")
+            .arg("create")
+            .arg("-q")
+            .arg(format!("-r{}", redundancy))
+            .arg("-B")
+            .arg(dir)
+            .arg(&recovery)
+            .args(&files)
+    )?;
+
+    info!(
+        "par2 recovery files created for {} ({} files)",
+        dir.display(),
+        files.len()
+    );
+
+    Ok(())
+}
+
+///
+/// PAR2ファイルか否かを判定する
+///
+/// # 引数
+/// * `path` - 判定対象のパス
+///
+/// # 戻り値
+/// 拡張子が`par2`の場合は`true`
+///
+fn is_par2_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(PAR2_EXT))
+}
+
+///
+/// 本ツールが生成したリカバリファイルか否かを判定する
+///
+/// # 引数
+/// * `path` - 判定対象のパス
+///
+/// # 戻り値
+/// `recovery.par2`または`recovery.volNN+NN.par2`の場合は`true`
+///
+fn is_recovery_file(path: &Path) -> bool {
+    match path.file_stem() {
+        Some(stem) => {
+            let stem = stem.to_string_lossy();
+            stem == RECOVERY_NAME
+                || stem.starts_with(&format!("{}.vol", RECOVERY_NAME))
+        }
+
+        None => false,
+    }
+}
@@ -0,0 +1,138 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 取り込み処理の一時停止と再開を管理するモジュール
+//!
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use anyhow::Result;
+use log::info;
+
+///
+/// 取り込み処理の一時停止状態
+///
+/// # 注記
+/// オプション設定ごとに保持し、デーモンモードの制御コマンドやシグナルから
+/// 操作する。
+///
+#[derive(Debug, Default)]
+pub(crate) struct PauseState {
+    /// 一時停止中か否か
+    paused: Mutex<bool>,
+
+    /// 一時停止の解除を通知する条件変数
+    resumed: Condvar,
+}
+
+impl PauseState {
+    ///
+    /// 一時停止状態のロック
+    ///
+    /// # 戻り値
+    /// 一時停止状態のガードオブジェクト
+    ///
+    fn lock(&self) -> MutexGuard<'_, bool> {
+        self.paused.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    ///
+    /// 取り込み処理を一時停止する
+    ///
+    /// # 注記
+    /// 処理中のファイルがある場合は、そのファイルの処理を終えた時点で停止す
+    /// る。
+    ///
+    pub(crate) fn pause(&self) {
+        let mut paused = self.lock();
+
+        if !*paused {
+            *paused = true;
+            info!("import paused");
+        }
+    }
+
+    ///
+    /// 取り込み処理を再開する
+    ///
+    pub(crate) fn resume(&self) {
+        let mut paused = self.lock();
+
+        if *paused {
+            *paused = false;
+            self.resumed.notify_all();
+            info!("import resumed");
+        }
+    }
+
+    ///
+    /// 一時停止中か否かを取得する
+    ///
+    /// # 戻り値
+    /// 一時停止中の場合は`true`
+    ///
+    pub(crate) fn is_paused(&self) -> bool {
+        *self.lock()
+    }
+
+    ///
+    /// 一時停止中であれば再開されるまで待つ
+    ///
+    /// # 注記
+    /// ファイルの処理の合間に呼び出す。
+    ///
+    pub(crate) fn wait(&self) {
+        let mut paused = self.lock();
+
+        while *paused {
+            paused = self
+                .resumed
+                .wait(paused)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+///
+/// 一時停止と再開を行うシグナルハンドラを登録する
+///
+/// # 引数
+/// * `state` - シグナルで操作する一時停止状態
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// SIGUSR1で一時停止し、SIGUSR2で再開する。シグナルを待つスレッドを起動する
+/// ため、プロセスにつき1回だけ呼び出す。Unix系以外のプラットフォームでは何も
+/// しない。
+///
+#[cfg(unix)]
+pub(crate) fn install_signal_handler(state: Arc<PauseState>) -> Result<()> {
+    use signal_hook::consts::{SIGUSR1, SIGUSR2};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGUSR1, SIGUSR2])?;
+
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            match signal {
+                SIGUSR1 => state.pause(),
+                SIGUSR2 => state.resume(),
+                _ => {}
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn install_signal_handler(_state: Arc<PauseState>) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先のファイルとディレクトリへの権限の適用を行うモジュール
+//!
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use log::debug;
+
+use crate::cmd_args::Options;
+use crate::external::execute;
+
+///
+/// まだ存在しないディレクトリを列挙する
+///
+/// # 引数
+/// * `dir` - これから作成する振り分け先のディレクトリ
+///
+/// # 戻り値
+/// `dir`とその祖先のうち存在しないものを上位から順に並べたもの
+///
+/// # 注記
+/// 権限はこの実行で作成したディレクトリにのみ適用するため、ファイルの格納前
+/// に呼び出して作成されるディレクトリを求めておく。
+///
+pub(crate) fn missing_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = dir
+        .ancestors()
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .map(Path::to_path_buf)
+        .collect::<Vec<_>>();
+
+    dirs.reverse();
+    dirs
+}
+
+///
+/// 格納したファイルと作成したディレクトリに権限を適用する
+///
+/// # 引数
+/// * `path` - 格納したファイルのパス
+/// * `new_dirs` - 格納にあたって作成したディレクトリ
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+pub(crate) fn apply(path: &Path, new_dirs: &[PathBuf], opts: &Options)
+    -> Result<()>
+{
+    if !is_active(opts) {
+        return Ok(());
+    }
+
+    for dir in new_dirs {
+        apply_one(dir, opts.dir_mode(), opts)?;
+    }
+
+    apply_one(path, opts.file_mode(), opts)
+}
+
+///
+/// 権限の指定があるか否かを判定する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// いずれかの指定がある場合は`true`
+///
+fn is_active(opts: &Options) -> bool {
+    opts.file_mode().is_some()
+        || opts.dir_mode().is_some()
+        || opts.owner().is_some()
+        || opts.group().is_some()
+        || opts.acl().is_some()
+}
+
+///
+/// 1件のファイルまたはディレクトリに権限を適用する
+///
+/// # 引数
+/// * `path` - 対象のパス
+/// * `mode` - 適用するパーミッション(指定しない場合はNone)
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 所有者とグループの変更は名前の解決をシステムに任せるため`chown`コマンドで
+/// 行う。ACLはLinuxでは`setfacl -m`、macOSでは`chmod +a`で追加する。
+///
+fn apply_one(path: &Path, mode: Option<u32>, opts: &Options) -> Result<()> {
+    /*
+     * パーミッションの適用
+     */
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+
+        let perms = std::fs::Permissions::from_mode(mode);
+
+        if let Err(err) = std::fs::set_permissions(path, perms) {
+            return Err(anyhow!("chmod {} failed: {}", path.display(), err));
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    /*
+     * 所有者とグループの適用
+     */
+    let spec = match (opts.owner(), opts.group()) {
+        (Some(owner), Some(group)) => Some(format!("{}:{}", owner, group)),
+        (Some(owner), None) => Some(owner),
+        (None, Some(group)) => Some(format!(":{}", group)),
+        (None, None) => None,
+    };
+
+    if let Some(spec) = spec {
+        execute(Command::new("chown").arg(spec).arg(path))?;
+    }
+
+    /*
+     * ACLの適用
+     */
+    if let Some(acl) = opts.acl() {
+        if cfg!(target_os = "macos") {
+            execute(Command::new("chmod").arg("+a").arg(acl).arg(path))?;
+        } else {
+            execute(Command::new("setfacl").arg("-m").arg(acl).arg(path))?;
+        }
+    }
+
+    debug!("applied permissions to {}", path.display());
+
+    Ok(())
+}
+
+///
+/// パーミッションの指定を解釈する
+///
+/// # 引数
+/// * `value` - 8進表記のパーミッション(`0640`、`750`等)
+///
+/// # 戻り値
+/// 解釈に成功した場合はパーミッションの値を`Ok()`でラップして返す。失敗した
+/// 場合はエラー情報を`Err()`でラップして返す。
+///
+pub(crate) fn parse_mode(value: &str) -> Result<u32> {
+    let digits = value.trim().trim_start_matches("0o");
+
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if !digits.is_empty() && mode <= 0o7777 => Ok(mode),
+        _ => Err(anyhow!("invalid permission mode: {}", value)),
+    }
+}
@@ -0,0 +1,131 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 入力元のフォルダ構成に合わせた既定の扱い(取り込みプロファイル)を定義する
+//! モジュール
+//!
+
+use std::path::{Path, PathBuf};
+
+use crate::cmd_args::{DatetimeFallback, IngestProfile, Options};
+
+///
+/// フォルダごとの既定の扱い
+///
+struct FolderRule {
+    /// 対象とするフォルダの名前(大文字小文字は区別しない)
+    names: &'static [&'static str],
+
+    /// 撮影日時が無い場合の補い方(Noneの場合は`--datetime-fallback`に従う)
+    fallback: Option<DatetimeFallback>,
+
+    /// 振り分け先(既定の基点からの相対パス、Noneの場合は既定の規則に従う)
+    route: Option<&'static str>,
+}
+
+/// スマートフォンのバックアップツールが書き出すフォルダ構成の規則
+const PHONE_RULES: [FolderRule; 5] = [
+    // カメラアプリ(IMG_20240501_123456.jpg等、ファイル名に日時を含む)
+    FolderRule {
+        names: &["Camera", "DCIM"],
+        fallback: Some(DatetimeFallback::Filename),
+        route: None,
+    },
+
+    // WhatsAppで受け取った画像・動画(Exif情報が取り除かれている)
+    FolderRule {
+        names: &[
+            "WhatsApp Images",
+            "WhatsApp Video",
+            "WhatsApp Animated Gifs",
+        ],
+        fallback: Some(DatetimeFallback::Filename),
+        route: Some("WhatsApp"),
+    },
+
+    // スクリーンショットと画面録画
+    FolderRule {
+        names: &["Screenshots", "Screen recordings", "ScreenRecorder"],
+        fallback: Some(DatetimeFallback::Filename),
+        route: Some("Screenshots"),
+    },
+
+    // Telegramで受け取った画像・動画(ファイル名に日時を含まない)
+    FolderRule {
+        names: &["Telegram Images", "Telegram Video"],
+        fallback: Some(DatetimeFallback::Mtime),
+        route: Some("Telegram"),
+    },
+
+    // ブラウザ等でダウンロードしたファイル
+    FolderRule {
+        names: &["Download", "Downloads"],
+        fallback: Some(DatetimeFallback::Mtime),
+        route: Some("Downloads"),
+    },
+];
+
+///
+/// 入力ファイルのフォルダに適用する規則を求める
+///
+/// # 引数
+/// * `dir` - 入力ディレクトリからの入力ファイルのディレクトリの相対パス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 適用する規則(プロファイルが指定されていない場合や該当する規則が無い場合
+/// はNone)
+///
+/// # 注記
+/// 相対パスを末尾のフォルダから順に遡り、最初に名前が一致した規則を用いる
+/// (`DCIM/Screenshots`は`Screenshots`の規則とする)。
+///
+fn rule_for(dir: &Path, opts: &Options) -> Option<&'static FolderRule> {
+    let rules: &[FolderRule] = match opts.ingest_profile()? {
+        IngestProfile::Phone => &PHONE_RULES,
+    };
+
+    dir.components().rev().find_map(|component| {
+        let name = component.as_os_str().to_string_lossy();
+
+        rules.iter().find(|rule| {
+            rule.names.iter().any(|x| x.eq_ignore_ascii_case(&name))
+        })
+    })
+}
+
+///
+/// フォルダの規則による撮影日時の補い方を求める
+///
+/// # 引数
+/// * `dir` - 入力ディレクトリからの入力ファイルのディレクトリの相対パス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 規則で補い方が定められている場合はその方法
+///
+pub(crate) fn datetime_fallback(dir: Option<&Path>, opts: &Options)
+    -> Option<DatetimeFallback>
+{
+    rule_for(dir?, opts).and_then(|rule| rule.fallback)
+}
+
+///
+/// フォルダの規則による振り分け先を求める
+///
+/// # 引数
+/// * `dir` - 入力ディレクトリからの入力ファイルのディレクトリの相対パス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 規則で振り分け先が定められている場合はそのパス(既定の基点からの相対パス)
+///
+pub(crate) fn route(dir: Option<&Path>, opts: &Options) -> Option<PathBuf> {
+    rule_for(dir?, opts)
+        .and_then(|rule| rule.route)
+        .map(PathBuf::from)
+}
@@ -0,0 +1,199 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 取り込みの進捗を端末に表示するモジュール
+//!
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use walkdir::DirEntry;
+
+use crate::cmd_args::Options;
+use crate::estimate::format_size;
+use crate::report::{Action, FileEntry};
+
+/// 表示を更新する最短の間隔
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+///
+/// 進捗表示の状態を保持する構造体
+///
+#[derive(Debug)]
+pub(crate) struct Progress {
+    /// 事前の走査で数えた入力ファイル数
+    total_files: usize,
+
+    /// 事前の走査で数えた入力ファイルの合計サイズ(バイト)
+    total_bytes: u64,
+
+    /// 処理を終えたファイル数
+    scanned: usize,
+
+    /// 処理を終えたファイルの合計サイズ(バイト)
+    scanned_bytes: u64,
+
+    /// コピーしたファイル数
+    copied: usize,
+
+    /// 処理を行わなかったファイル数
+    skipped: usize,
+
+    /// 処理に失敗したファイル数
+    failed: usize,
+
+    /// コピーしたバイト数
+    copied_bytes: u64,
+
+    /// 処理の開始時刻
+    started: Instant,
+
+    /// 最後に表示を更新した時刻
+    drawn: Option<Instant>,
+}
+
+impl Progress {
+    ///
+    /// インスタンスを構築する
+    ///
+    /// # 引数
+    /// * `opts` - オプション設定の参照
+    ///
+    /// # 戻り値
+    /// 進捗を表示する場合はインスタンスを`Some()`でラップして返す。`--quiet`
+    /// が指定されている場合や、標準エラー出力が端末でない場合はNoneを返す。
+    ///
+    pub(crate) fn new(opts: &Options) -> Option<Self> {
+        if opts.is_quiet() || !io::stderr().is_terminal() {
+            return None;
+        }
+
+        Some(Self {
+            total_files: 0,
+            total_bytes: 0,
+            scanned: 0,
+            scanned_bytes: 0,
+            copied: 0,
+            skipped: 0,
+            failed: 0,
+            copied_bytes: 0,
+            started: Instant::now(),
+            drawn: None,
+        })
+    }
+
+    ///
+    /// 事前の走査の結果から処理対象の総量を設定する
+    ///
+    /// # 引数
+    /// * `entries` - 処理対象の入力ファイルの一覧
+    ///
+    pub(crate) fn set_total(&mut self, entries: &[DirEntry]) {
+        self.total_files = entries.len();
+        self.total_bytes = entries
+            .iter()
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|meta| meta.len())
+            .sum();
+        self.started = Instant::now();
+    }
+
+    ///
+    /// ファイル1件分の処理結果を反映する
+    ///
+    /// # 引数
+    /// * `record` - ファイルの処理結果
+    ///
+    /// # 注記
+    /// 表示の更新は一定の間隔ごとに間引いて行う。
+    ///
+    pub(crate) fn update(&mut self, record: &FileEntry) {
+        self.scanned += 1;
+        self.scanned_bytes += record.size;
+
+        match record.action {
+            Action::Copied => {
+                self.copied += 1;
+                self.copied_bytes += record.size;
+            }
+
+            Action::Skipped => self.skipped += 1,
+            Action::Failed => self.failed += 1,
+        }
+
+        if self.drawn.is_none_or(|at| at.elapsed() >= REDRAW_INTERVAL) {
+            self.draw();
+        }
+    }
+
+    ///
+    /// 最終の状態を表示して進捗表示を終える
+    ///
+    pub(crate) fn finish(mut self) {
+        self.draw();
+        eprintln!();
+    }
+
+    ///
+    /// 進捗表示の行を書き直す
+    ///
+    fn draw(&mut self) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            self.copied_bytes as f64 / elapsed
+        } else {
+            0.0
+        };
+
+        // 残り時間はスキップしたファイルも含めた読み進めた量から見積もる
+        let eta = if self.scanned_bytes > 0 && elapsed > 0.0 {
+            let remain = self.total_bytes.saturating_sub(self.scanned_bytes);
+            let secs = remain as f64 * elapsed / self.scanned_bytes as f64;
+            format_eta(secs as u64)
+        } else {
+            "--:--".to_string()
+        };
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r\x1b[K{}/{} files (copied {}, skipped {}, failed {}), \
+             {} / {}, {}/s, ETA {}",
+            self.scanned,
+            self.total_files,
+            self.copied,
+            self.skipped,
+            self.failed,
+            format_size(self.scanned_bytes),
+            format_size(self.total_bytes),
+            format_size(rate as u64),
+            eta,
+        );
+        let _ = stderr.flush();
+
+        self.drawn = Some(Instant::now());
+    }
+}
+
+///
+/// 残り時間を表示用の文字列に変換する
+///
+/// # 引数
+/// * `secs` - 残り時間(秒)
+///
+/// # 戻り値
+/// 1時間未満は"MM:SS"、それ以上は"H:MM:SS"の形式の文字列
+///
+fn format_eta(secs: u64) -> String {
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, mins, secs)
+    } else {
+        format!("{:02}:{:02}", mins, secs)
+    }
+}
@@ -0,0 +1,390 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 保存期間を過ぎたファイルを振り分け先から削除するモジュール
+//!
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{Days, Local, NaiveDate};
+use log::{info, warn};
+
+use crate::cache::{Cache, ExifSummary};
+use crate::cmd_args::{Options, StorageLayout};
+use crate::{parse_datetime, shooting_date};
+
+///
+/// 保存期間を過ぎたファイルを振り分け先から削除する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュオブジェクトの参照
+///
+/// # 戻り値
+/// 処理が成功した場合は削除したファイル数（ドライランの場合は削除対象のファ
+/// イル数）を`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラッ
+/// プして返す。
+///
+/// # 注記
+/// 入力元のボリュームからコピーしたファイルとしてキャッシュに振り分け先が記録
+/// されたもののうち、`--prune-root`で指定されたディレクトリの配下にあり、撮影
+/// 日が`--retain-days`で指定された日数より前のものを削除する。imgdistがコピー
+/// したことの無いファイルには手を付けない。他のボリュームからコピーしたファイ
+/// ルが`--prune-root`の配下に記録されている場合は何も削除せずにエラーとする。
+/// 削除の前に対象を一覧表示して確認を求め、ドライランの場合は一覧表示のみ行
+/// う。キャッシュのレコードは残すため、削除したファイルが再度取り込まれること
+/// は無い。
+///
+pub(crate) fn run(opts: &Options, cache: &Cache) -> Result<usize> {
+    let Some(days) = opts.retain_days() else {
+        return Ok(0);
+    };
+
+    let Some(root) = opts.prune_root() else {
+        return Err(anyhow!("--retain-days requires --prune-root"));
+    };
+
+    let cutoff = cutoff_date(Local::now().date_naive(), days);
+    let (own, others) = cache.destinations()?;
+
+    // 空になったディレクトリの削除で、正規化したパスで記録されていたファイ
+    // ルの起点として用いる
+    let canonical = root.canonicalize().unwrap_or_else(|_| root.clone());
+
+    /*
+     * 削除対象の列挙
+     */
+    let targets = select_targets(&own, &others, &root, cutoff, opts)?;
+
+    if targets.is_empty() {
+        return Ok(0);
+    }
+
+    for path in &targets {
+        println!("prune {}", path.display());
+    }
+
+    if opts.is_dry_run() {
+        info!(
+            "would prune {} files older than {} under {}",
+            targets.len(),
+            cutoff,
+            root.display(),
+        );
+        return Ok(targets.len());
+    }
+
+    /*
+     * 削除の確認
+     */
+    let message = format!(
+        "prune {} files older than {} under {}?",
+        targets.len(),
+        cutoff,
+        root.display(),
+    );
+
+    if !opts.confirm(&message)? {
+        info!("prune cancelled");
+        return Ok(0);
+    }
+
+    /*
+     * 削除
+     */
+    let mut pruned = 0;
+
+    for path in targets {
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                info!("pruned {}", path.display());
+                if path.starts_with(&root) {
+                    remove_empty_dirs(&path, &root);
+                } else {
+                    remove_empty_dirs(&path, &canonical);
+                }
+                pruned += 1;
+            }
+
+            Err(err) => warn!("prune failed {}: {}", path.display(), err),
+        }
+    }
+
+    info!("pruned {} files older than {}", pruned, cutoff);
+
+    Ok(pruned)
+}
+
+///
+/// 保存期間の起点となる撮影日を求める
+///
+/// # 引数
+/// * `today` - 今日の日付
+/// * `days` - 保存期間の日数
+///
+/// # 戻り値
+/// 撮影日がこの日付より前のファイルが削除の対象となる
+///
+fn cutoff_date(today: NaiveDate, days: u32) -> NaiveDate {
+    today
+        .checked_sub_days(Days::new(days as u64))
+        .unwrap_or_default()
+}
+
+///
+/// 削除の対象となるファイルを列挙する
+///
+/// # 引数
+/// * `own` - 入力元のボリュームのレコードの振り分け先とExif情報の抜粋の組
+/// * `others` - 他のボリュームのレコードの振り分け先
+/// * `root` - 削除の対象とするディレクトリ
+/// * `cutoff` - 保存期間の起点となる撮影日
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は削除の対象となるファイルのパスの一覧を`Ok()`でラップ
+/// して返す。他のボリュームの振り分け先が`root`の配下にある場合はエラー情報
+/// を`Err()`でラップして返す。
+///
+/// # 注記
+/// 撮影日は取り込み時と同じ規則(`--datetime-format`、`--day-start`)で求め
+/// る。撮影日時を持たないもの、振り分け先に存在しないもの、オブジェクトスト
+/// アのオブジェクト(他のファイルと共有されている可能性がある)は対象としない。
+///
+fn select_targets(
+    own: &[(PathBuf, ExifSummary)],
+    others: &[PathBuf],
+    root: &Path,
+    cutoff: NaiveDate,
+    opts: &Options,
+) -> Result<Vec<PathBuf>> {
+    let cas_dir = (opts.layout() == StorageLayout::Cas).then(|| opts.cas_dir());
+
+    // キャッシュに記録されたパスと表記が異なる場合に備えて正規化したものも
+    // 照合に用いる
+    let canonical = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let is_under = |path: &Path| {
+        path.starts_with(root) || path.starts_with(&canonical)
+    };
+
+    /*
+     * 他のボリュームの振り分け先を含むディレクトリは対象外とする
+     */
+    if let Some(path) = others.iter().find(|path| is_under(path)) {
+        return Err(anyhow!(
+            "{} holds {} copied from another volume, refusing to prune",
+            root.display(),
+            path.display()
+        ));
+    }
+
+    /*
+     * 保存期間を過ぎたファイルの抽出
+     */
+    let mut targets = Vec::new();
+
+    for (path, exif) in own {
+        if !is_under(path) {
+            continue;
+        }
+
+        if cas_dir.as_ref().is_some_and(|dir| path.starts_with(dir)) {
+            continue;
+        }
+
+        let Some(value) = exif.datetime_original.as_deref() else {
+            continue;
+        };
+
+        let Ok(datetime) = parse_datetime(value, opts.datetime_formats()) else {
+            continue;
+        };
+
+        if shooting_date(&datetime, opts) >= cutoff {
+            continue;
+        }
+
+        if path.is_file() {
+            targets.push(path.clone());
+        }
+    }
+
+    Ok(targets)
+}
+
+///
+/// 削除したファイルの親ディレクトリのうち空になったものを削除する
+///
+/// # 引数
+/// * `path` - 削除したファイルのパス
+/// * `root` - 振り分け先のルートディレクトリ（これ自体は削除しない）
+///
+fn remove_empty_dirs(path: &Path, root: &Path) {
+    let mut dir: Option<PathBuf> = path.parent().map(Path::to_path_buf);
+
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) {
+            break;
+        }
+
+        // 空でない場合は失敗するので、そこで打ち切る
+        if fs::remove_dir(&current).is_err() {
+            break;
+        }
+
+        dir = current.parent().map(Path::to_path_buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// テスト用の作業ディレクトリを作成する
+    ///
+    fn work_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("imgdist-prune-{}-{}", name, std::process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    ///
+    /// 撮影日時を持つファイルを作成し、振り分け先の記録として返す
+    ///
+    fn record(dir: &Path, name: &str, datetime: &str)
+        -> (PathBuf, ExifSummary)
+    {
+        let path = dir.join(name);
+        fs::write(&path, name).unwrap();
+
+        let exif = ExifSummary {
+            datetime_original: Some(datetime.to_string()),
+            ..Default::default()
+        };
+
+        (path, exif)
+    }
+
+    ///
+    /// 日付の値を作成する
+    ///
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn cutoff_date_subtracts_retain_days() {
+        assert_eq!(cutoff_date(date(2024, 5, 10), 1), date(2024, 5, 9));
+        assert_eq!(cutoff_date(date(2024, 5, 10), 10), date(2024, 4, 30));
+        assert_eq!(cutoff_date(date(2024, 3, 1), 1), date(2024, 2, 29));
+    }
+
+    #[test]
+    fn select_targets_keeps_files_on_and_after_cutoff() {
+        let dir = work_dir("cutoff");
+        let opts = Options::with_day_start("00:00");
+        let own = vec![
+            record(&dir, "old.jpg", "2024:04:30 23:59:59"),
+            record(&dir, "edge.jpg", "2024:05:01 00:00:00"),
+            record(&dir, "new.jpg", "2024:05:02 12:00:00"),
+        ];
+
+        let targets = select_targets(&own, &[], &dir, date(2024, 5, 1), &opts)
+            .unwrap();
+
+        assert_eq!(targets, vec![dir.join("old.jpg")]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn select_targets_follows_day_start() {
+        let dir = work_dir("day-start");
+        let opts = Options::with_day_start("04:00");
+        let own = vec![record(&dir, "night.jpg", "2024:05:01 03:00:00")];
+
+        // 04:00より前の撮影は前日の撮影として扱われる
+        let targets = select_targets(&own, &[], &dir, date(2024, 5, 1), &opts)
+            .unwrap();
+
+        assert_eq!(targets, vec![dir.join("night.jpg")]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn select_targets_skips_missing_and_undated_files() {
+        let dir = work_dir("skip");
+        let opts = Options::with_day_start("00:00");
+        let missing = record(&dir, "missing.jpg", "2024:01:01 00:00:00");
+        let mut undated = record(&dir, "undated.jpg", "");
+
+        fs::remove_file(&missing.0).unwrap();
+        undated.1.datetime_original = None;
+
+        let own = vec![
+            missing,
+            undated,
+            record(&dir, "broken.jpg", "not a datetime"),
+        ];
+
+        let targets = select_targets(&own, &[], &dir, date(2024, 5, 1), &opts)
+            .unwrap();
+
+        assert!(targets.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn select_targets_ignores_files_outside_root() {
+        let dir = work_dir("outside");
+        let root = dir.join("root");
+        fs::create_dir_all(&root).unwrap();
+
+        let opts = Options::with_day_start("00:00");
+        let own = vec![record(&dir, "old.jpg", "2024:01:01 00:00:00")];
+
+        let targets = select_targets(&own, &[], &root, date(2024, 5, 1), &opts)
+            .unwrap();
+
+        assert!(targets.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn select_targets_refuses_root_with_other_volumes() {
+        let dir = work_dir("others");
+        let opts = Options::with_day_start("00:00");
+        let own = vec![record(&dir, "old.jpg", "2024:01:01 00:00:00")];
+        let others = vec![dir.join("sub").join("other.jpg")];
+
+        let cutoff = date(2024, 5, 1);
+        let result = select_targets(&own, &others, &dir, cutoff, &opts);
+
+        assert!(result.is_err());
+        assert!(dir.join("old.jpg").is_file());
+
+        /*
+         * 配下に無ければ他のボリュームの記録があっても削除できる
+         */
+        let others = vec![std::env::temp_dir().join("elsewhere.jpg")];
+        let targets = select_targets(&own, &others, &dir, cutoff, &opts)
+            .unwrap();
+
+        assert_eq!(targets, vec![dir.join("old.jpg")]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
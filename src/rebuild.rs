@@ -0,0 +1,195 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先の状態からキャッシュレコードを再構築するモジュール
+//!
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::error;
+
+use crate::audit::tree_destination;
+use crate::cache::{volume_label, Cache, CacheDecision, ExifCache};
+use crate::cmd_args::{Options, RebuildArgs};
+use crate::report::FileTimings;
+use crate::template::Sequencer;
+use crate::{input_files, is_target_file};
+
+///
+/// 再構築の件数を集計する構造体
+///
+#[derive(Debug, Default)]
+struct RebuildCount {
+    /// レコードを再構築したファイル数
+    rebuilt: usize,
+
+    /// 振り分け先に見当たらなかったファイル数
+    missing: usize,
+
+    /// 処理に失敗したファイル数
+    failed: usize,
+}
+
+///
+/// `cache rebuild`サブコマンドの実行関数
+///
+/// # 引数
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 入力ディレクトリ中の取り込み対象の形式のファイルについて、現在の設定で振
+/// り分けた場合の振り分け先を求め、そこにファイルが存在するものをコピー済み
+/// としてキャッシュに記録する。ファイルのコピーは一切行わない。壊れたキャッ
+/// シュデータベースを退避して作り直した後に、既に取り込んだファイルを再度コ
+/// ピーしないようにするために用いる。
+///
+pub(crate) fn run(args: &RebuildArgs, opts: &Options) -> Result<()> {
+    if !args.input_path.is_dir() {
+        return Err(anyhow!(
+            "{} is not directory",
+            args.input_path.display()
+        ));
+    }
+
+    let opts = opts.with_input_path(args.input_path.clone());
+    let cache = Cache::open(
+        opts.cache_db_path(),
+        opts.cache_eval_mode(),
+        opts.input_path(),
+    )?;
+
+    let mut count = RebuildCount::default();
+    let exif_cache = ExifCache::new(&opts);
+    let mut sequencer = Sequencer::default();
+    let label = volume_label(&args.input_path);
+
+    for entry in input_files(&opts, &exif_cache) {
+        let path = entry.path();
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if !is_target_file(&ext, &opts) {
+            continue;
+        }
+
+        let name = path.strip_prefix(&args.input_path).unwrap_or(path);
+
+        let result = rebuild_file(
+            path,
+            &ext,
+            &opts,
+            &cache,
+            &exif_cache,
+            label.as_deref(),
+            &mut sequencer,
+        );
+
+        match result {
+            Ok(Some(dst)) => {
+                println!("rebuilt    {} -> {}", name.display(), dst.display());
+                count.rebuilt += 1;
+            }
+
+            Ok(None) => {
+                println!("missing    {}", name.display());
+                count.missing += 1;
+            }
+
+            Err(err) => {
+                error!("rebuild failed {}: {}", path.display(), err);
+                count.failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "rebuilt: {}, missing: {}, failed: {}",
+        count.rebuilt,
+        count.missing,
+        count.failed
+    );
+
+    Ok(())
+}
+
+///
+/// 入力ファイル1件分のキャッシュレコードを再構築する
+///
+/// # 引数
+/// * `path` - 入力ファイルのパス
+/// * `ext` - 入力ファイルの拡張子
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュデータベース
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+/// * `label` - 入力元のボリュームのラベル
+/// * `sequencer` - ファイル名の通し番号のカウンタ
+///
+/// # 戻り値
+/// 処理が成功した場合は記録した振り分け先のパス(振り分け先が求められない場
+/// 合や、振り分け先にファイルが無い場合はNone)を`Ok()`でラップして返す。失
+/// 敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 暗号化または圧縮を行う設定の場合は振り分け先のファイルの大きさが入力ファ
+/// イルと異なるため、存在のみを確認する。それ以外の場合は大きさの一致も確認
+/// する。
+///
+fn rebuild_file(
+    path: &Path,
+    ext: &str,
+    opts: &Options,
+    cache: &Cache,
+    exif_cache: &ExifCache,
+    label: Option<&str>,
+    sequencer: &mut Sequencer,
+) -> Result<Option<PathBuf>> {
+    let meta = fs::metadata(path)?;
+
+    let dst = match tree_destination(
+        path,
+        ext,
+        opts,
+        exif_cache,
+        label,
+        sequencer,
+    )? {
+        Some(dst) => dst,
+        None => return Ok(None),
+    };
+
+    let dst_meta = match fs::metadata(&dst) {
+        Ok(dst_meta) => dst_meta,
+        Err(_) => return Ok(None),
+    };
+
+    let transformed = opts.encrypt_recipient().is_some()
+        || opts.compress_raw_level().is_some();
+
+    if !transformed && dst_meta.len() != meta.len() {
+        return Ok(None);
+    }
+
+    let mut timings = FileTimings::default();
+
+    if let CacheDecision::Miss {mut handle, ..} =
+        cache.evaluate_forced(path, meta, exif_cache, &mut timings)?
+    {
+        handle.set_destination(&dst);
+        cache.commit(handle)?;
+    }
+
+    Ok(Some(dst))
+}
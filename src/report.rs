@@ -0,0 +1,984 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 実行結果のサマリとレポートを取り扱うモジュール
+//!
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::Local;
+use log::{info, warn};
+use serde::{Serialize, Serializer};
+
+use crate::error::{ErrorKind, ImportError};
+use crate::estimate::format_size;
+
+///
+/// ファイルに対して行った処理の種別
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// 振り分け先へコピーした
+    Copied,
+
+    /// 処理を行わなかった
+    Skipped,
+
+    /// 処理に失敗した
+    Failed,
+}
+
+impl Action {
+    ///
+    /// 処理の種別の名称を返す
+    ///
+    /// # 戻り値
+    /// レポートに記録するものと同じ名称
+    ///
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Copied => "copied",
+            Self::Skipped => "skipped",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+///
+/// ファイルを処理しなかった理由
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkipReason {
+    /// キャッシュにヒットした(処理済み)
+    CacheHit,
+
+    /// 撮影日が処理対象の範囲外
+    OutOfRange,
+
+    /// 対応していない形式
+    UnsupportedExt,
+
+    /// 撮影日時の情報が無い
+    NoExif,
+
+    /// 入力元の取り込みマーカーにより取り込み済みと判断した
+    BeforeMarker,
+
+    /// 除外リストに含まれる(他の経路で受け渡し済み)
+    Excluded,
+
+    /// 画像の向きが処理対象と異なる
+    Orientation,
+
+    /// 除外するカメラで撮影された
+    ExcludedCamera,
+
+    /// コピー中に入力ファイルが更新された(次回の取り込みに回した)
+    ModifiedDuringCopy,
+
+    /// 親のファイルと共にコピーする付随ファイル(テレメトリ、ボイスメモ)
+    Companion,
+
+    /// 振り分け先に同名のファイルが存在する(ティアの指定により残した)
+    Collision,
+
+    /// 履歴データベースに記録されている(他のマシンで取り込み済み)
+    Archived,
+
+    /// コピーせずにキャッシュ情報のみを記録した(`--mark-processed`)
+    MarkedProcessed,
+
+    /// 内容が一致するファイルが振り分け先に格納済み(`--dedup`)
+    Duplicate,
+}
+
+impl SkipReason {
+    ///
+    /// 理由の名称を返す
+    ///
+    /// # 戻り値
+    /// レポートに記録するものと同じ名称
+    ///
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::CacheHit => "cache-hit",
+            Self::OutOfRange => "out-of-range",
+            Self::UnsupportedExt => "unsupported-ext",
+            Self::NoExif => "no-exif",
+            Self::BeforeMarker => "before-marker",
+            Self::Excluded => "excluded",
+            Self::Orientation => "orientation",
+            Self::ExcludedCamera => "excluded-camera",
+            Self::ModifiedDuringCopy => "modified-during-copy",
+            Self::Companion => "companion",
+            Self::Collision => "collision",
+            Self::Archived => "archived",
+            Self::MarkedProcessed => "marked-processed",
+            Self::Duplicate => "duplicate",
+        }
+    }
+}
+
+// Displayトレイトの実装
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+///
+/// ファイル1件あたりの処理時間
+///
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub(crate) struct FileTimings {
+    /// Exif情報の読み出しに要した時間(ミリ秒)
+    #[serde(rename = "exif_read_ms", serialize_with = "serialize_ms")]
+    pub(crate) exif_read: Option<Duration>,
+
+    /// キャッシュの評価に要した時間(Exif読み出しを除く、ミリ秒)
+    #[serde(rename = "cache_eval_ms", serialize_with = "serialize_ms")]
+    pub(crate) cache_eval: Option<Duration>,
+
+    /// 振り分け先へのコピーに要した時間(ミリ秒)
+    #[serde(rename = "copy_ms", serialize_with = "serialize_ms")]
+    pub(crate) copy: Option<Duration>,
+}
+
+impl FileTimings {
+    ///
+    /// Exif情報の読み出し時間を加算する
+    ///
+    /// # 引数
+    /// * `elapsed` - 読み出しに要した時間
+    ///
+    pub(crate) fn add_exif_read(&mut self, elapsed: Duration) {
+        self.exif_read = Some(self.exif_read.unwrap_or_default() + elapsed);
+    }
+}
+
+///
+/// 判定の1段階分の記録
+///
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct TraceStep {
+    /// 判定の名称
+    check: &'static str,
+
+    /// 判定に用いた値
+    value: String,
+
+    /// 判定結果
+    result: bool,
+}
+
+///
+/// ファイル1件分の処理結果
+///
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEntry {
+    /// 入力ファイルのパス
+    pub source: PathBuf,
+
+    /// 振り分け先のパス(コピーした場合のみ)
+    pub destination: Option<PathBuf>,
+
+    /// 共にコピーした付随ファイルの振り分け先のパス
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub companions: Vec<PathBuf>,
+
+    /// コピーした内容を読み戻して入力ファイルと照合したか否か
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub verified: bool,
+
+    /// 処理の種別
+    pub action: Action,
+
+    /// 撮影日(撮影日時を得られた場合のみ、YYYY-MM-DD形式)
+    pub date: Option<String>,
+
+    /// カメラの機種名(Exif情報に記録されている場合のみ)
+    pub camera: Option<String>,
+
+    /// ファイルサイズ(バイト)
+    pub size: u64,
+
+    /// エラーメッセージ(失敗した場合のみ)
+    pub error: Option<String>,
+
+    /// 失敗の種別(失敗した場合のみ)
+    pub error_kind: Option<ErrorKind>,
+
+    /// 処理を行わなかった理由(スキップした場合のみ)
+    pub reason: Option<SkipReason>,
+
+    /// 処理時間
+    pub(crate) timings: FileTimings,
+
+    /// 判定の経過(トレースレポートにのみ出力する)
+    #[serde(skip)]
+    pub(crate) trace: Vec<TraceStep>,
+}
+
+impl FileEntry {
+    ///
+    /// インスタンスを構築する
+    ///
+    /// # 引数
+    /// * `source` - 入力ファイルのパス
+    /// * `size` - ファイルサイズ(バイト)
+    ///
+    /// # 戻り値
+    /// 処理種別を`Skipped`とした初期状態のエントリ
+    ///
+    pub(crate) fn new(source: &Path, size: u64) -> Self {
+        Self {
+            source: source.to_path_buf(),
+            destination: None,
+            companions: Vec::new(),
+            verified: false,
+            action: Action::Skipped,
+            date: None,
+            camera: None,
+            size,
+            error: None,
+            error_kind: None,
+            reason: None,
+            timings: FileTimings::default(),
+            trace: Vec::new(),
+        }
+    }
+
+    ///
+    /// 判定の経過を記録する
+    ///
+    /// # 引数
+    /// * `check` - 判定の名称
+    /// * `value` - 判定に用いた値
+    /// * `result` - 判定結果
+    ///
+    pub(crate) fn check<T>(
+        &mut self,
+        check: &'static str,
+        value: T,
+        result: bool,
+    )
+    where
+        T: fmt::Display,
+    {
+        self.trace.push(TraceStep {
+            check,
+            value: value.to_string(),
+            result,
+        });
+    }
+}
+
+///
+/// トレースレポートの1行分のデータ
+///
+#[derive(Serialize)]
+struct TraceLine<'a> {
+    /// 記録日時(ISO8601)
+    time: String,
+
+    /// 処理結果
+    #[serde(flatten)]
+    entry: &'a FileEntry,
+
+    /// 判定の経過
+    steps: &'a [TraceStep],
+}
+
+///
+/// トレースレポート(JSONL形式)の書き出しを行う構造体
+///
+pub(crate) struct TraceWriter {
+    /// 書き出し先のパス
+    path: PathBuf,
+
+    /// 書き出し先
+    writer: BufWriter<File>,
+}
+
+impl TraceWriter {
+    ///
+    /// 書き出し先のファイルを作成する
+    ///
+    /// # 引数
+    /// * `path` - 書き出し先のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はオブジェクトを`Ok()`でラップして返す。失敗した場合
+    /// はエラー情報を`Err()`でラップして返す。
+    ///
+    pub(crate) fn create<P>(path: P) -> Result<Self, ImportError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+
+        match File::create(&path) {
+            Ok(file) => Ok(Self { path, writer: BufWriter::new(file) }),
+            Err(err) => {
+                let context = format!("create {} failed", path.display());
+                Err(ImportError::io(context, err))
+            }
+        }
+    }
+
+    ///
+    /// ファイル1件分の処理結果と判定の経過を1行で書き出す
+    ///
+    /// # 引数
+    /// * `entry` - 処理結果
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラッ
+    /// プして返す
+    ///
+    pub(crate) fn write(&mut self, entry: &FileEntry)
+        -> Result<(), ImportError>
+    {
+        let line = TraceLine {
+            time: Local::now().to_rfc3339(),
+            entry,
+            steps: &entry.trace,
+        };
+
+        let result = serde_json::to_writer(&mut self.writer, &line)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+
+        if let Err(err) = result {
+            let context = format!("write {} failed", self.path.display());
+            return Err(ImportError::io(context, err));
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// バッファの内容を書き出す
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラッ
+    /// プして返す
+    ///
+    pub(crate) fn flush(&mut self) -> Result<(), ImportError> {
+        if let Err(err) = self.writer.flush() {
+            let context = format!("write {} failed", self.path.display());
+            return Err(ImportError::io(context, err));
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// 処理時間の統計値
+///
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+struct TimingStats {
+    /// 計測件数
+    count: usize,
+
+    /// 50パーセンタイル(ミリ秒)
+    p50_ms: f64,
+
+    /// 90パーセンタイル(ミリ秒)
+    p90_ms: f64,
+
+    /// 99パーセンタイル(ミリ秒)
+    p99_ms: f64,
+
+    /// 最大値(ミリ秒)
+    max_ms: f64,
+}
+
+impl TimingStats {
+    ///
+    /// 計測値の列から統計値を求める
+    ///
+    /// # 引数
+    /// * `samples` - 計測値の列
+    ///
+    /// # 戻り値
+    /// 統計値(計測値が無い場合は件数0の統計値)
+    ///
+    fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+
+        samples.sort();
+
+        Self {
+            count: samples.len(),
+            p50_ms: to_ms(percentile(&samples, 50)),
+            p90_ms: to_ms(percentile(&samples, 90)),
+            p99_ms: to_ms(percentile(&samples, 99)),
+            max_ms: to_ms(samples[samples.len() - 1]),
+        }
+    }
+}
+
+///
+/// 実行結果のサマリ
+///
+#[derive(Debug, Default, Serialize)]
+struct Summary {
+    /// コピーしたファイル数
+    copied: usize,
+
+    /// 処理を行わなかったファイル数
+    skipped: usize,
+
+    /// 処理を行わなかった理由ごとのファイル数
+    skip_reasons: BTreeMap<SkipReason, usize>,
+
+    /// 処理に失敗したファイル数
+    failed: usize,
+
+    /// 失敗の種別ごとのファイル数
+    error_kinds: BTreeMap<ErrorKind, usize>,
+
+    /// コピーしたバイト数
+    copied_bytes: u64,
+
+    /// コピーしたファイルのうち内容を照合したファイル数
+    verified: usize,
+
+    /// コピーしたファイルのうち内容を照合したものの割合(0〜1)
+    verified_ratio: f64,
+
+    /// 実行に要した時間(秒)
+    elapsed_secs: f64,
+
+    /// コピーの平均スループット(バイト毎秒)
+    throughput: f64,
+
+    /// Exif情報の読み出し時間の統計
+    exif_read: TimingStats,
+
+    /// キャッシュ評価時間の統計
+    cache_eval: TimingStats,
+
+    /// コピー時間の統計
+    copy: TimingStats,
+}
+
+///
+/// 1回の実行分の処理結果を集約する構造体
+///
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// 実行開始日時(ISO8601)
+    started: String,
+
+    /// 実行終了日時(ISO8601)
+    finished: Option<String>,
+
+    /// 入力元のボリュームID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume_id: Option<String>,
+
+    /// サマリ
+    summary: Summary,
+
+    /// 入力元との接続が失われて処理を中断した場合はその理由
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parked: Option<String>,
+
+    /// 予行演習の結果か否か(コピーしたファイルはコピーする予定のもの)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    dry_run: bool,
+
+    /// 入力元への書き込みを伴う機能を禁止して実行したか否か
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    source_read_only: bool,
+
+    /// ファイルごとの処理結果
+    files: Vec<FileEntry>,
+
+    /// 実行時間の計測の起点
+    #[serde(skip)]
+    clock: Instant,
+}
+
+impl Report {
+    ///
+    /// インスタンスを構築する
+    ///
+    /// # 戻り値
+    /// 開始日時を現在時刻とした空のレポート
+    ///
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Local::now().to_rfc3339(),
+            finished: None,
+            volume_id: None,
+            summary: Summary::default(),
+            parked: None,
+            dry_run: false,
+            source_read_only: false,
+            files: Vec::new(),
+            clock: Instant::now(),
+        }
+    }
+
+    ///
+    /// 予行演習の結果であることを記録する
+    ///
+    pub(crate) fn set_dry_run(&mut self) {
+        self.dry_run = true;
+    }
+
+    ///
+    /// 入力元への書き込みを伴う機能を禁止して実行したことを記録する
+    ///
+    pub(crate) fn set_source_read_only(&mut self) {
+        self.source_read_only = true;
+    }
+
+    ///
+    /// ファイル1件分の処理結果を追加する
+    ///
+    /// # 引数
+    /// * `entry` - 追加する処理結果
+    ///
+    pub(crate) fn push(&mut self, entry: FileEntry) {
+        self.files.push(entry);
+    }
+
+    ///
+    /// 実行を終了しサマリを確定する
+    ///
+    /// # 注記
+    /// 呼び出しによって終了日時とサマリが更新される。
+    ///
+    pub(crate) fn finish(&mut self) {
+        let mut summary = Summary::default();
+        let mut exif_read = Vec::new();
+        let mut cache_eval = Vec::new();
+        let mut copy = Vec::new();
+
+        for entry in &self.files {
+            match entry.action {
+                Action::Copied => {
+                    summary.copied += 1;
+                    summary.copied_bytes += entry.size;
+
+                    if entry.verified {
+                        summary.verified += 1;
+                    }
+                }
+
+                Action::Skipped => {
+                    summary.skipped += 1;
+
+                    if let Some(reason) = entry.reason {
+                        *summary.skip_reasons.entry(reason).or_default() += 1;
+                    }
+                }
+
+                Action::Failed => {
+                    summary.failed += 1;
+
+                    if let Some(kind) = entry.error_kind {
+                        *summary.error_kinds.entry(kind).or_default() += 1;
+                    }
+                }
+            }
+
+            exif_read.extend(entry.timings.exif_read);
+            cache_eval.extend(entry.timings.cache_eval);
+            copy.extend(entry.timings.copy);
+        }
+
+        if summary.copied > 0 {
+            summary.verified_ratio =
+                summary.verified as f64 / summary.copied as f64;
+        }
+
+        summary.elapsed_secs = self.clock.elapsed().as_secs_f64();
+
+        if summary.elapsed_secs > 0.0 {
+            summary.throughput =
+                summary.copied_bytes as f64 / summary.elapsed_secs;
+        }
+
+        summary.exif_read = TimingStats::from_samples(exif_read);
+        summary.cache_eval = TimingStats::from_samples(cache_eval);
+        summary.copy = TimingStats::from_samples(copy);
+
+        self.summary = summary;
+        self.finished = Some(Local::now().to_rfc3339());
+    }
+
+    ///
+    /// 処理を中断したことを記録する
+    ///
+    /// # 引数
+    /// * `reason` - 中断の理由
+    ///
+    pub(crate) fn park(&mut self, reason: String) {
+        self.parked = Some(reason);
+    }
+
+    ///
+    /// 処理の中断理由へのアクセサ
+    ///
+    /// # 戻り値
+    /// 中断の理由（最後まで処理した場合はNone）
+    ///
+    pub(crate) fn parked(&self) -> Option<&str> {
+        self.parked.as_deref()
+    }
+
+    ///
+    /// 実行開始日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 実行開始日時(ISO8601)
+    ///
+    pub(crate) fn started(&self) -> &str {
+        &self.started
+    }
+
+    ///
+    /// 実行終了日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 実行終了日時(ISO8601、`finish()`の呼び出し前はNone)
+    ///
+    pub(crate) fn finished(&self) -> Option<&str> {
+        self.finished.as_deref()
+    }
+
+    ///
+    /// 入力元のボリュームIDを記録する
+    ///
+    /// # 引数
+    /// * `volume_id` - キャッシュのキーに用いたボリュームID
+    ///
+    pub(crate) fn set_volume_id(&mut self, volume_id: &str) {
+        self.volume_id = Some(volume_id.to_string());
+    }
+
+    ///
+    /// 入力元のボリュームIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// 入力元のボリュームID（記録されていない場合はNone）
+    ///
+    pub(crate) fn volume_id(&self) -> Option<&str> {
+        self.volume_id.as_deref()
+    }
+
+    ///
+    /// コピーしたファイル数へのアクセサ
+    ///
+    /// # 戻り値
+    /// コピーしたファイル数（`finish()`の呼び出し後に確定する）
+    ///
+    pub fn copied(&self) -> usize {
+        self.summary.copied
+    }
+
+    ///
+    /// 処理を行わなかったファイル数へのアクセサ
+    ///
+    /// # 戻り値
+    /// 処理を行わなかったファイル数（`finish()`の呼び出し後に確定する）
+    ///
+    pub fn skipped(&self) -> usize {
+        self.summary.skipped
+    }
+
+    ///
+    /// コピーしたバイト数へのアクセサ
+    ///
+    /// # 戻り値
+    /// コピーしたバイト数（`finish()`の呼び出し後に確定する）
+    ///
+    pub fn copied_bytes(&self) -> u64 {
+        self.summary.copied_bytes
+    }
+
+    ///
+    /// 処理に失敗したファイル数へのアクセサ
+    ///
+    /// # 戻り値
+    /// 処理に失敗したファイル数（`finish()`の呼び出し後に確定する）
+    ///
+    pub fn failed(&self) -> usize {
+        self.summary.failed
+    }
+
+    ///
+    /// 処理結果の列挙
+    ///
+    /// # 戻り値
+    /// 全てのファイルのエントリを順に返すイテレータ
+    ///
+    pub fn entries(&self) -> impl Iterator<Item = &FileEntry> {
+        self.files.iter()
+    }
+
+    ///
+    /// 処理に失敗したファイルの列挙
+    ///
+    /// # 戻り値
+    /// 処理に失敗したファイルのエントリを順に返すイテレータ
+    ///
+    pub fn failures(&self) -> impl Iterator<Item = &FileEntry> {
+        self.files
+            .iter()
+            .filter(|entry| entry.action == Action::Failed)
+    }
+
+    ///
+    /// ファイルをコピーした振り分け先フォルダの取得
+    ///
+    /// # 戻り値
+    /// コピー先のファイルを含むフォルダの集合
+    ///
+    pub(crate) fn destination_dirs(&self) -> BTreeSet<PathBuf> {
+        self.files
+            .iter()
+            .filter(|entry| entry.action == Action::Copied)
+            .filter_map(|entry| entry.destination.as_deref())
+            .filter_map(|path| path.parent())
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    ///
+    /// サマリをログに出力する
+    ///
+    pub(crate) fn log_summary(&self) {
+        let summary = &self.summary;
+
+        info!(
+            "summary{}: {} files (copied {}, skipped {}, failed {}), {} copied",
+            if self.dry_run { " (dry run)" } else { "" },
+            self.files.len(),
+            summary.copied,
+            summary.skipped,
+            summary.failed,
+            format_size(summary.copied_bytes),
+        );
+
+        if !summary.skip_reasons.is_empty() {
+            let reasons = summary
+                .skip_reasons
+                .iter()
+                .map(|(reason, count)| format!("{} {}", reason, count))
+                .collect::<Vec<_>>();
+
+            info!("skip reasons: {}", reasons.join(", "));
+        }
+
+        if !summary.error_kinds.is_empty() {
+            let kinds = summary
+                .error_kinds
+                .iter()
+                .map(|(kind, count)| format!("{} {}", kind, count))
+                .collect::<Vec<_>>();
+
+            info!("failure kinds: {}", kinds.join(", "));
+        }
+
+        if summary.verified > 0 {
+            info!(
+                "verified: {} of {} copied files ({:.1}%)",
+                summary.verified,
+                summary.copied,
+                summary.verified_ratio * 100.0,
+            );
+        }
+
+        if summary.copied > 0 {
+            info!(
+                "throughput: {}/s ({:.1}s elapsed)",
+                format_size(summary.throughput as u64),
+                summary.elapsed_secs,
+            );
+        }
+
+        if let Some(ref reason) = self.parked {
+            warn!("run parked: {}", reason);
+        }
+
+        log_timing("exif read", &summary.exif_read);
+        log_timing("cache eval", &summary.cache_eval);
+        log_timing("copy", &summary.copy);
+    }
+
+    ///
+    /// レポートをJSON形式でファイルに書き出す
+    ///
+    /// # 引数
+    /// * `path` - 書き出し先のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラッ
+    /// プして返す
+    ///
+    pub(crate) fn write_json<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+
+        Ok(())
+    }
+
+    ///
+    /// ファイルごとの処理結果をCSV形式でファイルに書き出す
+    ///
+    /// # 引数
+    /// * `path` - 書き出し先のパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラッ
+    /// プして返す
+    ///
+    /// # 注記
+    /// 表計算ソフトで開くことを想定し、1行目に列名を出力する。値が存在しない
+    /// 列は空欄とする。
+    ///
+    pub(crate) fn write_csv<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writeln!(writer, "source,destination,date,camera,size,action")?;
+
+        for entry in &self.files {
+            let destination = entry
+                .destination
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default();
+
+            writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                csv_field(&entry.source.display().to_string()),
+                csv_field(&destination),
+                csv_field(entry.date.as_deref().unwrap_or_default()),
+                csv_field(entry.camera.as_deref().unwrap_or_default()),
+                entry.size,
+                entry.action.as_str(),
+            )?;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+///
+/// CSVの1項目分の値を整形する
+///
+/// # 引数
+/// * `value` - 項目の値
+///
+/// # 戻り値
+/// 区切り文字、引用符、改行を含む場合は引用符で囲んだ値(RFC 4180に準拠)
+///
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+///
+/// 処理時間の統計値をログに出力する
+///
+/// # 引数
+/// * `label` - 計測対象の名称
+/// * `stats` - 統計値
+///
+fn log_timing(label: &str, stats: &TimingStats) {
+    if stats.count == 0 {
+        return;
+    }
+
+    info!(
+        "timing {}: n={} p50={:.1}ms p90={:.1}ms p99={:.1}ms max={:.1}ms",
+        label,
+        stats.count,
+        stats.p50_ms,
+        stats.p90_ms,
+        stats.p99_ms,
+        stats.max_ms,
+    );
+}
+
+///
+/// ソート済みの計測値の列からパーセンタイル値を求める(最近傍順位法)
+///
+/// # 引数
+/// * `sorted` - 昇順にソートされた計測値の列(空でないこと)
+/// * `pct` - パーセンタイル(0〜100)
+///
+/// # 戻り値
+/// パーセンタイル値
+///
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    let rank = (sorted.len() * pct).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+///
+/// 時間をミリ秒単位の浮動小数点数に変換する
+///
+/// # 引数
+/// * `duration` - 変換する時間
+///
+/// # 戻り値
+/// ミリ秒単位の値
+///
+fn to_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+///
+/// 処理時間をミリ秒単位でシリアライズする
+///
+/// # 引数
+/// * `duration` - シリアライズする時間
+/// * `serializer` - シリアライザ
+///
+/// # 戻り値
+/// シリアライザの処理結果
+///
+fn serialize_ms<S>(duration: &Option<Duration>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match duration {
+        Some(duration) => serializer.serialize_some(&to_ms(*duration)),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,215 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 複数のボリュームへの振り分け(シャーディング)を行うモジュール
+//!
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+
+use crate::cmd_args::Options;
+
+///
+/// 今回の取り込みで求めたシャードの空き容量の一覧
+///
+#[derive(Debug, Default)]
+pub(crate) struct FreeSpaces {
+    /// シャードごとの空き容量(求められなかった場合はNone)
+    spaces: Mutex<BTreeMap<PathBuf, Option<u64>>>,
+}
+
+impl FreeSpaces {
+    ///
+    /// 空き容量の一覧のロック
+    ///
+    /// # 戻り値
+    /// 空き容量の一覧のガードオブジェクト
+    ///
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Option<u64>>> {
+        self.spaces.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    ///
+    /// シャードの空き容量を求める
+    ///
+    /// # 引数
+    /// * `shard` - シャードのパス
+    ///
+    /// # 戻り値
+    /// 空き容量(求められなかった場合はNone)
+    ///
+    /// # 注記
+    /// 一度求めたシャードは`reset()`を呼ぶまで再度問い合わせない。
+    ///
+    fn get(&self, shard: &Path) -> Option<u64> {
+        if let Some(space) = self.lock().get(shard) {
+            return *space;
+        }
+
+        let space = match free_space(shard) {
+            Ok(free) => {
+                debug!("free space of {}: {} bytes", shard.display(), free);
+                Some(free)
+            }
+
+            Err(err) => {
+                warn!("{}", err);
+                None
+            }
+        };
+
+        self.lock().insert(shard.to_path_buf(), space);
+
+        space
+    }
+}
+
+///
+/// 出力ディレクトリを決定する
+///
+/// # 引数
+/// * `sub_path` - パステンプレートを展開した基点ディレクトリ以下のパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// シャードが設定されていればその中から選んだディレクトリ、設定されていなけ
+/// れば出力ディレクトリ
+///
+/// # 注記
+/// 同じ日のファイルが複数のボリュームに分かれないよう、展開したフォルダが既
+/// に存在するシャードがあればそれを選ぶ。無ければ空き容量が最も大きいシャー
+/// ドを選ぶ。空き容量はファイルごとに問い合わせず、取り込みごとにシャード1つ
+/// につき1回だけ求める。
+///
+pub(crate) fn output_root(sub_path: &Path, opts: &Options) -> PathBuf {
+    let shards = opts.shards();
+
+    if shards.is_empty() {
+        return opts.output_path();
+    }
+
+    /*
+     * 既にフォルダが存在するシャードの選択
+     */
+    if let Some(shard) = shards.iter().find(|shard| {
+        shard.join(sub_path).is_dir()
+    }) {
+        return shard.clone();
+    }
+
+    /*
+     * 空き容量が最も大きいシャードの選択
+     */
+    let spaces = opts.free_spaces();
+    let mut selected = (&shards[0], 0);
+
+    for shard in shards {
+        if let Some(free) = spaces.get(shard) {
+            if free > selected.1 {
+                selected = (shard, free);
+            }
+        }
+    }
+
+    selected.0.clone()
+}
+
+///
+/// 求めた空き容量の一覧を破棄する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 注記
+/// 取り込みの開始時に呼び出す(デーモンモード等で前回の取り込みの時点の空き
+/// 容量を用いないため)。
+///
+pub(crate) fn reset(opts: &Options) {
+    opts.free_spaces().lock().clear();
+}
+
+///
+/// ボリュームの空き容量を求める
+///
+/// # 引数
+/// * `path` - ボリューム上のパス
+///
+/// # 戻り値
+/// 処理が成功した場合は空き容量(バイト、一般ユーザが利用できるもの)を`Ok()`
+/// でラップして返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+#[cfg(unix)]
+fn free_space(path: &Path) -> Result<u64> {
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(anyhow!(
+            "statvfs {} failed: {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    let stat = unsafe { stat.assume_init() };
+
+    // 型の幅はプラットフォームにより異なる
+    #[allow(clippy::unnecessary_cast)]
+    let free = stat.f_bavail as u64 * stat.f_frsize as u64;
+
+    Ok(free)
+}
+
+///
+/// ボリュームの空き容量を求める
+///
+/// # 引数
+/// * `path` - ボリューム上のパス
+///
+/// # 戻り値
+/// 処理が成功した場合は空き容量(バイト、呼び出したユーザが利用できるもの)を
+/// `Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+#[cfg(target_os = "windows")]
+fn free_space(path: &Path) -> Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain([0])
+        .collect();
+    let mut available = 0u64;
+
+    let result = unsafe {
+        GetDiskFreeSpaceExW(
+            PCWSTR(wide.as_ptr()),
+            Some(&mut available as *mut u64),
+            None,
+            None,
+        )
+    };
+
+    match result {
+        Ok(()) => Ok(available),
+        Err(err) => Err(anyhow!(
+            "GetDiskFreeSpaceExW {} failed: {}",
+            path.display(),
+            err
+        )),
+    }
+}
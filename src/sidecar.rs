@@ -0,0 +1,324 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 付随ファイル(サイドカー)のみを補って取り込む修復処理を行うモジュール
+//!
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::{debug, error};
+
+use crate::cache::{volume_label, Cache, ExifCache};
+use crate::cmd_args::Options;
+use crate::companion;
+use crate::encrypt;
+use crate::exif_view::ExifView;
+use crate::template::{NameContext, PathContext, Sequencer};
+use crate::{
+    build_file_type, build_name, file_stem, input_files, original_dir,
+    resolve_datetime, shooting_date, src_dir, stored_name,
+};
+
+///
+/// 付随ファイルの処理結果
+///
+enum Outcome {
+    /// 振り分け先にコピーした
+    Copied,
+
+    /// 振り分け先に既に存在した
+    Present,
+
+    /// 親となる画像ファイルが振り分け先に無い
+    Orphan,
+}
+
+///
+/// 付随ファイルの処理件数の集計
+///
+#[derive(Debug, Default)]
+struct Summary {
+    /// コピーした件数
+    copied: u64,
+
+    /// 既に存在した件数
+    present: u64,
+
+    /// 親となる画像ファイルが見つからなかった件数
+    orphan: u64,
+
+    /// 処理に失敗した件数
+    failed: u64,
+}
+
+///
+/// 付随ファイルのみの取り込みの実行関数
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 入力ディレクトリを再走査し、親となる画像ファイルが既に振り分け先に存在す
+/// る付随ファイルのみをその隣にコピーする。画像ファイル自体の取り込みとキャッ
+/// シュの更新は行わない。
+///
+pub(crate) fn run(opts: &Options) -> Result<()> {
+    // 通し番号を含むファイル名は振り分け先の状態によって変わるため、親の振
+    // り分け先を求められない
+    if opts.name_template().is_some_and(|tmpl| tmpl.has_sequence()) {
+        return Err(anyhow!(
+            "--sidecars-only cannot be used with {{seq}} in name template"
+        ));
+    }
+
+    let mut summary = Summary::default();
+
+    // 同じ親を持つ付随ファイルが複数ある場合にExif情報の読み出しを1回で済ま
+    // せるため、実行中はキャッシュを共有する
+    let exif_cache = ExifCache::new(opts);
+    let label = volume_label(opts.input_path());
+
+    // 親の振り分け先の記録を参照するのみで更新はしない
+    let cache = opts.cache();
+
+    for entry in input_files(opts, &exif_cache) {
+        let path = entry.path();
+
+        if !companion::is_companion(path, opts) {
+            continue;
+        }
+
+        match repair_file(path, opts, &cache, &exif_cache, label.as_deref()) {
+            Ok(Outcome::Copied) => summary.copied += 1,
+            Ok(Outcome::Present) => summary.present += 1,
+            Ok(Outcome::Orphan) => summary.orphan += 1,
+            Err(err) => {
+                error!("{}", err);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    // 暗号化して格納した付随ファイルの対応をマニフェストに書き出す
+    encrypt::finalize(opts)?;
+
+    println!("copied:          {} files", summary.copied);
+    println!("already present: {} files", summary.present);
+    println!("no parent image: {} files", summary.orphan);
+    println!("failed:          {} files", summary.failed);
+
+    if summary.failed > 0 {
+        Err(anyhow!("{} sidecar files failed", summary.failed))
+    } else {
+        Ok(())
+    }
+}
+
+///
+/// 付随ファイル1件を振り分け先に補う
+///
+/// # 引数
+/// * `path` - 付随ファイルのパス
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュデータベース
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+/// * `label` - 入力元のボリュームのラベル
+///
+/// # 戻り値
+/// 処理が成功した場合は処理結果を`Ok()`でラップして返す。失敗した場合はエラー
+/// 情報を`Err()`でラップして返す。
+///
+fn repair_file(
+    path: &Path,
+    opts: &Options,
+    cache: &Cache,
+    exif_cache: &ExifCache,
+    label: Option<&str>,
+) -> Result<Outcome> {
+    /*
+     * 親となる画像ファイルの特定
+     */
+    let parent = match companion::parent(path, opts) {
+        Some(parent) => parent,
+        None => {
+            debug!("no parent image for {}", path.display());
+            return Ok(Outcome::Orphan);
+        }
+    };
+
+    /*
+     * 親となる画像ファイルの振り分け先の特定
+     */
+    // 取り込み時に名前を付け替えた場合(`--on-conflict rename`等)にも追従
+    // できるよう、キャッシュに記録された振り分け先を優先する
+    let dst = match recorded_destination(&parent, opts, cache)? {
+        Some(dst) => Some(dst),
+        None => parent_destination(&parent, opts, exif_cache, label)?,
+    };
+    let (dir, name) = match dst {
+        Some(dst) => dst,
+        None => {
+            debug!("parent of {} is not imported", path.display());
+            return Ok(Outcome::Orphan);
+        }
+    };
+
+    /*
+     * 付随ファイルのコピー
+     */
+    // 通常の取り込みと同じ規則で名前を付け、同名のファイルの取り扱いと暗号
+    // 化も取り込み時の付随ファイルと揃える
+    let name = companion::companion_name(path, &parent, &name);
+    let stored = if opts.encrypt_recipient().is_some() {
+        dir.join(encrypt::encrypted_name(&name, opts))
+    } else {
+        dir.join(&name)
+    };
+
+    // 暗号化した場合はサイズで比較できないため存在のみで判定する
+    let size = path.metadata()?.len();
+    let present = stored.metadata().is_ok_and(|meta| {
+        opts.encrypt_recipient().is_some() || meta.len() == size
+    });
+
+    if present {
+        debug!("sidecar already present: {}", stored.display());
+        return Ok(Outcome::Present);
+    }
+
+    match companion::store(path, &dir, &name, opts.on_conflict(), opts)? {
+        Some(_) => Ok(Outcome::Copied),
+        None => Ok(Outcome::Present),
+    }
+}
+
+///
+/// キャッシュに記録された親のファイルの振り分け先を求める
+///
+/// # 引数
+/// * `parent` - 親のファイルのパス
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュデータベース
+///
+/// # 戻り値
+/// 処理が成功した場合は、振り分け先が記録されていてそこに親が存在すれば格納
+/// 先のディレクトリと振り分け先でのファイル名(圧縮による名前の変更を適用する
+/// 前のもの)の組を、そうでなければNoneを`Ok()`でラップして返す。失敗した場
+/// 合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 暗号化した場合は記録された名前から元のファイル名を求められないため、常に
+/// Noneを返す。
+///
+fn recorded_destination(parent: &Path, opts: &Options, cache: &Cache)
+    -> Result<Option<(PathBuf, String)>>
+{
+    if opts.encrypt_recipient().is_some() {
+        return Ok(None);
+    }
+
+    let dst = match cache.destination_of(parent)? {
+        Some(dst) if dst.is_file() => dst,
+        _ => return Ok(None),
+    };
+
+    let (Some(dir), Some(name)) = (dst.parent(), dst.file_name()) else {
+        return Ok(None);
+    };
+
+    let mut name = name.to_string_lossy().to_string();
+
+    if opts.compress_raw_level().is_some() {
+        if let Some(stem) = name.strip_suffix(".zst") {
+            name = stem.to_string();
+        }
+    }
+
+    Ok(Some((dir.to_path_buf(), name)))
+}
+
+///
+/// 親となる画像ファイルの振り分け先を求める
+///
+/// # 引数
+/// * `parent` - 親となる画像ファイルのパス
+/// * `opts` - オプション設定の参照
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+/// * `label` - 入力元のボリュームのラベル
+///
+/// # 戻り値
+/// 処理が成功した場合は、振り分け先に親が存在すれば格納先のディレクトリと振
+/// り分け先でのファイル名(暗号化・圧縮による名前の変更を適用する前のもの)の
+/// 組を、存在しなければNoneを`Ok()`でラップして返す。失敗した場合はエラー情
+/// 報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 振り分け先は取り込み時と同じ規則で求める。ただし、GPS時刻による時計のず
+/// れの補正は適用しない。
+///
+fn parent_destination(
+    parent: &Path,
+    opts: &Options,
+    exif_cache: &ExifCache,
+    label: Option<&str>,
+) -> Result<Option<(PathBuf, String)>> {
+    let (exif, _) = exif_cache.read(parent)?;
+
+    let datetime = match resolve_datetime(&exif, parent, opts)? {
+        Some(datetime) => datetime,
+        None => return Ok(None),
+    };
+
+    let view = ExifView::from(&*exif);
+    let date = shooting_date(&datetime, opts);
+    let ext = parent.extension().unwrap().to_string_lossy();
+    let ctx = PathContext {
+        date,
+        artist: view.artist.as_deref(),
+        event: opts.event_at(&datetime),
+        camera: view.camera_name(),
+        ext: &ext,
+        original_dir: original_dir(parent, opts),
+        src_dir: src_dir(parent),
+        volume_label: label,
+    };
+
+    let file_type = match build_file_type(&ext, &ctx, opts) {
+        Some(file_type) => file_type,
+        None => return Ok(None),
+    };
+
+    let name_ctx = NameContext {
+        datetime: datetime.naive_local(),
+        subsec: view.subsec.as_deref(),
+        artist: ctx.artist,
+        event: ctx.event,
+        stem: &file_stem(parent),
+        src_dir: ctx.src_dir,
+        volume_label: ctx.volume_label,
+    };
+    let name = build_name(
+        parent,
+        &file_type,
+        &name_ctx,
+        opts,
+        &mut Sequencer::default(),
+    );
+
+    let dir = file_type.dir().to_path_buf();
+
+    if dir.join(stored_name(&name, &file_type, opts)).exists() {
+        Ok(Some((dir, name)))
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,183 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 取り込み後の出力先スナップショットの作成を行うモジュール
+//!
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process;
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use log::info;
+
+use crate::cmd_args::{Options, SnapshotMode};
+use crate::external::{execute, shell};
+
+/// スナップショット名の接頭辞
+const NAME_PREFIX: &str = "imgdist";
+
+/// btrfsのスナップショットを格納するディレクトリ名
+const BTRFS_SNAPSHOT_DIR: &str = ".snapshots";
+
+///
+/// 出力先のスナップショットを作成する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// スナップショット名は`imgdist-YYYYMMDD-HHMMSS`の形式とする。
+///
+pub(crate) fn take(opts: &Options) -> Result<()> {
+    let name = format!(
+        "{}-{}",
+        NAME_PREFIX,
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+
+    match opts.snapshot_mode() {
+        SnapshotMode::Off => {}
+
+        SnapshotMode::Btrfs => {
+            for root in opts.destination_roots() {
+                take_btrfs(&root, &name)?;
+            }
+        }
+
+        SnapshotMode::Zfs => {
+            // 同じデータセット上の出力先は一度だけスナップショットを作成する
+            let mut datasets = BTreeSet::new();
+
+            for root in opts.destination_roots() {
+                datasets.insert(zfs_dataset(&root)?);
+            }
+
+            for dataset in datasets {
+                let snapshot = format!("{}@{}", dataset, name);
+                execute(
+                    process::Command::new("zfs")
+                        .arg("snapshot")
+                        .arg(&snapshot)
+                )?;
+
+                info!("zfs snapshot created: {}", snapshot);
+            }
+        }
+
+        SnapshotMode::Apfs => {
+            // tmutilはボリューム単位での指定ができないため一度だけ実行する
+            execute(process::Command::new("tmutil").arg("localsnapshot"))?;
+            info!("apfs local snapshot created");
+        }
+
+        SnapshotMode::Command => run_hook(opts, &name)?,
+    }
+
+    Ok(())
+}
+
+///
+/// btrfsのスナップショットを作成する
+///
+/// # 引数
+/// * `root` - スナップショットを作成するサブボリューム
+/// * `name` - スナップショット名
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// スナップショットは読み取り専用とし、サブボリューム直下の`.snapshots`ディレ
+/// クトリに作成する。
+///
+fn take_btrfs(root: &Path, name: &str) -> Result<()> {
+    let dir = root.join(BTRFS_SNAPSHOT_DIR);
+    std::fs::create_dir_all(&dir)?;
+
+    let dst = dir.join(name);
+
+    execute(
+        process::Command::new("btrfs")
+            .args(["subvolume", "snapshot", "-r"])
+            .arg(root)
+            .arg(&dst)
+    )?;
+
+    info!("btrfs snapshot created: {}", dst.display());
+
+    Ok(())
+}
+
+///
+/// パスを含むZFSデータセットの名前を求める
+///
+/// # 引数
+/// * `path` - 対象のパス
+///
+/// # 戻り値
+/// 処理が成功した場合はデータセット名を`Ok()`でラップして返す。失敗した場合は
+/// エラー情報を`Err()`でラップして返す。
+///
+fn zfs_dataset(path: &Path) -> Result<String> {
+    let output = execute(
+        process::Command::new("zfs")
+            .args(["list", "-H", "-o", "name"])
+            .arg(path)
+    )?;
+
+    match output.lines().next() {
+        Some(name) if !name.trim().is_empty() => Ok(name.trim().to_string()),
+        _ => Err(anyhow!("zfs dataset not found for {}", path.display())),
+    }
+}
+
+///
+/// スナップショット作成用のフックコマンドを実行する
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `name` - スナップショット名
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// コマンドはシェル経由で実行し、スナップショット名と出力先を環境変数で渡す。
+///
+fn run_hook(opts: &Options, name: &str) -> Result<()> {
+    let command = match opts.snapshot_command() {
+        Some(command) => command,
+        None => return Err(anyhow!("snapshot command is not specified")),
+    };
+
+    let mut process = shell(&command);
+
+    process
+        .env("IMGDIST_SNAPSHOT_NAME", name)
+        .env("IMGDIST_OUTPUT", opts.output_path());
+
+    if let Some(path) = opts.raw_output_path() {
+        process.env("IMGDIST_RAW_OUTPUT", path);
+    }
+
+    if let Some(path) = opts.video_output_path() {
+        process.env("IMGDIST_VIDEO_OUTPUT", path);
+    }
+
+    execute(&mut process)?;
+    info!("snapshot hook executed: {}", command);
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 入力元の接続状態の確認を行うモジュール
+//!
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Error;
+use log::{info, warn};
+
+/// 再試行の待ち時間の上限
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+///
+/// 入力元に到達できるかを確認する
+///
+/// # 引数
+/// * `path` - 入力ディレクトリのパス
+/// * `timeout` - 応答を待つ時間
+///
+/// # 戻り値
+/// 時間内にディレクトリの読み出しができた場合は`true`
+///
+/// # 注記
+/// ネットワーク共有の停止時にはディレクトリの読み出し自体が戻らなくなること
+/// があるため、読み出しは別スレッドで行い応答の有無で判定する(応答の無いスレ
+/// ッドはそのまま放置する)。
+///
+pub(crate) fn is_reachable(path: &Path, timeout: Duration) -> bool {
+    let path = path.to_path_buf();
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let result = std::fs::read_dir(&path)
+            .and_then(|mut entries| entries.next().transpose())
+            .is_ok();
+
+        let _ = sender.send(result);
+    });
+
+    receiver.recv_timeout(timeout).unwrap_or(false)
+}
+
+///
+/// エラーが入力元との接続の喪失によるものかを判定する
+///
+/// # 引数
+/// * `err` - ファイルの処理で発生したエラー
+/// * `path` - 入力ディレクトリのパス
+/// * `timeout` - 入力元の応答を待つ時間
+///
+/// # 戻り値
+/// 接続の喪失によるものと判断した場合は`true`
+///
+/// # 注記
+/// エラーの種別が通信の失敗を示すもの、またはエラーの発生後に入力ディレクト
+/// リに到達できなくなっているものを接続の喪失と判断する。
+///
+pub(crate) fn is_lost(err: &Error, path: &Path, timeout: Duration) -> bool {
+    is_disconnect(err) || !is_reachable(path, timeout)
+}
+
+///
+/// 入力元への到達を待つ
+///
+/// # 引数
+/// * `path` - 入力ディレクトリのパス
+/// * `retries` - 確認の最大回数
+/// * `timeout` - 1回の確認で応答を待つ時間
+///
+/// # 戻り値
+/// 入力元に到達できるようになった場合は`true`
+///
+/// # 注記
+/// 確認の間隔は1秒から倍々に伸ばす(上限は60秒)。
+///
+pub(crate) fn wait(path: &Path, retries: usize, timeout: Duration) -> bool {
+    let mut delay = Duration::from_secs(1);
+
+    for attempt in 1..=retries {
+        warn!(
+            "{} is unreachable, retrying in {}s ({}/{})",
+            path.display(),
+            delay.as_secs(),
+            attempt,
+            retries
+        );
+
+        thread::sleep(delay);
+
+        if is_reachable(path, timeout) {
+            info!("{} is reachable again", path.display());
+            return true;
+        }
+
+        delay = (delay * 2).min(MAX_BACKOFF);
+    }
+
+    false
+}
+
+///
+/// エラーの種別が通信の失敗を示すものかを判定する
+///
+/// # 引数
+/// * `err` - 判定するエラー
+///
+/// # 戻り値
+/// 通信の失敗を示すものであれば`true`
+///
+fn is_disconnect(err: &Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|err| {
+            matches!(
+                err.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::NotConnected
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::HostUnreachable
+                    | ErrorKind::NetworkUnreachable
+                    | ErrorKind::NetworkDown
+                    | ErrorKind::StaleNetworkFileHandle
+            )
+        })
+}
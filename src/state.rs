@@ -0,0 +1,302 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 実行時の状態を保存するステートディレクトリを取り扱うモジュール
+//!
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ImportError;
+use crate::report::Report;
+
+/// 前回の実行結果を保存するファイルの名前
+const LAST_RUN: &str = "last_run.json";
+
+/// 中断した実行の情報を保存するファイルの名前
+const PARKED: &str = "parked.json";
+
+///
+/// 前回の実行結果の概要
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct LastRun {
+    /// 実行開始日時(ISO8601)
+    pub(crate) started: String,
+
+    /// 実行終了日時(ISO8601)
+    pub(crate) finished: Option<String>,
+
+    /// 入力ディレクトリのパス
+    pub(crate) input_path: PathBuf,
+
+    /// コピーしたファイル数
+    pub(crate) copied: usize,
+
+    /// 処理を行わなかったファイル数
+    pub(crate) skipped: usize,
+
+    /// 処理に失敗したファイル数
+    pub(crate) failed: usize,
+
+    /// コピーしたバイト数
+    pub(crate) copied_bytes: u64,
+}
+
+impl LastRun {
+    ///
+    /// レポートから実行結果の概要を構築する
+    ///
+    /// # 引数
+    /// * `report` - 取り込みの実行結果
+    /// * `input_path` - 入力ディレクトリのパス
+    ///
+    /// # 戻り値
+    /// 実行結果の概要
+    ///
+    pub(crate) fn new(report: &Report, input_path: &Path) -> Self {
+        Self {
+            started: report.started().to_string(),
+            finished: report.finished().map(str::to_string),
+            input_path: input_path.to_path_buf(),
+            copied: report.copied(),
+            skipped: report.skipped(),
+            failed: report.failed(),
+            copied_bytes: report.copied_bytes(),
+        }
+    }
+}
+
+///
+/// 入力元との接続が失われて中断した実行の情報
+///
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Parked {
+    /// 中断した日時(ISO8601)
+    pub(crate) parked_at: String,
+
+    /// 入力ディレクトリのパス
+    pub(crate) input_path: PathBuf,
+
+    /// 中断時に処理していたファイルのパス
+    pub(crate) last_file: Option<PathBuf>,
+
+    /// 中断の理由
+    pub(crate) reason: String,
+}
+
+///
+/// ステートディレクトリへのアクセスを提供する構造体
+///
+/// # 注記
+/// チェックポイントやロックファイル、前回の実行結果など、キャッシュデータベー
+/// スに記録する性質のものではない実行時の状態はこのディレクトリに保存する。
+/// 状態は名前をつけたJSONファイルとして保存する。
+///
+#[derive(Debug)]
+pub(crate) struct StateStore {
+    /// ステートディレクトリのパス
+    dir: PathBuf,
+}
+
+impl StateStore {
+    ///
+    /// ステートディレクトリをオープンする
+    ///
+    /// # 引数
+    /// * `dir` - ステートディレクトリのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はオブジェクトを`Ok()`でラップして返す。失敗した場合
+    /// はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// ディレクトリが存在しない場合は作成する。
+    ///
+    pub(crate) fn open<P>(dir: P) -> Result<Self, ImportError>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+
+        if let Err(err) = fs::create_dir_all(dir) {
+            let context = format!("create {} failed", dir.display());
+            return Err(ImportError::io(context, err));
+        }
+
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    ///
+    /// 状態を保存するファイルのパスを返す
+    ///
+    /// # 引数
+    /// * `name` - 状態の名前(ファイル名)
+    ///
+    /// # 戻り値
+    /// ステートディレクトリ内のファイルのパス
+    ///
+    pub(crate) fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    ///
+    /// 状態を読み込む
+    ///
+    /// # 引数
+    /// * `name` - 状態の名前(ファイル名)
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は状態を`Ok()`でラップして返す(保存されていない場合は
+    /// None)。失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    pub(crate) fn read<T>(&self, name: &str) -> Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let path = self.path(name);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let reader = BufReader::new(File::open(&path)?);
+
+        match serde_json::from_reader(reader) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => Err(anyhow!("read {} failed: {}", path.display(), err)),
+        }
+    }
+
+    ///
+    /// 状態を書き込む
+    ///
+    /// # 引数
+    /// * `name` - 状態の名前(ファイル名)
+    /// * `value` - 書き込む状態
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    /// # 注記
+    /// 書き込み途中で中断した場合に壊れたファイルが残らないよう、一時ファイル
+    /// に書き込んだ後にリネームする。
+    ///
+    pub(crate) fn write<T>(&self, name: &str, value: &T)
+        -> Result<(), ImportError>
+    where
+        T: Serialize,
+    {
+        let path = self.path(name);
+        let tmp = self.path(&format!(".{}.tmp", name));
+
+        let result = File::create(&tmp)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer_pretty(&mut writer, value)?;
+                writer.flush()
+            })
+            .and_then(|_| fs::rename(&tmp, &path));
+
+        if let Err(err) = result {
+            let context = format!("write {} failed", path.display());
+            return Err(ImportError::io(context, err));
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 状態を削除する
+    ///
+    /// # 引数
+    /// * `name` - 状態の名前(ファイル名)
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す(保存されていない場合も`Ok(())`を返す)
+    ///
+    pub(crate) fn remove(&self, name: &str) -> Result<()> {
+        let path = self.path(name);
+
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 前回の実行結果を読み込む
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は実行結果の概要を`Ok()`でラップして返す(記録が無い
+    /// 場合はNone)。失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    pub(crate) fn last_run(&self) -> Result<Option<LastRun>> {
+        self.read(LAST_RUN)
+    }
+
+    ///
+    /// 実行結果を記録する
+    ///
+    /// # 引数
+    /// * `last_run` - 実行結果の概要
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    pub(crate) fn set_last_run(&self, last_run: &LastRun) -> Result<()> {
+        Ok(self.write(LAST_RUN, last_run)?)
+    }
+
+    ///
+    /// 中断した実行の情報を読み込む
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は中断した実行の情報を`Ok()`でラップして返す(中断し
+    /// た実行が無い場合はNone)。失敗した場合はエラー情報を`Err()`でラップして
+    /// 返す。
+    ///
+    pub(crate) fn parked(&self) -> Result<Option<Parked>> {
+        self.read(PARKED)
+    }
+
+    ///
+    /// 中断した実行の情報を記録する
+    ///
+    /// # 引数
+    /// * `parked` - 中断した実行の情報
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    pub(crate) fn set_parked(&self, parked: &Parked)
+        -> Result<(), ImportError>
+    {
+        self.write(PARKED, parked)
+    }
+
+    ///
+    /// 中断した実行の情報を削除する
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップ
+    /// して返す
+    ///
+    pub(crate) fn clear_parked(&self) -> Result<()> {
+        self.remove(PARKED)
+    }
+}
@@ -0,0 +1,270 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 取り込みの記録からの統計情報の表示を行うモジュール
+//!
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use chrono::{Days, Local, NaiveDate};
+
+use crate::cache::{parse_date_range, summaries, ExifSummary};
+use crate::cmd_args::{Options, StatsArgs};
+use crate::{parse_datetime, shooting_date};
+
+///
+/// カメラごとの使用状況を集計する構造体
+///
+#[derive(Debug, Default)]
+struct CameraUsage {
+    /// 取り込んだファイル数
+    files: usize,
+
+    /// 撮影が行われた日付の集合
+    days: BTreeSet<NaiveDate>,
+
+    /// ImageNumberの最小値
+    first_number: Option<u32>,
+
+    /// ImageNumberの最大値
+    last_number: Option<u32>,
+}
+
+impl CameraUsage {
+    ///
+    /// Exif情報の抜粋を集計に加える
+    ///
+    /// # 引数
+    /// * `exif` - キャッシュレコードに記録されたExif情報の抜粋
+    /// * `date` - 撮影日(記録されていない場合は`None`)
+    ///
+    fn add(&mut self, exif: &ExifSummary, date: Option<NaiveDate>) {
+        self.files += 1;
+
+        if let Some(date) = date {
+            self.days.insert(date);
+        }
+
+        if let Some(number) = exif.image_number {
+            self.first_number = Some(
+                self.first_number.map_or(number, |first| first.min(number))
+            );
+            self.last_number = Some(
+                self.last_number.map_or(number, |last| last.max(number))
+            );
+        }
+    }
+}
+
+///
+/// `stats`サブコマンドの実行関数
+///
+/// # 引数
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// キャッシュデータベースに記録されたExif情報の抜粋のみを用いて集計する(振り
+/// 分け先のファイルは参照しない)。カメラは機種名とシリアル番号の組で区別する。
+/// 撮影日には撮影日の区切り時刻(`--day-start`)を適用する。
+///
+pub(crate) fn run(args: &StatsArgs, opts: &Options) -> Result<()> {
+    let gaps = match &args.gaps {
+        Some(range) => Some(parse_date_range(range)?),
+        None => None,
+    };
+
+    let mut cameras = BTreeMap::<(String, String), CameraUsage>::new();
+    let mut days = BTreeSet::new();
+
+    for (_, exif) in summaries(&opts.cache_db_path())? {
+        let date = record_date(&exif, opts);
+        days.extend(date);
+
+        // Exif情報を持たないもの(画像以外のファイル等)は集計しない
+        let model = match exif.make_model.as_deref() {
+            Some(model) => unquote(model),
+            None => continue,
+        };
+
+        let serial = exif
+            .camera_serial
+            .as_deref()
+            .map(unquote)
+            .unwrap_or_default();
+
+        cameras.entry((model, serial)).or_default().add(&exif, date);
+    }
+
+    show_cameras(&cameras);
+
+    if let Some((from, to)) = gaps {
+        show_gaps(&days, from, to)?;
+    }
+
+    Ok(())
+}
+
+///
+/// カメラごとの使用状況を表示する
+///
+/// # 引数
+/// * `cameras` - 機種名とシリアル番号の組ごとの使用状況
+///
+fn show_cameras(cameras: &BTreeMap<(String, String), CameraUsage>) {
+    if cameras.is_empty() {
+        println!("no camera records in cache");
+        return;
+    }
+
+    for ((model, serial), usage) in cameras {
+        if serial.is_empty() {
+            println!("camera:          {}", model);
+        } else {
+            println!("camera:          {} (serial {})", model, serial);
+        }
+
+        println!("  files:         {}", usage.files);
+
+        if let (Some(first), Some(last)) =
+            (usage.days.first(), usage.days.last())
+        {
+            println!(
+                "  days:          {} ({} .. {})",
+                usage.days.len(),
+                first,
+                last
+            );
+        }
+
+        if let (Some(first), Some(last)) =
+            (usage.first_number, usage.last_number)
+        {
+            println!(
+                "  shutter count: {} (+{} since {})",
+                last,
+                last - first,
+                first
+            );
+        }
+    }
+}
+
+///
+/// 撮影の無い日を表示する
+///
+/// # 引数
+/// * `days` - 撮影が行われた日付の集合
+/// * `from` - 範囲の始点(この日付を含む、省略時は最初の撮影日)
+/// * `to` - 範囲の終点(この日付を含まない、省略時は翌日)
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 連続する日付はまとめて1行に表示する。
+///
+fn show_gaps(
+    days: &BTreeSet<NaiveDate>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<()> {
+    let from = match from.or_else(|| days.first().copied()) {
+        Some(from) => from,
+        None => return Err(anyhow!("no dated records in cache")),
+    };
+
+    let to = to.unwrap_or_else(|| Local::now().date_naive() + Days::new(1));
+
+    if from >= to {
+        return Err(anyhow!("empty date range: {}..{}", from, to));
+    }
+
+    /*
+     * 撮影の無い日を連続する範囲ごとにまとめる
+     */
+    let mut gaps: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+    let total = to.signed_duration_since(from).num_days();
+
+    for date in from.iter_days().take_while(|date| *date < to) {
+        if days.contains(&date) {
+            continue;
+        }
+
+        match gaps.last_mut() {
+            Some((_, last)) if *last + Days::new(1) == date => *last = date,
+            _ => gaps.push((date, date)),
+        }
+    }
+
+    let missing = gaps
+        .iter()
+        .map(|(first, last)| last.signed_duration_since(*first).num_days() + 1)
+        .sum::<i64>();
+
+    println!(
+        "missing days:    {} of {} ({} .. {})",
+        missing,
+        total,
+        from,
+        to - Days::new(1)
+    );
+
+    for (first, last) in gaps {
+        if first == last {
+            println!("  {}", first);
+        } else {
+            println!(
+                "  {} .. {} ({} days)",
+                first,
+                last,
+                last.signed_duration_since(first).num_days() + 1
+            );
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Exif情報の抜粋から撮影日を求める
+///
+/// # 引数
+/// * `exif` - Exif情報の抜粋
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 撮影日の区切り時刻を適用した撮影日(記録されていない場合は`None`)
+///
+fn record_date(exif: &ExifSummary, opts: &Options) -> Option<NaiveDate> {
+    let value = exif.datetime_original.as_ref()?;
+    let datetime = parse_datetime(value, opts.datetime_formats()).ok()?;
+    Some(shooting_date(&datetime, opts))
+}
+
+///
+/// Exif情報の文字列値から引用符を取り除く
+///
+/// # 引数
+/// * `value` - Exif情報の表示用の文字列
+///
+/// # 戻り値
+/// 前後の引用符と空白を取り除いた文字列
+///
+fn unquote(value: &str) -> String {
+    value
+        .split('/')
+        .map(|part| part.trim_matches(|c: char| c == '"' || c.is_whitespace()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
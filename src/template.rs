@@ -0,0 +1,838 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 振り分け先のパステンプレートとファイル名テンプレートを取り扱うモジュール
+//!
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// デフォルトのパステンプレート
+pub(crate) const DEFAULT_TEMPLATE: &str = "{year}/{year}{month}{day}";
+
+/// 値が得られなかった変数に適用する文字列
+const UNKNOWN: &str = "unknown";
+
+/// `{subsec}`の桁数
+const SUBSEC_DIGITS: usize = 3;
+
+///
+/// テンプレート中の変数を指し示す列挙子
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variable {
+    /// 撮影年(4桁)
+    Year,
+
+    /// 撮影月(2桁)
+    Month,
+
+    /// 撮影日(2桁)
+    Day,
+
+    /// 撮影者名(Exifの撮影者・所有者情報)
+    Artist,
+
+    /// 撮影日時を含むカレンダーのイベント名
+    Event,
+
+    /// カメラの機種名(パステンプレートのみ)
+    Camera,
+
+    /// 元のファイルの拡張子(小文字、パステンプレートのみ)
+    Ext,
+
+    /// 入力ディレクトリからの元のファイルのディレクトリの相対パス(パステン
+    /// プレートのみ)
+    OriginalDir,
+
+    /// 元のファイルのあるディレクトリの名前
+    SrcDir,
+
+    /// 入力元のボリュームのラベル
+    VolumeLabel,
+
+    /// 撮影時(2桁、ファイル名テンプレートのみ)
+    Hour,
+
+    /// 撮影分(2桁、ファイル名テンプレートのみ)
+    Minute,
+
+    /// 撮影秒(2桁、ファイル名テンプレートのみ)
+    Second,
+
+    /// 撮影時刻の秒未満(3桁、ファイル名テンプレートのみ)
+    Subsec,
+
+    /// 同名となるファイルの通し番号(3桁、ファイル名テンプレートのみ)
+    Seq,
+
+    /// 元のファイル名(拡張子を除く、ファイル名テンプレートのみ)
+    Name,
+
+    /// 元のファイル名の末尾の番号(ファイル名テンプレートのみ)
+    FileNumber,
+}
+
+impl Variable {
+    ///
+    /// 変数名から変数を求める
+    ///
+    /// # 引数
+    /// * `name` - 変数名
+    ///
+    /// # 戻り値
+    /// 対応する変数（未知の変数名の場合はNone）
+    ///
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "year" => Some(Self::Year),
+            "month" => Some(Self::Month),
+            "day" => Some(Self::Day),
+            "artist" => Some(Self::Artist),
+            "event" => Some(Self::Event),
+            "camera" => Some(Self::Camera),
+            "ext" => Some(Self::Ext),
+            "original_dir" => Some(Self::OriginalDir),
+            "src_dir" => Some(Self::SrcDir),
+            "volume_label" => Some(Self::VolumeLabel),
+            "hour" => Some(Self::Hour),
+            "minute" => Some(Self::Minute),
+            "second" => Some(Self::Second),
+            "subsec" => Some(Self::Subsec),
+            "seq" => Some(Self::Seq),
+            "name" => Some(Self::Name),
+            "file_number" => Some(Self::FileNumber),
+            _ => None,
+        }
+    }
+
+    ///
+    /// パステンプレートで使用可能な変数かの判定
+    ///
+    /// # 戻り値
+    /// 使用可能な場合は`true`
+    ///
+    fn is_path_variable(&self) -> bool {
+        matches!(
+            self,
+            Self::Year
+                | Self::Month
+                | Self::Day
+                | Self::Artist
+                | Self::Event
+                | Self::Camera
+                | Self::Ext
+                | Self::OriginalDir
+                | Self::SrcDir
+                | Self::VolumeLabel
+        )
+    }
+
+    ///
+    /// ファイル名テンプレートで使用可能な変数かの判定
+    ///
+    /// # 戻り値
+    /// 使用可能な場合は`true`
+    ///
+    fn is_name_variable(&self) -> bool {
+        !matches!(self, Self::Camera | Self::Ext | Self::OriginalDir)
+    }
+}
+
+///
+/// テンプレートを構成する要素
+///
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// 固定文字列
+    Literal(String),
+
+    /// 変数
+    Variable(Variable),
+}
+
+///
+/// テンプレートの展開に用いる値をまとめた構造体
+///
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PathContext<'a> {
+    /// 撮影日（区切り時刻を適用済みのもの）
+    pub(crate) date: NaiveDate,
+
+    /// 撮影者名（Exifに記録されていない場合はNone）
+    pub(crate) artist: Option<&'a str>,
+
+    /// イベント名（該当するイベントが無い場合はNone）
+    pub(crate) event: Option<&'a str>,
+
+    /// カメラの機種名（Exifに記録されていない場合はNone）
+    pub(crate) camera: Option<&'a str>,
+
+    /// 元のファイルの拡張子
+    pub(crate) ext: &'a str,
+
+    /// 入力ディレクトリからの元のファイルのディレクトリの相対パス（入力ディ
+    /// レクトリの外のファイルの場合はNone）
+    pub(crate) original_dir: Option<&'a Path>,
+
+    /// 元のファイルのあるディレクトリの名前（得られない場合はNone）
+    pub(crate) src_dir: Option<&'a str>,
+
+    /// 入力元のボリュームのラベル（得られない場合はNone）
+    pub(crate) volume_label: Option<&'a str>,
+}
+
+///
+/// パース済みのパステンプレート
+///
+#[derive(Debug, Clone)]
+pub(crate) struct PathTemplate {
+    /// テンプレート文字列
+    source: String,
+
+    /// テンプレートを構成する要素の列
+    tokens: Vec<Token>,
+}
+
+impl PathTemplate {
+    ///
+    /// テンプレート文字列をパースする
+    ///
+    /// # 引数
+    /// * `source` - テンプレート文字列
+    ///
+    /// # 戻り値
+    /// パースが成功した場合はパース済みのテンプレートを`Ok()`でラップして返す。
+    /// 失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 出力ディレクトリの外を指し示すことが無いよう、絶対パスや`..`を含むテン
+    /// プレートはエラーとする。
+    ///
+    pub(crate) fn parse(source: &str) -> Result<Self> {
+        /*
+         * パス構成の確認
+         */
+        let path = Path::new(source);
+
+        if source.is_empty() {
+            return Err(anyhow!("path template is empty"));
+        }
+
+        if path
+            .components()
+            .any(|comp| !matches!(comp, Component::Normal(_)))
+        {
+            return Err(anyhow!(
+                "invalid path template: {} (must be a relative path)",
+                source
+            ));
+        }
+
+        /*
+         * 固定文字列と変数への分解
+         */
+        Ok(Self {
+            source: source.to_string(),
+            tokens: tokenize(source, "path", Variable::is_path_variable)?,
+        })
+    }
+
+    ///
+    /// テンプレートを展開する
+    ///
+    /// # 引数
+    /// * `ctx` - 展開に用いる値
+    ///
+    /// # 戻り値
+    /// 展開した相対パス
+    ///
+    /// # 注記
+    /// `{original_dir}`は階層を保ったまま展開する(入力ディレクトリ直下のファ
+    /// イルでは空となる)。展開の結果として生じた空の階層は取り除く。
+    ///
+    pub(crate) fn expand(&self, ctx: &PathContext) -> PathBuf {
+        let mut path = String::new();
+
+        for token in &self.tokens {
+            match token {
+                Token::Literal(text) => path.push_str(text),
+
+                Token::Variable(Variable::Year) => {
+                    path.push_str(&ctx.date.format("%Y").to_string())
+                }
+
+                Token::Variable(Variable::Month) => {
+                    path.push_str(&ctx.date.format("%m").to_string())
+                }
+
+                Token::Variable(Variable::Day) => {
+                    path.push_str(&ctx.date.format("%d").to_string())
+                }
+
+                Token::Variable(Variable::Artist) => {
+                    path.push_str(&sanitize(ctx.artist))
+                }
+
+                Token::Variable(Variable::Event) => {
+                    path.push_str(&sanitize(ctx.event))
+                }
+
+                Token::Variable(Variable::Camera) => {
+                    path.push_str(&sanitize(ctx.camera))
+                }
+
+                Token::Variable(Variable::Ext) => {
+                    path.push_str(&sanitize(Some(&ctx.ext.to_lowercase())))
+                }
+
+                Token::Variable(Variable::OriginalDir) => {
+                    match ctx.original_dir {
+                        Some(dir) => {
+                            let parts = dir
+                                .components()
+                                .filter_map(|comp| match comp {
+                                    Component::Normal(part) => Some(sanitize(
+                                        Some(&part.to_string_lossy())
+                                    )),
+                                    _ => None,
+                                })
+                                .collect::<Vec<_>>();
+
+                            path.push_str(&parts.join("/"));
+                        }
+
+                        None => path.push_str(UNKNOWN),
+                    }
+                }
+
+                Token::Variable(Variable::SrcDir) => {
+                    path.push_str(&sanitize(ctx.src_dir))
+                }
+
+                Token::Variable(Variable::VolumeLabel) => {
+                    path.push_str(&sanitize(ctx.volume_label))
+                }
+
+                // パステンプレートのパース時に除外済み
+                Token::Variable(_) => {}
+            }
+        }
+
+        // 空の階層(`{original_dir}`が空の場合等)を取り除く
+        Path::new(&path)
+            .components()
+            .filter(|comp| matches!(comp, Component::Normal(_)))
+            .collect()
+    }
+}
+
+// Defaultトレイトの実装
+impl Default for PathTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE).unwrap()
+    }
+}
+
+// Displayトレイトの実装
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+///
+/// ファイル名テンプレートの展開に用いる値をまとめた構造体
+///
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NameContext<'a> {
+    /// 撮影日時（区切り時刻は適用しない）
+    pub(crate) datetime: NaiveDateTime,
+
+    /// 撮影時刻の秒未満（Exifの値のまま、記録されていない場合はNone）
+    pub(crate) subsec: Option<&'a str>,
+
+    /// 撮影者名（Exifに記録されていない場合はNone）
+    pub(crate) artist: Option<&'a str>,
+
+    /// イベント名（該当するイベントが無い場合はNone）
+    pub(crate) event: Option<&'a str>,
+
+    /// 元のファイル名（拡張子を除く）
+    pub(crate) stem: &'a str,
+
+    /// 元のファイルのあるディレクトリの名前（得られない場合はNone）
+    pub(crate) src_dir: Option<&'a str>,
+
+    /// 入力元のボリュームのラベル（得られない場合はNone）
+    pub(crate) volume_label: Option<&'a str>,
+}
+
+///
+/// パース済みのファイル名テンプレート
+///
+#[derive(Debug, Clone)]
+pub(crate) struct NameTemplate {
+    /// テンプレート文字列
+    source: String,
+
+    /// テンプレートを構成する要素の列
+    tokens: Vec<Token>,
+}
+
+impl NameTemplate {
+    ///
+    /// テンプレート文字列をパースする
+    ///
+    /// # 引数
+    /// * `source` - テンプレート文字列
+    ///
+    /// # 戻り値
+    /// パースが成功した場合はパース済みのテンプレートを`Ok()`でラップして返す。
+    /// 失敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// ファイル名のみを指定するものであるため、パス区切り文字を含むテンプレー
+    /// トはエラーとする。拡張子はテンプレートに含めない(元のファイルのものを
+    /// 引き継ぐ)。
+    ///
+    pub(crate) fn parse(source: &str) -> Result<Self> {
+        if source.is_empty() {
+            return Err(anyhow!("name template is empty"));
+        }
+
+        if source.contains(['/', '\\']) {
+            return Err(anyhow!(
+                "invalid name template: {} (must not contain separator)",
+                source
+            ));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            tokens: tokenize(source, "name", Variable::is_name_variable)?,
+        })
+    }
+
+    ///
+    /// 通し番号を含むテンプレートかの判定
+    ///
+    /// # 戻り値
+    /// `{seq}`を含む場合は`true`
+    ///
+    pub(crate) fn has_sequence(&self) -> bool {
+        self.tokens.contains(&Token::Variable(Variable::Seq))
+    }
+
+    ///
+    /// テンプレートを展開する
+    ///
+    /// # 引数
+    /// * `ctx` - 展開に用いる値
+    /// * `seq` - `{seq}`に適用する通し番号
+    ///
+    /// # 戻り値
+    /// 展開したファイル名（拡張子を除く）
+    ///
+    pub(crate) fn expand(&self, ctx: &NameContext, seq: u32) -> String {
+        let mut name = String::new();
+
+        for token in &self.tokens {
+            let value = match token {
+                Token::Literal(text) => text.clone(),
+                Token::Variable(var) => match var {
+                    Variable::Year => ctx.datetime.format("%Y").to_string(),
+                    Variable::Month => ctx.datetime.format("%m").to_string(),
+                    Variable::Day => ctx.datetime.format("%d").to_string(),
+                    Variable::Hour => ctx.datetime.format("%H").to_string(),
+                    Variable::Minute => ctx.datetime.format("%M").to_string(),
+                    Variable::Second => ctx.datetime.format("%S").to_string(),
+                    Variable::Subsec => format_subsec(ctx.subsec),
+                    Variable::Seq => format!("{:03}", seq),
+                    Variable::Artist => sanitize(ctx.artist),
+                    Variable::Event => sanitize(ctx.event),
+                    Variable::Name => sanitize(Some(ctx.stem)),
+                    Variable::FileNumber => sanitize(file_number(ctx.stem)),
+                    Variable::SrcDir => sanitize(ctx.src_dir),
+                    Variable::VolumeLabel => sanitize(ctx.volume_label),
+
+                    // ファイル名テンプレートのパース時に除外済み
+                    Variable::Camera
+                        | Variable::Ext
+                        | Variable::OriginalDir => String::new(),
+                },
+            };
+
+            name.push_str(&value);
+        }
+
+        name
+    }
+}
+
+// Displayトレイトの実装
+impl fmt::Display for NameTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+///
+/// ファイル名の通し番号を払い出すカウンタ
+///
+/// # 注記
+/// 通し番号を除いたファイル名が同じになるもの(同じ秒に連写したもの等)ごとに
+/// 1から順に番号を払い出す。1回の取り込みの間のみ保持する。
+///
+#[derive(Debug, Default)]
+pub(crate) struct Sequencer {
+    /// 通し番号を除いた振り分け先のパスごとの次の番号
+    counters: HashMap<PathBuf, u32>,
+}
+
+impl Sequencer {
+    ///
+    /// 通し番号を払い出す
+    ///
+    /// # 引数
+    /// * `key` - 通し番号を除いた振り分け先のパス
+    ///
+    /// # 戻り値
+    /// 払い出した通し番号
+    ///
+    pub(crate) fn next(&mut self, key: PathBuf) -> u32 {
+        let counter = self.counters.entry(key).or_insert(0);
+        *counter += 1;
+        *counter
+    }
+}
+
+///
+/// テンプレート文字列を固定文字列と変数に分解する
+///
+/// # 引数
+/// * `source` - テンプレート文字列
+/// * `kind` - エラーメッセージに用いるテンプレートの種別
+/// * `allowed` - 使用可能な変数かを判定する関数
+///
+/// # 戻り値
+/// 分解が成功した場合は要素の列を`Ok()`でラップして返す。失敗した場合はエラー
+/// 情報を`Err()`でラップして返す。
+///
+fn tokenize(
+    source: &str,
+    kind: &str,
+    allowed: fn(&Variable) -> bool,
+) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            tokens.push(Token::Literal(rest[..start].to_string()));
+        }
+
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => {
+                return Err(anyhow!(
+                    "invalid {} template: {} (unclosed brace)",
+                    kind,
+                    source
+                ));
+            }
+        };
+
+        let name = &rest[start + 1..end];
+
+        match Variable::from_name(name) {
+            Some(var) if allowed(&var) => tokens.push(Token::Variable(var)),
+            Some(_) => {
+                return Err(anyhow!(
+                    "template variable {{{}}} is not available in {} template",
+                    name,
+                    kind
+                ));
+            }
+            None => {
+                return Err(anyhow!("unknown template variable: {{{}}}", name));
+            }
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    if rest.contains('}') {
+        return Err(anyhow!(
+            "invalid {} template: {} (unbalanced brace)",
+            kind,
+            source
+        ));
+    }
+
+    if !rest.is_empty() {
+        tokens.push(Token::Literal(rest.to_string()));
+    }
+
+    Ok(tokens)
+}
+
+///
+/// 秒未満の値を固定桁数の文字列に整える
+///
+/// # 引数
+/// * `value` - Exifに記録された秒未満の値（記録されていない場合はNone）
+///
+/// # 戻り値
+/// 3桁の数字列（桁が不足する場合は末尾を`0`で補い、超過する場合は切り捨て
+/// る。値が無い場合は`000`）
+///
+/// # 注記
+/// SubSecTimeOriginalは小数点以下の数字列であるため、桁数を揃えることで名前
+/// の順序と撮影順が一致する。
+///
+pub(crate) fn format_subsec(value: Option<&str>) -> String {
+    value
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_digit)
+        .chain(std::iter::repeat('0'))
+        .take(SUBSEC_DIGITS)
+        .collect()
+}
+
+///
+/// 元のファイル名から番号を取り出す
+///
+/// # 引数
+/// * `stem` - 元のファイル名（拡張子を除く）
+///
+/// # 戻り値
+/// ファイル名の末尾の数字の並び（`DSC_1234`の場合は`1234`、数字で終わらない
+/// 場合はNone）
+///
+/// # 注記
+/// カメラの付けた番号を残すために用いる。先頭の0は取り除かない。
+///
+fn file_number(stem: &str) -> Option<&str> {
+    let rest = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &stem[rest.len()..];
+
+    (!digits.is_empty()).then_some(digits)
+}
+
+///
+/// 変数の値をパスの要素として使用できる形に整える
+///
+/// # 引数
+/// * `value` - 変数の値（値が無い場合はNone）
+///
+/// # 戻り値
+/// パス区切り文字や制御文字を`_`に置き換えた文字列（値が無い場合や空になる場
+/// 合は`unknown`）
+///
+fn sanitize(value: Option<&str>) -> String {
+    let value = value
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+
+    if value.is_empty() || value == "." || value == ".." {
+        UNKNOWN.to_string()
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// ファイル名テンプレートの展開に用いる値を作成する
+    ///
+    fn name_context(stem: &str) -> NameContext<'_> {
+        let datetime = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 34, 56)
+            .unwrap();
+
+        NameContext {
+            datetime,
+            subsec: Some("7"),
+            artist: None,
+            event: None,
+            stem,
+            src_dir: None,
+            volume_label: None,
+        }
+    }
+
+    #[test]
+    fn name_template_expands_datetime_and_subsec() {
+        let template = NameTemplate::parse(
+            "{year}{month}{day}_{hour}{minute}{second}{subsec}"
+        ).unwrap();
+
+        assert_eq!(
+            template.expand(&name_context("IMG_0001"), 1),
+            "20240501_123456700"
+        );
+    }
+
+    #[test]
+    fn name_template_pads_sequence() {
+        let template = NameTemplate::parse("{name}-{seq}").unwrap();
+        let ctx = name_context("IMG_0001");
+
+        assert!(template.has_sequence());
+        assert_eq!(template.expand(&ctx, 1), "IMG_0001-001");
+        assert_eq!(template.expand(&ctx, 12), "IMG_0001-012");
+        assert_eq!(template.expand(&ctx, 1234), "IMG_0001-1234");
+    }
+
+    #[test]
+    fn name_template_without_sequence() {
+        let template = NameTemplate::parse("{year}_{name}").unwrap();
+
+        assert!(!template.has_sequence());
+    }
+
+    #[test]
+    fn name_template_rejects_separator_and_path_variables() {
+        assert!(NameTemplate::parse("{year}/{name}").is_err());
+        assert!(NameTemplate::parse("{camera}").is_err());
+        assert!(NameTemplate::parse("{unknown}").is_err());
+        assert!(NameTemplate::parse("{year").is_err());
+        assert!(NameTemplate::parse("").is_err());
+    }
+
+    #[test]
+    fn sequencer_counts_per_key() {
+        let mut seq = Sequencer::default();
+
+        assert_eq!(seq.next(PathBuf::from("a/x")), 1);
+        assert_eq!(seq.next(PathBuf::from("a/x")), 2);
+        assert_eq!(seq.next(PathBuf::from("a/y")), 1);
+    }
+
+    #[test]
+    fn subsec_is_fixed_width() {
+        assert_eq!(format_subsec(None), "000");
+        assert_eq!(format_subsec(Some("5")), "500");
+        assert_eq!(format_subsec(Some("12345")), "123");
+    }
+
+    #[test]
+    fn path_template_expands_default() {
+        let ctx = PathContext {
+            date: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            artist: None,
+            event: None,
+            camera: None,
+            ext: "JPG",
+            original_dir: None,
+            src_dir: None,
+            volume_label: None,
+        };
+
+        assert_eq!(
+            PathTemplate::default().expand(&ctx),
+            PathBuf::from("2024/20240501")
+        );
+    }
+
+    #[test]
+    fn path_template_drops_empty_original_dir() {
+        let template = PathTemplate::parse("{original_dir}/{ext}").unwrap();
+        let mut ctx = PathContext {
+            date: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            artist: None,
+            event: None,
+            camera: Some("EOS R5"),
+            ext: "CR3",
+            original_dir: Some(Path::new("")),
+            src_dir: None,
+            volume_label: None,
+        };
+
+        assert_eq!(template.expand(&ctx), PathBuf::from("cr3"));
+
+        ctx.original_dir = Some(Path::new("DCIM/100CANON"));
+        assert_eq!(template.expand(&ctx), PathBuf::from("DCIM/100CANON/cr3"));
+    }
+
+    #[test]
+    fn file_number_takes_trailing_digits() {
+        assert_eq!(file_number("DSC_1234"), Some("1234"));
+        assert_eq!(file_number("IMG_0001"), Some("0001"));
+        assert_eq!(file_number("1234"), Some("1234"));
+        assert_eq!(file_number("P1230045a"), None);
+        assert_eq!(file_number("photo"), None);
+        assert_eq!(file_number(""), None);
+    }
+
+    #[test]
+    fn name_template_expands_file_number() {
+        let template = NameTemplate::parse("{year}_{file_number}").unwrap();
+
+        assert_eq!(template.expand(&name_context("DSC_0042"), 1), "2024_0042");
+        assert_eq!(template.expand(&name_context("photo"), 1), "2024_unknown");
+    }
+
+    #[test]
+    fn sanitize_replaces_separators() {
+        assert_eq!(sanitize(Some("Canon EOS R5")), "Canon EOS R5");
+        assert_eq!(sanitize(Some("a/b\\c:d")), "a_b_c_d");
+        assert_eq!(sanitize(Some("tab\tname")), "tab_name");
+        assert_eq!(sanitize(Some("  padded  ")), "padded");
+    }
+
+    #[test]
+    fn sanitize_falls_back_to_unknown() {
+        assert_eq!(sanitize(None), UNKNOWN);
+        assert_eq!(sanitize(Some("")), UNKNOWN);
+        assert_eq!(sanitize(Some("   ")), UNKNOWN);
+        assert_eq!(sanitize(Some(".")), UNKNOWN);
+        assert_eq!(sanitize(Some("..")), UNKNOWN);
+    }
+
+    #[test]
+    fn path_template_sanitizes_artist() {
+        let template = PathTemplate::parse("{artist}/{year}").unwrap();
+        let ctx = PathContext {
+            date: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            artist: Some("../Alice"),
+            event: None,
+            camera: None,
+            ext: "JPG",
+            original_dir: None,
+            src_dir: None,
+            volume_label: None,
+        };
+
+        assert_eq!(template.expand(&ctx), PathBuf::from(".._Alice/2024"));
+    }
+
+    #[test]
+    fn path_template_rejects_escape() {
+        assert!(PathTemplate::parse("../{year}").is_err());
+        assert!(PathTemplate::parse("/{year}").is_err());
+        assert!(PathTemplate::parse("{seq}").is_err());
+    }
+}
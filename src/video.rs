@@ -0,0 +1,410 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 動画ファイル(MP4、QuickTime、AVI形式)から撮影日時を読み出すモジュール
+//!
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use exif::Exif;
+
+/// MP4の時刻の基点(1904-01-01T00:00:00Z)からUNIX時刻の基点までの秒数
+const MP4_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+///
+/// 動画ファイルの撮影日時をExif情報として読み出す
+///
+/// # 引数
+/// * `path` - 対象の動画ファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は撮影日時(DateTimeOriginal)のみを含むExif情報を`Ok()`
+/// でラップして返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 動画ファイルはExif情報を持たないため、コンテナに記録された作成日時を取り
+/// 出し、それを格納した最小限のTIFF構造を組み立てて読み込ませる。これにより
+/// 以降の処理は画像ファイルと同じ経路で扱える。作成日時が記録されていない場
+/// 合は撮影日時を含まないExif情報を返す(撮影日時の無い画像ファイルと同様に
+/// `--datetime-fallback`の指定に従って扱われる)。
+///
+pub(crate) fn read_exif(path: &Path) -> Result<Exif> {
+    let datetime = match creation_time(path) {
+        Ok(datetime) => datetime,
+        Err(err) => {
+            return Err(anyhow!("read {} failed: {}", path.display(), err));
+        }
+    };
+
+    let tiff = build_tiff(
+        datetime
+            .map(|datetime| datetime.format("%Y:%m:%d %H:%M:%S").to_string())
+            .as_deref()
+    );
+
+    match exif::Reader::new().read_raw(tiff) {
+        Ok(exif) => Ok(exif),
+        Err(err) => Err(anyhow!("build exif failed: {}", err)),
+    }
+}
+
+///
+/// コンテナの形式に応じて作成日時を読み出す
+///
+/// # 引数
+/// * `path` - 対象の動画ファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は作成日時(記録されていない場合はNone)を`Ok()`でラップ
+/// して返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 拡張子が`avi`の場合はRIFF形式として、それ以外はMP4形式(QuickTime形式も
+/// 同じボックス構造を持つ)として読み出す。
+///
+fn creation_time(path: &Path) -> Result<Option<DateTime<Local>>> {
+    let is_avi = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("avi"));
+
+    if is_avi {
+        idit_creation_time(path)
+    } else {
+        mvhd_creation_time(path)
+    }
+}
+
+///
+/// mvhdボックスから作成日時を読み出す
+///
+/// # 引数
+/// * `path` - 対象の動画ファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は作成日時(記録されていない場合はNone)を`Ok()`でラップ
+/// して返す。失敗した場合はエラー情報を`Err()`でラップして返す。
+///
+fn mvhd_creation_time(path: &Path) -> Result<Option<DateTime<Local>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let file_end = reader.seek(SeekFrom::End(0))?;
+
+    /*
+     * moovボックスの探索
+     */
+    let moov_end = match find_box(&mut reader, 0, file_end, b"moov")? {
+        Some(end) => end,
+        None => return Err(anyhow!("moov box not found")),
+    };
+
+    let pos = reader.stream_position()?;
+    if find_box(&mut reader, pos, moov_end, b"mvhd")?.is_none() {
+        return Err(anyhow!("mvhd box not found"));
+    }
+
+    /*
+     * 作成日時の読み出し(バージョン1の場合は64ビット)
+     */
+    let mut head = [0u8; 4];
+    reader.read_exact(&mut head)?;
+
+    let seconds = if head[0] == 1 {
+        let mut buf = [0u8; 8];
+        reader.read_exact(&mut buf)?;
+        u64::from_be_bytes(buf)
+    } else {
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf)?;
+        u32::from_be_bytes(buf) as u64
+    };
+
+    // 未設定の場合は0が記録されている
+    if seconds == 0 {
+        return Ok(None);
+    }
+
+    let unix = seconds as i64 - MP4_EPOCH_OFFSET;
+
+    Ok(DateTime::from_timestamp(unix, 0).map(|utc| utc.with_timezone(&Local)))
+}
+
+///
+/// 指定の範囲に並ぶボックスから指定の種別のものを探す
+///
+/// # 引数
+/// * `reader` - 読み出し元
+/// * `start` - 範囲の先頭のオフセット
+/// * `end` - 範囲の末尾のオフセット
+/// * `kind` - 探すボックスの種別
+///
+/// # 戻り値
+/// 処理が成功した場合は、見つかればボックスの末尾のオフセットを、見つからな
+/// ければNoneを`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラ
+/// ップして返す。
+///
+/// # 注記
+/// 見つかった場合、読み出し位置はボックスの本体(ヘッダの直後)を指す。
+///
+fn find_box<R>(reader: &mut R, start: u64, end: u64, kind: &[u8; 4])
+    -> Result<Option<u64>>
+where
+    R: Read + Seek,
+{
+    let mut pos = start;
+
+    while pos + 8 <= end {
+        reader.seek(SeekFrom::Start(pos))?;
+
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+
+        let mut size = u32::from_be_bytes([
+            header[0], header[1], header[2], header[3]
+        ]) as u64;
+        let mut header_len = 8;
+
+        // サイズが1の場合は64ビットのサイズが続き、0の場合は範囲の末尾まで
+        // を表す
+        if size == 1 {
+            let mut buf = [0u8; 8];
+            reader.read_exact(&mut buf)?;
+            size = u64::from_be_bytes(buf);
+            header_len = 16;
+        } else if size == 0 {
+            size = end - pos;
+        }
+
+        if size < header_len || pos + size > end {
+            return Err(anyhow!("broken box at offset {}", pos));
+        }
+
+        if &header[4..8] == kind {
+            return Ok(Some(pos + size));
+        }
+
+        pos += size;
+    }
+
+    Ok(None)
+}
+
+///
+/// AVIファイルのIDITチャンクから作成日時を読み出す
+///
+/// # 引数
+/// * `path` - 対象の動画ファイルのパス
+///
+/// # 戻り値
+/// 処理が成功した場合は作成日時(記録されていない場合や解釈できない場合は
+/// None)を`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラップ
+/// して返す。
+///
+/// # 注記
+/// IDITチャンクはhdrlリスト中に置かれ、作成日時を文字列(タイムゾーン無し)で
+/// 保持する。記録の形式はカメラによって異なるため、`parse_idit()`で既知の形
+/// 式を順に試す。
+///
+fn idit_creation_time(path: &Path) -> Result<Option<DateTime<Local>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"AVI " {
+        return Err(anyhow!("not AVI file"));
+    }
+
+    let file_end = reader.seek(SeekFrom::End(0))?;
+    let riff_end = (8 + u32::from_le_bytes([
+        header[4], header[5], header[6], header[7]
+    ]) as u64).min(file_end);
+
+    /*
+     * hdrlリストの探索
+     */
+    let hdrl_end = match find_chunk(&mut reader, 12, riff_end, b"hdrl")? {
+        Some(end) => end,
+        None => return Err(anyhow!("hdrl list not found")),
+    };
+
+    /*
+     * IDITチャンクの読み出し
+     */
+    let pos = reader.stream_position()?;
+    let idit_end = match find_chunk(&mut reader, pos, hdrl_end, b"IDIT")? {
+        Some(end) => end,
+        None => return Ok(None),
+    };
+
+    let pos = reader.stream_position()?;
+    let mut buf = vec![0u8; (idit_end - pos) as usize];
+    reader.read_exact(&mut buf)?;
+
+    Ok(parse_idit(&String::from_utf8_lossy(&buf)))
+}
+
+///
+/// IDITチャンクに記録された日時を解釈する
+///
+/// # 引数
+/// * `value` - IDITチャンクの内容
+///
+/// # 戻り値
+/// 解釈した日時(既知の形式でない場合はNone)
+///
+/// # 注記
+/// `MON JAN 30 12:34:56 2006`(C言語の`asctime()`の形式)、
+/// `2006:01:30 12:34:56`(Exifの形式)、`2006/01/30 12:34:56`の何れかに対応す
+/// る。日時はローカル時刻として扱う。
+///
+fn parse_idit(value: &str) -> Option<DateTime<Local>> {
+    const FORMATS: [&str; 3] = [
+        "%a %b %d %H:%M:%S %Y",
+        "%Y:%m:%d %H:%M:%S",
+        "%Y/%m/%d %H:%M:%S",
+    ];
+
+    // 末尾の改行やヌル文字、桁揃えの空白を取り除く
+    let value = value
+        .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&value, format).ok())
+        .and_then(|datetime| Local.from_local_datetime(&datetime).earliest())
+}
+
+///
+/// 指定の範囲に並ぶRIFFチャンクから指定の種別のものを探す
+///
+/// # 引数
+/// * `reader` - 読み出し元
+/// * `start` - 範囲の先頭のオフセット
+/// * `end` - 範囲の末尾のオフセット
+/// * `kind` - 探すチャンクの種別(LISTチャンクの場合はリストの種別)
+///
+/// # 戻り値
+/// 処理が成功した場合は、見つかればチャンクの末尾のオフセットを、見つからな
+/// ければNoneを`Ok()`でラップして返す。失敗した場合はエラー情報を`Err()`でラ
+/// ップして返す。
+///
+/// # 注記
+/// 見つかった場合、読み出し位置はチャンクの本体(LISTチャンクの場合はリスト
+/// の種別の直後)を指す。
+///
+fn find_chunk<R>(reader: &mut R, start: u64, end: u64, kind: &[u8; 4])
+    -> Result<Option<u64>>
+where
+    R: Read + Seek,
+{
+    let mut pos = start;
+
+    while pos + 8 <= end {
+        reader.seek(SeekFrom::Start(pos))?;
+
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+
+        let size = u32::from_le_bytes([
+            header[4], header[5], header[6], header[7]
+        ]) as u64;
+        let chunk_end = pos + 8 + size;
+
+        if chunk_end > end {
+            return Err(anyhow!("broken chunk at offset {}", pos));
+        }
+
+        if &header[0..4] == kind {
+            return Ok(Some(chunk_end));
+        }
+
+        if &header[0..4] == b"LIST" && size >= 4 {
+            let mut list_kind = [0u8; 4];
+            reader.read_exact(&mut list_kind)?;
+
+            if &list_kind == kind {
+                return Ok(Some(chunk_end));
+            }
+        }
+
+        // チャンクは2バイト境界に揃えて並ぶ
+        pos = chunk_end + (size & 1);
+    }
+
+    Ok(None)
+}
+
+///
+/// 撮影日時のみを格納したTIFF構造を組み立てる
+///
+/// # 引数
+/// * `datetime` - Exif形式("YYYY:MM:DD HH:MM:SS")の撮影日時(無い場合は
+///   None)
+///
+/// # 戻り値
+/// リトルエンディアンのTIFF構造のバイト列
+///
+/// # 注記
+/// IFD0にはExif IFDへのポインタのみを、Exif IFDにはDateTimeOriginalのみを格
+/// 納する。撮影日時が無い場合はエントリの無いIFD0のみとする。
+///
+pub(crate) fn build_tiff(datetime: Option<&str>) -> Vec<u8> {
+    // 各要素のオフセット(ヘッダ8バイト、IFDは1エントリで18バイト)
+    const EXIF_IFD: u32 = 8 + 18;
+    const DATETIME: u32 = EXIF_IFD + 18;
+
+    let mut data = Vec::new();
+
+    /*
+     * ヘッダ
+     */
+    data.extend_from_slice(b"II");
+    data.extend_from_slice(&42u16.to_le_bytes());
+    data.extend_from_slice(&8u32.to_le_bytes());
+
+    let datetime = match datetime {
+        Some(datetime) => datetime,
+        None => {
+            data.extend_from_slice(&0u16.to_le_bytes());
+            data.extend_from_slice(&0u32.to_le_bytes());
+            return data;
+        }
+    };
+
+    /*
+     * IFD0 (ExifIFDPointer)
+     */
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&0x8769u16.to_le_bytes());
+    data.extend_from_slice(&4u16.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&EXIF_IFD.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+
+    /*
+     * Exif IFD (DateTimeOriginal)
+     */
+    let mut value = datetime.as_bytes().to_vec();
+    value.push(0);
+
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&0x9003u16.to_le_bytes());
+    data.extend_from_slice(&2u16.to_le_bytes());
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(&DATETIME.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+
+    data.extend_from_slice(&value);
+
+    data
+}
@@ -0,0 +1,233 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! ボリュームシャドウコピー(VSS)経由での入力ファイルの読み出しを行うモジュール
+//!
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Error, Result};
+use log::{info, warn};
+
+use crate::external::execute;
+
+/// 共有違反を示すWindowsのエラーコード(ERROR_SHARING_VIOLATION)
+const ERROR_SHARING_VIOLATION: i32 = 32;
+
+/// ロック違反を示すWindowsのエラーコード(ERROR_LOCK_VIOLATION)
+const ERROR_LOCK_VIOLATION: i32 = 33;
+
+/// シャドウコピーを作成するPowerShellスクリプト(`{volume}`を引用符で囲んだ
+/// 文字列リテラルに置き換えて使用)
+const CREATE_SCRIPT: &str = "\
+    $r = Invoke-CimMethod -ClassName Win32_ShadowCopy -MethodName Create \
+        -Arguments @{Volume={volume}; Context='ClientAccessible'}; \
+    if ($r.ReturnValue -ne 0) { \
+        [Console]::Error.WriteLine(\"error code $($r.ReturnValue)\"); exit 1 \
+    }; \
+    $s = Get-CimInstance -ClassName Win32_ShadowCopy \
+        -Filter \"ID='$($r.ShadowID)'\"; \
+    Write-Output $s.ID; \
+    Write-Output $s.DeviceObject";
+
+///
+/// 作成したシャドウコピーを表す構造体
+///
+/// # 注記
+/// 破棄時にシャドウコピーを削除する。
+///
+#[derive(Debug)]
+pub(crate) struct Shadow {
+    /// シャドウコピーのID
+    id: String,
+
+    /// 元のボリュームのマウントポイント
+    volume: PathBuf,
+
+    /// シャドウコピーのルート(`\\?\GLOBALROOT\Device\...`の形式)
+    root: PathBuf,
+}
+
+impl Shadow {
+    ///
+    /// ボリュームのシャドウコピーを作成する
+    ///
+    /// # 引数
+    /// * `volume` - 対象のボリュームのマウントポイント
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はシャドウコピーを`Ok()`でラップして返す。失敗した場
+    /// 合はエラー情報を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// シャドウコピーの作成には管理者権限が必要となる。
+    ///
+    pub(crate) fn create(volume: &Path) -> Result<Self> {
+        if cfg!(not(windows)) {
+            return Err(anyhow!(
+                "volume shadow copy is not supported on this platform"
+            ));
+        }
+
+        // WMIには`\\?\`の接頭辞を除いたドライブのルートを渡す
+        let display = volume.to_string_lossy();
+        let name = display.strip_prefix(r"\\?\").unwrap_or(&display);
+        let script = CREATE_SCRIPT.replace("{volume}", &quote_literal(name));
+
+        let output = execute(
+            Command::new("powershell")
+                .arg("-NoProfile")
+                .arg("-NonInteractive")
+                .arg("-Command")
+                .arg(script)
+        )?;
+
+        let mut lines = output.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        let (id, device) = match (lines.next(), lines.next()) {
+            (Some(id), Some(device)) => (id.to_string(), device.to_string()),
+            _ => {
+                return Err(anyhow!(
+                    "unexpected output of shadow copy creation: {}",
+                    output.trim()
+                ));
+            }
+        };
+
+        info!("created shadow copy {} of {}", id, name);
+
+        Ok(Self {
+            id,
+            volume: volume.to_path_buf(),
+            root: PathBuf::from(format!("{}\\", device)),
+        })
+    }
+
+    ///
+    /// シャドウコピーのルートへのアクセサ
+    ///
+    /// # 戻り値
+    /// シャドウコピーのルートのパス
+    ///
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    ///
+    /// ファイルのパスをシャドウコピー上のパスに読み替える
+    ///
+    /// # 引数
+    /// * `path` - 元のボリューム上のファイルのパス
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合はシャドウコピー上のパスを`Ok()`でラップして返す。失
+    /// 敗した場合はエラー情報を`Err()`でラップして返す。
+    ///
+    pub(crate) fn map(&self, path: &Path) -> Result<PathBuf> {
+        let abs_path = path.canonicalize()?;
+
+        match abs_path.strip_prefix(&self.volume) {
+            Ok(rel_path) => Ok(self.root.join(rel_path)),
+            Err(_) => Err(anyhow!(
+                "{} is not on {}",
+                path.display(),
+                self.volume.display()
+            )),
+        }
+    }
+}
+
+// Dropトレイトの実装
+impl Drop for Shadow {
+    fn drop(&mut self) {
+        let result = execute(
+            Command::new("vssadmin")
+                .arg("delete")
+                .arg("shadows")
+                .arg(format!("/shadow={}", self.id))
+                .arg("/quiet")
+        );
+
+        match result {
+            Ok(_) => info!("deleted shadow copy {}", self.id),
+            Err(err) => warn!("delete shadow copy {} failed: {}", self.id, err),
+        }
+    }
+}
+
+///
+/// 文字列をPowerShellの単一引用符の文字列リテラルに変換する
+///
+/// # 引数
+/// * `value` - 変換する文字列
+///
+/// # 戻り値
+/// 単一引用符で囲んだ文字列リテラル
+///
+/// # 注記
+/// PowerShellは単一引用符に加えて類似の引用符(U+2018〜U+201B)も引用符とし
+/// て扱うため、それらも重ねてエスケープする。
+///
+fn quote_literal(value: &str) -> String {
+    let mut literal = String::from("'");
+
+    for ch in value.chars() {
+        if matches!(ch, '\'' | '\u{2018}'..='\u{201b}') {
+            literal.push(ch);
+        }
+
+        literal.push(ch);
+    }
+
+    literal.push('\'');
+    literal
+}
+
+///
+/// エラーが共有違反(他のアプリケーションによるロック)によるものかを判定する
+///
+/// # 引数
+/// * `err` - ファイルの処理で発生したエラー
+///
+/// # 戻り値
+/// 共有違反またはロック違反によるものであれば`true`(Windows以外では常に
+/// `false`)
+///
+pub(crate) fn is_sharing_violation(err: &Error) -> bool {
+    cfg!(windows)
+        && err
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+            .any(|err| {
+                matches!(
+                    err.raw_os_error(),
+                    Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+                )
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_literal_wraps_plain_value() {
+        assert_eq!(quote_literal(r"C:\"), r"'C:\'");
+        assert_eq!(quote_literal(""), "''");
+    }
+
+    #[test]
+    fn quote_literal_doubles_single_quotes() {
+        assert_eq!(quote_literal(r"C:\it's\"), r"'C:\it''s\'");
+        assert_eq!(
+            quote_literal("'; Remove-Item x; '"),
+            "'''; Remove-Item x; '''"
+        );
+        assert_eq!(quote_literal("a\u{2019}b"), "'a\u{2019}\u{2019}b'");
+    }
+}
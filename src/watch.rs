@@ -0,0 +1,143 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! ファイルシステムの変更通知により入力ディレクトリを監視するモジュール
+//!
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::cache::Cache;
+use crate::cmd_args::Options;
+use crate::mail;
+use crate::{finalize, import, prune_expired};
+
+/// 変更の通知が途絶えてから取り込みを始めるまでの最短の待ち時間
+const MIN_SETTLE: Duration = Duration::from_secs(2);
+
+///
+/// 監視モードの実行関数
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 監視を継続できなくなった場合はエラー情報を`Err()`でラップして返す(正常に
+/// 終了することは無い)。
+///
+/// # 概要
+/// 起動時に入力ディレクトリの取り込みを行った後、OSの変更通知(inotify、
+/// FSEvents、ReadDirectoryChangesW)で入力ディレクトリ以下へのファイルの追加
+/// と更新を監視し、変更があるたびに取り込みを行う。書き込み中のファイルを取
+/// り込まないよう、変更の通知が静止時間(`--quiet-period`、最短2秒)の間途絶
+/// えるのを待ってからまとめて1回の取り込みを行う。取り込み済みのファイルは
+/// キャッシュにより読み飛ばすため、取り込みのたびに新たなファイルのみが振り
+/// 分けられる。
+///
+pub(crate) fn run(opts: &Options) -> Result<()> {
+    let cache = opts.cache();
+    let input = opts.input_path();
+    let settle = opts.quiet_period().max(MIN_SETTLE);
+
+    /*
+     * 変更通知の登録
+     */
+    let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+
+    let mut watcher = notify::recommended_watcher(sender)
+        .map_err(|err| anyhow!("create watcher failed: {}", err))?;
+
+    watcher
+        .watch(&input, RecursiveMode::Recursive)
+        .map_err(|err| anyhow!("watch {} failed: {}", input.display(), err))?;
+
+    info!("watching {}", input.display());
+
+    /*
+     * 起動時の取り込み
+     */
+    import_once(opts, &cache);
+
+    /*
+     * 変更のたびの取り込み
+     */
+    loop {
+        // ファイルの追加または更新を待つ
+        match receiver.recv() {
+            Ok(Ok(event)) if is_addition(&event) => {}
+            Ok(Ok(_)) => continue,
+
+            Ok(Err(err)) => {
+                warn!("watch error: {}", err);
+                continue;
+            }
+
+            Err(_) => return Err(anyhow!("watcher stopped")),
+        }
+
+        // 変更の通知が静止するのを待つ
+        loop {
+            match receiver.recv_timeout(settle) {
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(anyhow!("watcher stopped"));
+                }
+            }
+        }
+
+        import_once(opts, &cache);
+    }
+}
+
+///
+/// ファイルの追加または更新の通知か否かを判定する
+///
+/// # 引数
+/// * `event` - 変更通知
+///
+/// # 戻り値
+/// ファイルの作成、書き込み、名前の変更の通知の場合は`true`(参照や削除の通
+/// 知の場合は`false`)
+///
+fn is_addition(event: &Event) -> bool {
+    matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+}
+
+///
+/// 1回分の取り込みを行う
+///
+/// # 引数
+/// * `opts` - オプション設定の参照
+/// * `cache` - キャッシュデータベース
+///
+/// # 注記
+/// 取り込みに失敗しても監視は継続する。
+///
+fn import_once(opts: &Options, cache: &Cache) {
+    info!("import started from {}", opts.input_path().display());
+
+    match import(opts, cache) {
+        Ok(report) => {
+            let result = finalize(opts, &report)
+                .and_then(|_| prune_expired(opts, cache, &report));
+
+            if let Err(err) = result {
+                error!("{}", err);
+            }
+        }
+
+        Err(err) => {
+            error!("import failed: {}", err);
+            mail::notify_failure(opts, &err);
+        }
+    }
+}
@@ -0,0 +1,583 @@
+//
+// Image file distributor
+//
+//  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+//
+
+//!
+//! 単一ファイルに対する処理内容の追跡表示を行うモジュール
+//!
+
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use exif::Exif;
+
+use crate::cache::{Cache, CacheDecision, ExifCache};
+use crate::clock;
+use crate::companion;
+use crate::cmd_args::{Collision, GpsClockMode, Options, WhyArgs};
+use crate::exif_view::ExifView;
+use crate::fallback;
+use crate::report::{FileTimings, SkipReason};
+use crate::template::{NameContext, PathContext, Sequencer};
+use crate::{
+    build_file_type, build_name, datetime_fallback, file_stem,
+    get_datetime_field, is_date_in_range, is_excluded_camera, is_jpeg_file,
+    is_raw_file, is_target_file, is_video_file, original_dir,
+    parse_datetime, shooting_date, src_dir, stored_name, unique_name,
+};
+
+///
+/// `why`サブコマンドの実行関数
+///
+/// # 引数
+/// * `args` - サブコマンドの引数
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// 振り分け処理と同じ手順で判定を行い、各段階の結果を表示する。ファイルのコピ
+/// ーおよびキャッシュの更新は一切行わない。入力ディレクトリはファイルの親ディ
+/// レクトリとして扱う。
+///
+pub(crate) fn run(args: &WhyArgs, opts: &Options) -> Result<()> {
+    let path = args.path.as_path();
+
+    if !path.is_file() {
+        return Err(anyhow!("{} is not file", path.display()));
+    }
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => Path::new(".").to_path_buf(),
+    };
+
+    let opts = opts.with_input_path(dir);
+
+    println!("file:            {}", path.display());
+
+    trace(path, &opts)
+}
+
+///
+/// 処理内容を追跡しながら表示する
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+fn trace(path: &Path, opts: &Options) -> Result<()> {
+    /*
+     * 付随ファイルの判定
+     */
+    if let Some(parent) = companion::parent(path, opts) {
+        println!("companion of:    {}", parent.display());
+        return skip(SkipReason::Companion);
+    }
+
+    /*
+     * 形式の判定
+     */
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let kind = if is_jpeg_file(&ext, opts.classes()) {
+        "jpeg"
+    } else if is_raw_file(&ext, opts.classes()) {
+        "raw"
+    } else if is_video_file(&ext, opts.classes()) {
+        "video"
+    } else if is_target_file(&ext, opts) {
+        "other"
+    } else {
+        "unsupported"
+    };
+
+    if kind == "unsupported" {
+        return match opts.copy_unknown_dir() {
+            Some(dir) => trace_unknown(path, &dir, opts),
+            None => {
+                println!("file type:       {}", kind);
+                skip(SkipReason::UnsupportedExt)
+            }
+        };
+    }
+
+    println!("file type:       {}", kind);
+
+    /*
+     * 除外リストの判定
+     */
+    if let Some(list) = opts.exclude_list() {
+        if list.contains(path)? {
+            println!("exclude list:    excluded");
+            return skip(SkipReason::Excluded);
+        }
+
+        println!("exclude list:    not excluded");
+    }
+
+    /*
+     * キャッシュの参照
+     */
+    let cache = Cache::open(
+        opts.cache_db_path(),
+        opts.cache_eval_mode(),
+        opts.input_path(),
+    )?;
+
+    let info = cache.trace(path)?;
+
+    println!("volume id:       {}", info.volume_id);
+    println!("volume prefix:   {}", info.volume_prefix.display());
+    println!("relative path:   {}", info.rel_path.display());
+    println!("cache key:       {}", info.key);
+    println!(
+        "cache record:    {}",
+        info.record.as_deref().unwrap_or("(none)")
+    );
+
+    /*
+     * キャッシュの評価
+     */
+    let meta = path.metadata()?;
+    let mut timings = FileTimings::default();
+    let exif_cache = ExifCache::new(opts);
+    let forced = opts.is_force_target(path);
+
+    let decision = if forced {
+        cache.evaluate_forced(path, meta, &exif_cache, &mut timings)?
+    } else {
+        cache.evaluate(path, meta, &exif_cache, &mut timings)?
+    };
+
+    // 履歴データベースと内容のハッシュ値の照会は強制再処理の対象でない場合
+    // にのみ行う
+    let (hit, exif, archived, renamed, duplicate) = match decision {
+        CacheDecision::Hit => {
+            (true, read_exif(path, &exif_cache)?, None, None, None)
+        }
+
+        CacheDecision::Miss { mut handle, exif } => {
+            if let Some(jpeg) = handle.paired_exif() {
+                println!("paired exif:     {}", jpeg);
+            }
+
+            let archived = opts
+                .history()
+                .filter(|_| !forced)
+                .map(|history| history.contains(&handle));
+
+            let algo = opts.checksum_algorithm();
+
+            let renamed = if opts.is_hash_index() && !forced {
+                Some(cache.find_by_hash(&mut handle, path, algo)?)
+            } else {
+                None
+            };
+
+            let duplicate = if opts.is_dedup() && !forced {
+                Some(cache.find_in_archive(&mut handle, path, algo)?)
+            } else {
+                None
+            };
+
+            (false, exif, archived, renamed, duplicate)
+        }
+    };
+
+    println!(
+        "cache decision:  {} (eval mode {:?}{})",
+        if hit { "hit" } else { "miss" },
+        opts.cache_eval_mode(),
+        if forced { ", forced" } else { "" }
+    );
+
+    /*
+     * 履歴データベースの判定
+     */
+    match archived {
+        Some(true) => {
+            println!("history:         archived");
+            return skip(SkipReason::Archived);
+        }
+
+        Some(false) => println!("history:         not archived"),
+        None => {}
+    }
+
+    /*
+     * 内容のハッシュ値の判定
+     */
+    match renamed {
+        Some(Some(key)) => {
+            println!("hash index:      {}", key);
+            return skip(SkipReason::CacheHit);
+        }
+
+        Some(None) => println!("hash index:      (none)"),
+        None => {}
+    }
+
+    /*
+     * 振り分け先全体での重複の判定
+     */
+    match duplicate {
+        Some(Some(stored)) => {
+            println!("dedup:           {}", stored.display());
+            return skip(SkipReason::Duplicate);
+        }
+
+        Some(None) => println!("dedup:           (none)"),
+        None => {}
+    }
+
+    /*
+     * 撮影日時と撮影日の判定
+     */
+    let datetime = match get_datetime_field(&exif) {
+        Some(field) => {
+            let value = field.display_value().to_string();
+            println!("datetime:        {}", value);
+            parse_datetime(&value, opts.datetime_formats())?
+        }
+
+        None => {
+            println!("datetime:        (none)");
+
+            let mode = datetime_fallback(path, opts);
+
+            match fallback::datetime(path, mode) {
+                Some(datetime) => {
+                    println!(
+                        "fallback:        {} ({:?})",
+                        datetime.to_rfc3339(),
+                        mode
+                    );
+                    datetime
+                }
+
+                None => return skip(SkipReason::NoExif),
+            }
+        }
+    };
+
+    /*
+     * 撮影条件の表示
+     */
+    let view = ExifView::from(&*exif);
+    let none = || "(none)".to_string();
+
+    println!("camera:          {}", view.camera().unwrap_or_else(none));
+    println!("lens:            {}", view.lens().unwrap_or("(none)"));
+    println!(
+        "exposure:        {} f/{} ISO {}",
+        view.exposure().unwrap_or_else(none),
+        view.f_number.map(|f| f.to_string()).unwrap_or_else(none),
+        view.iso.map(|iso| iso.to_string()).unwrap_or_else(none),
+    );
+
+    if opts.gps_clock_mode() != GpsClockMode::Off {
+        match clock::gps_offset(&view, &datetime) {
+            Some(offset) => println!("gps clock:       {:+}s", offset),
+            None => println!("gps clock:       (none)"),
+        }
+    }
+
+    /*
+     * 画像の向きの判定
+     */
+    if let Some(wanted) = opts.orientation() {
+        let actual = view.orientation();
+
+        println!(
+            "orientation:     {} (wanted {:?})",
+            actual
+                .map(|actual| format!("{:?}", actual))
+                .unwrap_or_else(|| "(unknown)".to_string()),
+            wanted
+        );
+
+        if actual != Some(wanted) {
+            return skip(SkipReason::Orientation);
+        }
+    }
+
+    /*
+     * カメラによる除外の判定
+     */
+    if !opts.exclude_cameras().is_empty()
+        || !opts.exclude_serials().is_empty()
+    {
+        let excluded = is_excluded_camera(&view, opts);
+
+        println!(
+            "exclude camera:  {}",
+            if excluded { "excluded" } else { "not excluded" }
+        );
+
+        if excluded {
+            return skip(SkipReason::ExcludedCamera);
+        }
+    }
+
+    let date = shooting_date(&datetime, opts);
+
+    println!(
+        "shooting date:   {} (day start {})",
+        date,
+        opts.day_start().format("%H:%M")
+    );
+
+    /*
+     * 日付範囲の判定
+     */
+    let in_range = is_date_in_range(&date, opts);
+
+    println!(
+        "date range:      {}..{} ({})",
+        opts.from_date()
+            .map(|date| date.date_naive().to_string())
+            .unwrap_or_default(),
+        opts.to_date()
+            .map(|date| date.date_naive().to_string())
+            .unwrap_or_default(),
+        if in_range { "in range" } else { "out of range" }
+    );
+
+    /*
+     * 振り分け先の決定
+     */
+    let ctx = PathContext {
+        date,
+        artist: view.artist.as_deref(),
+        event: opts.event_at(&datetime),
+        camera: view.camera_name(),
+        ext: &ext,
+        original_dir: original_dir(path, opts),
+        src_dir: src_dir(path),
+        volume_label: cache.volume_label(),
+    };
+
+    println!("artist:          {}", ctx.artist.unwrap_or("(none)"));
+    println!("event:           {}", ctx.event.unwrap_or("(none)"));
+
+    let file_type = match build_file_type(&ext, &ctx, opts) {
+        Some(file_type) => file_type,
+        None => return skip(SkipReason::UnsupportedExt),
+    };
+
+    let name_ctx = NameContext {
+        datetime: datetime.naive_local(),
+        subsec: view.subsec.as_deref(),
+        artist: ctx.artist,
+        event: ctx.event,
+        stem: &file_stem(path),
+        src_dir: ctx.src_dir,
+        volume_label: ctx.volume_label,
+    };
+
+    let name = build_name(
+        path,
+        &file_type,
+        &name_ctx,
+        opts,
+        &mut Sequencer::default(),
+    );
+
+    let tier = opts.tier_for(file_type.label(), &ext);
+    let dst = file_type.dir().join(stored_name(&name, &file_type, opts));
+
+    println!(
+        "tier:            {}",
+        tier.map_or("(none)", |tier| tier.name.as_str())
+    );
+    println!("destination:     {}", dst.display());
+
+    /*
+     * 最終判定
+     */
+    if hit {
+        return skip(SkipReason::CacheHit);
+    }
+
+    if !in_range {
+        return skip(SkipReason::OutOfRange);
+    }
+
+    // 同名のファイルが存在する場合はティアまたは--on-conflictの指定に従う
+    let dst = if dst.exists() {
+        match opts.collision_for(tier) {
+            Collision::Skip => return skip(SkipReason::Collision),
+            Collision::Rename => {
+                let name = unique_name(&name, file_type.dir(), |name| {
+                    stored_name(name, &file_type, opts)
+                });
+                file_type.dir().join(stored_name(&name, &file_type, opts))
+            }
+            Collision::Error => {
+                println!("result:          error (destination exists)");
+                return Ok(());
+            }
+            Collision::Overwrite => dst,
+        }
+    } else {
+        dst
+    };
+
+    println!("result:          copy to {}", dst.display());
+
+    Ok(())
+}
+
+///
+/// 画像以外のファイルの処理内容を追跡しながら表示する
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `dir` - 画像以外のファイルの保全先
+/// * `opts` - オプション設定の参照
+///
+/// # 戻り値
+/// 処理が成功した場合は`Ok(())`、失敗した場合はエラー情報を`Err()`でラップして
+/// 返す
+///
+/// # 注記
+/// キャッシュはサイズと更新日時のみで評価し、撮影日の代わりにファイルの更新
+/// 日時を用いる。
+///
+fn trace_unknown(path: &Path, dir: &Path, opts: &Options) -> Result<()> {
+    println!("file type:       unknown");
+
+    let cache = Cache::open(
+        opts.cache_db_path(),
+        opts.cache_eval_mode(),
+        opts.input_path(),
+    )?;
+
+    let meta = path.metadata()?;
+    let forced = opts.is_force_target(path);
+    let hit = cache.evaluate_plain(path, &meta, forced)?.is_none();
+
+    println!(
+        "cache decision:  {} (size and mtime{})",
+        if hit { "hit" } else { "miss" },
+        if forced { ", forced" } else { "" }
+    );
+
+    let datetime = DateTime::<Local>::from(meta.modified()?);
+    let date = shooting_date(&datetime, opts);
+    let in_range = is_date_in_range(&date, opts);
+
+    println!("modified:        {}", datetime.format("%Y-%m-%d %H:%M:%S"));
+    println!(
+        "shooting date:   {} ({})",
+        date,
+        if in_range { "in range" } else { "out of range" }
+    );
+
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy())
+        .unwrap_or_default();
+    let ctx = PathContext {
+        date,
+        artist: None,
+        event: opts.event_at(&datetime),
+        camera: None,
+        ext: &ext,
+        original_dir: original_dir(path, opts),
+        src_dir: src_dir(path),
+        volume_label: cache.volume_label(),
+    };
+
+    let name = path.file_name().unwrap_or_default();
+    let dst = dir.join(opts.path_template().expand(&ctx)).join(name);
+
+    println!("destination:     {}", dst.display());
+
+    if hit {
+        return skip(SkipReason::CacheHit);
+    }
+
+    if !in_range {
+        return skip(SkipReason::OutOfRange);
+    }
+
+    // 同名のファイルが存在する場合は--on-conflictの指定に従う
+    let dst = if dst.exists() {
+        match opts.on_conflict() {
+            Collision::Skip => return skip(SkipReason::Collision),
+            Collision::Rename => {
+                let name = unique_name(
+                    &name.to_string_lossy(),
+                    dst.parent().unwrap_or(dir),
+                    str::to_string,
+                );
+                dst.with_file_name(name)
+            }
+            Collision::Error => {
+                println!("result:          error (destination exists)");
+                return Ok(());
+            }
+            Collision::Overwrite => dst,
+        }
+    } else {
+        dst
+    };
+
+    println!("result:          copy to {}", dst.display());
+
+    Ok(())
+}
+
+///
+/// スキップする旨を表示する
+///
+/// # 引数
+/// * `reason` - スキップする理由
+///
+/// # 戻り値
+/// 常に`Ok(())`を返す
+///
+fn skip(reason: SkipReason) -> Result<()> {
+    println!("result:          skip ({})", reason);
+    Ok(())
+}
+
+///
+/// Exif情報を読み込む
+///
+/// # 引数
+/// * `path` - 対象ファイルのパス
+/// * `exif_cache` - 読み出したExif情報のキャッシュ
+///
+/// # 戻り値
+/// 処理が成功した場合はExif情報を`Ok()`でラップして返す。失敗した場合はエラー
+/// 情報を`Err()`でラップして返す。
+///
+/// # 注記
+/// 振り分け処理と同様に、RAWファイルのExif情報が読めない場合はペアのJPEGファ
+/// イルのもので代用する。
+///
+fn read_exif(path: &Path, exif_cache: &ExifCache) -> Result<Rc<Exif>> {
+    let (exif, summary) = exif_cache.read(path)?;
+
+    if let Some(jpeg) = summary.paired_exif {
+        println!("paired exif:     {}", jpeg);
+    }
+
+    Ok(exif)
+}